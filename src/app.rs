@@ -1,4 +1,23 @@
+pub mod backoff;
+pub mod check;
 pub mod config;
 pub mod constant;
+pub mod cors;
+pub mod daily_summary;
+pub mod i18n;
+pub mod ip_filter;
+pub mod lease;
+pub mod listen;
+pub mod log_sink;
+pub mod log_stream;
+pub mod logging;
 pub mod model;
+pub mod quota;
+pub mod report;
+pub mod request_id;
+pub mod rotation;
+pub mod session;
+pub mod static_dir;
 pub mod lazy;
+#[cfg(feature = "tls")]
+pub mod tls;
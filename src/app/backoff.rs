@@ -0,0 +1,114 @@
+use super::lazy::RATE_LIMIT_BACKOFF;
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    sync::LazyLock,
+    time::{Duration, Instant},
+};
+
+struct Backoff {
+    // 已连续被限流的次数
+    strikes: usize,
+    until: Instant,
+}
+
+static BACKOFFS: LazyLock<Mutex<HashMap<String, Backoff>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+#[inline]
+pub fn is_enabled() -> bool {
+    !RATE_LIMIT_BACKOFF.is_empty()
+}
+
+// 处于冷却期的 token 不参与号池的选择
+pub fn is_cooling_down(token: &str) -> bool {
+    BACKOFFS
+        .lock()
+        .get(token)
+        .is_some_and(|backoff| backoff.until > Instant::now())
+}
+
+// 被上游限流时进入冷却，连续被限流时按 RATE_LIMIT_BACKOFF 逐级延长，达到最后一级后保持不变
+pub fn rate_limited(token: &str) {
+    if !is_enabled() {
+        return;
+    }
+    let mut backoffs = BACKOFFS.lock();
+    let backoff = backoffs.entry(token.to_string()).or_insert(Backoff {
+        strikes: 0,
+        until: Instant::now(),
+    });
+    let cooldown = cooldown(&RATE_LIMIT_BACKOFF, backoff.strikes);
+    backoff.strikes += 1;
+    backoff.until = Instant::now() + cooldown;
+    tracing::warn!(
+        "token 被上游限流（连续 {} 次），冷却 {} 秒",
+        backoff.strikes,
+        cooldown.as_secs()
+    );
+}
+
+// 第 strikes + 1 次被限流时的冷却时间
+fn cooldown(schedule: &[Duration], strikes: usize) -> Duration {
+    schedule[strikes.min(schedule.len() - 1)]
+}
+
+// 请求成功后重置
+pub fn succeeded(token: &str) {
+    let mut backoffs = BACKOFFS.lock();
+    if !backoffs.is_empty() {
+        backoffs.remove(token);
+    }
+}
+
+// 剩余的冷却时间
+pub fn remaining(token: &str) -> Option<Duration> {
+    BACKOFFS
+        .lock()
+        .get(token)
+        .and_then(|backoff| backoff.until.checked_duration_since(Instant::now()))
+        .filter(|remaining| !remaining.is_zero())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cooldown_schedule() {
+        let schedule = [60, 300, 900].map(Duration::from_secs);
+        let cooldowns: Vec<u64> = (0..5)
+            .map(|strikes| cooldown(&schedule, strikes).as_secs())
+            .collect();
+        // 达到最后一级后保持不变
+        assert_eq!(cooldowns, [60, 300, 900, 900, 900]);
+
+        let single = [Duration::from_secs(30)];
+        assert_eq!(cooldown(&single, 0), cooldown(&single, 10));
+    }
+
+    #[test]
+    fn test_rate_limited_escalates_and_resets() {
+        if !is_enabled() {
+            return;
+        }
+        let token = "test_backoff_token";
+        assert!(!is_cooling_down(token));
+        assert_eq!(remaining(token), None);
+
+        for strikes in 0..RATE_LIMIT_BACKOFF.len() + 2 {
+            rate_limited(token);
+            let expected = cooldown(&RATE_LIMIT_BACKOFF, strikes);
+            let remaining = remaining(token).unwrap();
+            assert!(is_cooling_down(token));
+            assert!(remaining <= expected && remaining > expected - Duration::from_secs(5));
+        }
+
+        // 成功一次后从第一级重新开始
+        succeeded(token);
+        assert!(!is_cooling_down(token));
+        rate_limited(token);
+        assert!(remaining(token).unwrap() <= RATE_LIMIT_BACKOFF[0]);
+        succeeded(token);
+    }
+}
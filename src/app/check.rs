@@ -0,0 +1,158 @@
+use super::{
+    constant::COMMA,
+    lazy::{
+        AUTH_TOKEN, CURSOR_API2_CHAT_URL, LISTEN_ADDRS, TLS_CERT_PATH, TLS_KEY_PATH,
+        TOKEN_LIST_FILE,
+    },
+    model::{AppConfig, AppState, TokenBlacklist, SAVED_DATA},
+};
+use crate::common::{
+    client::HTTP_CLIENT,
+    utils::{decrypt_field, get_token_profile, parse_token, validate_token},
+};
+use std::{fmt::Display, net::SocketAddr, time::Duration};
+
+// 探测上游时的超时时间
+const PING_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Default)]
+struct Report {
+    failed: usize,
+}
+
+impl Report {
+    fn pass(&self, name: &str, detail: impl Display) {
+        println!("[通过] {}: {}", name, detail);
+    }
+
+    fn warn(&self, name: &str, detail: impl Display) {
+        println!("[警告] {}: {}", name, detail);
+    }
+
+    fn fail(&mut self, name: &str, detail: impl Display) {
+        self.failed += 1;
+        println!("[失败] {}: {}", name, detail);
+    }
+
+    fn result<T, E: Display>(&mut self, name: &str, result: Result<T, E>) {
+        match result {
+            Ok(_) => self.pass(name, "可读取"),
+            Err(e) => self.fail(name, e),
+        }
+    }
+}
+
+/// 启动前自检：检查环境变量、读取持久化文件、探测上游并校验一个 token
+///
+/// 只读取文件而不写入，全部通过时返回 `true`
+pub async fn run() -> bool {
+    let mut report = Report::default();
+    println!("cursor-api 启动自检");
+
+    check_env(&mut report);
+    check_files(&mut report).await;
+    check_upstream(&mut report).await;
+    check_token(&mut report).await;
+
+    if report.failed == 0 {
+        println!("自检通过");
+        true
+    } else {
+        println!("自检失败: {} 项未通过", report.failed);
+        false
+    }
+}
+
+fn check_env(report: &mut Report) {
+    if AUTH_TOKEN.is_empty() {
+        report.fail("AUTH_TOKEN", "未设置");
+    } else {
+        report.pass("AUTH_TOKEN", "已设置");
+    }
+
+    for addr in LISTEN_ADDRS.iter() {
+        if addr.parse::<SocketAddr>().is_ok() {
+            report.pass("监听地址", addr);
+        } else {
+            report.fail("监听地址", format!("无法解析 {}", addr));
+        }
+    }
+
+    match (TLS_CERT_PATH.is_empty(), TLS_KEY_PATH.is_empty()) {
+        (true, true) => {}
+        (false, false) => {
+            for path in [TLS_CERT_PATH.as_str(), TLS_KEY_PATH.as_str()] {
+                if std::path::Path::new(path).is_file() {
+                    report.pass("TLS", path);
+                } else {
+                    report.fail("TLS", format!("文件不存在 {}", path));
+                }
+            }
+        }
+        _ => report.fail("TLS", "TLS_CERT_PATH 与 TLS_KEY_PATH 需要同时设置"),
+    }
+}
+
+// 与启动时的加载顺序一致，任一文件损坏都会导致启动后丢失对应数据
+async fn check_files(report: &mut Report) {
+    match AppState::load_saved_logs().await {
+        Ok(logs) => report.pass("请求日志", format!("{} 条", logs.len())),
+        Err(e) => report.fail("请求日志", e),
+    }
+    report.result("配置", AppConfig::load_saved_config());
+    report.result("token 黑名单", TokenBlacklist::load());
+    for (name, load) in SAVED_DATA {
+        report.result(name, load());
+    }
+}
+
+// 只要收到响应即视为可达，不关心状态码
+async fn check_upstream(report: &mut Report) {
+    let request = HTTP_CLIENT
+        .read()
+        .head(CURSOR_API2_CHAT_URL.as_str())
+        .timeout(PING_TIMEOUT);
+    match request.send().await {
+        Ok(response) => report.pass(
+            "上游",
+            format!("{} 响应 {}", *CURSOR_API2_CHAT_URL, response.status()),
+        ),
+        Err(e) => report.fail("上游", format!("{} 不可达: {}", *CURSOR_API2_CHAT_URL, e)),
+    }
+}
+
+// 取 token 文件中的第一个 token 校验格式并获取账户资料
+async fn check_token(report: &mut Report) {
+    let content = match std::fs::read_to_string(TOKEN_LIST_FILE.as_str()) {
+        Ok(content) => content,
+        Err(e) => {
+            report.warn("token", format!("无法读取 {}: {}", *TOKEN_LIST_FILE, e));
+            return;
+        }
+    };
+
+    let Some(line) = content
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with('#'))
+    else {
+        report.warn("token", "号池为空");
+        return;
+    };
+
+    let Some(token) = line.split(COMMA).next().and_then(decrypt_field) else {
+        report.fail("token", "无法解密，请检查 TOKEN_ENCRYPTION_KEY");
+        return;
+    };
+    let token = parse_token(&token);
+
+    if !validate_token(&token) {
+        report.fail("token", "格式无效或已过期");
+        return;
+    }
+
+    match get_token_profile(&token).await {
+        Some(profile) => report.pass("token", format!("有效，用户 ID {}", profile.user.sub)),
+        None => report.fail("token", "无法获取账户资料"),
+    }
+}
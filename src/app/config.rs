@@ -1,12 +1,34 @@
-use super::{constant::AUTHORIZATION_BEARER_PREFIX, lazy::AUTH_TOKEN, model::AppConfig};
-use crate::common::model::{
-    config::{ConfigData, ConfigUpdateRequest},
-    ApiStatus, ErrorResponse, NormalResponse,
+use super::{
+    constant::{
+        CONTENT_TYPE_TEXT_TOML_WITH_UTF8, EMPTY_STRING, ROUTE_ABOUT_PATH, ROUTE_API_PATH,
+        ROUTE_BUILD_KEY_PATH, ROUTE_CONFIG_PATH, ROUTE_LOGS_PATH, ROUTE_README_PATH,
+        ROUTE_ROOT_PATH, ROUTE_SHARED_JS_PATH, ROUTE_SHARED_STYLES_PATH, ROUTE_TOKENS_PATH,
+    },
+    cors::{is_valid_header_rule, is_valid_origin_rule},
+    model::{
+        is_valid_ip_rule, AppConfig, AuditActor, AuditLogs, ModelAliases, ModelPolicies,
+        PageContent, QuotaSnapshot, QuotaSnapshots,
+    },
+    session,
+};
+use crate::{
+    chat::constant::AVAILABLE_MODELS,
+    common::model::{
+        config::{
+            ConfigData, ConfigDocument, ConfigImportDocument, ConfigUpdateRequest, QuotaEntry,
+        },
+        ApiStatus, ErrorResponse, NormalResponse,
+    },
 };
 use axum::{
-    http::{header::AUTHORIZATION, HeaderMap, StatusCode},
+    http::{
+        header::{AUTHORIZATION, CONTENT_TYPE},
+        HeaderMap, StatusCode,
+    },
+    response::{IntoResponse as _, Response},
     Json,
 };
+use std::collections::HashSet;
 
 // 定义处理更新操作的宏
 macro_rules! handle_updates {
@@ -32,56 +54,28 @@ macro_rules! handle_resets {
 
 pub async fn handle_config_update(
     headers: HeaderMap,
-    Json(request): Json<ConfigUpdateRequest>,
+    actor: AuditActor,
+    Json(mut request): Json<ConfigUpdateRequest>,
 ) -> Result<Json<NormalResponse<ConfigData>>, (StatusCode, Json<ErrorResponse>)> {
-    let auth_header = headers
-        .get(AUTHORIZATION)
-        .and_then(|h| h.to_str().ok())
-        .and_then(|h| h.strip_prefix(AUTHORIZATION_BEARER_PREFIX))
-        .ok_or((
-            StatusCode::UNAUTHORIZED,
-            Json(ErrorResponse {
-                status: ApiStatus::Failed,
-                code: Some(401),
-                error: Some("未提供认证令牌".to_string()),
-                message: None,
-            }),
-        ))?;
-
-    if auth_header != AUTH_TOKEN.as_str() {
-        return Err((
-            StatusCode::UNAUTHORIZED,
-            Json(ErrorResponse {
-                status: ApiStatus::Failed,
-                code: Some(401),
-                error: Some("无效的认证令牌".to_string()),
-                message: None,
-            }),
-        ));
-    }
+    authorize(&headers)?;
 
     match request.action.as_str() {
         "get" => Ok(Json(NormalResponse {
             status: ApiStatus::Success,
-            data: Some(ConfigData {
-                page_content: AppConfig::get_page_content(&request.path),
-                vision_ability: AppConfig::get_vision_ability(),
-                enable_slow_pool: AppConfig::get_slow_pool(),
-                enable_all_claude: AppConfig::get_allow_claude(),
-                usage_check_models: AppConfig::get_usage_check(),
-                enable_dynamic_key: AppConfig::get_dynamic_key(),
-                share_token: AppConfig::get_share_token(),
-                proxies: AppConfig::get_proxies(),
-                include_web_references: AppConfig::get_web_refs(),
-            }),
+            data: Some(current_config(&request.path)),
             message: None,
         })),
 
         "update" => {
+            // 先校验，避免部分设置已生效
+            validate_update(&request)?;
+
+            let path = std::mem::take(&mut request.path);
+            let before = audit_snapshot(&path);
+
             // 处理页面内容更新
-            if !request.path.is_empty() && request.content.is_some() {
-                let content = request.content.unwrap();
-                if let Err(e) = AppConfig::update_page_content(&request.path, content) {
+            if let Some(content) = request.content.take().filter(|_| !path.is_empty()) {
+                if let Err(e) = AppConfig::update_page_content(&path, content) {
                     return Err((
                         StatusCode::INTERNAL_SERVER_ERROR,
                         Json(ErrorResponse {
@@ -94,16 +88,12 @@ pub async fn handle_config_update(
                 }
             }
 
-            handle_updates!(request,
-                vision_ability => AppConfig::update_vision_ability,
-                enable_slow_pool => AppConfig::update_slow_pool,
-                enable_all_claude => AppConfig::update_allow_claude,
-                usage_check_models => AppConfig::update_usage_check,
-                enable_dynamic_key => AppConfig::update_dynamic_key,
-                share_token => AppConfig::update_share_token,
-                proxies => AppConfig::update_proxies,
-                include_web_references => AppConfig::update_web_refs,
-            );
+            apply_updates(request);
+
+            let after = audit_snapshot(&path);
+            AuditLogs::record(&actor, "config.update", before, after).await;
+
+            save_config()?;
 
             Ok(Json(NormalResponse {
                 status: ApiStatus::Success,
@@ -113,6 +103,8 @@ pub async fn handle_config_update(
         }
 
         "reset" => {
+            let before = audit_snapshot(&request.path);
+
             // 重置页面内容
             if !request.path.is_empty() {
                 if let Err(e) = AppConfig::reset_page_content(&request.path) {
@@ -137,8 +129,21 @@ pub async fn handle_config_update(
                 share_token => AppConfig::reset_share_token,
                 proxies => AppConfig::reset_proxies,
                 include_web_references => AppConfig::reset_web_refs,
+                token_warmup => AppConfig::reset_token_warmup,
+                token_warmup_required => AppConfig::reset_token_warmup_required,
+                ip_allowlist => AppConfig::reset_ip_allowlist,
+                ip_denylist => AppConfig::reset_ip_denylist,
+                cors_allowed_origins => AppConfig::reset_cors_allowed_origins,
+                cors_admin_origins => AppConfig::reset_cors_admin_origins,
+                cors_allowed_headers => AppConfig::reset_cors_allowed_headers,
+                cors_allow_credentials => AppConfig::reset_cors_allow_credentials,
             );
 
+            let after = audit_snapshot(&request.path);
+            AuditLogs::record(&actor, "config.reset", before, after).await;
+
+            save_config()?;
+
             Ok(Json(NormalResponse {
                 status: ApiStatus::Success,
                 data: None,
@@ -157,3 +162,357 @@ pub async fn handle_config_update(
         )),
     }
 }
+
+// 可自定义内容的页面
+const PAGE_PATHS: [&str; 10] = [
+    ROUTE_ROOT_PATH,
+    ROUTE_LOGS_PATH,
+    ROUTE_CONFIG_PATH,
+    ROUTE_TOKENS_PATH,
+    ROUTE_SHARED_STYLES_PATH,
+    ROUTE_SHARED_JS_PATH,
+    ROUTE_ABOUT_PATH,
+    ROUTE_README_PATH,
+    ROUTE_API_PATH,
+    ROUTE_BUILD_KEY_PATH,
+];
+
+// 以 TOML 导出运行时配置、自定义页面、模型别名、模型策略与额度快照，用于备份与复现部署
+pub async fn handle_config_export(
+    headers: HeaderMap,
+) -> Result<Response, (StatusCode, Json<ErrorResponse>)> {
+    authorize(&headers)?;
+
+    let content = toml::to_string(&config_document()).map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                status: ApiStatus::Failed,
+                code: Some(500),
+                error: Some("导出配置失败".to_string()),
+                message: Some(e.to_string()),
+            }),
+        )
+    })?;
+
+    Ok(([(CONTENT_TYPE, CONTENT_TYPE_TEXT_TOML_WITH_UTF8)], content).into_response())
+}
+
+// 导入 TOML 配置，全部校验通过后才生效，保存失败时整体回滚
+pub async fn handle_config_import(
+    headers: HeaderMap,
+    actor: AuditActor,
+    body: String,
+) -> Result<Json<NormalResponse<()>>, (StatusCode, Json<ErrorResponse>)> {
+    authorize(&headers)?;
+
+    let bad_request = |message: String| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                status: ApiStatus::Failed,
+                code: Some(400),
+                error: Some("无效的配置".to_string()),
+                message: Some(message),
+            }),
+        )
+    };
+
+    let document: ConfigImportDocument =
+        toml::from_str(&body).map_err(|e| bad_request(e.to_string()))?;
+
+    validate_update(&document.config)?;
+    if let Some(path) = document
+        .pages
+        .keys()
+        .find(|path| !PAGE_PATHS.contains(&path.as_str()))
+    {
+        return Err(bad_request(format!("无效的页面路径: {}", path)));
+    }
+    for alias in &document.model_aliases {
+        if alias.alias.trim().is_empty() || alias.alias == alias.model {
+            return Err(bad_request(format!("无效的模型别名: {}", alias.alias)));
+        }
+        if !AVAILABLE_MODELS.iter().any(|m| m.id == alias.model)
+            && !alias.model.starts_with("claude")
+        {
+            return Err(bad_request(format!("未知模型: {}", alias.model)));
+        }
+    }
+    if document
+        .model_policies
+        .iter()
+        .any(|policy| policy.user_id.trim().is_empty())
+    {
+        return Err(bad_request("模型策略缺少 user_id".to_string()));
+    }
+    let mut tokens = HashSet::new();
+    if let Some(quota) = document
+        .quotas
+        .iter()
+        .find(|quota| quota.token.trim().is_empty() || !tokens.insert(quota.token.as_str()))
+    {
+        return Err(bad_request(format!(
+            "额度快照的 token 为空或重复: {}",
+            quota.token
+        )));
+    }
+
+    let before = import_snapshot();
+    let previous = (
+        AppConfig::snapshot(),
+        ModelAliases::list(),
+        ModelPolicies::list(),
+        QuotaSnapshots::list(),
+    );
+
+    let ConfigImportDocument {
+        config,
+        mut pages,
+        model_aliases,
+        model_policies,
+        quotas,
+    } = document;
+    for path in PAGE_PATHS {
+        // 路径均为可自定义的页面，不会失败
+        let _ = match pages.remove(path) {
+            Some(content) => AppConfig::update_page_content(path, content),
+            None => AppConfig::reset_page_content(path),
+        };
+    }
+    apply_updates(config);
+    ModelAliases::replace_all(model_aliases);
+    ModelPolicies::replace_all(model_policies);
+    QuotaSnapshots::replace_all(quotas.into_iter().map(quota_snapshot).collect());
+
+    // 任一文件保存失败时恢复导入前的状态并重新保存，避免内存与文件只导入了一部分
+    if let Err(e) = save_import().await {
+        let (config, model_aliases, model_policies, quotas) = previous;
+        AppConfig::restore(config);
+        ModelAliases::replace_all(model_aliases);
+        ModelPolicies::replace_all(model_policies);
+        QuotaSnapshots::replace_all(quotas);
+        if let Err(e) = save_import().await {
+            tracing::error!("回滚导入的配置失败: {}", e);
+        }
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                status: ApiStatus::Failed,
+                code: Some(500),
+                error: Some(format!("保存失败，导入已回滚: {}", e)),
+                message: None,
+            }),
+        ));
+    }
+
+    let after = import_snapshot();
+    AuditLogs::record(&actor, "config.import", before, after).await;
+
+    Ok(Json(NormalResponse {
+        status: ApiStatus::Success,
+        data: None,
+        message: Some("配置已导入".to_string()),
+    }))
+}
+
+async fn save_import() -> Result<(), Box<dyn std::error::Error>> {
+    AppConfig::save_config()?;
+    ModelAliases::save().await?;
+    ModelPolicies::save().await?;
+    QuotaSnapshots::save().await
+}
+
+// 请求头中的 AUTH_TOKEN 或网页会话均可
+fn authorize(headers: &HeaderMap) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    let has_auth_header = headers.contains_key(AUTHORIZATION);
+    if !has_auth_header && session::expires_at(headers).is_none() {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse {
+                status: ApiStatus::Failed,
+                code: Some(401),
+                error: Some("未提供认证令牌".to_string()),
+                message: None,
+            }),
+        ));
+    }
+
+    if !session::is_admin(headers) {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse {
+                status: ApiStatus::Failed,
+                code: Some(401),
+                error: Some("无效的认证令牌".to_string()),
+                message: None,
+            }),
+        ));
+    }
+    Ok(())
+}
+
+// 校验 IP 规则、跨域来源与请求头
+fn validate_update(request: &ConfigUpdateRequest) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    if let Some(rule) = [&request.ip_allowlist, &request.ip_denylist]
+        .into_iter()
+        .flatten()
+        .flatten()
+        .find(|rule| !is_valid_ip_rule(rule))
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                status: ApiStatus::Failed,
+                code: Some(400),
+                error: Some(format!("无效的 IP 规则: {}", rule)),
+                message: None,
+            }),
+        ));
+    }
+    if let Some(rule) = [&request.cors_allowed_origins, &request.cors_admin_origins]
+        .into_iter()
+        .flatten()
+        .flatten()
+        .find(|rule| !is_valid_origin_rule(rule))
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                status: ApiStatus::Failed,
+                code: Some(400),
+                error: Some(format!("无效的跨域来源: {}", rule)),
+                message: None,
+            }),
+        ));
+    }
+    if let Some(header) = request
+        .cors_allowed_headers
+        .iter()
+        .flatten()
+        .find(|header| !is_valid_header_rule(header))
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                status: ApiStatus::Failed,
+                code: Some(400),
+                error: Some(format!("无效的请求头: {}", header)),
+                message: None,
+            }),
+        ));
+    }
+    Ok(())
+}
+
+fn apply_updates(request: ConfigUpdateRequest) {
+    handle_updates!(request,
+        vision_ability => AppConfig::update_vision_ability,
+        enable_slow_pool => AppConfig::update_slow_pool,
+        enable_all_claude => AppConfig::update_allow_claude,
+        usage_check_models => AppConfig::update_usage_check,
+        enable_dynamic_key => AppConfig::update_dynamic_key,
+        share_token => AppConfig::update_share_token,
+        proxies => AppConfig::update_proxies,
+        include_web_references => AppConfig::update_web_refs,
+        token_warmup => AppConfig::update_token_warmup,
+        token_warmup_required => AppConfig::update_token_warmup_required,
+        ip_allowlist => AppConfig::update_ip_allowlist,
+        ip_denylist => AppConfig::update_ip_denylist,
+        cors_allowed_origins => AppConfig::update_cors_allowed_origins,
+        cors_admin_origins => AppConfig::update_cors_admin_origins,
+        cors_allowed_headers => AppConfig::update_cors_allowed_headers,
+        cors_allow_credentials => AppConfig::update_cors_allow_credentials,
+    );
+}
+
+fn current_config(path: &str) -> ConfigData {
+    ConfigData {
+        page_content: AppConfig::get_page_content(path),
+        vision_ability: AppConfig::get_vision_ability(),
+        enable_slow_pool: AppConfig::get_slow_pool(),
+        enable_all_claude: AppConfig::get_allow_claude(),
+        usage_check_models: AppConfig::get_usage_check(),
+        enable_dynamic_key: AppConfig::get_dynamic_key(),
+        share_token: AppConfig::get_share_token(),
+        proxies: AppConfig::get_proxies(),
+        include_web_references: AppConfig::get_web_refs(),
+        token_warmup: AppConfig::get_token_warmup(),
+        token_warmup_required: AppConfig::get_token_warmup_required(),
+        ip_allowlist: AppConfig::get_ip_allowlist(),
+        ip_denylist: AppConfig::get_ip_denylist(),
+        cors_allowed_origins: AppConfig::get_cors_allowed_origins(),
+        cors_admin_origins: AppConfig::get_cors_admin_origins(),
+        cors_allowed_headers: AppConfig::get_cors_allowed_headers(),
+        cors_allow_credentials: AppConfig::get_cors_allow_credentials(),
+    }
+}
+
+// 审计快照中隐藏共享令牌
+fn audit_snapshot(path: &str) -> Option<String> {
+    let mut config = current_config(path);
+    if !config.share_token.is_empty() {
+        config.share_token = "***".to_string();
+    }
+    AuditLogs::snapshot(&config)
+}
+
+fn config_document() -> ConfigDocument {
+    ConfigDocument {
+        config: current_config(EMPTY_STRING),
+        pages: PAGE_PATHS
+            .into_iter()
+            .filter_map(|path| Some((path, AppConfig::get_page_content(path)?)))
+            .filter(|(_, content)| !matches!(content, PageContent::Default))
+            .collect(),
+        model_aliases: ModelAliases::list(),
+        model_policies: ModelPolicies::list(),
+        quotas: QuotaSnapshots::list()
+            .into_iter()
+            .map(|snapshot| QuotaEntry {
+                token: snapshot.token,
+                synced_at: snapshot.synced_at,
+                fast_requests: snapshot.fast_requests,
+                max_fast_requests: snapshot.max_fast_requests,
+            })
+            .collect(),
+    }
+}
+
+fn quota_snapshot(entry: QuotaEntry) -> QuotaSnapshot {
+    QuotaSnapshot {
+        token: entry.token,
+        synced_at: entry.synced_at,
+        fast_requests: entry.fast_requests,
+        max_fast_requests: entry.max_fast_requests,
+    }
+}
+
+// 导入会整体替换页面、模型别名、模型策略与额度快照，快照需覆盖完整文档，并隐藏共享令牌与 token
+fn import_snapshot() -> Option<String> {
+    let mut document = config_document();
+    if !document.config.share_token.is_empty() {
+        document.config.share_token = "***".to_string();
+    }
+    for quota in &mut document.quotas {
+        quota.token = "***".to_string();
+    }
+    AuditLogs::snapshot(&document)
+}
+
+// 修改后立即保存，重启后仍然生效
+fn save_config() -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    AppConfig::save_config().map_err(save_failed)
+}
+
+fn save_failed(e: Box<dyn std::error::Error>) -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(ErrorResponse {
+            status: ApiStatus::Failed,
+            code: Some(500),
+            error: Some(format!("配置已生效，但保存失败: {}", e)),
+            message: None,
+        }),
+    )
+}
@@ -24,30 +24,97 @@ def_pub_const!(ROUTE_GET_TIMESTAMP_HEADER, "/get-tsheader");
 def_pub_const!(ROUTE_USER_INFO_PATH, "/userinfo");
 def_pub_const!(ROUTE_API_PATH, "/api");
 def_pub_const!(ROUTE_LOGS_PATH, "/logs");
+def_pub_const!(ROUTE_LOGS_CLEANUP_PATH, "/logs/cleanup");
+def_pub_const!(ROUTE_LOGS_STREAM_PATH, "/logs/stream");
 def_pub_const!(ROUTE_CONFIG_PATH, "/config");
+def_pub_const!(ROUTE_CONFIG_EXPORT_PATH, "/config/export");
+def_pub_const!(ROUTE_CONFIG_IMPORT_PATH, "/config/import");
 def_pub_const!(ROUTE_TOKENS_PATH, "/tokens");
 def_pub_const!(ROUTE_TOKENS_GET_PATH, "/tokens/get");
 def_pub_const!(ROUTE_TOKENS_RELOAD_PATH, "/tokens/reload");
 def_pub_const!(ROUTE_TOKENS_UPDATE_PATH, "/tokens/update");
 def_pub_const!(ROUTE_TOKENS_ADD_PATH, "/tokens/add");
 def_pub_const!(ROUTE_TOKENS_DELETE_PATH, "/tokens/delete");
+def_pub_const!(ROUTE_TOKENS_IMPORT_PATH, "/tokens/import");
+def_pub_const!(ROUTE_TOKENS_EXPORT_PATH, "/tokens/export");
+def_pub_const!(ROUTE_TOKENS_META_PATH, "/tokens/meta");
+def_pub_const!(ROUTE_TOKENS_BLACKLIST_PATH, "/tokens/blacklist");
+def_pub_const!(ROUTE_TOKENS_DELETED_PATH, "/tokens/deleted");
+def_pub_const!(ROUTE_TOKEN_PATH, "/api/tokens/by-alias/{alias}");
+def_pub_const!(ROUTE_TOKEN_RESTORE_PATH, "/api/tokens/by-alias/{alias}/restore");
+def_pub_const!(ROUTE_TOKENS_VALIDATE_PATH, "/api/tokens/validate");
 def_pub_const!(ROUTE_ENV_EXAMPLE_PATH, "/env-example");
-def_pub_const!(ROUTE_STATIC_PATH, "/static/{path}");
+def_pub_const!(ROUTE_STATIC_PATH, "/static/{*path}");
 def_pub_const!(ROUTE_SHARED_STYLES_PATH, "/static/shared-styles.css");
 def_pub_const!(ROUTE_SHARED_JS_PATH, "/static/shared.js");
 def_pub_const!(ROUTE_ABOUT_PATH, "/about");
 def_pub_const!(ROUTE_README_PATH, "/readme");
 def_pub_const!(ROUTE_BASIC_CALIBRATION_PATH, "/basic-calibration");
 def_pub_const!(ROUTE_BUILD_KEY_PATH, "/build-key");
+def_pub_const!(ROUTE_MODEL_POLICIES_PATH, "/api/admin/model-policies");
+def_pub_const!(ROUTE_MODEL_PRICES_PATH, "/api/admin/model-prices");
+def_pub_const!(ROUTE_SPEND_PATH, "/api/admin/spend");
+def_pub_const!(ROUTE_API_KEYS_PATH, "/api/admin/api-keys");
+def_pub_const!(ROUTE_RUNTIME_PATH, "/api/admin/runtime");
+def_pub_const!(ROUTE_MODEL_ALIASES_PATH, "/api/admin/models");
+def_pub_const!(ROUTE_AUDIT_LOGS_PATH, "/api/admin/audit");
+def_pub_const!(ROUTE_PROMPT_TEMPLATES_PATH, "/api/admin/templates");
+def_pub_const!(ROUTE_MODERATION_PATH, "/api/admin/moderation");
+def_pub_const!(ROUTE_REPORTS_PATH, "/api/admin/reports");
+def_pub_const!(ROUTE_TOKEN_STATS_PATH, "/api/stats/tokens");
+def_pub_const!(ROUTE_ME_PATH, "/api/me");
+def_pub_const!(ROUTE_DAILY_STATS_PATH, "/api/stats/daily");
+def_pub_const!(ROUTE_LATENCY_STATS_PATH, "/api/stats/latency");
+def_pub_const!(ROUTE_CONVERSATIONS_PATH, "/v1/conversations");
+// 位于 ROUTE_PREFIX 之下的对话路由，服务端与客户端共用
+def_pub_const!(CHAT_COMPLETIONS_PATH, "/v1/chat/completions");
+def_pub_const!(CHAT_CANCEL_PATH, "/v1/chat/cancel");
+def_pub_const!(ROUTE_QUALITY_PATH, "/api/admin/quality");
+def_pub_const!(ROUTE_FAULTS_PATH, "/api/admin/faults");
+def_pub_const!(ROUTE_CHECKSUMS_PATH, "/api/admin/checksums");
+def_pub_const!(ROUTE_SESSION_PATH, "/api/session");
+def_pub_const!(ROUTE_PAYLOADS_PATH, "/api/admin/payloads/{id}");
+def_pub_const!(ROUTE_TENANTS_PATH, "/api/admin/tenants");
+def_pub_const!(ROUTE_SYSTEM_PROMPTS_PATH, "/api/admin/system-prompts");
+def_pub_const!(
+    ROUTE_MODEL_CAPABILITIES_PATH,
+    "/api/admin/model-capabilities"
+);
+def_pub_const!(ROUTE_AZURE_DEPLOYMENTS_PATH, "/api/admin/azure-deployments");
+def_pub_const!(ROUTE_INVITE_CODES_PATH, "/api/admin/invite-codes");
+def_pub_const!(ROUTE_REGISTER_PATH, "/api/register");
 
 def_pub_const!(DEFAULT_TOKEN_LIST_FILE_NAME, ".tokens");
+def_pub_const!(DEFAULT_TOKEN_BLACKLIST_FILE_NAME, ".tokens_blacklist");
+def_pub_const!(DEFAULT_TOKEN_TRASH_FILE_NAME, ".tokens_deleted");
+
+// 与动态 key 的 sk- 前缀区分
+def_pub_const!(API_KEY_PREFIX, "ak-");
+def_pub_const!(API_KEY_SCOPE_CHAT, "chat");
+def_pub_const!(INVITE_CODE_PREFIX, "inv-");
 
 def_pub_const!(STATUS_PENDING, "pending");
 def_pub_const!(STATUS_SUCCESS, "success");
 def_pub_const!(STATUS_FAILED, "failed");
+def_pub_const!(STATUS_CANCELLED, "cancelled");
 
 def_pub_const!(HEADER_NAME_GHOST_MODE, "x-ghost-mode");
 
+def_pub_const!(SESSION_COOKIE_NAME, "cursor_api_session");
+def_pub_const!(HEADER_NAME_IGNORED_PARAMS, "x-ignored-params");
+def_pub_const!(HEADER_NAME_NON_STREAM_KEEPALIVE, "x-non-stream-keepalive");
+def_pub_const!(HEADER_NAME_STREAM_PRELUDE, "x-stream-prelude");
+def_pub_const!(HEADER_NAME_FAULT_INJECT, "x-fault-inject");
+def_pub_const!(HEADER_NAME_MODEL_REDIRECTED, "x-model-redirected");
+def_pub_const!(HEADER_NAME_TOKEN_ALIAS, "x-token-alias");
+def_pub_const!(HEADER_NAME_TOKEN_TAG, "x-token-tag");
+def_pub_const!(HEADER_NAME_PUBLIC_POOL, "x-public-pool");
+def_pub_const!(HEADER_NAME_REQUEST_ID, "x-request-id");
+// Azure OpenAI 风格的认证请求头
+def_pub_const!(HEADER_NAME_API_KEY, "api-key");
+def_pub_const!(HEADER_NAME_IDEMPOTENCY_KEY, "idempotency-key");
+def_pub_const!(HEADER_NAME_IDEMPOTENT_REPLAYED, "idempotent-replayed");
+
 def_pub_const!(TRUE, "true");
 def_pub_const!(FALSE, "false");
 
@@ -63,6 +130,11 @@ def_pub_const!(
     CONTENT_TYPE_TEXT_JS_WITH_UTF8,
     "text/javascript;charset=utf-8"
 );
+def_pub_const!(CONTENT_TYPE_TEXT_CSV_WITH_UTF8, "text/csv;charset=utf-8");
+def_pub_const!(
+    CONTENT_TYPE_TEXT_TOML_WITH_UTF8,
+    "application/toml;charset=utf-8"
+);
 
 def_pub_const!(AUTHORIZATION_BEARER_PREFIX, "Bearer ");
 
@@ -77,6 +149,8 @@ def_pub_const!(OBJECT_CHAT_COMPLETION_CHUNK, "chat.completion.chunk");
 // def_pub_const!(CURSOR_API2_GET_USER_INFO, "GetUserInfo");
 
 def_pub_const!(FINISH_REASON_STOP, "stop");
+def_pub_const!(FINISH_REASON_CONTENT_FILTER, "content_filter");
+def_pub_const!(FINISH_REASON_ERROR, "error");
 
 def_pub_const!(ERR_INVALID_PATH, "无效的路径");
 
@@ -0,0 +1,109 @@
+use super::model::AppConfig;
+use axum::{
+    extract::Request,
+    http::{
+        header::{
+            ACCESS_CONTROL_ALLOW_CREDENTIALS, ACCESS_CONTROL_ALLOW_HEADERS,
+            ACCESS_CONTROL_ALLOW_METHODS, ACCESS_CONTROL_ALLOW_ORIGIN,
+            ACCESS_CONTROL_EXPOSE_HEADERS, ACCESS_CONTROL_REQUEST_HEADERS,
+            ACCESS_CONTROL_REQUEST_METHOD, ORIGIN, VARY,
+        },
+        HeaderMap, HeaderName, HeaderValue, Method, StatusCode,
+    },
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+// 来源为 * 或不带路径的 http(s)://host[:port]
+pub fn is_valid_origin_rule(rule: &str) -> bool {
+    if rule == "*" {
+        return true;
+    }
+    let Some(host) = rule
+        .strip_prefix("http://")
+        .or_else(|| rule.strip_prefix("https://"))
+    else {
+        return false;
+    };
+    !host.is_empty() && !host.contains('/') && HeaderValue::from_str(rule).is_ok()
+}
+
+pub fn is_valid_header_rule(rule: &str) -> bool {
+    rule == "*" || HeaderName::from_bytes(rule.as_bytes()).is_ok()
+}
+
+// 路径中包含 v1 段的为对话等 API 接口，其余为管理接口与页面
+fn is_api_path(path: &str) -> bool {
+    path.split('/').any(|segment| segment == "v1")
+}
+
+// 对话接口使用 CORS_ALLOWED_ORIGINS，其余接口使用 CORS_ADMIN_ORIGINS，不允许的来源不返回任何 CORS 响应头
+pub async fn apply(request: Request, next: Next) -> Response {
+    let Some(origin) = request.headers().get(ORIGIN).cloned() else {
+        return next.run(request).await;
+    };
+
+    let api = is_api_path(request.uri().path());
+    let allowed = origin
+        .to_str()
+        .is_ok_and(|origin| AppConfig::is_origin_allowed(origin, api));
+    if !allowed {
+        tracing::debug!("不允许来自 {:?} 的跨域请求", origin);
+    }
+
+    let credentials = AppConfig::get_cors_allow_credentials();
+    let preflight = request.method() == Method::OPTIONS
+        && request
+            .headers()
+            .contains_key(ACCESS_CONTROL_REQUEST_METHOD);
+
+    if preflight {
+        let mut response = StatusCode::NO_CONTENT.into_response();
+        let request_headers = request.headers();
+        let headers = response.headers_mut();
+        headers.append(VARY, HeaderValue::from_static("Origin"));
+        if allowed {
+            allow_origin(headers, origin, credentials);
+            if let Some(method) = request_headers.get(ACCESS_CONTROL_REQUEST_METHOD) {
+                headers.insert(ACCESS_CONTROL_ALLOW_METHODS, method.clone());
+            }
+            if let Some(value) = allow_headers(request_headers.get(ACCESS_CONTROL_REQUEST_HEADERS))
+            {
+                headers.insert(ACCESS_CONTROL_ALLOW_HEADERS, value);
+            }
+        }
+        return response;
+    }
+
+    let mut response = next.run(request).await;
+    let headers = response.headers_mut();
+    headers.append(VARY, HeaderValue::from_static("Origin"));
+    if allowed {
+        allow_origin(headers, origin, credentials);
+        // 携带凭据时浏览器不把 * 视为通配符
+        if !credentials {
+            headers.insert(ACCESS_CONTROL_EXPOSE_HEADERS, HeaderValue::from_static("*"));
+        }
+    }
+    response
+}
+
+// 总是回显请求的来源，允许携带凭据时不能使用 *
+fn allow_origin(headers: &mut HeaderMap, origin: HeaderValue, credentials: bool) {
+    headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+    if credentials {
+        headers.insert(
+            ACCESS_CONTROL_ALLOW_CREDENTIALS,
+            HeaderValue::from_static("true"),
+        );
+    }
+}
+
+// 允许任意请求头时回显预检请求中的请求头
+fn allow_headers(requested: Option<&HeaderValue>) -> Option<HeaderValue> {
+    let allowed = AppConfig::get_cors_allowed_headers();
+    if allowed.is_empty() || allowed.iter().any(|header| header == "*") {
+        return requested.cloned();
+    }
+    HeaderValue::from_str(&allowed.join(", ")).ok()
+}
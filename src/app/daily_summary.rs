@@ -0,0 +1,107 @@
+use super::{
+    model::{AppState, DailySummaries, DailySummary, LogStatus, UsageEntry},
+    report::until_next,
+};
+use crate::common::utils::extract_user_id;
+use chrono::{Local, NaiveDate, NaiveTime};
+use std::{collections::HashMap, sync::Arc};
+use tokio::sync::Mutex;
+
+pub const DATE_FORMAT: &str = "%Y-%m-%d";
+
+// 启动时补上前一天缺失的汇总，之后每天本地零点汇总前一天的请求日志
+pub fn init(state: Arc<Mutex<AppState>>) {
+    if !DailySummaries::is_enabled() {
+        return;
+    }
+
+    tokio::spawn(async move {
+        if let Some(yesterday) = Local::now().date_naive().pred_opt() {
+            if !DailySummaries::contains(&yesterday.format(DATE_FORMAT).to_string()) {
+                generate(&state, yesterday, false).await;
+            }
+        }
+        loop {
+            tokio::time::sleep(until_next(NaiveTime::MIN)).await;
+            if let Some(yesterday) = Local::now().date_naive().pred_opt() {
+                generate(&state, yesterday, true).await;
+            }
+        }
+    });
+}
+
+// 汇总并保存 date 当天的请求日志，keep_empty 为 false 时当天没有日志则不保存
+async fn generate(state: &Mutex<AppState>, date: NaiveDate, keep_empty: bool) {
+    let summary = summarize(state, date).await;
+    if summary.requests == 0 && !keep_empty {
+        return;
+    }
+    DailySummaries::insert(summary);
+    if let Err(e) = DailySummaries::save().await {
+        tracing::warn!("保存每日用量汇总失败: {}", e);
+    }
+}
+
+// 统计内存中 date 当天（本地时间）的请求日志
+pub async fn summarize(state: &Mutex<AppState>, date: NaiveDate) -> DailySummary {
+    let mut summary = DailySummary {
+        date: date.format(DATE_FORMAT).to_string(),
+        generated_at: Local::now().timestamp(),
+        requests: 0,
+        failures: 0,
+        prompt_tokens: 0,
+        completion_tokens: 0,
+        cost: 0.0,
+        models: Vec::new(),
+        users: Vec::new(),
+    };
+
+    let mut models: HashMap<String, UsageEntry> = HashMap::new();
+    let mut users: HashMap<String, UsageEntry> = HashMap::new();
+    {
+        let state = state.lock().await;
+        for log in state
+            .request_logs
+            .iter()
+            .filter(|log| log.timestamp.date_naive() == date)
+        {
+            let failed = matches!(log.status, LogStatus::Failed);
+            let (prompt_tokens, completion_tokens, cost) =
+                log.cost.as_ref().map_or((0, 0, 0.0), |cost| {
+                    (cost.prompt_tokens, cost.completion_tokens, cost.cost)
+                });
+            summary.requests += 1;
+            summary.failures += failed as u64;
+            summary.prompt_tokens += prompt_tokens as u64;
+            summary.completion_tokens += completion_tokens as u64;
+            summary.cost += cost;
+
+            let user = log
+                .api_key
+                .clone()
+                .or_else(|| extract_user_id(&log.token_info.token))
+                .unwrap_or_else(|| "unknown".to_string());
+            for (entries, name) in [(&mut models, log.model.clone()), (&mut users, user)] {
+                let entry = entries.entry(name.clone()).or_insert_with(|| UsageEntry {
+                    name,
+                    requests: 0,
+                    failures: 0,
+                    cost: 0.0,
+                });
+                entry.requests += 1;
+                entry.failures += failed as u64;
+                entry.cost += cost;
+            }
+        }
+    }
+
+    summary.models = sorted(models);
+    summary.users = sorted(users);
+    summary
+}
+
+fn sorted(entries: HashMap<String, UsageEntry>) -> Vec<UsageEntry> {
+    let mut entries: Vec<_> = entries.into_values().collect();
+    entries.sort_unstable_by(|a, b| b.requests.cmp(&a.requests).then(a.name.cmp(&b.name)));
+    entries
+}
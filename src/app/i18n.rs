@@ -0,0 +1,219 @@
+mod page;
+pub use page::translate_page;
+
+use super::lazy::DEFAULT_LOCALE;
+use axum::body::HttpBody as _;
+use axum::{
+    extract::Request,
+    http::{
+        header::{ACCEPT_LANGUAGE, CONTENT_LANGUAGE, VARY},
+        HeaderMap, HeaderValue,
+    },
+    middleware::Next,
+    response::Response,
+};
+use futures::StreamExt as _;
+use std::future::Future;
+use tokio::task::futures::TaskLocalFuture;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Locale {
+    ZhCn,
+    EnUs,
+}
+
+tokio::task_local! {
+    static LOCALE: Locale;
+}
+
+impl Locale {
+    // 只比较主语言，zh-TW、en-GB 等同样匹配
+    pub fn parse(tag: &str) -> Option<Self> {
+        let primary = tag.trim().split(['-', '_']).next()?;
+        if primary.eq_ignore_ascii_case("zh") {
+            Some(Self::ZhCn)
+        } else if primary.eq_ignore_ascii_case("en") {
+            Some(Self::EnUs)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::ZhCn => "zh-CN",
+            Self::EnUs => "en-US",
+        }
+    }
+
+    // 当前请求的语言，不在请求中时使用 DEFAULT_LOCALE
+    pub fn current() -> Self {
+        LOCALE.try_with(|locale| *locale).unwrap_or(*DEFAULT_LOCALE)
+    }
+
+    // 在请求之外继续执行的任务（spawn、WebSocket 等）需要显式沿用请求的语言
+    pub fn scope<F: Future>(self, future: F) -> TaskLocalFuture<Locale, F> {
+        LOCALE.scope(self, future)
+    }
+
+    // 按 q 值从高到低取第一个支持的语言
+    fn from_accept_language(headers: &HeaderMap) -> Option<Self> {
+        let header = headers.get(ACCEPT_LANGUAGE)?.to_str().ok()?;
+        let mut candidates: Vec<(f32, Self)> = header
+            .split(',')
+            .filter_map(|item| {
+                let mut parts = item.split(';');
+                let locale = Self::parse(parts.next()?)?;
+                let quality = parts
+                    .find_map(|param| param.trim().strip_prefix("q="))
+                    .map_or(Some(1.0), |q| q.trim().parse::<f32>().ok())?;
+                (quality > 0.0).then_some((quality, locale))
+            })
+            .collect();
+        candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+        candidates.first().map(|(_, locale)| *locale)
+    }
+
+    fn from_query(query: Option<&str>) -> Option<Self> {
+        query?
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("lang="))
+            .and_then(Self::parse)
+    }
+}
+
+// 请求的语言依次取查询参数 lang、Accept-Language，都没有时使用 DEFAULT_LOCALE
+// 响应体在处理函数返回后才被读取，流式响应同样需要在该语言下生成
+pub async fn apply(request: Request, next: Next) -> Response {
+    let locale = Locale::from_query(request.uri().query())
+        .or_else(|| Locale::from_accept_language(request.headers()))
+        .unwrap_or(*DEFAULT_LOCALE);
+
+    let response = locale.scope(next.run(request)).await;
+    let (mut parts, body) = response.into_parts();
+    parts
+        .headers
+        .insert(CONTENT_LANGUAGE, HeaderValue::from_static(locale.as_str()));
+    parts
+        .headers
+        .append(VARY, HeaderValue::from_static("Accept-Language"));
+
+    if body.size_hint().exact().is_some() {
+        return Response::from_parts(parts, body);
+    }
+    let mut stream = body.into_data_stream();
+    let stream = futures::stream::poll_fn(move |cx| {
+        LOCALE.sync_scope(locale, || stream.poll_next_unpin(cx))
+    });
+    Response::from_parts(parts, axum::body::Body::from_stream(stream))
+}
+
+// 返回给客户端的错误信息，{} 为参数的位置
+#[derive(Clone, Copy)]
+pub enum Text {
+    ModelNotSupported,
+    ModelNotAllowed,
+    EmptyMessages,
+    NoTokens,
+    ServerBusy,
+    RequestFailed,
+    InvalidImage,
+    Unauthorized,
+    IpNotAllowed,
+    TokenAliasNotFound,
+    TokenTagNotFound,
+    TenantNotFound,
+    InvalidTemplate,
+    ContentBlocked,
+    PublicPoolQuotaExceeded,
+    RequestCancelled,
+    UpstreamTimeout,
+    InvalidChoices,
+    LogprobsUnsupported,
+    InvalidJsonOutput,
+    IpBlocked,
+    ImagesUnsupported,
+    // 上游响应流相关
+    StreamDataTooShort,
+    StreamEmpty,
+    StreamTruncated,
+    EmptyResponse,
+    ChunkReadFailed,
+}
+
+impl Text {
+    pub fn get(self) -> &'static str {
+        match Locale::current() {
+            Locale::ZhCn => zh_cn(self),
+            Locale::EnUs => en_us(self),
+        }
+    }
+
+    pub fn format(self, arg: impl std::fmt::Display) -> String {
+        self.get().replacen("{}", &arg.to_string(), 1)
+    }
+}
+
+fn en_us(text: Text) -> &'static str {
+    match text {
+        Text::ModelNotSupported => "Model '{}' is not supported",
+        Text::ModelNotAllowed => "Model '{}' is not allowed for this user",
+        Text::EmptyMessages => "Message array cannot be empty",
+        Text::NoTokens => "No available tokens",
+        Text::ServerBusy => "Too many concurrent requests",
+        Text::RequestFailed => "Request failed: {}",
+        Text::InvalidImage => "Invalid image: {}",
+        Text::Unauthorized => "Invalid authorization token",
+        Text::IpNotAllowed => "Requests from '{}' are not allowed for this key",
+        Text::TokenAliasNotFound => "No available token with alias '{}'",
+        Text::TokenTagNotFound => "No available token with tag '{}'",
+        Text::TenantNotFound => "Tenant '{}' not found",
+        Text::InvalidTemplate => "Invalid template: {}",
+        Text::ContentBlocked => "Content blocked: {}",
+        Text::PublicPoolQuotaExceeded => "Daily public pool quota exceeded",
+        Text::RequestCancelled => "Request was cancelled",
+        Text::UpstreamTimeout => "Upstream request timed out",
+        Text::InvalidChoices => "Invalid n: {}",
+        Text::LogprobsUnsupported => "logprobs is not supported by the upstream",
+        Text::InvalidJsonOutput => "Model output is not valid JSON: {}",
+        Text::IpBlocked => "Requests from '{}' are blocked",
+        Text::ImagesUnsupported => "Image generation is not configured on this server",
+        Text::StreamDataTooShort => "Upstream response data is too short",
+        Text::StreamEmpty => "Empty stream response",
+        Text::StreamTruncated => "Upstream response ended before completion",
+        Text::EmptyResponse => "Empty response received",
+        Text::ChunkReadFailed => "Failed to read response chunk: {}",
+    }
+}
+
+fn zh_cn(text: Text) -> &'static str {
+    match text {
+        Text::ModelNotSupported => "不支持模型 '{}'",
+        Text::ModelNotAllowed => "当前用户不允许使用模型 '{}'",
+        Text::EmptyMessages => "消息列表不能为空",
+        Text::NoTokens => "没有可用的 token",
+        Text::ServerBusy => "并发请求过多",
+        Text::RequestFailed => "请求失败: {}",
+        Text::InvalidImage => "无效的图片: {}",
+        Text::Unauthorized => "无效的认证令牌",
+        Text::IpNotAllowed => "该 key 不允许来自 '{}' 的请求",
+        Text::TokenAliasNotFound => "没有别名为 '{}' 的可用 token",
+        Text::TokenTagNotFound => "没有标签为 '{}' 的可用 token",
+        Text::TenantNotFound => "租户 '{}' 不存在",
+        Text::InvalidTemplate => "无效的模板: {}",
+        Text::ContentBlocked => "内容已被拦截: {}",
+        Text::PublicPoolQuotaExceeded => "公共号池今日额度已用完",
+        Text::RequestCancelled => "请求已取消",
+        Text::UpstreamTimeout => "上游请求超时",
+        Text::InvalidChoices => "无效的 n: {}",
+        Text::LogprobsUnsupported => "上游不支持 logprobs",
+        Text::InvalidJsonOutput => "模型输出不是有效的 JSON: {}",
+        Text::IpBlocked => "来自 '{}' 的请求已被禁止",
+        Text::ImagesUnsupported => "服务未配置图片生成",
+        Text::StreamDataTooShort => "上游响应数据过短",
+        Text::StreamEmpty => "上游返回了空的响应流",
+        Text::StreamTruncated => "上游响应在完成前中断",
+        Text::EmptyResponse => "上游返回了空的响应",
+        Text::ChunkReadFailed => "读取响应数据失败: {}",
+    }
+}
@@ -0,0 +1,259 @@
+use super::Locale;
+use std::{borrow::Cow, collections::HashMap, sync::LazyLock};
+
+// 内置页面以中文编写，其他语言按原文查表替换标签内的文字与 placeholder、title 属性，脚本中的提示不翻译
+const EN_US: &[(&str, &str)] = &[
+    // 通用
+    ("复制", "Copy"),
+    ("取消", "Cancel"),
+    ("删除", "Delete"),
+    ("默认", "Default"),
+    ("启用", "Enabled"),
+    ("禁用", "Disabled"),
+    ("允许", "Allow"),
+    ("禁止", "Deny"),
+    ("自定义", "Custom"),
+    ("所有", "All"),
+    ("模型", "Model"),
+    ("状态", "Status"),
+    ("时间", "Time"),
+    ("操作", "Actions"),
+    ("邮箱", "Email"),
+    ("会员类型", "Membership"),
+    ("试用剩余", "Trial remaining"),
+    ("模型列表", "Model list"),
+    ("请输入 AUTH Token", "Enter AUTH Token"),
+    ("输入 AUTH_TOKEN", "Enter AUTH_TOKEN"),
+    ("认证令牌:", "Auth token:"),
+    // api.html
+    ("API 管理", "API Management"),
+    ("校准 Token", "Calibrate Token"),
+    ("获取用户信息", "Get user info"),
+    ("获取模型列表", "Get model list"),
+    ("添加自定义后缀", "Add custom suffix"),
+    ("用户信息", "User info"),
+    // build_key.html
+    ("Key 构建", "Key Builder"),
+    ("服务认证令牌:", "Service auth token:"),
+    ("数据认证令牌:", "Data auth token:"),
+    ("图片处理能力:", "Image handling:"),
+    ("跟随全局", "Follow global"),
+    ("慢速池:", "Slow pool:"),
+    ("使用量检查模型规则:", "Usage check models:"),
+    ("包含网络引用:", "Include web references:"),
+    ("构建 Key", "Build Key"),
+    ("清空表单", "Clear form"),
+    ("输入服务认证令牌", "Enter service auth token"),
+    ("输入数据认证令牌", "Enter data auth token"),
+    // config.html
+    ("配置管理", "Configuration"),
+    ("路径:", "Path:"),
+    ("根路径 (/)", "Root (/)"),
+    ("日志页面 (/logs)", "Logs page (/logs)"),
+    ("配置页面 (/config)", "Config page (/config)"),
+    ("Token 管理页面 (/tokens)", "Token page (/tokens)"),
+    (
+        "共享样式 (/static/shared-styles.css)",
+        "Shared styles (/static/shared-styles.css)",
+    ),
+    (
+        "共享脚本 (/static/shared.js)",
+        "Shared script (/static/shared.js)",
+    ),
+    ("关于页面 (/about)", "About page (/about)"),
+    ("ReadMe文档 (/readme)", "ReadMe (/readme)"),
+    ("api调用 (/api)", "API calls (/api)"),
+    (
+        "构建动态 Key (/build-key)",
+        "Build dynamic Key (/build-key)",
+    ),
+    ("内容类型:", "Content type:"),
+    ("纯文本", "Plain text"),
+    ("内容:", "Content:"),
+    ("保持不变", "Unchanged"),
+    ("仅 Base64", "Base64 only"),
+    ("允许所有Claude模型:", "Allow all Claude models:"),
+    ("自定义列表", "Custom list"),
+    ("是否允许动态配置Key:", "Allow dynamic Key config:"),
+    ("代理设置:", "Proxy:"),
+    ("不使用代理", "No proxy"),
+    ("使用系统代理", "System proxy"),
+    ("自定义代理列表", "Custom proxy list"),
+    ("添加Token时预热:", "Warm up tokens when adding:"),
+    ("预热失败时拒绝添加:", "Reject tokens that fail warmup:"),
+    (
+        "IP 允许列表(逗号分隔的 IP 或 CIDR，空表示不限制):",
+        "IP allow list (comma-separated IPs or CIDRs, empty for no limit):",
+    ),
+    (
+        "IP 拒绝列表(逗号分隔的 IP 或 CIDR):",
+        "IP deny list (comma-separated IPs or CIDRs):",
+    ),
+    (
+        "对话接口跨域来源(逗号分隔，* 表示任意来源):",
+        "CORS origins for chat API (comma-separated, * for any):",
+    ),
+    (
+        "管理接口跨域来源(逗号分隔，空表示不允许跨域):",
+        "CORS origins for admin API (comma-separated, empty to disallow):",
+    ),
+    (
+        "跨域允许的请求头(逗号分隔，* 表示任意请求头):",
+        "CORS allowed headers (comma-separated, * for any):",
+    ),
+    ("跨域请求携带凭据:", "CORS credentials:"),
+    ("共享令牌(空表示禁用):", "Shared token (empty to disable):"),
+    ("获取配置", "Get config"),
+    ("更新配置", "Update config"),
+    ("重置配置", "Reset config"),
+    ("模型列表，以逗号分隔", "Models, comma-separated"),
+    (
+        "代理地址列表，以逗号分隔 (例如: http://127.0.0.1:7890)",
+        "Proxy URLs, comma-separated (e.g. http://127.0.0.1:7890)",
+    ),
+    // logs.html
+    ("请求日志查看", "Request Logs"),
+    ("刷新日志", "Refresh"),
+    ("自动刷新 (60秒)", "Auto refresh (60s)"),
+    ("实时更新", "Live updates"),
+    ("总请求数", "Total requests"),
+    ("活跃请求数", "Active requests"),
+    ("错误请求数", "Failed requests"),
+    ("最后更新", "Last updated"),
+    ("序", "#"),
+    ("Token信息", "Token"),
+    ("用时/首字", "Time / first token"),
+    ("流式响应", "Stream"),
+    ("错误信息", "Error"),
+    ("Token 详细信息", "Token details"),
+    ("删除此Token", "Delete this token"),
+    ("校验和:", "Checksum:"),
+    ("邮箱:", "Email:"),
+    ("用户名:", "Name:"),
+    ("用户ID:", "User ID:"),
+    ("更新时间:", "Updated at:"),
+    ("会员信息", "Membership"),
+    ("会员类型:", "Membership:"),
+    ("支付ID:", "Payment ID:"),
+    ("试用剩余:", "Trial remaining:"),
+    ("使用量统计 (最近30天)", "Usage (last 30 days)"),
+    ("对话内容", "Conversation"),
+    // tokens.html
+    ("Token 信息管理", "Token Management"),
+    ("Token 管理", "Tokens"),
+    ("获取当前配置", "Get current tokens"),
+    ("重载Token", "Reload tokens"),
+    ("添加Token", "Add tokens"),
+    ("删除Token", "Delete tokens"),
+    ("Token 操作:", "Tokens:"),
+    (
+        "添加模式: 输入要添加的token，每行一个 删除模式: 输入要删除的token，每行一个",
+        "Add: enter tokens to add, one per line. Delete: enter tokens to delete, one per line.",
+    ),
+    ("当前Token列表:", "Current tokens:"),
+    ("复制列表", "Copy list"),
+    ("Premium用量", "Premium usage"),
+    ("今日请求", "Today"),
+    ("最近使用", "Last used"),
+    ("备注", "Note"),
+    ("联系方式", "Contact"),
+    ("标签", "Tags"),
+    ("快捷键:", "Shortcuts:"),
+    ("执行当前操作", "run the current action"),
+    ("生成动态Key", "Generate dynamic Key"),
+    ("生成", "Generate"),
+    ("确认删除", "Confirm deletion"),
+    ("确定要删除这个token吗？", "Delete this token?"),
+    ("每行一个 token", "One token per line"),
+];
+
+static CATALOG_EN_US: LazyLock<HashMap<&'static str, &'static str>> =
+    LazyLock::new(|| EN_US.iter().copied().collect());
+
+const TRANSLATED_ATTRIBUTES: [&str; 2] = ["placeholder=\"", "title=\""];
+
+// 按当前请求的语言返回内置页面，中文直接返回原文
+pub fn translate_page(html: &'static str) -> Cow<'static, str> {
+    match Locale::current() {
+        Locale::ZhCn => Cow::Borrowed(html),
+        Locale::EnUs => Cow::Owned(translate(html, &CATALOG_EN_US, "en")),
+    }
+}
+
+fn translate(html: &str, catalog: &HashMap<&str, &str>, lang: &str) -> String {
+    let mut output = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some(start) = rest.find('<') {
+        translate_text(&rest[..start], catalog, &mut output);
+        rest = &rest[start..];
+
+        // 注释、脚本与样式原样保留
+        let raw_until = if rest.starts_with("<!--") {
+            Some("-->")
+        } else if rest.starts_with("<script") {
+            Some("</script>")
+        } else if rest.starts_with("<style") {
+            Some("</style>")
+        } else {
+            None
+        };
+        if let Some(end_marker) = raw_until {
+            let end = rest
+                .find(end_marker)
+                .map_or(rest.len(), |end| end + end_marker.len());
+            output.push_str(&rest[..end]);
+            rest = &rest[end..];
+            continue;
+        }
+
+        let end = rest.find('>').map_or(rest.len(), |end| end + 1);
+        let tag = &rest[..end];
+        if tag.starts_with("<html") {
+            output.push_str(&tag.replacen("lang=\"zh\"", &format!("lang=\"{lang}\""), 1));
+        } else {
+            translate_attributes(tag, catalog, &mut output);
+        }
+        rest = &rest[end..];
+    }
+    translate_text(rest, catalog, &mut output);
+    output
+}
+
+// 比较时合并连续空白，与压缩后的页面一致
+fn lookup<'a>(text: &str, catalog: &HashMap<&str, &'a str>) -> Option<&'a str> {
+    let normalized = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    catalog.get(normalized.as_str()).copied()
+}
+
+fn translate_text(text: &str, catalog: &HashMap<&str, &str>, output: &mut String) {
+    let trimmed = text.trim();
+    match lookup(trimmed, catalog).filter(|_| !trimmed.is_empty()) {
+        Some(translated) => {
+            let leading = text.len() - text.trim_start().len();
+            output.push_str(&text[..leading]);
+            output.push_str(translated);
+            output.push_str(&text[leading + trimmed.len()..]);
+        }
+        None => output.push_str(text),
+    }
+}
+
+fn translate_attributes(tag: &str, catalog: &HashMap<&str, &str>, output: &mut String) {
+    let mut rest = tag;
+    while let Some((position, attribute)) = TRANSLATED_ATTRIBUTES
+        .iter()
+        .filter_map(|attribute| rest.find(attribute).map(|position| (position, attribute)))
+        .min_by_key(|(position, _)| *position)
+    {
+        let value_start = position + attribute.len();
+        let Some(value_len) = rest[value_start..].find('"') else {
+            break;
+        };
+        let value = &rest[value_start..value_start + value_len];
+        output.push_str(&rest[..value_start]);
+        output.push_str(lookup(value, catalog).unwrap_or(value));
+        rest = &rest[value_start + value_len..];
+    }
+    output.push_str(rest);
+}
@@ -0,0 +1,185 @@
+use super::{
+    log_stream::{self, DeniedSummary},
+    model::AppConfig,
+};
+use crate::common::{
+    model::{error::ChatError, health::IpStats},
+    utils::client_ip,
+};
+use axum::{
+    extract::{ConnectInfo, Request},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use parking_lot::RwLock;
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        LazyLock,
+    },
+    time::{Duration, Instant},
+};
+
+// 最多记录的 IP 数量，超出时一次丢弃请求数最少的 EVICT_BATCH 条记录
+// 按批清理使每次遍历的开销分摊到之后的多个新 IP 上
+const MAX_TRACKED_IPS: usize = 10000;
+const EVICT_BATCH: usize = MAX_TRACKED_IPS / 10;
+
+#[derive(Default, Clone, Copy)]
+struct Counter {
+    requests: u64,
+    denied: u64,
+}
+
+static COUNTERS: LazyLock<RwLock<HashMap<IpAddr, Counter>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+// 被拒绝的请求不写入请求日志，实时日志中每隔 DENIED_SUMMARY_INTERVAL 最多推送一次汇总
+const DENIED_SUMMARY_INTERVAL: Duration = Duration::from_secs(1);
+
+static PENDING_DENIED: AtomicU64 = AtomicU64::new(0);
+static LAST_SUMMARY: parking_lot::Mutex<Option<Instant>> = parking_lot::Mutex::new(None);
+
+// 按 IP_ALLOWLIST 与 IP_DENYLIST 过滤请求，并统计每个 IP 的请求数
+pub async fn enforce(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let ip = client_ip(request.headers(), addr);
+    let allowed = AppConfig::is_ip_allowed(ip);
+    record(ip, allowed);
+
+    if !allowed {
+        tracing::debug!("已拒绝来自 {} 的请求", ip);
+        summarize_denied(ip);
+        return (
+            StatusCode::FORBIDDEN,
+            Json(ChatError::IpBlocked(ip.to_string()).to_json()),
+        )
+            .into_response();
+    }
+
+    next.run(request).await
+}
+
+// 距上次汇总未满间隔时只累计次数，计入下一次汇总
+fn summarize_denied(ip: IpAddr) {
+    PENDING_DENIED.fetch_add(1, Ordering::Relaxed);
+    {
+        let mut last = LAST_SUMMARY.lock();
+        if last.is_some_and(|last| last.elapsed() < DENIED_SUMMARY_INTERVAL) {
+            return;
+        }
+        *last = Some(Instant::now());
+    }
+    log_stream::publish_denied(&DeniedSummary {
+        count: PENDING_DENIED.swap(0, Ordering::Relaxed),
+        last_ip: ip.to_string(),
+    });
+}
+
+fn record(ip: IpAddr, allowed: bool) {
+    let mut counters = COUNTERS.write();
+    if counters.len() >= MAX_TRACKED_IPS && !counters.contains_key(&ip) {
+        evict(&mut counters);
+    }
+
+    let counter = counters.entry(ip).or_default();
+    counter.requests += 1;
+    if !allowed {
+        counter.denied += 1;
+    }
+}
+
+fn evict(counters: &mut HashMap<IpAddr, Counter>) {
+    let mut entries: Vec<(u64, IpAddr)> = counters
+        .iter()
+        .map(|(ip, counter)| (counter.requests, *ip))
+        .collect();
+    let batch = EVICT_BATCH.min(entries.len());
+    if batch == 0 {
+        return;
+    }
+    entries.select_nth_unstable(batch - 1);
+    for (_, ip) in &entries[..batch] {
+        counters.remove(ip);
+    }
+}
+
+// 请求数最多的前 limit 个 IP
+pub fn top_ips(limit: usize) -> Vec<IpStats> {
+    let mut stats: Vec<IpStats> = COUNTERS
+        .read()
+        .iter()
+        .map(|(ip, counter)| IpStats {
+            ip: ip.to_string(),
+            requests: counter.requests,
+            denied: counter.denied,
+        })
+        .collect();
+    stats.sort_unstable_by_key(|stats| std::cmp::Reverse(stats.requests));
+    stats.truncate(limit);
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evict_removes_least_requested_batch() {
+        let mut counters: HashMap<IpAddr, Counter> = (0..MAX_TRACKED_IPS as u32)
+            .map(|i| {
+                let counter = Counter {
+                    requests: i as u64,
+                    denied: 0,
+                };
+                (IpAddr::from(i.to_be_bytes()), counter)
+            })
+            .collect();
+
+        evict(&mut counters);
+
+        assert_eq!(counters.len(), MAX_TRACKED_IPS - EVICT_BATCH);
+        assert!(counters
+            .values()
+            .all(|counter| counter.requests >= EVICT_BATCH as u64));
+    }
+
+    // 只有此测试修改全局的 IP 列表
+    #[test]
+    fn test_is_ip_allowed_matches_cidr() {
+        let ip = |s: &str| s.parse::<IpAddr>().unwrap();
+
+        AppConfig::update_ip_allowlist(vec!["10.0.0.0/8".to_string(), "2001:db8::/32".to_string()]);
+        AppConfig::update_ip_denylist(vec!["10.1.0.0/16".to_string(), "10.2.3.4".to_string()]);
+        assert!(AppConfig::is_ip_allowed(ip("10.0.0.1")));
+        assert!(AppConfig::is_ip_allowed(ip("10.255.255.255")));
+        assert!(AppConfig::is_ip_allowed(ip("10.2.3.5")));
+        assert!(!AppConfig::is_ip_allowed(ip("10.1.2.3"))); // 拒绝列表优先
+        assert!(!AppConfig::is_ip_allowed(ip("10.2.3.4")));
+        assert!(!AppConfig::is_ip_allowed(ip("11.0.0.1"))); // 不在允许列表中
+        assert!(AppConfig::is_ip_allowed(ip("::ffff:10.0.0.1"))); // IPv4 映射地址按 IPv4 比较
+        assert!(AppConfig::is_ip_allowed(ip("2001:db8::1")));
+        assert!(!AppConfig::is_ip_allowed(ip("2001:db9::1")));
+
+        // 允许列表为空时只检查拒绝列表
+        AppConfig::update_ip_allowlist(Vec::new());
+        AppConfig::update_ip_denylist(vec!["192.168.0.0/24".to_string()]);
+        assert!(AppConfig::is_ip_allowed(ip("11.0.0.1")));
+        assert!(AppConfig::is_ip_allowed(ip("192.168.1.1")));
+        assert!(!AppConfig::is_ip_allowed(ip("192.168.0.200")));
+
+        AppConfig::update_ip_denylist(vec!["0.0.0.0/0".to_string()]);
+        assert!(!AppConfig::is_ip_allowed(ip("8.8.8.8")));
+        assert!(AppConfig::is_ip_allowed(ip("::1"))); // IPv4 规则不匹配 IPv6 地址
+
+        AppConfig::update_ip_denylist(Vec::new());
+        assert!(AppConfig::is_ip_allowed(ip("8.8.8.8")));
+    }
+}
@@ -1,6 +1,9 @@
 use super::constant::{
-    COMMA, CURSOR_API2_HOST, CURSOR_HOST, DEFAULT_TOKEN_LIST_FILE_NAME, EMPTY_STRING,
+    CHAT_CANCEL_PATH, CHAT_COMPLETIONS_PATH, COMMA, CURSOR_API2_HOST, CURSOR_HOST,
+    DEFAULT_TOKEN_BLACKLIST_FILE_NAME, DEFAULT_TOKEN_LIST_FILE_NAME, DEFAULT_TOKEN_TRASH_FILE_NAME,
+    EMPTY_STRING,
 };
+use super::i18n::Locale;
 use crate::common::utils::{
     parse_ascii_char_from_env, parse_bool_from_env, parse_string_from_env, parse_usize_from_env,
 };
@@ -31,13 +34,147 @@ macro_rules! def_pub_static {
 // }
 
 def_pub_static!(ROUTE_PREFIX, env: "ROUTE_PREFIX", default: EMPTY_STRING);
+
+// 部署在子路径下时的路径，如 /cursor，规范为以 / 开头且不以 / 结尾，为空时部署在根路径
+pub static BASE_PATH: LazyLock<String> = LazyLock::new(|| {
+    let path = parse_string_from_env("BASE_PATH", EMPTY_STRING);
+    let path = path.trim().trim_matches('/');
+    if path.is_empty() {
+        String::new()
+    } else {
+        format!("/{}", path)
+    }
+});
 def_pub_static!(AUTH_TOKEN, env: "AUTH_TOKEN", default: EMPTY_STRING);
+
+// 监听的 Unix 套接字路径，为空时不监听
+def_pub_static!(LISTEN_UNIX_SOCKET, env: "LISTEN_UNIX_SOCKET", default: EMPTY_STRING);
+
+// Unix 套接字文件权限，八进制，如 660
+#[cfg(unix)]
+pub static LISTEN_UNIX_SOCKET_MODE: LazyLock<Option<u32>> = LazyLock::new(|| {
+    let mode = parse_string_from_env("LISTEN_UNIX_SOCKET_MODE", EMPTY_STRING);
+    u32::from_str_radix(mode.trim(), 8).ok()
+});
+
+// 监听的 TCP 地址，逗号分隔；未设置时监听 0.0.0.0:PORT，只设置了 Unix 套接字时不监听 TCP
+pub static LISTEN_ADDRS: LazyLock<Vec<String>> = LazyLock::new(|| {
+    let addrs: Vec<String> = parse_string_from_env("LISTEN_ADDRS", EMPTY_STRING)
+        .split(COMMA)
+        .map(str::trim)
+        .filter(|addr| !addr.is_empty())
+        .map(str::to_string)
+        .collect();
+    if addrs.is_empty() && LISTEN_UNIX_SOCKET.is_empty() {
+        vec![format!("0.0.0.0:{}", parse_string_from_env("PORT", "3000"))]
+    } else {
+        addrs
+    }
+});
+
+// 反向代理传递客户端地址的请求头，如 x-forwarded-for，为空时使用连接地址
+def_pub_static!(REAL_IP_HEADER, env: "REAL_IP_HEADER", default: EMPTY_STRING);
+
+// 可信的反向代理地址或 CIDR，只有来自这些地址的连接才读取 REAL_IP_HEADER，默认只信任本机
+pub static TRUSTED_PROXIES: LazyLock<Vec<String>> = LazyLock::new(|| {
+    parse_string_from_env("TRUSTED_PROXIES", "127.0.0.1,::1")
+        .split(COMMA)
+        .map(str::trim)
+        .filter(|rule| !rule.is_empty())
+        .filter(|rule| {
+            let valid = super::model::is_valid_ip_rule(rule);
+            if !valid {
+                tracing::warn!("忽略 TRUSTED_PROXIES 中无效的规则: {}", rule);
+            }
+            valid
+        })
+        .map(str::to_string)
+        .collect()
+});
+
+// 证书与私钥均为 PEM 格式，都设置时以 HTTPS 提供服务（需启用 tls 特性）
+def_pub_static!(TLS_CERT_PATH, env: "TLS_CERT_PATH", default: EMPTY_STRING);
+def_pub_static!(TLS_KEY_PATH, env: "TLS_KEY_PATH", default: EMPTY_STRING);
+
+// 启用 HTTPS 时监听该端口并将 HTTP 请求重定向到 HTTPS，为0时禁用
+#[cfg(feature = "tls")]
+pub static HTTP_REDIRECT_PORT: LazyLock<u16> = LazyLock::new(|| {
+    let port = parse_usize_from_env("HTTP_REDIRECT_PORT", 0);
+    u16::try_from(port).unwrap_or(0)
+});
 def_pub_static!(TOKEN_LIST_FILE, env: "TOKEN_LIST_FILE", default: DEFAULT_TOKEN_LIST_FILE_NAME);
+def_pub_static!(TOKEN_BLACKLIST_FILE, env: "TOKEN_BLACKLIST_FILE", default: DEFAULT_TOKEN_BLACKLIST_FILE_NAME);
+def_pub_static!(TOKEN_TRASH_FILE, env: "TOKEN_TRASH_FILE", default: DEFAULT_TOKEN_TRASH_FILE_NAME);
+// 加密保存在文件中的 token 与 checksum，为空时以明文保存
+def_pub_static!(TOKEN_ENCRYPTION_KEY, env: "TOKEN_ENCRYPTION_KEY", default: EMPTY_STRING);
 def_pub_static!(ROUTE_MODELS_PATH, format!("{}/v1/models", *ROUTE_PREFIX));
 def_pub_static!(
     ROUTE_CHAT_PATH,
-    format!("{}/v1/chat/completions", *ROUTE_PREFIX)
+    format!("{}{}", *ROUTE_PREFIX, CHAT_COMPLETIONS_PATH)
+);
+// 客户端只提供模板 ID 与变量，由服务端渲染消息
+def_pub_static!(
+    ROUTE_CHAT_TEMPLATE_PATH,
+    format!("{}/v1/chat/template", *ROUTE_PREFIX)
 );
+// 租户路由，{tenant} 为租户 ID
+def_pub_static!(
+    ROUTE_TENANT_MODELS_PATH,
+    format!("/{{tenant}}{}/v1/models", *ROUTE_PREFIX)
+);
+def_pub_static!(
+    ROUTE_TENANT_CHAT_PATH,
+    format!("/{{tenant}}{}{}", *ROUTE_PREFIX, CHAT_COMPLETIONS_PATH)
+);
+// Azure OpenAI 风格的路由，{deployment} 为部署名称
+def_pub_static!(
+    ROUTE_AZURE_CHAT_PATH,
+    format!(
+        "{}/openai/deployments/{{deployment}}/chat/completions",
+        *ROUTE_PREFIX
+    )
+);
+def_pub_static!(ROUTE_CHAT_WS_PATH, format!("{}/v1/chat/ws", *ROUTE_PREFIX));
+def_pub_static!(
+    ROUTE_COMPLETIONS_PATH,
+    format!("{}/v1/completions", *ROUTE_PREFIX)
+);
+def_pub_static!(
+    ROUTE_IMAGES_GENERATIONS_PATH,
+    format!("{}/v1/images/generations", *ROUTE_PREFIX)
+);
+def_pub_static!(
+    ROUTE_CHAT_CANCEL_PATH,
+    format!("{}{}/{{id}}", *ROUTE_PREFIX, CHAT_CANCEL_PATH)
+);
+def_pub_static!(
+    ROUTE_DEBUG_ECHO_PATH,
+    format!("{}/v1/debug/echo", *ROUTE_PREFIX)
+);
+
+pub static ENABLE_DEBUG_ECHO: LazyLock<bool> =
+    LazyLock::new(|| parse_bool_from_env("ENABLE_DEBUG_ECHO", false));
+
+// 开发用的故障注入，开启后按概率模拟上游故障，被注入的请求不会发送到上游
+pub static ENABLE_FAULT_INJECTION: LazyLock<bool> =
+    LazyLock::new(|| parse_bool_from_env("ENABLE_FAULT_INJECTION", false));
+
+// 各类故障的注入概率(0-100)
+pub static FAULT_TIMEOUT_PERCENT: LazyLock<usize> =
+    LazyLock::new(|| parse_usize_from_env("FAULT_TIMEOUT_PERCENT", 0).min(100));
+
+pub static FAULT_RATE_LIMIT_PERCENT: LazyLock<usize> =
+    LazyLock::new(|| parse_usize_from_env("FAULT_RATE_LIMIT_PERCENT", 0).min(100));
+
+pub static FAULT_STREAM_ERROR_PERCENT: LazyLock<usize> =
+    LazyLock::new(|| parse_usize_from_env("FAULT_STREAM_ERROR_PERCENT", 0).min(100));
+
+pub static FAULT_MALFORMED_PERCENT: LazyLock<usize> =
+    LazyLock::new(|| parse_usize_from_env("FAULT_MALFORMED_PERCENT", 0).min(100));
+
+// 以固定内容的模拟响应代替上游，不消耗额度
+pub static MOCK_UPSTREAM: LazyLock<bool> =
+    LazyLock::new(|| parse_bool_from_env("MOCK_UPSTREAM", false));
 
 pub static START_TIME: LazyLock<chrono::DateTime<chrono::Local>> =
     LazyLock::new(chrono::Local::now);
@@ -130,7 +267,174 @@ pub(super) static LOGS_FILE_PATH: LazyLock<String> =
 pub(super) static PAGES_FILE_PATH: LazyLock<String> =
     LazyLock::new(|| parse_string_from_env("PAGES_FILE_PATH", "pages.bin"));
 
-pub static DEBUG: LazyLock<bool> = LazyLock::new(|| parse_bool_from_env("DEBUG", false));
+pub(super) static CONFIG_FILE_PATH: LazyLock<String> =
+    LazyLock::new(|| parse_string_from_env("CONFIG_FILE_PATH", "config.bin"));
+
+pub(super) static STATS_FILE_PATH: LazyLock<String> =
+    LazyLock::new(|| parse_string_from_env("STATS_FILE_PATH", "stats.bin"));
+
+pub(super) static MODEL_POLICIES_FILE_PATH: LazyLock<String> =
+    LazyLock::new(|| parse_string_from_env("MODEL_POLICIES_FILE_PATH", "model_policies.bin"));
+
+pub(super) static MODEL_ALIASES_FILE_PATH: LazyLock<String> =
+    LazyLock::new(|| parse_string_from_env("MODEL_ALIASES_FILE_PATH", "model_aliases.bin"));
+
+pub(super) static MODEL_PRICES_FILE_PATH: LazyLock<String> =
+    LazyLock::new(|| parse_string_from_env("MODEL_PRICES_FILE_PATH", "model_prices.bin"));
+
+pub(super) static MODEL_CAPABILITIES_FILE_PATH: LazyLock<String> = LazyLock::new(|| {
+    parse_string_from_env("MODEL_CAPABILITIES_FILE_PATH", "model_capabilities.bin")
+});
+
+pub(super) static AZURE_DEPLOYMENTS_FILE_PATH: LazyLock<String> =
+    LazyLock::new(|| parse_string_from_env("AZURE_DEPLOYMENTS_FILE_PATH", "azure_deployments.bin"));
+
+pub(super) static SPEND_FILE_PATH: LazyLock<String> =
+    LazyLock::new(|| parse_string_from_env("SPEND_FILE_PATH", "spend.bin"));
+
+pub(super) static API_KEYS_FILE_PATH: LazyLock<String> =
+    LazyLock::new(|| parse_string_from_env("API_KEYS_FILE_PATH", "api_keys.bin"));
+
+pub(super) static INVITE_CODES_FILE_PATH: LazyLock<String> =
+    LazyLock::new(|| parse_string_from_env("INVITE_CODES_FILE_PATH", "invite_codes.bin"));
+
+pub(super) static AUDIT_LOGS_FILE_PATH: LazyLock<String> =
+    LazyLock::new(|| parse_string_from_env("AUDIT_LOGS_FILE_PATH", "audit_logs.bin"));
+
+pub(super) static PROMPT_TEMPLATES_FILE_PATH: LazyLock<String> =
+    LazyLock::new(|| parse_string_from_env("PROMPT_TEMPLATES_FILE_PATH", "templates.bin"));
+
+pub(super) static MODERATION_RULES_FILE_PATH: LazyLock<String> =
+    LazyLock::new(|| parse_string_from_env("MODERATION_RULES_FILE_PATH", "moderation_rules.bin"));
+
+pub(super) static REPORTS_FILE_PATH: LazyLock<String> =
+    LazyLock::new(|| parse_string_from_env("REPORTS_FILE_PATH", "reports.bin"));
+
+pub(super) static CONVERSATIONS_FILE_PATH: LazyLock<String> =
+    LazyLock::new(|| parse_string_from_env("CONVERSATIONS_FILE_PATH", "conversations.bin"));
+
+pub(super) static QUALITY_SAMPLES_FILE_PATH: LazyLock<String> =
+    LazyLock::new(|| parse_string_from_env("QUALITY_SAMPLES_FILE_PATH", "quality_samples.bin"));
+
+pub(super) static PAYLOADS_FILE_PATH: LazyLock<String> =
+    LazyLock::new(|| parse_string_from_env("PAYLOADS_FILE_PATH", "payloads.bin"));
+
+pub(super) static CHECKSUM_ROTATIONS_FILE_PATH: LazyLock<String> = LazyLock::new(|| {
+    parse_string_from_env("CHECKSUM_ROTATIONS_FILE_PATH", "checksum_rotations.bin")
+});
+
+pub(super) static QUOTA_SNAPSHOTS_FILE_PATH: LazyLock<String> =
+    LazyLock::new(|| parse_string_from_env("QUOTA_SNAPSHOTS_FILE_PATH", "quota_snapshots.bin"));
+
+pub(super) static DAILY_SUMMARIES_FILE_PATH: LazyLock<String> =
+    LazyLock::new(|| parse_string_from_env("DAILY_SUMMARIES_FILE_PATH", "daily_summaries.bin"));
+
+pub(super) static TENANTS_FILE_PATH: LazyLock<String> =
+    LazyLock::new(|| parse_string_from_env("TENANTS_FILE_PATH", "tenants.bin"));
+
+pub(super) static SYSTEM_PROMPTS_FILE_PATH: LazyLock<String> =
+    LazyLock::new(|| parse_string_from_env("SYSTEM_PROMPTS_FILE_PATH", "system_prompts.bin"));
+
+pub(super) static REVOKED_SESSIONS_FILE_PATH: LazyLock<String> =
+    LazyLock::new(|| parse_string_from_env("REVOKED_SESSIONS_FILE_PATH", "revoked_sessions.bin"));
+
+// 保留的审计日志条数，为0时不记录
+pub static AUDIT_LOGS_LIMIT: LazyLock<usize> =
+    LazyLock::new(|| parse_usize_from_env("AUDIT_LOGS_LIMIT", 1000));
+
+// 保留的使用报告份数
+pub static REPORTS_LIMIT: LazyLock<usize> =
+    LazyLock::new(|| parse_usize_from_env("REPORTS_LIMIT", 30));
+
+// 保留的每日用量汇总天数，为0时不生成
+pub static DAILY_SUMMARIES_LIMIT: LazyLock<usize> =
+    LazyLock::new(|| parse_usize_from_env("DAILY_SUMMARIES_LIMIT", 90));
+
+// 是否在服务端保存带 conversation_id 的请求的会话历史
+pub static CONVERSATION_HISTORY: LazyLock<bool> =
+    LazyLock::new(|| parse_bool_from_env("CONVERSATION_HISTORY", false));
+
+// 每个会话保留的消息条数
+pub static CONVERSATION_MAX_MESSAGES: LazyLock<usize> =
+    LazyLock::new(|| parse_usize_from_env("CONVERSATION_MAX_MESSAGES", 100).max(2));
+
+// 保存的会话总数，超出时删除最久未更新的会话
+pub static CONVERSATIONS_LIMIT: LazyLock<usize> =
+    LazyLock::new(|| parse_usize_from_env("CONVERSATIONS_LIMIT", 1000).max(1));
+
+// 抽样记录回复质量指标的请求百分比(0-100)，为0时禁用
+pub static QUALITY_SAMPLE_PERCENT: LazyLock<usize> =
+    LazyLock::new(|| parse_usize_from_env("QUALITY_SAMPLE_PERCENT", 0).min(100));
+
+// 保留的质量抽样条数
+pub static QUALITY_SAMPLES_LIMIT: LazyLock<usize> =
+    LazyLock::new(|| parse_usize_from_env("QUALITY_SAMPLES_LIMIT", 10000));
+
+// 判断拒答的正则表达式（不区分大小写，匹配回复开头），为空时使用内置的常见拒答模式
+pub static QUALITY_REFUSAL_PATTERN: LazyLock<String> =
+    LazyLock::new(|| parse_string_from_env("QUALITY_REFUSAL_PATTERN", EMPTY_STRING));
+
+// 是否按日志 ID 保存完整的请求消息与最终回复
+pub static PAYLOAD_ARCHIVE: LazyLock<bool> =
+    LazyLock::new(|| parse_bool_from_env("PAYLOAD_ARCHIVE", false));
+
+// 保留的请求内容条数
+pub static PAYLOADS_LIMIT: LazyLock<usize> =
+    LazyLock::new(|| parse_usize_from_env("PAYLOADS_LIMIT", 1000).max(1));
+
+// 请求与回复各自保存的最大字节数，超出部分截断
+pub static PAYLOAD_MAX_BYTES: LazyLock<usize> =
+    LazyLock::new(|| parse_usize_from_env("PAYLOAD_MAX_BYTES", 65536));
+
+// 保存前替换为 [REDACTED] 的正则表达式，为空时使用内置规则（API key、令牌、邮箱）
+pub static PAYLOAD_REDACT_PATTERN: LazyLock<String> =
+    LazyLock::new(|| parse_string_from_env("PAYLOAD_REDACT_PATTERN", EMPTY_STRING));
+
+// 号池中每个 token 重新生成 checksum 的间隔(小时)，为0时禁用
+pub static CHECKSUM_ROTATION_INTERVAL: LazyLock<u64> = LazyLock::new(|| {
+    let interval = parse_usize_from_env("CHECKSUM_ROTATION_INTERVAL", 0);
+    u64::try_from(interval).unwrap_or(0)
+});
+
+// 保留的 checksum 轮换记录条数，应不少于号池中的 token 数
+pub static CHECKSUM_ROTATIONS_LIMIT: LazyLock<usize> =
+    LazyLock::new(|| parse_usize_from_env("CHECKSUM_ROTATIONS_LIMIT", 1000).max(1));
+
+// 从上游同步号池中各 token 快速请求额度的间隔(分钟)，为0时禁用
+pub static QUOTA_SYNC_INTERVAL: LazyLock<u64> = LazyLock::new(|| {
+    let interval = parse_usize_from_env("QUOTA_SYNC_INTERVAL", 0);
+    u64::try_from(interval).unwrap_or(0)
+});
+
+// 同步时相邻两个 token 之间的等待时间(毫秒)，避免短时间内大量请求上游
+pub static QUOTA_SYNC_DELAY: LazyLock<u64> = LazyLock::new(|| {
+    let delay = parse_usize_from_env("QUOTA_SYNC_DELAY", 1000);
+    u64::try_from(delay).unwrap_or(1000)
+});
+
+// 额度用完的 token 全部不可选时，是否改用这些 token 并切换到慢速池
+pub static QUOTA_SLOW_FALLBACK: LazyLock<bool> =
+    LazyLock::new(|| parse_bool_from_env("QUOTA_SLOW_FALLBACK", false));
+
+// 统计数据定期保存的间隔(秒)，为0时仅在关闭时保存
+pub static STATS_SAVE_INTERVAL: LazyLock<u64> = LazyLock::new(|| {
+    let interval = parse_usize_from_env("STATS_SAVE_INTERVAL", 300);
+    u64::try_from(interval).unwrap_or(300)
+});
+
+def_pub_static!(LOG_LEVEL, env: "LOG_LEVEL", default: "info");
+
+pub static LOG_FORMAT: LazyLock<String> = LazyLock::new(|| {
+    parse_string_from_env("LOG_FORMAT", "pretty")
+        .trim()
+        .to_lowercase()
+});
+
+// 外部日志转发目标: http(s)://...、file://路径 或 syslog://主机:端口，为空时禁用
+def_pub_static!(LOG_SINK, env: "LOG_SINK", default: EMPTY_STRING);
+
+pub static LOG_SINK_INCLUDE_CONTENT: LazyLock<bool> =
+    LazyLock::new(|| parse_bool_from_env("LOG_SINK_INCLUDE_CONTENT", false));
 
 // 使用环境变量 "DEBUG_LOG_FILE" 来指定日志文件路径，默认值为 "debug.log"
 static DEBUG_LOG_FILE: LazyLock<String> =
@@ -157,21 +461,21 @@ pub(crate) async fn get_log_file() -> &'static Mutex<tokio::fs::File> {
 #[macro_export]
 macro_rules! debug_println {
     ($($arg:tt)*) => {
-        if *crate::app::lazy::DEBUG {
+        if $crate::app::model::AppConfig::get_debug() {
             let time = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
             let log_message = format!("{} - {}", time, format!($($arg)*));
             use tokio::io::AsyncWriteExt as _;
 
             // 使用 tokio 的 spawn 在后台异步写入日志
             tokio::spawn(async move {
-                let log_file = crate::app::lazy::get_log_file().await;
+                let log_file = $crate::app::lazy::get_log_file().await;
                 // 使用 MutexGuard 获取可变引用
                 let mut file = log_file.lock().await;
                 if let Err(err) = file.write_all(log_message.as_bytes()).await {
-                    eprintln!("写入日志文件失败: {}", err);
+                    tracing::error!("写入日志文件失败: {}", err);
                 }
                 if let Err(err) = file.write_all(b"\n").await {
-                    eprintln!("写入换行符失败: {}", err);
+                    tracing::error!("写入换行符失败: {}", err);
                 }
                 // 可以选择在写入失败时 panic，或者忽略
                 // panic!("写入日志文件失败: {}", err);
@@ -183,7 +487,221 @@ macro_rules! debug_println {
 pub static REQUEST_LOGS_LIMIT: LazyLock<usize> =
     LazyLock::new(|| std::cmp::min(parse_usize_from_env("REQUEST_LOGS_LIMIT", 100), 2000));
 
+// 每个 token 保留的日志条数，为0时不限制
+pub static REQUEST_LOGS_USER_LIMIT: LazyLock<usize> =
+    LazyLock::new(|| parse_usize_from_env("REQUEST_LOGS_USER_LIMIT", 0));
+
+// 日志保留时长(秒)，为0时不限制
+pub static REQUEST_LOGS_MAX_AGE: LazyLock<u64> = LazyLock::new(|| {
+    let age = parse_usize_from_env("REQUEST_LOGS_MAX_AGE", 0);
+    u64::try_from(age).unwrap_or(0)
+});
+
+// 日志定期清理的间隔(秒)，为0时不启用
+pub static LOGS_CLEANUP_INTERVAL: LazyLock<u64> = LazyLock::new(|| {
+    let interval = parse_usize_from_env("LOGS_CLEANUP_INTERVAL", 3600);
+    u64::try_from(interval).unwrap_or(3600)
+});
+
+// 流式请求结束后是否与上游用量统计对账
+pub static USAGE_RECONCILE: LazyLock<bool> =
+    LazyLock::new(|| parse_bool_from_env("USAGE_RECONCILE", false));
+
+// 对账差异超过估算值的该百分比时标记
+pub static USAGE_RECONCILE_TOLERANCE: LazyLock<u64> = LazyLock::new(|| {
+    let tolerance = parse_usize_from_env("USAGE_RECONCILE_TOLERANCE", 50);
+    u64::try_from(tolerance).unwrap_or(50)
+});
+
 pub static SERVICE_TIMEOUT: LazyLock<u64> = LazyLock::new(|| {
     let timeout = parse_usize_from_env("SERVICE_TIMEOUT", 30);
     u64::try_from(timeout).map(|t| t.min(600)).unwrap_or(30)
 });
+
+// 与上游建立连接的超时(秒)
+pub static UPSTREAM_CONNECT_TIMEOUT: LazyLock<u64> = LazyLock::new(|| {
+    let timeout = parse_usize_from_env("UPSTREAM_CONNECT_TIMEOUT", 10);
+    u64::try_from(timeout)
+        .map(|t| t.clamp(1, 600))
+        .unwrap_or(10)
+});
+
+// 两次读取上游数据之间的最长间隔(秒)，为0时不限制
+pub static UPSTREAM_READ_TIMEOUT: LazyLock<u64> = LazyLock::new(|| {
+    let timeout = parse_usize_from_env("UPSTREAM_READ_TIMEOUT", 120);
+    u64::try_from(timeout).map(|t| t.min(3600)).unwrap_or(120)
+});
+
+// 连接上游失败时的重试次数(最大值5)
+pub static UPSTREAM_CONNECT_RETRIES: LazyLock<u32> = LazyLock::new(|| {
+    let retries = parse_usize_from_env("UPSTREAM_CONNECT_RETRIES", 2);
+    u32::try_from(retries.min(5)).unwrap_or(2)
+});
+
+// 首次重试前的等待时间(毫秒)，之后每次翻倍
+pub static UPSTREAM_RETRY_BACKOFF: LazyLock<u64> = LazyLock::new(|| {
+    let backoff = parse_usize_from_env("UPSTREAM_RETRY_BACKOFF", 500);
+    u64::try_from(backoff).map(|b| b.min(10_000)).unwrap_or(500)
+});
+
+// 非流式请求超过该时长(秒)仍未完成时开始发送保活空白，为0时禁用
+pub static NON_STREAM_KEEPALIVE_AFTER: LazyLock<u64> = LazyLock::new(|| {
+    let after = parse_usize_from_env("NON_STREAM_KEEPALIVE_AFTER", 20);
+    u64::try_from(after).unwrap_or(20)
+});
+
+// 保活空白的发送间隔(秒)
+pub static NON_STREAM_KEEPALIVE_INTERVAL: LazyLock<u64> = LazyLock::new(|| {
+    let interval = parse_usize_from_env("NON_STREAM_KEEPALIVE_INTERVAL", 10);
+    u64::try_from(interval).map(|i| i.max(1)).unwrap_or(10)
+});
+
+// 单个请求的 n 参数上限，每个回复各发起一次上游请求
+pub static CHAT_MAX_CHOICES: LazyLock<u32> = LazyLock::new(|| {
+    let max = parse_usize_from_env("CHAT_MAX_CHOICES", 4);
+    u32::try_from(max).map(|m| m.clamp(1, 16)).unwrap_or(4)
+});
+
+// 请求 logprobs 时返回 400，而不是在回复中以 null 返回
+pub static LOGPROBS_REJECT: LazyLock<bool> =
+    LazyLock::new(|| parse_bool_from_env("LOGPROBS_REJECT", false));
+
+// response_format 要求 JSON 时对回复的处理: off 不校验，repair 尝试修复，strict 无法修复时返回错误
+pub static RESPONSE_FORMAT_STRICTNESS: LazyLock<String> = LazyLock::new(|| {
+    parse_string_from_env("RESPONSE_FORMAT_STRICTNESS", "repair")
+        .trim()
+        .to_lowercase()
+});
+
+// 流式请求需要立即发送前导片段的客户端，按 User-Agent 关键字匹配（不区分大小写），* 表示全部，为空时禁用
+pub static STREAM_PRELUDE_CLIENTS: LazyLock<Vec<String>> = LazyLock::new(|| {
+    parse_string_from_env("STREAM_PRELUDE_CLIENTS", EMPTY_STRING)
+        .split(COMMA)
+        .map(|client| client.trim().to_ascii_lowercase())
+        .filter(|client| !client.is_empty())
+        .collect()
+});
+
+// 记录到日志并在响应中回显的请求 metadata 键，逗号分隔，* 表示全部，为空时忽略 metadata
+pub static REQUEST_METADATA_KEYS: LazyLock<Vec<String>> = LazyLock::new(|| {
+    parse_string_from_env("REQUEST_METADATA_KEYS", EMPTY_STRING)
+        .split(COMMA)
+        .map(|key| key.trim().to_string())
+        .filter(|key| !key.is_empty())
+        .collect()
+});
+
+// 响应缓存有效期(秒)，为0时禁用缓存
+pub static RESPONSE_CACHE_TTL: LazyLock<u64> = LazyLock::new(|| {
+    let ttl = parse_usize_from_env("RESPONSE_CACHE_TTL", 0);
+    u64::try_from(ttl).unwrap_or(0)
+});
+
+pub static RESPONSE_CACHE_CAPACITY: LazyLock<usize> =
+    LazyLock::new(|| parse_usize_from_env("RESPONSE_CACHE_CAPACITY", 256));
+
+// 幂等键的有效期(秒)，为0时不处理 Idempotency-Key
+pub static IDEMPOTENCY_TTL: LazyLock<u64> = LazyLock::new(|| {
+    let ttl = parse_usize_from_env("IDEMPOTENCY_TTL", 0);
+    u64::try_from(ttl).unwrap_or(0)
+});
+
+pub static IDEMPOTENCY_CAPACITY: LazyLock<usize> =
+    LazyLock::new(|| parse_usize_from_env("IDEMPOTENCY_CAPACITY", 1024));
+
+def_pub_static!(TOKEN_LEASE_DIR, env: "TOKEN_LEASE_DIR", default: EMPTY_STRING);
+def_pub_static!(TOKEN_LEASE_REDIS_URL, env: "TOKEN_LEASE_REDIS_URL", default: EMPTY_STRING);
+
+pub static TOKEN_LEASE_TTL: LazyLock<u64> = LazyLock::new(|| {
+    let ttl = parse_usize_from_env("TOKEN_LEASE_TTL", 60);
+    u64::try_from(ttl).map(|t| t.max(1)).unwrap_or(60)
+});
+
+// 兼容 OpenAI /v1/moderations 的外部审核接口，为空时只使用本地规则
+def_pub_static!(MODERATION_API_URL, env: "MODERATION_API_URL", default: EMPTY_STRING);
+
+def_pub_static!(MODERATION_API_KEY, env: "MODERATION_API_KEY", default: EMPTY_STRING);
+
+// 请求上游时使用的客户端版本
+def_pub_static!(CURSOR_CLIENT_VERSION, env: "CURSOR_CLIENT_VERSION", default: "0.42.5");
+
+// 每日生成使用报告的本地时间(HH:MM)，为空时不定时生成
+def_pub_static!(REPORT_TIME, env: "REPORT_TIME", default: EMPTY_STRING);
+
+// 报告发送目标，逗号分隔，支持 http(s):// webhook 和 smtp:// 邮件
+def_pub_static!(REPORT_TARGETS, env: "REPORT_TARGETS", default: EMPTY_STRING);
+
+// 兼容 OpenAI 的图片生成服务地址（如 https://api.openai.com/v1），为空时图片接口返回 501
+def_pub_static!(IMAGE_API_BASE, env: "IMAGE_API_BASE", default: EMPTY_STRING);
+
+// 图片生成服务的 API key
+def_pub_static!(IMAGE_API_KEY, env: "IMAGE_API_KEY", default: EMPTY_STRING);
+
+// 没有可用 token 时最多排队等待的请求数，为0时直接返回 503
+pub static TOKEN_QUEUE_SIZE: LazyLock<usize> =
+    LazyLock::new(|| parse_usize_from_env("TOKEN_QUEUE_SIZE", 0));
+
+// 排队等待 token 的最长时间(秒)
+pub static TOKEN_QUEUE_TIMEOUT: LazyLock<u64> = LazyLock::new(|| {
+    let timeout = parse_usize_from_env("TOKEN_QUEUE_TIMEOUT", 30);
+    u64::try_from(timeout).unwrap_or(30)
+});
+
+// 同时发往上游的请求数上限，为0时不限制；达到上限时按优先级排队
+pub static DISPATCH_LIMIT: LazyLock<usize> =
+    LazyLock::new(|| parse_usize_from_env("DISPATCH_LIMIT", 0));
+
+// 各优先级单独的并发上限，为0时只受 DISPATCH_LIMIT 限制
+pub static DISPATCH_LIMIT_HIGH: LazyLock<usize> =
+    LazyLock::new(|| parse_usize_from_env("DISPATCH_LIMIT_HIGH", 0));
+pub static DISPATCH_LIMIT_NORMAL: LazyLock<usize> =
+    LazyLock::new(|| parse_usize_from_env("DISPATCH_LIMIT_NORMAL", 0));
+pub static DISPATCH_LIMIT_LOW: LazyLock<usize> =
+    LazyLock::new(|| parse_usize_from_env("DISPATCH_LIMIT_LOW", 0));
+
+// 达到并发上限时排队等待的最长时间(秒)
+pub static DISPATCH_QUEUE_TIMEOUT: LazyLock<u64> = LazyLock::new(|| {
+    let timeout = parse_usize_from_env("DISPATCH_QUEUE_TIMEOUT", 30);
+    u64::try_from(timeout).unwrap_or(30)
+});
+
+// 是否启用公共号池，启用后使用号池的请求可选择只使用标记为公共的 token
+pub static PUBLIC_POOL_ENABLED: LazyLock<bool> =
+    LazyLock::new(|| parse_bool_from_env("PUBLIC_POOL_ENABLED", false));
+
+// 是否允许用户使用邀请码自助注册并获取 API key
+pub static INVITE_REGISTRATION: LazyLock<bool> =
+    LazyLock::new(|| parse_bool_from_env("INVITE_REGISTRATION", false));
+
+// 每个用户每天可通过公共号池发起的请求数，为0时不限制
+pub static PUBLIC_POOL_DAILY_LIMIT: LazyLock<usize> =
+    LazyLock::new(|| parse_usize_from_env("PUBLIC_POOL_DAILY_LIMIT", 50));
+
+// 网页会话的有效期(小时)，为0时禁用会话登录
+pub static SESSION_TTL: LazyLock<u64> = LazyLock::new(|| {
+    let ttl = parse_usize_from_env("SESSION_TTL", 24);
+    u64::try_from(ttl).unwrap_or(24)
+});
+
+// 请求没有指定语言时错误信息与页面使用的语言，支持 zh-CN 与 en-US
+pub static DEFAULT_LOCALE: LazyLock<Locale> = LazyLock::new(|| {
+    let locale = parse_string_from_env("DEFAULT_LOCALE", "en-US");
+    Locale::parse(&locale).unwrap_or(Locale::EnUs)
+});
+
+// 设置后优先从该目录提供页面与 /static 下的文件，不存在的文件回退到内置版本
+def_pub_static!(STATIC_DIR, env: "STATIC_DIR", default: EMPTY_STRING);
+
+// STATIC_DIR 中页面以外的文件的缓存时间(秒)
+pub static STATIC_MAX_AGE: LazyLock<usize> =
+    LazyLock::new(|| parse_usize_from_env("STATIC_MAX_AGE", 3600));
+
+// 号池 token 被上游限流后依次使用的冷却时间(秒)，逗号分隔，为空或0时不冷却
+pub static RATE_LIMIT_BACKOFF: LazyLock<Vec<std::time::Duration>> = LazyLock::new(|| {
+    parse_string_from_env("RATE_LIMIT_BACKOFF", "60,300,900,1800,3600")
+        .split(COMMA)
+        .filter_map(|secs| secs.trim().parse::<u64>().ok())
+        .filter(|&secs| secs > 0)
+        .map(std::time::Duration::from_secs)
+        .collect()
+});
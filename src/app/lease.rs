@@ -0,0 +1,189 @@
+mod redis;
+
+use super::lazy::{TOKEN_LEASE_DIR, TOKEN_LEASE_TTL};
+use sha2::{Digest, Sha256};
+use std::{
+    fs::{OpenOptions, TryLockError},
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
+
+// 当前实例的唯一标识
+static INSTANCE_ID: LazyLock<String> = LazyLock::new(|| uuid::Uuid::new_v4().simple().to_string());
+
+const LEASE_EXTENSION: &str = "lease";
+const GUARD_EXTENSION: &str = "lease.lock";
+
+// 设置了 TOKEN_LEASE_REDIS_URL 时租约保存在 Redis 中，可用于多个主机之间；
+// 否则使用 TOKEN_LEASE_DIR 中的租约文件，只适用于同一主机上的多个实例
+#[inline]
+pub fn is_enabled() -> bool {
+    redis::is_enabled() || !TOKEN_LEASE_DIR.is_empty()
+}
+
+/// 初始化租约目录，使用 Redis 时检查能否连接
+pub async fn init() {
+    if redis::is_enabled() {
+        if let Err(e) = redis::ping().await {
+            tracing::warn!("无法连接到租约使用的 Redis: {}", e);
+        }
+    } else if is_enabled() {
+        if let Err(e) = std::fs::create_dir_all(&*TOKEN_LEASE_DIR) {
+            tracing::warn!("无法创建租约目录 '{}': {}", *TOKEN_LEASE_DIR, e);
+        }
+    }
+}
+
+// 租约名使用 token 的哈希，避免在共享目录或 Redis 中暴露 token
+fn lease_name(token: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(token.as_bytes());
+    hex::encode(&hasher.finalize()[..16])
+}
+
+fn lease_path(token: &str) -> PathBuf {
+    PathBuf::from(&*TOKEN_LEASE_DIR).join(format!("{}.{}", lease_name(token), LEASE_EXTENSION))
+}
+
+// 租约文件内容格式: "<实例ID> <过期时间戳>"
+fn parse_lease(content: &str) -> Option<(&str, i64)> {
+    let (owner, expires_at) = content.trim().split_once(' ')?;
+    Some((owner, expires_at.parse().ok()?))
+}
+
+// 租约检查与写入期间对锁文件持有排他的 flock，同一时间只有一个实例能进入临界区
+//
+// 锁文件创建后不再删除，锁随文件句柄关闭释放，持有者异常退出时由系统释放，不需要判断锁是否失效。
+// flock 只在同一主机内可靠，NFS 等网络文件系统上的多个主机之间不能保证互斥，跨主机部署需使用 Redis
+struct LeaseGuard(std::fs::File);
+
+impl LeaseGuard {
+    fn open(lease_path: &Path) -> std::io::Result<std::fs::File> {
+        OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(lease_path.with_extension(GUARD_EXTENSION))
+    }
+
+    fn acquire(lease_path: &Path) -> std::io::Result<Option<Self>> {
+        let file = Self::open(lease_path)?;
+        match file.try_lock() {
+            Ok(()) => Ok(Some(Self(file))),
+            Err(TryLockError::WouldBlock) => Ok(None),
+            Err(TryLockError::Error(e)) => Err(e),
+        }
+    }
+
+    // 等待其他实例完成对该租约的操作
+    fn wait(lease_path: &Path) -> std::io::Result<Self> {
+        let file = Self::open(lease_path)?;
+        file.lock()?;
+        Ok(Self(file))
+    }
+}
+
+impl Drop for LeaseGuard {
+    fn drop(&mut self) {
+        if let Err(e) = self.0.unlock() {
+            tracing::warn!("释放租约锁失败: {}", e);
+        }
+    }
+}
+
+fn acquire_blocking(token: &str) -> std::io::Result<bool> {
+    let path = lease_path(token);
+    let Some(_guard) = LeaseGuard::acquire(&path)? else {
+        return Ok(false);
+    };
+
+    let now = chrono::Utc::now().timestamp();
+    match std::fs::read_to_string(&path) {
+        Ok(content) => {
+            if let Some((owner, expires_at)) = parse_lease(&content) {
+                if owner != INSTANCE_ID.as_str() && expires_at > now {
+                    return Ok(false);
+                }
+            }
+        }
+        Err(e) if e.kind() == ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+
+    // 先写入临时文件再重命名，保证其他实例不会读到不完整的内容
+    let content = format!("{} {}", *INSTANCE_ID, now + *TOKEN_LEASE_TTL as i64);
+    let tmp_path = path.with_extension(format!("{}.tmp", *INSTANCE_ID));
+    std::fs::write(&tmp_path, content)?;
+    std::fs::rename(&tmp_path, &path)?;
+    Ok(true)
+}
+
+/// 尝试为当前实例获取或续期 token 的租约
+///
+/// 未启用租约时总是成功；租约被其他实例持有且未过期、其他实例正在操作该租约或读写失败时返回 `false`
+pub async fn try_acquire(token: &str) -> bool {
+    if redis::is_enabled() {
+        return match redis::try_acquire(&lease_name(token), &INSTANCE_ID).await {
+            Ok(acquired) => acquired,
+            Err(e) => {
+                tracing::warn!("读写 Redis 租约失败: {}", e);
+                false
+            }
+        };
+    }
+    if !is_enabled() {
+        return true;
+    }
+
+    let token = token.to_string();
+    match tokio::task::spawn_blocking(move || acquire_blocking(&token)).await {
+        Ok(Ok(acquired)) => acquired,
+        Ok(Err(e)) => {
+            tracing::warn!("读写租约文件失败: {}", e);
+            false
+        }
+        Err(e) => {
+            tracing::warn!("租约任务失败: {}", e);
+            false
+        }
+    }
+}
+
+/// 释放当前实例持有的所有租约
+pub async fn release_all() {
+    if redis::is_enabled() {
+        redis::release_all(&INSTANCE_ID).await;
+        return;
+    }
+    if !is_enabled() {
+        return;
+    }
+
+    match tokio::task::spawn_blocking(release_all_blocking).await {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => tracing::warn!("释放租约失败: {}", e),
+        Err(e) => tracing::warn!("租约任务失败: {}", e),
+    }
+}
+
+// 持有锁文件后再检查持有者，避免删除其他实例刚刚获取或续期的租约
+fn release_all_blocking() -> std::io::Result<()> {
+    for entry in std::fs::read_dir(&*TOKEN_LEASE_DIR)? {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some(LEASE_EXTENSION) {
+            continue;
+        }
+        let _guard = LeaseGuard::wait(&path)?;
+        match std::fs::read_to_string(&path) {
+            Ok(content) => {
+                if parse_lease(&content).is_some_and(|(owner, _)| owner == INSTANCE_ID.as_str()) {
+                    std::fs::remove_file(&path)?;
+                }
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
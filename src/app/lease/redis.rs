@@ -0,0 +1,165 @@
+use super::super::lazy::{TOKEN_LEASE_REDIS_URL, TOKEN_LEASE_TTL};
+use std::{collections::HashSet, sync::LazyLock, time::Duration};
+use tokio::{
+    io::{AsyncBufReadExt as _, AsyncWriteExt as _, BufReader},
+    net::TcpStream,
+    sync::Mutex,
+};
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+const KEY_PREFIX: &str = "cursor-api:lease:";
+
+// 单次 Redis 操作的超时，超时后断开连接，下次操作时重连
+const TIMEOUT: Duration = Duration::from_secs(3);
+
+// 键不存在或由当前实例持有时写入并设置过期时间，检查与写入在 Redis 中原子执行
+const ACQUIRE_SCRIPT: &str = "local owner = redis.call('GET', KEYS[1]) \
+    if owner == false or owner == ARGV[1] then \
+    redis.call('SET', KEYS[1], ARGV[1], 'PX', ARGV[2]) return 1 end return 0";
+
+// 只删除由当前实例持有的键
+const RELEASE_SCRIPT: &str =
+    "if redis.call('GET', KEYS[1]) == ARGV[1] then return redis.call('DEL', KEYS[1]) end return 0";
+
+// 所有租约操作共用一个连接
+static CONNECTION: LazyLock<Mutex<Option<BufReader<TcpStream>>>> =
+    LazyLock::new(|| Mutex::new(None));
+
+// 当前实例获取过的租约，退出时逐个释放
+static HELD: LazyLock<parking_lot::Mutex<HashSet<String>>> =
+    LazyLock::new(|| parking_lot::Mutex::new(HashSet::new()));
+
+#[inline]
+pub fn is_enabled() -> bool {
+    !TOKEN_LEASE_REDIS_URL.is_empty()
+}
+
+/// 检查能否连接到 Redis
+pub async fn ping() -> Result<(), BoxError> {
+    match run(&["PING"]).await? {
+        Reply::Status(_) => Ok(()),
+        reply => Err(reply.unexpected()),
+    }
+}
+
+/// 获取或续期租约，被其他实例持有时返回 `false`
+pub async fn try_acquire(name: &str, owner: &str) -> Result<bool, BoxError> {
+    let key = format!("{}{}", KEY_PREFIX, name);
+    let ttl = (*TOKEN_LEASE_TTL * 1000).to_string();
+    match run(&["EVAL", ACQUIRE_SCRIPT, "1", &key, owner, &ttl]).await? {
+        Reply::Integer(1) => {
+            HELD.lock().insert(key);
+            Ok(true)
+        }
+        Reply::Integer(_) => Ok(false),
+        reply => Err(reply.unexpected()),
+    }
+}
+
+/// 释放当前实例持有的所有租约
+pub async fn release_all(owner: &str) {
+    let keys: Vec<String> = HELD.lock().drain().collect();
+    for key in keys {
+        if let Err(e) = run(&["EVAL", RELEASE_SCRIPT, "1", &key, owner]).await {
+            tracing::warn!("释放租约失败: {}", e);
+        }
+    }
+}
+
+enum Reply {
+    Status(String),
+    Integer(i64),
+}
+
+impl Reply {
+    fn unexpected(self) -> BoxError {
+        match self {
+            Self::Status(status) => format!("Redis 返回了意外的响应: {}", status).into(),
+            Self::Integer(n) => format!("Redis 返回了意外的响应: {}", n).into(),
+        }
+    }
+}
+
+// 出错或超时时丢弃连接，避免后续命令读到上一条命令的响应
+async fn run(args: &[&str]) -> Result<Reply, BoxError> {
+    let mut connection = CONNECTION.lock().await;
+    let result = tokio::time::timeout(TIMEOUT, async {
+        if connection.is_none() {
+            *connection = Some(connect().await?);
+        }
+        let stream = connection.as_mut().unwrap();
+        command(stream, args).await
+    })
+    .await
+    .unwrap_or_else(|_| Err("Redis 操作超时".into()));
+    if result.is_err() {
+        *connection = None;
+    }
+    result
+}
+
+// 格式: redis://主机:端口?password=密码&user=用户名&db=数据库编号
+async fn connect() -> Result<BufReader<TcpStream>, BoxError> {
+    let url = url::Url::parse(&TOKEN_LEASE_REDIS_URL)?;
+    if url.scheme() != "redis" {
+        return Err("TOKEN_LEASE_REDIS_URL 只支持 redis://".into());
+    }
+    let host = url.host_str().ok_or("缺少主机")?;
+    let port = url.port().unwrap_or(6379);
+
+    let mut user = None;
+    let mut password = None;
+    let mut db = None;
+    for (key, value) in url.query_pairs() {
+        match key.as_ref() {
+            "user" => user = Some(value.into_owned()),
+            "password" => password = Some(value.into_owned()),
+            "db" => db = Some(value.into_owned()),
+            _ => {}
+        }
+    }
+
+    let mut stream = BufReader::new(TcpStream::connect((host, port)).await?);
+    if let Some(password) = password {
+        let reply = match user {
+            Some(user) => command(&mut stream, &["AUTH", &user, &password]).await?,
+            None => command(&mut stream, &["AUTH", &password]).await?,
+        };
+        if !matches!(reply, Reply::Status(_)) {
+            return Err(reply.unexpected());
+        }
+    }
+    if let Some(db) = db {
+        let reply = command(&mut stream, &["SELECT", &db]).await?;
+        if !matches!(reply, Reply::Status(_)) {
+            return Err(reply.unexpected());
+        }
+    }
+    Ok(stream)
+}
+
+async fn command(stream: &mut BufReader<TcpStream>, args: &[&str]) -> Result<Reply, BoxError> {
+    let mut request = format!("*{}\r\n", args.len());
+    for arg in args {
+        request.push_str(&format!("${}\r\n{}\r\n", arg.len(), arg));
+    }
+    stream.write_all(request.as_bytes()).await?;
+    read_reply(stream).await
+}
+
+// 只解析租约用到的状态与整数响应，错误响应转为 Err，其他类型的响应会使连接被丢弃
+async fn read_reply(stream: &mut BufReader<TcpStream>) -> Result<Reply, BoxError> {
+    let mut line = String::new();
+    if stream.read_line(&mut line).await? == 0 {
+        return Err("连接已关闭".into());
+    }
+    let line = line.trim_end();
+    let (kind, value) = line.split_at_checked(1).ok_or("Redis 响应为空")?;
+    match kind {
+        "+" => Ok(Reply::Status(value.to_string())),
+        "-" => Err(format!("Redis 错误: {}", value).into()),
+        ":" => Ok(Reply::Integer(value.parse()?)),
+        _ => Err(format!("无法解析的 Redis 响应: {}", line).into()),
+    }
+}
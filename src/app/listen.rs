@@ -0,0 +1,67 @@
+use super::lazy::{LISTEN_ADDRS, LISTEN_UNIX_SOCKET};
+use axum::Router;
+use futures::future::BoxFuture;
+use std::net::SocketAddr;
+
+// 在所有配置的 TCP 地址与 Unix 套接字上提供服务，任一监听失败时返回错误
+pub async fn serve(app: Router) -> std::io::Result<()> {
+    #[cfg(not(feature = "tls"))]
+    if !super::lazy::TLS_CERT_PATH.is_empty() || !super::lazy::TLS_KEY_PATH.is_empty() {
+        tracing::warn!("未启用 tls 特性，忽略 TLS_CERT_PATH 与 TLS_KEY_PATH");
+    }
+
+    let mut servers: Vec<BoxFuture<'static, std::io::Result<()>>> = LISTEN_ADDRS
+        .iter()
+        .map(|addr| Box::pin(serve_tcp(addr, app.clone())) as BoxFuture<_>)
+        .collect();
+
+    if !LISTEN_UNIX_SOCKET.is_empty() {
+        #[cfg(unix)]
+        servers.push(Box::pin(serve_unix(&LISTEN_UNIX_SOCKET, app)));
+        #[cfg(not(unix))]
+        tracing::warn!("当前平台不支持 Unix 套接字，忽略 LISTEN_UNIX_SOCKET");
+    }
+
+    futures::future::try_join_all(servers).await.map(|_| ())
+}
+
+async fn serve_tcp(addr: &str, app: Router) -> std::io::Result<()> {
+    #[cfg(feature = "tls")]
+    if super::tls::is_enabled() {
+        return super::tls::serve(addr, app).await;
+    }
+
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    tracing::info!("服务器运行在 {}", addr);
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+}
+
+// 通常位于反向代理之后，不启用 HTTPS
+#[cfg(unix)]
+async fn serve_unix(path: &str, app: Router) -> std::io::Result<()> {
+    use super::lazy::LISTEN_UNIX_SOCKET_MODE;
+    use axum::{extract::ConnectInfo, Extension};
+    use std::os::unix::fs::{FileTypeExt as _, PermissionsExt as _};
+
+    // 清理上次运行遗留的套接字文件，不是套接字的同名文件保持原样
+    if std::fs::symlink_metadata(path).is_ok_and(|meta| meta.file_type().is_socket()) {
+        std::fs::remove_file(path)?;
+    }
+
+    let listener = tokio::net::UnixListener::bind(path)?;
+    if let Some(mode) = *LISTEN_UNIX_SOCKET_MODE {
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))?;
+    }
+    tracing::info!("服务器运行在 Unix 套接字 {}", path);
+
+    // Unix 套接字没有对端 IP，以回环地址代替，真实地址可通过 REAL_IP_HEADER 获取
+    let app = app.layer(Extension(ConnectInfo(SocketAddr::from((
+        [127, 0, 0, 1],
+        0,
+    )))));
+    axum::serve(listener, app).await
+}
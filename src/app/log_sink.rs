@@ -0,0 +1,163 @@
+use super::{
+    lazy::{LOG_SINK, LOG_SINK_INCLUDE_CONTENT},
+    log_stream,
+    model::{CostInfo, LogStatus, RequestLog, TimingInfo},
+};
+use crate::common::utils::extract_user_id;
+use serde::Serialize;
+use std::sync::OnceLock;
+use tokio::{io::AsyncWriteExt as _, sync::mpsc};
+
+// 队列满时丢弃新记录，避免拖慢请求处理
+const QUEUE_CAPACITY: usize = 1024;
+// 单次批量发送的最大记录数
+const BATCH_SIZE: usize = 100;
+
+static SENDER: OnceLock<mpsc::Sender<String>> = OnceLock::new();
+
+enum Sink {
+    Http(String),
+    File(String),
+    Syslog(String),
+}
+
+impl Sink {
+    fn parse(s: &str) -> Option<Self> {
+        if s.starts_with("http://") || s.starts_with("https://") {
+            Some(Self::Http(s.to_string()))
+        } else if let Some(addr) = s.strip_prefix("syslog://") {
+            Some(Self::Syslog(addr.to_string()))
+        } else {
+            s.strip_prefix("file://")
+                .or_else(|| s.strip_prefix("file:"))
+                .map(|path| Self::File(path.to_string()))
+        }
+    }
+}
+
+// 转发到外部的日志记录，不包含 token
+#[derive(Serialize)]
+struct SinkRecord<'a> {
+    id: u64,
+    timestamp: &'a chrono::DateTime<chrono::Local>,
+    model: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    user_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    api_key: Option<&'a str>,
+    stream: bool,
+    status: &'a LogStatus,
+    timing: &'a TimingInfo,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cost: Option<&'a CostInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    prompt: Option<&'a str>,
+}
+
+// 根据 LOG_SINK 启动后台转发任务
+pub fn init() {
+    if LOG_SINK.is_empty() {
+        return;
+    }
+
+    let Some(sink) = Sink::parse(&LOG_SINK) else {
+        tracing::warn!("无效的 LOG_SINK: {}", *LOG_SINK);
+        return;
+    };
+
+    let (tx, rx) = mpsc::channel(QUEUE_CAPACITY);
+    if SENDER.set(tx).is_ok() {
+        tokio::spawn(run(sink, rx));
+    }
+}
+
+// 提交一条已结束的请求日志，同时推送给实时日志的订阅者
+pub fn submit(log: &RequestLog) {
+    log_stream::publish(log);
+
+    let Some(tx) = SENDER.get() else {
+        return;
+    };
+
+    let record = SinkRecord {
+        id: log.id,
+        timestamp: &log.timestamp,
+        model: &log.model,
+        user_id: extract_user_id(&log.token_info.token),
+        api_key: log.api_key.as_deref(),
+        stream: log.stream,
+        status: &log.status,
+        timing: &log.timing,
+        cost: log.cost.as_ref(),
+        error: log.error.as_deref(),
+        prompt: if *LOG_SINK_INCLUDE_CONTENT {
+            log.prompt.as_deref()
+        } else {
+            None
+        },
+    };
+
+    if let Ok(line) = serde_json::to_string(&record) {
+        if tx.try_send(line).is_err() {
+            tracing::debug!("日志转发队列已满，丢弃记录 {}", log.id);
+        }
+    }
+}
+
+async fn run(sink: Sink, mut rx: mpsc::Receiver<String>) {
+    let client = reqwest::Client::new();
+    let mut batch = Vec::with_capacity(BATCH_SIZE);
+
+    while rx.recv_many(&mut batch, BATCH_SIZE).await > 0 {
+        if let Err(e) = send(&sink, &client, &batch).await {
+            tracing::warn!("日志转发失败: {}", e);
+        }
+        batch.clear();
+    }
+}
+
+async fn send(
+    sink: &Sink,
+    client: &reqwest::Client,
+    batch: &[String],
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    match sink {
+        // 以 JSON Lines 批量发送
+        Sink::Http(url) => {
+            let mut body = batch.join("\n");
+            body.push('\n');
+            client
+                .post(url)
+                .header(reqwest::header::CONTENT_TYPE, "application/x-ndjson")
+                .body(body)
+                .send()
+                .await?
+                .error_for_status()?;
+        }
+        Sink::File(path) => {
+            let mut file = tokio::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .await?;
+            for line in batch {
+                file.write_all(line.as_bytes()).await?;
+                file.write_all(b"\n").await?;
+            }
+            file.flush().await?;
+        }
+        // local0.info
+        Sink::Syslog(addr) => {
+            let socket = tokio::net::UdpSocket::bind("0.0.0.0:0").await?;
+            for line in batch {
+                socket
+                    .send_to(format!("<134>cursor-api: {}", line).as_bytes(), addr)
+                    .await?;
+            }
+        }
+    }
+
+    Ok(())
+}
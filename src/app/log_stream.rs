@@ -0,0 +1,45 @@
+use super::model::RequestLog;
+use serde::Serialize;
+use std::sync::{Arc, LazyLock};
+use tokio::sync::broadcast;
+
+// 订阅者处理过慢时最多积压的记录数，超出后该订阅者会收到 lagged 事件
+const CHANNEL_CAPACITY: usize = 256;
+
+// 事件名与 JSON 数据
+pub type Event = (&'static str, Arc<str>);
+
+static CHANNEL: LazyLock<broadcast::Sender<Event>> =
+    LazyLock::new(|| broadcast::channel(CHANNEL_CAPACITY).0);
+
+// 推送新建或状态变化的请求日志，没有订阅者时不做序列化
+pub fn publish(log: &RequestLog) {
+    if CHANNEL.receiver_count() == 0 {
+        return;
+    }
+    if let Ok(json) = serde_json::to_string(log) {
+        let _ = CHANNEL.send(("log", json.into()));
+    }
+}
+
+#[derive(Serialize)]
+pub struct DeniedSummary {
+    // 上次汇总之后被拒绝的请求数
+    pub count: u64,
+    // 最近一次被拒绝的来源地址
+    pub last_ip: String,
+}
+
+// 推送 IP 过滤拒绝请求的汇总，不写入请求日志
+pub fn publish_denied(summary: &DeniedSummary) {
+    if CHANNEL.receiver_count() == 0 {
+        return;
+    }
+    if let Ok(json) = serde_json::to_string(summary) {
+        let _ = CHANNEL.send(("denied", json.into()));
+    }
+}
+
+pub fn subscribe() -> broadcast::Receiver<Event> {
+    CHANNEL.subscribe()
+}
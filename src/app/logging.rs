@@ -0,0 +1,56 @@
+use super::lazy::{LOG_FORMAT, LOG_LEVEL};
+use parking_lot::RwLock;
+use std::sync::OnceLock;
+use tracing_subscriber::{fmt, layer::SubscriberExt as _, reload, util::SubscriberInitExt as _};
+use tracing_subscriber::{EnvFilter, Registry};
+
+static FILTER_HANDLE: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+static CURRENT_LEVEL: RwLock<String> = RwLock::new(String::new());
+
+/// 初始化全局日志
+///
+/// 日志级别优先读取 `RUST_LOG`，其次为 `LOG_LEVEL`，运行时可通过 [`set_level`] 修改；
+/// `LOG_FORMAT` 为 `json` 时输出结构化日志，否则输出便于阅读的文本格式
+pub fn init() {
+    let level = std::env::var(EnvFilter::DEFAULT_ENV)
+        .ok()
+        .filter(|level| EnvFilter::try_new(level).is_ok())
+        .unwrap_or_else(|| LOG_LEVEL.clone());
+    let (filter, level) = match EnvFilter::try_new(&level) {
+        Ok(filter) => (filter, level),
+        Err(_) => (EnvFilter::new("info"), "info".to_string()),
+    };
+
+    let (filter, handle) = reload::Layer::new(filter);
+    let _ = FILTER_HANDLE.set(handle);
+    *CURRENT_LEVEL.write() = level;
+
+    let registry = tracing_subscriber::registry().with(filter);
+
+    match LOG_FORMAT.as_str() {
+        "json" => registry
+            .with(
+                fmt::layer()
+                    .json()
+                    .flatten_event(true)
+                    .with_current_span(true)
+                    .with_span_list(false)
+                    .with_target(false),
+            )
+            .init(),
+        _ => registry.with(fmt::layer().with_target(false)).init(),
+    }
+}
+
+pub fn current_level() -> String {
+    CURRENT_LEVEL.read().clone()
+}
+
+/// 替换日志过滤规则，格式与 `RUST_LOG` 相同，如 `debug` 或 `info,cursor_api=debug`
+pub fn set_level(level: &str) -> Result<(), String> {
+    let filter = EnvFilter::try_new(level).map_err(|e| e.to_string())?;
+    let handle = FILTER_HANDLE.get().ok_or("日志尚未初始化")?;
+    handle.reload(filter).map_err(|e| e.to_string())?;
+    *CURRENT_LEVEL.write() = level.to_string();
+    Ok(())
+}
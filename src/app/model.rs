@@ -1,10 +1,17 @@
 use crate::{
+    app::cors::{is_valid_header_rule, is_valid_origin_rule},
     app::constant::{
         EMPTY_STRING, ERR_INVALID_PATH, ROUTE_ABOUT_PATH, ROUTE_API_PATH, ROUTE_BUILD_KEY_PATH,
         ROUTE_CONFIG_PATH, ROUTE_LOGS_PATH, ROUTE_README_PATH, ROUTE_ROOT_PATH,
         ROUTE_SHARED_JS_PATH, ROUTE_SHARED_STYLES_PATH, ROUTE_TOKENS_PATH,
     },
-    chat::model::Message,
+    app::lazy::{
+        REQUEST_LOGS_LIMIT, REQUEST_LOGS_MAX_AGE, REQUEST_LOGS_USER_LIMIT, REQUEST_METADATA_KEYS,
+    },
+    chat::{
+        constant::{METADATA_MAX_KEYS, METADATA_MAX_KEY_LENGTH, METADATA_MAX_VALUE_LENGTH},
+        model::{Message, MessageContent, Role},
+    },
     common::{
         client::rebuild_http_client,
         model::{userinfo::TokenProfile, ApiStatus},
@@ -12,19 +19,69 @@ use crate::{
     },
 };
 use parking_lot::RwLock;
-use rkyv::{Archive, Deserialize as RkyvDeserialize, Serialize as RkyvSerialize};
-use serde::{Deserialize, Serialize};
-use std::sync::LazyLock;
+use rkyv::{with::Skip, Archive, Deserialize as RkyvDeserialize, Serialize as RkyvSerialize};
+use serde::{de::IgnoredAny, Deserialize, Serialize};
+use std::{collections::HashMap, net::IpAddr, sync::LazyLock};
+use tokio_util::sync::CancellationToken;
 
 mod usage_check;
 pub use usage_check::UsageCheck;
 mod config;
+pub use config::SAVED_DATA;
 mod proxies;
 pub use proxies::Proxies;
 mod build_key;
 pub use build_key::*;
+mod model_policy;
+pub use model_policy::{ModelPolicies, UserModelPolicy};
+mod token_blacklist;
+pub use token_blacklist::TokenBlacklist;
+mod model_alias;
+pub use model_alias::{ModelAlias, ModelAliases};
+mod azure_deployment;
+pub use azure_deployment::{AzureDeployment, AzureDeployments};
+mod model_capability;
+pub use model_capability::{Capabilities, ModelCapabilities, ModelCapability};
+mod pricing;
+pub use pricing::{CostInfo, ModelPrice, ModelPrices, SpendLedger, SpendRecord};
+mod api_key;
+pub use api_key::{ip_matches, is_valid_ip_rule, ApiKey, ApiKeys, RegisterError};
+mod invite_code;
+pub use invite_code::{InviteCode, InviteCodes};
+mod audit_log;
+pub use audit_log::{AuditActor, AuditLog, AuditLogs};
+mod prompt_template;
+pub use prompt_template::{PromptTemplate, PromptTemplates, TemplateMessage, TemplateVariable};
+mod moderation;
+pub use moderation::{ModerationAction, ModerationRule, ModerationRules, ModerationScope};
+mod report;
+pub use report::{ModelUsage, Report, Reports};
+mod conversation;
+pub use conversation::{Conversation, ConversationMessage, Conversations};
+mod quality;
+pub use quality::{QualityBucket, QualitySample, QualitySamples};
+mod payload;
+pub use payload::{Payload, Payloads};
+mod quota;
+pub use quota::{QuotaSnapshot, QuotaSnapshots};
+
+mod daily_summary;
+pub use daily_summary::{DailySummaries, DailySummary, UsageEntry};
+
+mod tenant;
+pub use tenant::{Tenant, Tenants};
+
+mod revoked_session;
+pub use revoked_session::{RevokedSession, RevokedSessions};
+
+mod system_prompt;
+pub use system_prompt::{PromptMode, PromptScope, SystemPrompt, SystemPrompts};
+mod checksum_rotation;
+pub use checksum_rotation::{
+    ChecksumRotation, ChecksumRotations, ROTATION_MANUAL, ROTATION_REJECTED, ROTATION_SCHEDULED,
+};
 
-use super::constant::{STATUS_FAILED, STATUS_PENDING, STATUS_SUCCESS};
+use super::constant::{STATUS_CANCELLED, STATUS_FAILED, STATUS_PENDING, STATUS_SUCCESS};
 
 // 页面内容类型枚举
 #[derive(Clone, Serialize, Deserialize, Archive, RkyvDeserialize, RkyvSerialize)]
@@ -57,6 +114,18 @@ pub struct AppConfig {
     is_share: bool,
     proxies: Proxies,
     web_refs: bool,
+    token_warmup: bool,
+    token_warmup_required: bool,
+    // 单个 IP 或 CIDR，拒绝列表优先，允许列表为空时不限制
+    ip_allowlist: Vec<String>,
+    ip_denylist: Vec<String>,
+    // 允许跨域访问的来源，对话接口与管理接口分开设置，* 表示任意来源
+    cors_allowed_origins: Vec<String>,
+    cors_admin_origins: Vec<String>,
+    // 为空或包含 * 时允许任意请求头
+    cors_allowed_headers: Vec<String>,
+    cors_allow_credentials: bool,
+    debug: bool,
 }
 
 #[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
@@ -82,6 +151,14 @@ impl VisionAbility {
     pub fn is_none(&self) -> bool {
         matches!(self, VisionAbility::None)
     }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Base64 => "base64",
+            Self::All => "all",
+        }
+    }
 }
 
 impl Default for VisionAbility {
@@ -110,7 +187,29 @@ pub struct AppState {
     pub active_requests: u64,
     pub error_requests: u64,
     pub request_logs: Vec<RequestLog>,
+    // 每个 token 在 request_logs 中的日志条数，写入日志时无需遍历全部日志
+    log_counts: HashMap<String, usize>,
     pub token_infos: Vec<TokenInfo>,
+    // 进行中请求的取消令牌，按 chatcmpl id 索引
+    pub cancellations: HashMap<String, Cancellation>,
+    // 各优先级正在发往上游的请求数，依次为 high、normal、low
+    pub dispatching: [u64; 3],
+    // 下一条日志的 ID，只增不减，日志被清理后 ID 也不会复用
+    next_log_id: u64,
+}
+
+pub struct Cancellation {
+    // 发起请求时使用的认证令牌，只有它或 AUTH_TOKEN 可以取消
+    pub owner: String,
+    pub token: CancellationToken,
+}
+
+// 跨重启累计的请求统计
+#[derive(Clone, Copy, Default, Archive, RkyvDeserialize, RkyvSerialize)]
+#[archive(check_bytes)]
+pub struct RequestStats {
+    pub total_requests: u64,
+    pub error_requests: u64,
 }
 
 // 全局配置实例
@@ -205,7 +304,19 @@ impl AppConfig {
             Ok(proxies) => Proxies::from_str(proxies.as_str()),
             Err(_) => Proxies::default(),
         };
-        config.web_refs = parse_bool_from_env("INCLUDE_WEB_REFERENCES", false)
+        config.web_refs = parse_bool_from_env("INCLUDE_WEB_REFERENCES", false);
+        config.token_warmup = parse_bool_from_env("TOKEN_WARMUP", false);
+        config.token_warmup_required = parse_bool_from_env("TOKEN_WARMUP_REQUIRED", false);
+        config.ip_allowlist = parse_rules("IP_ALLOWLIST", EMPTY_STRING, is_valid_ip_rule);
+        config.ip_denylist = parse_rules("IP_DENYLIST", EMPTY_STRING, is_valid_ip_rule);
+        config.cors_allowed_origins =
+            parse_rules("CORS_ALLOWED_ORIGINS", "*", is_valid_origin_rule);
+        config.cors_admin_origins =
+            parse_rules("CORS_ADMIN_ORIGINS", EMPTY_STRING, is_valid_origin_rule);
+        config.cors_allowed_headers =
+            parse_rules("CORS_ALLOWED_HEADERS", "*", is_valid_header_rule);
+        config.cors_allow_credentials = parse_bool_from_env("CORS_ALLOW_CREDENTIALS", false);
+        config.debug = parse_bool_from_env("DEBUG", false);
     }
 
     config_methods! {
@@ -213,11 +324,41 @@ impl AppConfig {
         allow_claude: bool, false;
         dynamic_key: bool, false;
         web_refs: bool, false;
+        token_warmup: bool, false;
+        token_warmup_required: bool, false;
+        cors_allow_credentials: bool, false;
+        debug: bool, false;
     }
 
     config_methods_clone! {
         vision_ability: VisionAbility, VisionAbility::default();
         usage_check: UsageCheck, UsageCheck::default();
+        ip_allowlist: Vec<String>, Vec::new();
+        ip_denylist: Vec<String>, Vec::new();
+        cors_allowed_origins: Vec<String>, vec!["*".to_string()];
+        cors_admin_origins: Vec<String>, Vec::new();
+        cors_allowed_headers: Vec<String>, vec!["*".to_string()];
+    }
+
+    // 命中拒绝列表时拒绝，允许列表非空时只允许其中的地址
+    pub fn is_ip_allowed(ip: IpAddr) -> bool {
+        let config = APP_CONFIG.read();
+        !config.ip_denylist.iter().any(|rule| ip_matches(rule, ip))
+            && (config.ip_allowlist.is_empty()
+                || config.ip_allowlist.iter().any(|rule| ip_matches(rule, ip)))
+    }
+
+    // 对话接口与管理接口分别匹配各自的来源列表，来源不区分大小写
+    pub fn is_origin_allowed(origin: &str, api: bool) -> bool {
+        let config = APP_CONFIG.read();
+        let origins = if api {
+            &config.cors_allowed_origins
+        } else {
+            &config.cors_admin_origins
+        };
+        origins
+            .iter()
+            .any(|rule| rule == "*" || rule.eq_ignore_ascii_case(origin))
     }
 
     pub fn get_share_token() -> String {
@@ -265,6 +406,19 @@ impl AppConfig {
         }
     }
 
+    // 导入配置失败时用于回滚到导入前的状态
+    pub fn snapshot() -> Self {
+        APP_CONFIG.read().clone()
+    }
+
+    pub fn restore(config: Self) {
+        let proxies_changed = Self::get_proxies() != config.proxies;
+        *APP_CONFIG.write() = config;
+        if proxies_changed {
+            rebuild_http_client();
+        }
+    }
+
     pub fn get_page_content(path: &str) -> Option<PageContent> {
         match path {
             ROUTE_ROOT_PATH => Some(APP_CONFIG.read().pages.root_content.clone()),
@@ -322,6 +476,23 @@ impl AppConfig {
     }
 }
 
+// 逗号分隔的 IP 或 CIDR，忽略无效的规则
+fn parse_rules(key: &str, default: &str, is_valid: fn(&str) -> bool) -> Vec<String> {
+    parse_string_from_env(key, default)
+        .split(',')
+        .map(str::trim)
+        .filter(|rule| !rule.is_empty())
+        .filter(|rule| {
+            let valid = is_valid(rule);
+            if !valid {
+                tracing::warn!("忽略 {} 中无效的规则: {}", key, rule);
+            }
+            valid
+        })
+        .map(str::to_string)
+        .collect()
+}
+
 impl AppState {
     pub fn new(token_infos: Vec<TokenInfo>) -> Self {
         // 尝试加载保存的日志
@@ -330,15 +501,168 @@ impl AppState {
                 .block_on(async { Self::load_saved_logs().await.unwrap_or_default() })
         });
 
-        Self {
-            total_requests: request_logs.len() as u64,
+        // 尝试加载保存的统计，日志可能被截断，取两者中较大的值
+        let stats = RequestStats::load().unwrap_or_else(|e| {
+            tracing::warn!("加载保存的统计失败: {}", e);
+            RequestStats::default()
+        });
+
+        // 每条日志都计入 total_requests，从两者中较大的值之后开始编号，日志被清空后重启也不会复用 ID
+        let next_log_id = request_logs
+            .iter()
+            .map(|log| log.id)
+            .max()
+            .unwrap_or(0)
+            .max(stats.total_requests)
+            + 1;
+
+        let mut state = Self {
+            total_requests: stats.total_requests.max(request_logs.len() as u64),
             active_requests: 0,
-            error_requests: request_logs
-                .iter()
-                .filter(|log| matches!(log.status, LogStatus::Failed))
-                .count() as u64,
+            error_requests: stats.error_requests.max(
+                request_logs
+                    .iter()
+                    .filter(|log| matches!(log.status, LogStatus::Failed))
+                    .count() as u64,
+            ),
             request_logs,
+            log_counts: HashMap::new(),
             token_infos,
+            cancellations: HashMap::new(),
+            dispatching: [0; 3],
+            next_log_id,
+        };
+        state.prune_logs();
+        state
+    }
+
+    pub fn next_log_id(&mut self) -> u64 {
+        let id = self.next_log_id;
+        self.next_log_id += 1;
+        id
+    }
+
+    // 按保留策略清理日志，返回删除的条数
+    pub fn prune_logs(&mut self) -> usize {
+        let before = self.request_logs.len();
+
+        // 进行中的请求不按时长清理
+        if *REQUEST_LOGS_MAX_AGE > 0 {
+            let cutoff =
+                chrono::Local::now() - chrono::Duration::seconds(*REQUEST_LOGS_MAX_AGE as i64);
+            self.request_logs
+                .retain(|log| matches!(log.status, LogStatus::Pending) || log.timestamp >= cutoff);
+        }
+
+        // 每个 token 只保留最新的若干条
+        let user_limit = *REQUEST_LOGS_USER_LIMIT;
+        if user_limit > 0 {
+            let mut counts = HashMap::new();
+            let keep: Vec<bool> = self
+                .request_logs
+                .iter()
+                .rev()
+                .map(|log| {
+                    let count = counts
+                        .entry(log.token_info.token.as_str())
+                        .or_insert(0usize);
+                    *count += 1;
+                    *count <= user_limit
+                })
+                .collect();
+            let mut keep = keep.into_iter().rev();
+            self.request_logs.retain(|_| keep.next().unwrap_or(true));
+        }
+
+        let limit = *REQUEST_LOGS_LIMIT;
+        if self.request_logs.len() > limit {
+            let excess = self.request_logs.len() - limit;
+            self.request_logs.drain(..excess);
+        }
+
+        self.log_counts.clear();
+        for log in &self.request_logs {
+            *self
+                .log_counts
+                .entry(log.token_info.token.clone())
+                .or_insert(0) += 1;
+        }
+
+        before - self.request_logs.len()
+    }
+
+    // 写入一条日志并按保留策略清理，只检查过期的前缀与该 token 的计数
+    // 过期前缀之后的旧日志（例如长时间进行中的请求之后的）由 prune_logs 定期清理
+    pub fn push_log(&mut self, log: RequestLog) {
+        let token = log.token_info.token.clone();
+        self.request_logs.push(log);
+        let count = self.log_counts.entry(token.clone()).or_insert(0);
+        *count += 1;
+
+        if *REQUEST_LOGS_MAX_AGE > 0 {
+            let cutoff =
+                chrono::Local::now() - chrono::Duration::seconds(*REQUEST_LOGS_MAX_AGE as i64);
+            let expired = self
+                .request_logs
+                .iter()
+                .take_while(|log| log.timestamp < cutoff)
+                .count();
+            if expired > 0 {
+                let log_counts = &mut self.log_counts;
+                let pending: Vec<RequestLog> = self
+                    .request_logs
+                    .drain(..expired)
+                    .filter(|log| {
+                        let keep = matches!(log.status, LogStatus::Pending);
+                        if !keep {
+                            Self::forget_log(log_counts, &log.token_info.token);
+                        }
+                        keep
+                    })
+                    .collect();
+                self.request_logs.splice(0..0, pending);
+            }
+        }
+
+        let user_limit = *REQUEST_LOGS_USER_LIMIT;
+        if user_limit > 0
+            && self
+                .log_counts
+                .get(&token)
+                .is_some_and(|count| *count > user_limit)
+        {
+            if let Some(pos) = self
+                .request_logs
+                .iter()
+                .position(|log| log.token_info.token == token)
+            {
+                self.request_logs.remove(pos);
+                Self::forget_log(&mut self.log_counts, &token);
+            }
+        }
+
+        let limit = *REQUEST_LOGS_LIMIT;
+        if self.request_logs.len() > limit {
+            let excess = self.request_logs.len() - limit;
+            for log in self.request_logs.drain(..excess) {
+                Self::forget_log(&mut self.log_counts, &log.token_info.token);
+            }
+        }
+    }
+
+    fn forget_log(log_counts: &mut HashMap<String, usize>, token: &str) {
+        if let Some(count) = log_counts.get_mut(token) {
+            *count -= 1;
+            if *count == 0 {
+                log_counts.remove(token);
+            }
+        }
+    }
+
+    pub fn stats(&self) -> RequestStats {
+        RequestStats {
+            total_requests: self.total_requests,
+            error_requests: self.error_requests,
         }
     }
 
@@ -350,10 +674,13 @@ impl AppState {
 }
 
 #[derive(Clone, Archive, RkyvDeserialize, RkyvSerialize)]
+#[archive(check_bytes)]
 pub enum LogStatus {
     Pending,
     Success,
     Failed,
+    // 客户端在响应完成前断开
+    Cancelled,
 }
 
 impl Serialize for LogStatus {
@@ -365,12 +692,34 @@ impl Serialize for LogStatus {
     }
 }
 
+#[cfg(feature = "client")]
+impl<'de> Deserialize<'de> for LogStatus {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = <String as Deserialize>::deserialize(deserializer)?;
+        Self::from_str_name(&s).ok_or_else(|| {
+            serde::de::Error::unknown_variant(
+                &s,
+                &[
+                    STATUS_PENDING,
+                    STATUS_SUCCESS,
+                    STATUS_FAILED,
+                    STATUS_CANCELLED,
+                ],
+            )
+        })
+    }
+}
+
 impl LogStatus {
     pub fn as_str_name(&self) -> &'static str {
         match self {
             Self::Pending => STATUS_PENDING,
             Self::Success => STATUS_SUCCESS,
             Self::Failed => STATUS_FAILED,
+            Self::Cancelled => STATUS_CANCELLED,
         }
     }
 
@@ -379,6 +728,7 @@ impl LogStatus {
             STATUS_PENDING => Some(Self::Pending),
             STATUS_SUCCESS => Some(Self::Success),
             STATUS_FAILED => Some(Self::Failed),
+            STATUS_CANCELLED => Some(Self::Cancelled),
             _ => None,
         }
     }
@@ -386,6 +736,8 @@ impl LogStatus {
 
 // 请求日志
 #[derive(Serialize, Clone, Archive, RkyvDeserialize, RkyvSerialize)]
+#[archive(check_bytes)]
+#[cfg_attr(feature = "client", derive(serde::Deserialize))]
 pub struct RequestLog {
     pub id: u64,
     pub timestamp: chrono::DateTime<chrono::Local>,
@@ -398,31 +750,385 @@ pub struct RequestLog {
     pub status: LogStatus,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
+    // 不写入日志文件，保持文件格式兼容
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[with(Skip)]
+    pub cost: Option<CostInfo>,
+    // 通过 API key 发起的请求记录 key 的 ID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[with(Skip)]
+    pub api_key: Option<String>,
+    // 启用 USAGE_RECONCILE 时流式请求结束后的对账结果
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[with(Skip)]
+    pub reconciliation: Option<UsageReconciliation>,
+    // 请求是否使用慢速池
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub slow_pool: bool,
+    // 请求中允许记录的 metadata
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<HashMap<String, String>>,
+    // 请求的 X-Request-Id，与响应头及错误事件中的相同
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+impl RequestLog {
+    // 未分配 token 就结束的请求，token 信息留空，状态为失败，其余字段由调用方按需覆盖
+    pub fn rejected(
+        id: u64,
+        timestamp: chrono::DateTime<chrono::Local>,
+        model: String,
+        request_id: Option<String>,
+    ) -> Self {
+        Self {
+            id,
+            timestamp,
+            model,
+            token_info: TokenInfo {
+                token: String::new(),
+                checksum: String::new(),
+                alias: None,
+                profile: None,
+                warmup: None,
+                is_public: false,
+                note: None,
+                contact: None,
+                tags: Vec::new(),
+            },
+            prompt: None,
+            timing: TimingInfo {
+                total: 0.0,
+                first: None,
+                upstream: None,
+            },
+            stream: false,
+            status: LogStatus::Failed,
+            error: None,
+            cost: None,
+            api_key: None,
+            reconciliation: None,
+            slow_pool: false,
+            metadata: None,
+            request_id,
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Default)]
+#[cfg_attr(feature = "client", derive(serde::Deserialize))]
+pub struct UsageReconciliation {
+    // 本地估算的 token 数
+    pub counted_tokens: u32,
+    // 上游用量统计在请求前后的变化
+    pub reported_tokens: u32,
+    pub reported_requests: u32,
+    // 差异超过 USAGE_RECONCILE_TOLERANCE
+    pub discrepancy: bool,
 }
 
 #[derive(Serialize, Clone, Archive, RkyvDeserialize, RkyvSerialize)]
+#[archive(check_bytes)]
+#[cfg_attr(feature = "client", derive(serde::Deserialize))]
 pub struct TimingInfo {
     pub total: f64, // 总用时(秒)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub first: Option<f64>, // 首字时间(秒)
+    // 从发出上游请求到收到响应头的用时(秒)，含重试
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upstream: Option<f64>,
 }
 
 // 聊天请求
-#[derive(Deserialize)]
+#[derive(Deserialize, Clone)]
 pub struct ChatRequest {
+    // Azure OpenAI 风格的路由由部署名称决定模型，请求体中可以省略
+    #[serde(default)]
     pub model: String,
+    // 使用模板时可以省略，由模板渲染出消息
+    #[serde(default)]
     pub messages: Vec<Message>,
     #[serde(default)]
     pub stream: bool,
+    #[serde(default)]
+    pub stream_options: Option<StreamOptions>,
+    #[serde(default)]
+    pub n: Option<u32>,
+    // 上游不提供 logprobs，请求时回复中的 logprobs 为 null
+    #[serde(default)]
+    pub logprobs: Option<bool>,
+    #[serde(default)]
+    pub top_logprobs: Option<u32>,
+    // 通过系统指令要求 JSON 输出，并在返回前校验
+    #[serde(default)]
+    pub response_format: Option<ResponseFormat>,
+    // 非 OpenAI 参数，相同的值复用同一个上游会话
+    #[serde(default)]
+    pub conversation_id: Option<String>,
+    // 非 OpenAI 参数，指定是否使用慢速池，优先于模型名的 -slow 后缀
+    #[serde(default)]
+    pub slow_pool: Option<bool>,
+    // 非 OpenAI 参数，只从带有该标签的 token 中选择，优先于模型名的 @标签 后缀与请求头
+    #[serde(default)]
+    pub token_tag: Option<String>,
+    // 非 OpenAI 参数，使用服务端保存的提示词模板
+    #[serde(default, alias = "template_id")]
+    pub template: Option<String>,
+    #[serde(default, alias = "variables")]
+    pub template_vars: HashMap<String, String>,
+    // 客户端附加的键值对，允许的键会记录到日志并在响应中回显
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
+    // 通过租户路由访问时由服务端设置，不从请求中读取
+    #[serde(skip)]
+    pub tenant: Option<String>,
+    // 其余 OpenAI 参数上游无法支持，只保留名称用于告知客户端
+    #[serde(flatten)]
+    pub extra: HashMap<String, Option<IgnoredAny>>,
+}
+
+#[derive(Deserialize, Clone)]
+#[cfg_attr(feature = "client", derive(serde::Serialize))]
+pub struct StreamOptions {
+    #[serde(default)]
+    pub include_usage: bool,
+}
+
+#[derive(Deserialize, Clone)]
+#[cfg_attr(feature = "client", derive(serde::Serialize))]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ResponseFormat {
+    Text,
+    JsonObject,
+    JsonSchema { json_schema: JsonSchemaFormat },
+}
+
+#[derive(Deserialize, Clone)]
+#[cfg_attr(feature = "client", derive(serde::Serialize))]
+pub struct JsonSchemaFormat {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub schema: Option<serde_json::Value>,
+    // 上游无法保证，仅为兼容而接受
+    #[serde(default)]
+    pub strict: Option<bool>,
+}
+
+impl ResponseFormat {
+    #[inline]
+    pub fn is_json(&self) -> bool {
+        !matches!(self, ResponseFormat::Text)
+    }
+}
+
+impl ChatRequest {
+    // 去掉模型名的 -slow 后缀，未明确指定 slow_pool 时改为使用慢速池
+    pub fn apply_slow_pool_suffix(&mut self) {
+        if let Some(model) = self.model.strip_suffix("-slow") {
+            self.model = model.to_string();
+            self.slow_pool.get_or_insert(true);
+        }
+    }
+
+    // 去掉模型名的 @标签 后缀，未明确指定 token_tag 时改为使用该标签
+    pub fn apply_token_tag_suffix(&mut self) {
+        if let Some((model, tag)) = self.model.split_once('@') {
+            if !tag.is_empty() {
+                self.token_tag.get_or_insert_with(|| tag.to_string());
+            }
+            self.model = model.to_string();
+        }
+    }
+
+    // 渲染 template 并将得到的消息插入到最前面
+    pub fn apply_template(&mut self) -> Result<(), String> {
+        let Some(ref name) = self.template else {
+            if self.template_vars.is_empty() {
+                return Ok(());
+            }
+            return Err("template_vars requires template".to_string());
+        };
+        let template =
+            PromptTemplates::get(name).ok_or_else(|| format!("template '{}' not found", name))?;
+        let messages = template.render(&self.template_vars)?;
+        self.messages.splice(0..0, messages);
+        Ok(())
+    }
+
+    // 要求 JSON 输出时将格式说明作为系统消息追加到已有系统消息之后
+    pub fn apply_response_format(&mut self) {
+        let Some(instruction) = self
+            .response_format
+            .as_ref()
+            .and_then(crate::chat::json_mode::instruction)
+        else {
+            return;
+        };
+        let position = self
+            .messages
+            .iter()
+            .take_while(|message| message.role == Role::System)
+            .count();
+        self.messages.insert(
+            position,
+            Message {
+                role: Role::System,
+                content: MessageContent::Text(instruction),
+            },
+        );
+    }
+
+    // 按 REQUEST_METADATA_KEYS 筛选 metadata，超出长度限制的键值对会被丢弃
+    pub fn take_metadata(&mut self) -> Option<HashMap<String, String>> {
+        let allowed = &*REQUEST_METADATA_KEYS;
+        if allowed.is_empty() {
+            return None;
+        }
+        let allow_all = allowed.iter().any(|key| key == "*");
+
+        let mut entries: Vec<(String, String)> = std::mem::take(&mut self.metadata)
+            .into_iter()
+            .filter(|(key, value)| {
+                (allow_all || allowed.contains(key))
+                    && key.chars().count() <= METADATA_MAX_KEY_LENGTH
+                    && value.chars().count() <= METADATA_MAX_VALUE_LENGTH
+            })
+            .collect();
+        if entries.is_empty() {
+            return None;
+        }
+        // 超出数量限制时按键名保留，结果与传入顺序无关
+        entries.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        entries.truncate(METADATA_MAX_KEYS);
+        Some(entries.into_iter().collect())
+    }
+
+    pub fn include_usage(&self) -> bool {
+        self.stream
+            && self
+                .stream_options
+                .as_ref()
+                .is_some_and(|o| o.include_usage)
+    }
+
+    pub fn wants_logprobs(&self) -> bool {
+        self.logprobs == Some(true) || self.top_logprobs.is_some_and(|n| n > 0)
+    }
+
+    // 被忽略的参数名，值为 null 的参数不计入
+    pub fn ignored_params(&self) -> Vec<&str> {
+        let mut params: Vec<&str> = self
+            .extra
+            .iter()
+            .filter(|(_, value)| value.is_some())
+            .map(|(name, _)| name.as_str())
+            .collect();
+        if self.stream_options.is_some() && !self.stream {
+            params.push("stream_options");
+        }
+        if self.logprobs == Some(true) {
+            params.push("logprobs");
+        }
+        if self.top_logprobs.is_some() {
+            params.push("top_logprobs");
+        }
+        params.sort_unstable();
+        params
+    }
+}
+
+// 客户端只发送服务端识别的参数
+#[cfg(feature = "client")]
+impl Serialize for ChatRequest {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStruct as _;
+
+        let mut state = serializer.serialize_struct("ChatRequest", 14)?;
+        state.serialize_field("model", &self.model)?;
+        state.serialize_field("messages", &self.messages)?;
+        state.serialize_field("stream", &self.stream)?;
+        if let Some(ref stream_options) = self.stream_options {
+            state.serialize_field("stream_options", stream_options)?;
+        }
+        if let Some(n) = self.n {
+            state.serialize_field("n", &n)?;
+        }
+        if let Some(logprobs) = self.logprobs {
+            state.serialize_field("logprobs", &logprobs)?;
+        }
+        if let Some(top_logprobs) = self.top_logprobs {
+            state.serialize_field("top_logprobs", &top_logprobs)?;
+        }
+        if let Some(ref response_format) = self.response_format {
+            state.serialize_field("response_format", response_format)?;
+        }
+        if let Some(ref conversation_id) = self.conversation_id {
+            state.serialize_field("conversation_id", conversation_id)?;
+        }
+        if let Some(slow_pool) = self.slow_pool {
+            state.serialize_field("slow_pool", &slow_pool)?;
+        }
+        if let Some(ref token_tag) = self.token_tag {
+            state.serialize_field("token_tag", token_tag)?;
+        }
+        if let Some(ref template) = self.template {
+            state.serialize_field("template", template)?;
+        }
+        if !self.template_vars.is_empty() {
+            state.serialize_field("template_vars", &self.template_vars)?;
+        }
+        if !self.metadata.is_empty() {
+            state.serialize_field("metadata", &self.metadata)?;
+        }
+        state.end()
+    }
 }
 
 // 用于存储 token 信息
 #[derive(Serialize, Clone, Archive, RkyvDeserialize, RkyvSerialize)]
+#[archive(check_bytes)]
+#[cfg_attr(feature = "client", derive(serde::Deserialize))]
 pub struct TokenInfo {
     pub token: String,
     pub checksum: String,
+    // 别名仅保存在 token list 文件中，不写入日志
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[with(Skip)]
+    pub alias: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub profile: Option<TokenProfile>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[with(Skip)]
+    pub warmup: Option<TokenWarmup>,
+    // 是否加入公共号池，仅保存在 token list 文件中
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[with(Skip)]
+    pub is_public: bool,
+    // 备注与联系方式仅供管理员查看，同样只保存在 token list 文件中
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[with(Skip)]
+    pub note: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[with(Skip)]
+    pub contact: Option<String>,
+    // 标签用于按组选择 token，仅保存在 token list 文件中
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[with(Skip)]
+    pub tags: Vec<String>,
+}
+
+// 添加 token 时预热请求的结果，仅保存在内存中
+#[derive(Serialize, Clone)]
+#[cfg_attr(feature = "client", derive(serde::Deserialize))]
+pub struct TokenWarmup {
+    pub success: bool,
+    pub latency: f64,
+    pub checked_at: chrono::DateTime<chrono::Local>,
 }
 
 // TokenUpdateRequest 结构体
@@ -432,22 +1138,94 @@ pub struct TokenUpdateRequest {
 }
 
 #[derive(Deserialize)]
+#[cfg_attr(feature = "client", derive(serde::Serialize))]
 pub struct TokenAddRequestTokenInfo {
     pub token: String,
     #[serde(default)]
     pub checksum: Option<String>,
+    #[serde(default)]
+    pub alias: Option<String>,
+    #[serde(default)]
+    pub is_public: bool,
+    #[serde(default)]
+    pub note: Option<String>,
+    #[serde(default)]
+    pub contact: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+// 修改 token 的备注与联系方式，为空时清除
+#[derive(Deserialize)]
+#[cfg_attr(feature = "client", derive(serde::Serialize))]
+pub struct TokenMetaRequest {
+    pub token: String,
+    #[serde(default)]
+    pub note: Option<String>,
+    #[serde(default)]
+    pub contact: Option<String>,
+    // 不提供时保留原有标签，为空数组时清除
+    #[serde(default)]
+    pub tags: Option<Vec<String>>,
+}
+
+// 导入导出格式
+#[derive(Deserialize, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TokensTransferFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+#[derive(Deserialize, Default)]
+pub struct TokensTransferQuery {
+    #[serde(default)]
+    pub format: TokensTransferFormat,
+}
+
+#[derive(Serialize)]
+pub struct TokenExportInfo {
+    pub token: String,
+    pub checksum: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub is_public: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contact: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+// TokensImportResponse 结构体
+#[derive(Serialize)]
+pub struct TokensImportResponse {
+    pub status: ApiStatus,
+    pub imported_count: usize,
+    pub skipped_count: usize,
+    pub tokens_count: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub invalid_tokens: Vec<String>,
 }
 
 // TokensDeleteRequest 结构体
 #[derive(Deserialize)]
+#[cfg_attr(feature = "client", derive(serde::Serialize))]
 pub struct TokensDeleteRequest {
     #[serde(default)]
     pub tokens: Vec<String>,
     #[serde(default)]
     pub expectation: TokensDeleteResponseExpectation,
+    // 移入回收站而不是直接删除，之后可以恢复
+    #[serde(default)]
+    pub soft: bool,
 }
 
 #[derive(Deserialize, Default)]
+#[cfg_attr(feature = "client", derive(serde::Serialize))]
 #[serde(rename_all = "snake_case")]
 pub enum TokensDeleteResponseExpectation {
     #[default]
@@ -477,6 +1255,7 @@ impl TokensDeleteResponseExpectation {
 
 // TokensDeleteResponse 结构体
 #[derive(Serialize)]
+#[cfg_attr(feature = "client", derive(serde::Deserialize))]
 pub struct TokensDeleteResponse {
     pub status: ApiStatus,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -0,0 +1,202 @@
+use parking_lot::RwLock;
+use rkyv::{Archive, Deserialize as RkyvDeserialize, Serialize as RkyvSerialize};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::{net::IpAddr, sync::LazyLock};
+
+use super::{InviteCode, InviteCodes};
+use crate::app::constant::API_KEY_PREFIX;
+
+// 只保存密钥的哈希，明文仅在创建时返回一次
+#[derive(Clone, Serialize, Archive, RkyvDeserialize, RkyvSerialize)]
+#[archive(check_bytes)]
+pub struct ApiKey {
+    pub id: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    // 绑定到租户时只能通过该租户的路由使用
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+    #[serde(skip)]
+    pub key_hash: String,
+    // 明文前缀，便于辨认
+    pub key_prefix: String,
+    pub created_at: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<i64>,
+    // 为空时允许全部
+    pub scopes: Vec<String>,
+    // 允许的来源 IP 或 CIDR，为空时不限制
+    pub allowed_ips: Vec<String>,
+    pub revoked: bool,
+}
+
+impl ApiKey {
+    pub fn is_active(&self) -> bool {
+        !self.revoked
+            && self
+                .expires_at
+                .is_none_or(|expires_at| expires_at > chrono::Utc::now().timestamp())
+    }
+
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.is_empty() || self.scopes.iter().any(|s| s == scope)
+    }
+
+    pub fn allows_ip(&self, ip: IpAddr) -> bool {
+        self.allowed_ips.is_empty() || self.allowed_ips.iter().any(|rule| ip_matches(rule, ip))
+    }
+}
+
+// 解析单个 IP 或 CIDR（如 10.0.0.0/8），返回网络地址、前缀长度与地址位数
+fn parse_ip_rule(rule: &str) -> Option<(IpAddr, u32, u32)> {
+    let (addr, prefix) = match rule.trim().split_once('/') {
+        Some((addr, prefix)) => (addr, Some(prefix)),
+        None => (rule.trim(), None),
+    };
+    let addr: IpAddr = addr.parse().ok()?;
+    let bits = if addr.is_ipv4() { 32 } else { 128 };
+    let prefix = match prefix {
+        Some(prefix) => prefix.parse().ok().filter(|prefix| *prefix <= bits)?,
+        None => bits,
+    };
+    Some((addr, prefix, bits))
+}
+
+pub fn is_valid_ip_rule(rule: &str) -> bool {
+    parse_ip_rule(rule).is_some()
+}
+
+pub fn ip_matches(rule: &str, ip: IpAddr) -> bool {
+    let Some((net, prefix, bits)) = parse_ip_rule(rule) else {
+        return false;
+    };
+    // IPv4 映射的 IPv6 地址按 IPv4 比较
+    let (net, ip) = match (net, ip.to_canonical()) {
+        (IpAddr::V4(net), IpAddr::V4(ip)) => (u32::from(net) as u128, u32::from(ip) as u128),
+        (IpAddr::V6(net), IpAddr::V6(ip)) => (u128::from(net), u128::from(ip)),
+        _ => return false,
+    };
+    let shift = bits - prefix;
+    shift == bits || (net >> shift) == (ip >> shift)
+}
+
+static API_KEYS: LazyLock<RwLock<Vec<ApiKey>>> = LazyLock::new(|| RwLock::new(Vec::new()));
+
+fn hash_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+// 生成新的密钥，返回记录与明文密钥
+fn new_key(
+    name: String,
+    owner: Option<String>,
+    tenant: Option<String>,
+    expires_at: Option<i64>,
+    scopes: Vec<String>,
+    allowed_ips: Vec<String>,
+) -> (ApiKey, String) {
+    let key = format!("{}{}", API_KEY_PREFIX, uuid::Uuid::new_v4().simple());
+    let api_key = ApiKey {
+        id: uuid::Uuid::new_v4().simple().to_string()[..12].to_string(),
+        name,
+        owner,
+        tenant,
+        key_hash: hash_key(&key),
+        key_prefix: key[..API_KEY_PREFIX.len() + 6].to_string(),
+        created_at: chrono::Utc::now().timestamp(),
+        expires_at,
+        scopes,
+        allowed_ips,
+        revoked: false,
+    };
+    (api_key, key)
+}
+
+pub enum RegisterError {
+    NameTaken,
+    InvalidInvite,
+}
+
+pub struct ApiKeys;
+
+impl ApiKeys {
+    // 返回新建的记录与明文密钥
+    pub fn create(
+        name: String,
+        owner: Option<String>,
+        tenant: Option<String>,
+        expires_at: Option<i64>,
+        scopes: Vec<String>,
+        allowed_ips: Vec<String>,
+    ) -> (ApiKey, String) {
+        let (api_key, key) = new_key(name, owner, tenant, expires_at, scopes, allowed_ips);
+        API_KEYS.write().push(api_key.clone());
+        (api_key, key)
+    }
+
+    // 在同一把写锁内检查用户名、兑换邀请码并签发密钥，并发注册同名用户时只有一个成功
+    pub fn register(name: &str, code: &str) -> Result<(InviteCode, ApiKey, String), RegisterError> {
+        let mut keys = API_KEYS.write();
+        if keys
+            .iter()
+            .any(|api_key| api_key.owner.as_deref() == Some(name))
+        {
+            return Err(RegisterError::NameTaken);
+        }
+        let invite = InviteCodes::redeem(code, name).ok_or(RegisterError::InvalidInvite)?;
+        let (api_key, key) = new_key(
+            name.to_string(),
+            Some(name.to_string()),
+            invite.tenant.clone(),
+            None,
+            Vec::new(),
+            Vec::new(),
+        );
+        keys.push(api_key.clone());
+        Ok((invite, api_key, key))
+    }
+
+    // 仅返回未吊销且未过期的密钥
+    pub fn authenticate(key: &str) -> Option<ApiKey> {
+        Self::find(key).filter(|api_key| api_key.is_active())
+    }
+
+    // 包括已吊销或已过期的密钥
+    pub fn find(key: &str) -> Option<ApiKey> {
+        if !key.starts_with(API_KEY_PREFIX) {
+            return None;
+        }
+        let hash = hash_key(key);
+        API_KEYS
+            .read()
+            .iter()
+            .find(|api_key| api_key.key_hash == hash)
+            .cloned()
+    }
+
+    pub fn list() -> Vec<ApiKey> {
+        API_KEYS.read().clone()
+    }
+
+    pub fn revoke(id: &str) -> bool {
+        API_KEYS
+            .write()
+            .iter_mut()
+            .find(|api_key| api_key.id == id)
+            .map(|api_key| api_key.revoked = true)
+            .is_some()
+    }
+
+    pub fn remove(id: &str) -> bool {
+        let mut keys = API_KEYS.write();
+        let before = keys.len();
+        keys.retain(|api_key| api_key.id != id);
+        keys.len() < before
+    }
+
+    pub(super) fn replace_all(list: Vec<ApiKey>) {
+        *API_KEYS.write() = list;
+    }
+}
@@ -0,0 +1,96 @@
+use parking_lot::RwLock;
+use rkyv::{Archive, Deserialize as RkyvDeserialize, Serialize as RkyvSerialize};
+use serde::Serialize;
+use std::sync::LazyLock;
+
+use super::TokenInfo;
+use crate::{app::lazy::AUDIT_LOGS_LIMIT, common::utils::extract_user_id};
+
+// 管理操作的审计记录，快照为 JSON 文本且不包含 token 明文
+#[derive(Clone, Serialize, Archive, RkyvDeserialize, RkyvSerialize)]
+#[archive(check_bytes)]
+pub struct AuditLog {
+    pub id: u64,
+    pub timestamp: i64,
+    pub actor: String,
+    pub ip: String,
+    // 如 config.update、tokens.add、blacklist.add、logs.cleanup
+    pub action: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub before: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<String>,
+}
+
+// 发起操作的管理员，名称由请求使用的认证方式决定
+pub struct AuditActor {
+    pub name: String,
+    pub ip: String,
+}
+
+static AUDIT_LOGS: LazyLock<RwLock<Vec<AuditLog>>> = LazyLock::new(|| RwLock::new(Vec::new()));
+
+pub struct AuditLogs;
+
+impl AuditLogs {
+    // 记录后立即保存，保存失败只记录警告
+    pub async fn record(
+        actor: &AuditActor,
+        action: &str,
+        before: Option<String>,
+        after: Option<String>,
+    ) {
+        if *AUDIT_LOGS_LIMIT == 0 {
+            return;
+        }
+
+        tracing::info!("审计: {} ({}) {}", actor.name, actor.ip, action);
+        {
+            let mut logs = AUDIT_LOGS.write();
+            let id = logs.last().map_or(1, |log| log.id + 1);
+            logs.push(AuditLog {
+                id,
+                timestamp: chrono::Utc::now().timestamp(),
+                actor: actor.name.clone(),
+                ip: actor.ip.clone(),
+                action: action.to_string(),
+                before,
+                after,
+            });
+            let excess = logs.len().saturating_sub(*AUDIT_LOGS_LIMIT);
+            logs.drain(..excess);
+        }
+
+        if let Err(e) = Self::save().await {
+            tracing::warn!("保存审计日志失败: {}", e);
+        }
+    }
+
+    pub fn list() -> Vec<AuditLog> {
+        AUDIT_LOGS.read().clone()
+    }
+
+    pub(super) fn replace_all(list: Vec<AuditLog>) {
+        *AUDIT_LOGS.write() = list;
+    }
+
+    pub fn snapshot<T: Serialize + ?Sized>(value: &T) -> Option<String> {
+        serde_json::to_string(value).ok()
+    }
+
+    // token 只保留别名或用户 ID，都没有时保留前缀
+    pub fn token_snapshot(token_infos: &[TokenInfo]) -> Option<String> {
+        let tokens: Vec<String> = token_infos
+            .iter()
+            .map(|info| {
+                info.alias
+                    .clone()
+                    .or_else(|| extract_user_id(&info.token))
+                    .unwrap_or_else(|| {
+                        format!("{}...", info.token.chars().take(8).collect::<String>())
+                    })
+            })
+            .collect();
+        Self::snapshot(&tokens)
+    }
+}
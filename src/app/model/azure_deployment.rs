@@ -0,0 +1,58 @@
+use parking_lot::RwLock;
+use rkyv::{Archive, Deserialize as RkyvDeserialize, Serialize as RkyvSerialize};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::LazyLock};
+
+// Azure OpenAI 风格路由中的部署名称到实际模型的映射
+#[derive(Clone, Serialize, Deserialize, Archive, RkyvDeserialize, RkyvSerialize)]
+#[archive(check_bytes)]
+pub struct AzureDeployment {
+    pub deployment: String,
+    pub model: String,
+}
+
+static AZURE_DEPLOYMENTS: LazyLock<RwLock<HashMap<String, String>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+pub struct AzureDeployments;
+
+impl AzureDeployments {
+    // 未配置的部署名称直接作为模型名称使用
+    pub fn resolve(deployment: &str) -> String {
+        AZURE_DEPLOYMENTS
+            .read()
+            .get(deployment)
+            .cloned()
+            .unwrap_or_else(|| deployment.to_string())
+    }
+
+    pub fn list() -> Vec<AzureDeployment> {
+        let mut list: Vec<_> = AZURE_DEPLOYMENTS
+            .read()
+            .iter()
+            .map(|(deployment, model)| AzureDeployment {
+                deployment: deployment.clone(),
+                model: model.clone(),
+            })
+            .collect();
+        list.sort_unstable_by(|a, b| a.deployment.cmp(&b.deployment));
+        list
+    }
+
+    pub fn set(entry: AzureDeployment) {
+        AZURE_DEPLOYMENTS
+            .write()
+            .insert(entry.deployment, entry.model);
+    }
+
+    pub fn remove(deployment: &str) -> bool {
+        AZURE_DEPLOYMENTS.write().remove(deployment).is_some()
+    }
+
+    pub(super) fn replace_all(list: Vec<AzureDeployment>) {
+        *AZURE_DEPLOYMENTS.write() = list
+            .into_iter()
+            .map(|entry| (entry.deployment, entry.model))
+            .collect();
+    }
+}
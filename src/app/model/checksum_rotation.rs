@@ -0,0 +1,74 @@
+use parking_lot::RwLock;
+use rkyv::{Archive, Deserialize as RkyvDeserialize, Serialize as RkyvSerialize};
+use serde::Serialize;
+use std::{collections::VecDeque, sync::LazyLock};
+
+use crate::app::lazy::CHECKSUM_ROTATIONS_LIMIT;
+
+// 定时轮换
+pub const ROTATION_SCHEDULED: &str = "scheduled";
+// 通过管理接口轮换
+pub const ROTATION_MANUAL: &str = "manual";
+// 上游拒绝 checksum 后重新生成
+pub const ROTATION_REJECTED: &str = "rejected";
+
+// checksum 的轮换记录
+#[derive(Clone, Serialize, Archive, RkyvDeserialize, RkyvSerialize)]
+#[archive(check_bytes)]
+pub struct ChecksumRotation {
+    pub timestamp: i64,
+    pub token: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+    pub reason: String,
+}
+
+static ROTATIONS: LazyLock<RwLock<VecDeque<ChecksumRotation>>> =
+    LazyLock::new(|| RwLock::new(VecDeque::new()));
+
+pub struct ChecksumRotations;
+
+impl ChecksumRotations {
+    // 超出 CHECKSUM_ROTATIONS_LIMIT 时删除最早的记录
+    pub fn push(rotation: ChecksumRotation) {
+        let mut rotations = ROTATIONS.write();
+        rotations.push_back(rotation);
+        while rotations.len() > *CHECKSUM_ROTATIONS_LIMIT {
+            rotations.pop_front();
+        }
+    }
+
+    // 最新的记录在前，可按 token 过滤
+    pub fn recent(token: Option<&str>, limit: usize) -> Vec<ChecksumRotation> {
+        ROTATIONS
+            .read()
+            .iter()
+            .rev()
+            .filter(|rotation| token.is_none_or(|token| rotation.token == token))
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    // token 最近一次轮换的时间
+    pub fn last_rotated(token: &str) -> Option<i64> {
+        ROTATIONS
+            .read()
+            .iter()
+            .rev()
+            .find(|rotation| rotation.token == token)
+            .map(|rotation| rotation.timestamp)
+    }
+
+    pub(super) fn list() -> Vec<ChecksumRotation> {
+        ROTATIONS.read().iter().cloned().collect()
+    }
+
+    pub(super) fn replace_all(list: Vec<ChecksumRotation>) {
+        let mut rotations = ROTATIONS.write();
+        *rotations = list.into();
+        while rotations.len() > *CHECKSUM_ROTATIONS_LIMIT {
+            rotations.pop_front();
+        }
+    }
+}
@@ -1,89 +1,728 @@
 use memmap2::{MmapMut, MmapOptions};
-use rkyv::{archived_root, Deserialize as _};
-use std::fs::OpenOptions;
+use parking_lot::Mutex;
+use rkyv::{
+    archived_root, check_archived_root, ser::serializers::AllocSerializer,
+    validation::validators::DefaultValidator, Archive, CheckBytes, Deserialize as RkyvDeserialize,
+    Infallible, Serialize as RkyvSerialize,
+};
+use std::{
+    collections::HashMap,
+    fs::OpenOptions,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, LazyLock,
+    },
+};
 
-use crate::app::lazy::{LOGS_FILE_PATH, PAGES_FILE_PATH};
+use crate::app::{
+    lazy::{
+        API_KEYS_FILE_PATH, AUDIT_LOGS_FILE_PATH, AZURE_DEPLOYMENTS_FILE_PATH,
+        CHECKSUM_ROTATIONS_FILE_PATH, CONFIG_FILE_PATH, CONVERSATIONS_FILE_PATH,
+        DAILY_SUMMARIES_FILE_PATH, INVITE_CODES_FILE_PATH, LOGS_FILE_PATH, MODEL_ALIASES_FILE_PATH,
+        MODEL_CAPABILITIES_FILE_PATH, MODEL_POLICIES_FILE_PATH, MODEL_PRICES_FILE_PATH,
+        MODERATION_RULES_FILE_PATH, PAGES_FILE_PATH, PAYLOADS_FILE_PATH,
+        PROMPT_TEMPLATES_FILE_PATH, QUALITY_SAMPLES_FILE_PATH, QUOTA_SNAPSHOTS_FILE_PATH,
+        REPORTS_FILE_PATH, REVOKED_SESSIONS_FILE_PATH, SPEND_FILE_PATH, STATS_FILE_PATH,
+        SYSTEM_PROMPTS_FILE_PATH, TENANTS_FILE_PATH,
+    },
+    logging,
+};
+use crate::common::utils::{decrypt_field, encrypt_field, is_encryption_enabled, EncryptError};
 
-use super::{AppConfig, AppState, Pages, RequestLog, APP_CONFIG};
+use super::{
+    ApiKeys, AppConfig, AppState, AuditLogs, AzureDeployments, ChecksumRotation, ChecksumRotations,
+    Conversations, DailySummaries, InviteCodes, LogStatus, ModelAliases, ModelCapabilities,
+    ModelPolicies, ModelPrices, ModerationRules, Pages, Payload, Payloads, PromptTemplates,
+    Proxies, QualitySamples, QuotaSnapshot, QuotaSnapshots, Reports, RequestLog, RequestStats,
+    RevokedSessions, SpendLedger, SpendRecord, SystemPrompts, Tenants, TimingInfo, TokenInfo,
+    UsageCheck, VisionAbility, APP_CONFIG,
+};
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+type Loader = fn() -> Result<(), BoxError>;
+
+// 同一文件的写入需要串行，否则并发的保存会互相截断正在写入的临时文件
+static WRITE_LOCKS: LazyLock<Mutex<HashMap<String, Arc<Mutex<()>>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+static TMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+fn write_lock(path: &str) -> Arc<Mutex<()>> {
+    WRITE_LOCKS
+        .lock()
+        .entry(path.to_string())
+        .or_default()
+        .clone()
+}
+
+// 通过内存映射将序列化数据写入临时文件，同步到磁盘后替换原文件，写入中途失败不会损坏已有数据
+fn write_mmap_file(path: &str, bytes: &[u8]) -> Result<(), BoxError> {
+    let lock = write_lock(path);
+    let _guard = lock.lock();
+
+    // 临时文件名按进程与写入次数区分，多个实例共用数据目录时也不会互相覆盖
+    let tmp_path = format!(
+        "{}.{}.{}.tmp",
+        path,
+        std::process::id(),
+        TMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    );
+    let result = write_and_replace(&tmp_path, path, bytes);
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp_path);
+    }
+    result
+}
+
+fn write_and_replace(tmp_path: &str, path: &str, bytes: &[u8]) -> Result<(), BoxError> {
+    // 创建文件
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create_new(true)
+        .open(tmp_path)?;
+
+    // 添加大小检查
+    if bytes.len() > usize::MAX / 2 {
+        return Err("数据过大".into());
+    }
+
+    // 设置文件大小
+    file.set_len(bytes.len() as u64)?;
+
+    // 创建可写入的内存映射
+    let mut mmap = unsafe { MmapMut::map_mut(&file)? };
+
+    // 写入数据
+    mmap.copy_from_slice(bytes);
+
+    // 同步到磁盘
+    mmap.flush()?;
+    drop(mmap);
+    file.sync_all()?;
+    drop(file);
+
+    std::fs::rename(tmp_path, path)?;
+
+    Ok(())
+}
+
+/// 启动时按顺序加载的持久化数据，启动流程与自检共用
+pub const SAVED_DATA: &[(&str, Loader)] = &[
+    ("模型策略", ModelPolicies::load),
+    ("模型别名", ModelAliases::load),
+    ("模型能力信息", ModelCapabilities::load),
+    ("部署映射", AzureDeployments::load),
+    ("模型单价", ModelPrices::load),
+    ("消费统计", SpendLedger::load),
+    ("API key", ApiKeys::load),
+    ("邀请码", InviteCodes::load),
+    ("审计日志", AuditLogs::load),
+    ("提示词模板", PromptTemplates::load),
+    ("审核规则", ModerationRules::load),
+    ("使用报告", Reports::load),
+    ("会话历史", Conversations::load),
+    ("质量抽样", QualitySamples::load),
+    ("请求内容", Payloads::load),
+    ("checksum 轮换记录", ChecksumRotations::load),
+    ("额度快照", QuotaSnapshots::load),
+    ("租户", Tenants::load),
+    ("系统提示词", SystemPrompts::load),
+    ("已退出的会话", RevokedSessions::load),
+    ("每日用量汇总", DailySummaries::load),
+];
+
+// 序列化后在阻塞线程池中写入文件，避免阻塞异步运行时
+async fn save_archive<T>(path: &'static str, value: &T) -> Result<(), Box<dyn std::error::Error>>
+where
+    T: RkyvSerialize<AllocSerializer<256>>,
+{
+    let bytes = rkyv::to_bytes::<_, 256>(value)?;
+
+    tokio::task::spawn_blocking(move || write_mmap_file(path, &bytes))
+        .await?
+        .map_err(|e| e as Box<dyn std::error::Error>)
+}
+
+// 校验后再读取，损坏的文件不会导致未定义行为；文件不存在时返回 None
+fn load_archive<T>(path: &str, corrupted: &'static str) -> Result<Option<T>, BoxError>
+where
+    T: Archive,
+    T::Archived: for<'a> CheckBytes<DefaultValidator<'a>> + RkyvDeserialize<T, Infallible>,
+{
+    let file = match OpenOptions::new().read(true).open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(Box::new(e)),
+    };
+
+    let mmap = unsafe { MmapOptions::new().map(&file)? };
+
+    let archived = check_archived_root::<T>(&mmap).map_err(|_| corrupted)?;
+    Ok(Some(archived.deserialize(&mut Infallible)?))
+}
+
+// 设置了 TOKEN_ENCRYPTION_KEY 时，日志与消费统计中的 token 加密后保存，加密失败时不保存
+fn encrypt_token_info(info: &mut TokenInfo) -> Result<(), EncryptError> {
+    info.token = encrypt_field(&info.token)?;
+    info.checksum = encrypt_field(&info.checksum)?;
+    Ok(())
+}
+
+// 无法解密时保留密文，只影响展示
+fn decrypt_token_info(info: &mut TokenInfo) {
+    if let Some(token) = decrypt_field(&info.token) {
+        info.token = token;
+    }
+    if let Some(checksum) = decrypt_field(&info.checksum) {
+        info.checksum = checksum;
+    }
+}
+
+// 日志文件以魔数与版本号开头，长度保持 16 字节以免影响归档数据的对齐；
+// 没有文件头的是旧版本格式，加载时迁移，下次保存时改写为当前格式
+const LOGS_MAGIC: &[u8; 12] = b"cursor-logs\0";
+const LOGS_VERSION: u32 = 1;
+const LOGS_HEADER_LEN: usize = 16;
+
+// 旧版本日志文件的布局，只包含当时写入文件的字段
+#[derive(Archive, RkyvDeserialize)]
+#[archive(check_bytes)]
+struct LegacyRequestLog {
+    id: u64,
+    timestamp: chrono::DateTime<chrono::Local>,
+    model: String,
+    token_info: TokenInfo,
+    prompt: Option<String>,
+    timing: LegacyTimingInfo,
+    stream: bool,
+    status: LogStatus,
+    error: Option<String>,
+}
+
+#[derive(Archive, RkyvDeserialize)]
+#[archive(check_bytes)]
+struct LegacyTimingInfo {
+    total: f64,
+    first: Option<f64>,
+}
+
+impl From<LegacyRequestLog> for RequestLog {
+    fn from(log: LegacyRequestLog) -> Self {
+        Self {
+            id: log.id,
+            timestamp: log.timestamp,
+            model: log.model,
+            token_info: log.token_info,
+            prompt: log.prompt,
+            timing: TimingInfo {
+                total: log.timing.total,
+                first: log.timing.first,
+                upstream: None,
+            },
+            stream: log.stream,
+            status: log.status,
+            error: log.error,
+            cost: None,
+            api_key: None,
+            reconciliation: None,
+            slow_pool: false,
+            metadata: None,
+            request_id: None,
+        }
+    }
+}
 
 impl AppState {
     // 保存日志的方法
-    pub(crate) async fn save_logs(&self) -> Result<(), Box<dyn std::error::Error>> {
+    pub async fn save_logs(&self) -> Result<(), Box<dyn std::error::Error>> {
         // 序列化日志
-        let bytes = rkyv::to_bytes::<_, 256>(&self.request_logs)?;
+        let archive = if is_encryption_enabled() {
+            let mut logs = self.request_logs.clone();
+            for log in &mut logs {
+                encrypt_token_info(&mut log.token_info)?;
+            }
+            rkyv::to_bytes::<_, 256>(&logs)?
+        } else {
+            rkyv::to_bytes::<_, 256>(&self.request_logs)?
+        };
+        let mut bytes = Vec::with_capacity(LOGS_HEADER_LEN + archive.len());
+        bytes.extend_from_slice(LOGS_MAGIC);
+        bytes.extend_from_slice(&LOGS_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&archive);
+
+        // 文件写入在阻塞线程池中进行，避免阻塞异步运行时
+        tokio::task::spawn_blocking(move || write_mmap_file(LOGS_FILE_PATH.as_str(), &bytes))
+            .await?
+            .map_err(|e| e as Box<dyn std::error::Error>)
+    }
+
+    // 加载日志的方法
+    pub(crate) async fn load_saved_logs() -> Result<Vec<RequestLog>, Box<dyn std::error::Error>> {
+        tokio::task::spawn_blocking(|| -> Result<Vec<RequestLog>, BoxError> {
+            let file = match OpenOptions::new().read(true).open(LOGS_FILE_PATH.as_str()) {
+                Ok(file) => file,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    return Ok(Vec::new());
+                }
+                Err(e) => return Err(Box::new(e)),
+            };
+
+            // 添加文件大小检查
+            if file.metadata()?.len() > usize::MAX as u64 {
+                return Err("日志文件过大".into());
+            }
+
+            // 创建只读内存映射
+            let mmap = unsafe { MmapOptions::new().map(&file)? };
+
+            // 验证并反序列化数据
+            let mut logs: Vec<RequestLog> = match mmap.strip_prefix(LOGS_MAGIC.as_slice()) {
+                Some(rest) => {
+                    let version = rest
+                        .get(..4)
+                        .map(|v| u32::from_le_bytes([v[0], v[1], v[2], v[3]]));
+                    if version != Some(LOGS_VERSION) {
+                        return Err(format!("不支持的日志文件版本: {:?}", version).into());
+                    }
+                    let archived = check_archived_root::<Vec<RequestLog>>(&mmap[LOGS_HEADER_LEN..])
+                        .map_err(|_| "日志文件已损坏")?;
+                    archived.deserialize(&mut Infallible)?
+                }
+                None => {
+                    let archived = check_archived_root::<Vec<LegacyRequestLog>>(&mmap)
+                        .map_err(|_| "日志文件已损坏")?;
+                    let logs: Vec<LegacyRequestLog> = archived.deserialize(&mut Infallible)?;
+                    logs.into_iter().map(RequestLog::from).collect()
+                }
+            };
+            logs.iter_mut()
+                .for_each(|log| decrypt_token_info(&mut log.token_info));
+            Ok(logs)
+        })
+        .await?
+        .map_err(|e| e as Box<dyn std::error::Error>)
+    }
+}
+
+impl RequestStats {
+    // 保存统计的方法
+    pub async fn save(self) -> Result<(), Box<dyn std::error::Error>> {
+        save_archive(STATS_FILE_PATH.as_str(), &self).await
+    }
+
+    // 加载统计的方法
+    pub(super) fn load() -> Result<Self, BoxError> {
+        Ok(load_archive::<Self>(STATS_FILE_PATH.as_str(), "统计文件已损坏")?.unwrap_or_default())
+    }
+}
 
-        // 创建或打开文件
-        let file = OpenOptions::new()
-            .read(true)
-            .write(true)
-            .create(true)
-            .open(LOGS_FILE_PATH.as_str())?;
+impl ModelPolicies {
+    // 保存模型策略的方法
+    pub async fn save() -> Result<(), Box<dyn std::error::Error>> {
+        save_archive(MODEL_POLICIES_FILE_PATH.as_str(), &Self::list()).await
+    }
 
-        // 添加大小检查
-        if bytes.len() > usize::MAX / 2 {
-            return Err("日志数据过大".into());
+    // 加载模型策略的方法
+    pub fn load() -> Result<(), BoxError> {
+        if let Some(list) = load_archive(MODEL_POLICIES_FILE_PATH.as_str(), "模型策略文件已损坏")?
+        {
+            Self::replace_all(list);
         }
 
-        // 设置文件大小
-        file.set_len(bytes.len() as u64)?;
+        Ok(())
+    }
+}
 
-        // 创建可写入的内存映射
-        let mut mmap = unsafe { MmapMut::map_mut(&file)? };
+impl ModelAliases {
+    // 保存模型别名的方法
+    pub async fn save() -> Result<(), Box<dyn std::error::Error>> {
+        save_archive(MODEL_ALIASES_FILE_PATH.as_str(), &Self::list()).await
+    }
 
-        // 写入数据
-        mmap.copy_from_slice(&bytes);
+    // 加载模型别名的方法
+    pub fn load() -> Result<(), BoxError> {
+        if let Some(list) = load_archive(MODEL_ALIASES_FILE_PATH.as_str(), "模型别名文件已损坏")?
+        {
+            Self::replace_all(list);
+        }
 
-        // 同步到磁盘
-        mmap.flush()?;
+        Ok(())
+    }
+}
+
+impl ModelPrices {
+    // 保存模型单价的方法
+    pub async fn save() -> Result<(), Box<dyn std::error::Error>> {
+        save_archive(MODEL_PRICES_FILE_PATH.as_str(), &Self::list()).await
+    }
+
+    // 加载模型单价的方法
+    pub fn load() -> Result<(), BoxError> {
+        if let Some(list) = load_archive(MODEL_PRICES_FILE_PATH.as_str(), "模型单价文件已损坏")?
+        {
+            Self::replace_all(list);
+        }
 
         Ok(())
     }
+}
 
-    // 加载日志的方法
-    pub(super) async fn load_saved_logs() -> Result<Vec<RequestLog>, Box<dyn std::error::Error>> {
-        let file = match OpenOptions::new().read(true).open(LOGS_FILE_PATH.as_str()) {
-            Ok(file) => file,
-            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
-                return Ok(Vec::new());
+impl SpendLedger {
+    // 保存消费统计的方法
+    pub async fn save() -> Result<(), Box<dyn std::error::Error>> {
+        let mut records = Self::list();
+        for record in &mut records {
+            record.token = encrypt_field(&record.token)?;
+        }
+        save_archive(SPEND_FILE_PATH.as_str(), &records).await
+    }
+
+    // 加载消费统计的方法
+    pub fn load() -> Result<(), BoxError> {
+        let Some(mut records) =
+            load_archive::<Vec<SpendRecord>>(SPEND_FILE_PATH.as_str(), "消费统计文件已损坏")?
+        else {
+            return Ok(());
+        };
+        for record in &mut records {
+            if let Some(token) = decrypt_field(&record.token) {
+                record.token = token;
             }
-            Err(e) => return Err(Box::new(e)),
+        }
+        Self::replace_all(records);
+
+        Ok(())
+    }
+}
+
+impl ApiKeys {
+    // 保存 API key 的方法
+    pub async fn save() -> Result<(), Box<dyn std::error::Error>> {
+        save_archive(API_KEYS_FILE_PATH.as_str(), &Self::list()).await
+    }
+
+    // 加载 API key 的方法
+    pub fn load() -> Result<(), BoxError> {
+        if let Some(list) = load_archive(API_KEYS_FILE_PATH.as_str(), "API key 文件已损坏")? {
+            Self::replace_all(list);
+        }
+
+        Ok(())
+    }
+}
+
+impl InviteCodes {
+    // 保存邀请码的方法
+    pub async fn save() -> Result<(), Box<dyn std::error::Error>> {
+        save_archive(INVITE_CODES_FILE_PATH.as_str(), &Self::list()).await
+    }
+
+    // 加载邀请码的方法
+    pub fn load() -> Result<(), BoxError> {
+        if let Some(list) = load_archive(INVITE_CODES_FILE_PATH.as_str(), "邀请码文件已损坏")?
+        {
+            Self::replace_all(list);
+        }
+
+        Ok(())
+    }
+}
+
+impl AuditLogs {
+    // 保存审计日志的方法
+    pub async fn save() -> Result<(), Box<dyn std::error::Error>> {
+        save_archive(AUDIT_LOGS_FILE_PATH.as_str(), &Self::list()).await
+    }
+
+    // 加载审计日志的方法
+    pub fn load() -> Result<(), BoxError> {
+        if let Some(list) = load_archive(AUDIT_LOGS_FILE_PATH.as_str(), "审计日志文件已损坏")?
+        {
+            Self::replace_all(list);
+        }
+
+        Ok(())
+    }
+}
+
+impl PromptTemplates {
+    // 保存提示词模板的方法
+    pub async fn save() -> Result<(), Box<dyn std::error::Error>> {
+        save_archive(PROMPT_TEMPLATES_FILE_PATH.as_str(), &Self::list()).await
+    }
+
+    // 加载提示词模板的方法
+    pub fn load() -> Result<(), BoxError> {
+        if let Some(list) =
+            load_archive(PROMPT_TEMPLATES_FILE_PATH.as_str(), "提示词模板文件已损坏")?
+        {
+            Self::replace_all(list);
+        }
+
+        Ok(())
+    }
+}
+
+impl ModerationRules {
+    // 保存审核规则的方法
+    pub async fn save() -> Result<(), Box<dyn std::error::Error>> {
+        save_archive(MODERATION_RULES_FILE_PATH.as_str(), &Self::list()).await
+    }
+
+    // 加载审核规则的方法
+    pub fn load() -> Result<(), BoxError> {
+        if let Some(list) = load_archive(MODERATION_RULES_FILE_PATH.as_str(), "审核规则文件已损坏")?
+        {
+            Self::replace_all(list);
+        }
+
+        Ok(())
+    }
+}
+
+impl Reports {
+    // 保存历史报告的方法
+    pub async fn save() -> Result<(), Box<dyn std::error::Error>> {
+        save_archive(REPORTS_FILE_PATH.as_str(), &Self::list()).await
+    }
+
+    // 加载历史报告的方法
+    pub fn load() -> Result<(), BoxError> {
+        if let Some(list) = load_archive(REPORTS_FILE_PATH.as_str(), "报告文件已损坏")? {
+            Self::replace_all(list);
+        }
+
+        Ok(())
+    }
+}
+
+impl Conversations {
+    // 保存会话历史的方法
+    pub async fn save() -> Result<(), Box<dyn std::error::Error>> {
+        save_archive(CONVERSATIONS_FILE_PATH.as_str(), &Self::list()).await
+    }
+
+    // 加载会话历史的方法
+    pub fn load() -> Result<(), BoxError> {
+        if let Some(list) = load_archive(CONVERSATIONS_FILE_PATH.as_str(), "会话历史文件已损坏")?
+        {
+            Self::replace_all(list);
+        }
+
+        Ok(())
+    }
+}
+
+impl QualitySamples {
+    // 保存质量抽样的方法
+    pub async fn save() -> Result<(), Box<dyn std::error::Error>> {
+        save_archive(QUALITY_SAMPLES_FILE_PATH.as_str(), &Self::list()).await
+    }
+
+    // 加载质量抽样的方法
+    pub fn load() -> Result<(), BoxError> {
+        if let Some(list) = load_archive(QUALITY_SAMPLES_FILE_PATH.as_str(), "质量抽样文件已损坏")?
+        {
+            Self::replace_all(list);
+        }
+
+        Ok(())
+    }
+}
+
+impl Payloads {
+    // 保存请求内容的方法，启用加密时与 token 一样加密保存
+    pub async fn save() -> Result<(), Box<dyn std::error::Error>> {
+        let mut payloads = Self::list();
+        for payload in &mut payloads {
+            payload.request = encrypt_field(&payload.request)?;
+            payload.response = payload.response.as_deref().map(encrypt_field).transpose()?;
+        }
+        save_archive(PAYLOADS_FILE_PATH.as_str(), &payloads).await
+    }
+
+    // 加载请求内容的方法，无法解密的记录被丢弃
+    pub fn load() -> Result<(), BoxError> {
+        let Some(payloads) =
+            load_archive::<Vec<Payload>>(PAYLOADS_FILE_PATH.as_str(), "请求内容文件已损坏")?
+        else {
+            return Ok(());
         };
+        Self::replace_all(
+            payloads
+                .into_iter()
+                .filter_map(|payload| {
+                    let response = match payload.response {
+                        Some(ref response) => Some(decrypt_field(response)?),
+                        None => None,
+                    };
+                    Some(Payload {
+                        request: decrypt_field(&payload.request)?,
+                        response,
+                        ..payload
+                    })
+                })
+                .collect(),
+        );
 
-        // 添加文件大小检查
-        if file.metadata()?.len() > usize::MAX as u64 {
-            return Err("日志文件过大".into());
+        Ok(())
+    }
+}
+
+impl ChecksumRotations {
+    // 保存 checksum 轮换记录的方法
+    pub async fn save() -> Result<(), Box<dyn std::error::Error>> {
+        let mut rotations = Self::list();
+        for rotation in &mut rotations {
+            rotation.token = encrypt_field(&rotation.token)?;
         }
+        save_archive(CHECKSUM_ROTATIONS_FILE_PATH.as_str(), &rotations).await
+    }
 
-        // 创建只读内存映射
-        let mmap = unsafe { MmapOptions::new().map(&file)? };
+    // 加载 checksum 轮换记录的方法
+    pub fn load() -> Result<(), BoxError> {
+        let Some(mut rotations) = load_archive::<Vec<ChecksumRotation>>(
+            CHECKSUM_ROTATIONS_FILE_PATH.as_str(),
+            "checksum 轮换记录文件已损坏",
+        )?
+        else {
+            return Ok(());
+        };
+        for rotation in &mut rotations {
+            if let Some(token) = decrypt_field(&rotation.token) {
+                rotation.token = token;
+            }
+        }
+        Self::replace_all(rotations);
 
-        // 验证并反序列化数据
-        let archived = unsafe { archived_root::<Vec<RequestLog>>(&mmap) };
-        Ok(archived.deserialize(&mut rkyv::Infallible)?)
+        Ok(())
     }
 }
 
+impl QuotaSnapshots {
+    // 保存额度快照的方法
+    pub async fn save() -> Result<(), Box<dyn std::error::Error>> {
+        let mut snapshots = Self::list();
+        for snapshot in &mut snapshots {
+            snapshot.token = encrypt_field(&snapshot.token)?;
+        }
+        save_archive(QUOTA_SNAPSHOTS_FILE_PATH.as_str(), &snapshots).await
+    }
+
+    // 加载额度快照的方法，无法解密的记录被丢弃
+    pub fn load() -> Result<(), BoxError> {
+        let Some(snapshots) = load_archive::<Vec<QuotaSnapshot>>(
+            QUOTA_SNAPSHOTS_FILE_PATH.as_str(),
+            "额度快照文件已损坏",
+        )?
+        else {
+            return Ok(());
+        };
+        Self::replace_all(
+            snapshots
+                .into_iter()
+                .filter_map(|snapshot| {
+                    Some(QuotaSnapshot {
+                        token: decrypt_field(&snapshot.token)?,
+                        ..snapshot
+                    })
+                })
+                .collect(),
+        );
+
+        Ok(())
+    }
+}
+
+// 通过配置接口修改的设置，枚举值按环境变量的格式保存
+#[derive(Archive, RkyvDeserialize, RkyvSerialize)]
+#[archive(check_bytes)]
+struct Settings {
+    vision_ability: String,
+    slow_pool: bool,
+    allow_claude: bool,
+    usage_check: String,
+    dynamic_key: bool,
+    share_token: String,
+    proxies: String,
+    web_refs: bool,
+    token_warmup: bool,
+    token_warmup_required: bool,
+    ip_allowlist: Vec<String>,
+    ip_denylist: Vec<String>,
+    cors_allowed_origins: Vec<String>,
+    cors_admin_origins: Vec<String>,
+    cors_allowed_headers: Vec<String>,
+    cors_allow_credentials: bool,
+    debug: bool,
+    log_level: String,
+}
+
 impl AppConfig {
     pub fn save_config() -> Result<(), Box<dyn std::error::Error>> {
-        let pages = APP_CONFIG.read().pages.clone();
-        let bytes = rkyv::to_bytes::<_, 256>(&pages)?;
+        let (pages, settings) = {
+            let config = APP_CONFIG.read();
+            let settings = Settings {
+                vision_ability: config.vision_ability.as_str().to_string(),
+                slow_pool: config.slow_pool,
+                allow_claude: config.allow_claude,
+                usage_check: config.usage_check.to_env_string(),
+                dynamic_key: config.dynamic_key,
+                share_token: config.share_token.clone(),
+                proxies: config.proxies.to_env_string(),
+                web_refs: config.web_refs,
+                token_warmup: config.token_warmup,
+                token_warmup_required: config.token_warmup_required,
+                ip_allowlist: config.ip_allowlist.clone(),
+                ip_denylist: config.ip_denylist.clone(),
+                cors_allowed_origins: config.cors_allowed_origins.clone(),
+                cors_admin_origins: config.cors_admin_origins.clone(),
+                cors_allowed_headers: config.cors_allowed_headers.clone(),
+                cors_allow_credentials: config.cors_allow_credentials,
+                debug: config.debug,
+                log_level: logging::current_level(),
+            };
+            (config.pages.clone(), settings)
+        };
 
-        let file = OpenOptions::new()
-            .read(true)
-            .write(true)
-            .create(true)
-            .open(PAGES_FILE_PATH.as_str())?;
+        let bytes = rkyv::to_bytes::<_, 256>(&pages)?;
+        write_mmap_file(PAGES_FILE_PATH.as_str(), &bytes)
+            .map_err(|e| e as Box<dyn std::error::Error>)?;
 
-        // 添加大小检查
-        if bytes.len() > usize::MAX / 2 {
-            return Err("配置数据过大".into());
-        }
+        let bytes = rkyv::to_bytes::<_, 256>(&settings)?;
+        write_mmap_file(CONFIG_FILE_PATH.as_str(), &bytes)
+            .map_err(|e| e as Box<dyn std::error::Error>)
+    }
 
-        file.set_len(bytes.len() as u64)?;
+    // 保存的设置优先于环境变量
+    fn load_saved_settings() -> Result<(), Box<dyn std::error::Error>> {
+        let Some(settings) = load_archive::<Settings>(CONFIG_FILE_PATH.as_str(), "设置文件已损坏")
+            .map_err(|e| e as Box<dyn std::error::Error>)?
+        else {
+            return Ok(());
+        };
 
-        let mut mmap = unsafe { MmapMut::map_mut(&file)? };
-        mmap.copy_from_slice(&bytes);
-        mmap.flush()?;
+        Self::update_vision_ability(VisionAbility::from_str(&settings.vision_ability));
+        Self::update_slow_pool(settings.slow_pool);
+        Self::update_allow_claude(settings.allow_claude);
+        Self::update_usage_check(UsageCheck::from_str(&settings.usage_check));
+        Self::update_dynamic_key(settings.dynamic_key);
+        Self::update_share_token(settings.share_token);
+        Self::update_proxies(Proxies::from_str(&settings.proxies));
+        Self::update_web_refs(settings.web_refs);
+        Self::update_token_warmup(settings.token_warmup);
+        Self::update_token_warmup_required(settings.token_warmup_required);
+        Self::update_ip_allowlist(settings.ip_allowlist);
+        Self::update_ip_denylist(settings.ip_denylist);
+        Self::update_cors_allowed_origins(settings.cors_allowed_origins);
+        Self::update_cors_admin_origins(settings.cors_admin_origins);
+        Self::update_cors_allowed_headers(settings.cors_allowed_headers);
+        Self::update_cors_allow_credentials(settings.cors_allow_credentials);
+        Self::update_debug(settings.debug);
+        if let Err(e) = logging::set_level(&settings.log_level) {
+            tracing::warn!("无法应用保存的日志级别: {}", e);
+        }
 
         Ok(())
     }
@@ -92,7 +731,7 @@ impl AppConfig {
         let file = match OpenOptions::new().read(true).open(PAGES_FILE_PATH.as_str()) {
             Ok(file) => file,
             Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
-                return Ok(());
+                return Self::load_saved_settings();
             }
             Err(e) => return Err(Box::new(e)),
         };
@@ -105,9 +744,113 @@ impl AppConfig {
         let mmap = unsafe { MmapOptions::new().map(&file)? };
 
         let archived = unsafe { archived_root::<Pages>(&mmap) };
-        let pages = archived.deserialize(&mut rkyv::Infallible)?;
-        let mut config = APP_CONFIG.write();
-        config.pages = pages;
+        let pages = archived.deserialize(&mut Infallible)?;
+        APP_CONFIG.write().pages = pages;
+
+        Self::load_saved_settings()
+    }
+}
+
+impl DailySummaries {
+    // 保存每日用量汇总的方法
+    pub async fn save() -> Result<(), Box<dyn std::error::Error>> {
+        save_archive(DAILY_SUMMARIES_FILE_PATH.as_str(), &Self::list()).await
+    }
+
+    // 加载每日用量汇总的方法
+    pub fn load() -> Result<(), BoxError> {
+        if let Some(list) =
+            load_archive(DAILY_SUMMARIES_FILE_PATH.as_str(), "每日用量汇总文件已损坏")?
+        {
+            Self::replace_all(list);
+        }
+
+        Ok(())
+    }
+}
+
+impl Tenants {
+    // 保存租户的方法
+    pub async fn save() -> Result<(), Box<dyn std::error::Error>> {
+        save_archive(TENANTS_FILE_PATH.as_str(), &Self::list()).await
+    }
+
+    // 加载租户的方法
+    pub fn load() -> Result<(), BoxError> {
+        if let Some(list) = load_archive(TENANTS_FILE_PATH.as_str(), "租户文件已损坏")? {
+            Self::replace_all(list);
+        }
+
+        Ok(())
+    }
+}
+
+impl SystemPrompts {
+    // 保存系统提示词的方法
+    pub async fn save() -> Result<(), Box<dyn std::error::Error>> {
+        save_archive(SYSTEM_PROMPTS_FILE_PATH.as_str(), &Self::list()).await
+    }
+
+    // 加载系统提示词的方法
+    pub fn load() -> Result<(), BoxError> {
+        if let Some(list) = load_archive(SYSTEM_PROMPTS_FILE_PATH.as_str(), "系统提示词文件已损坏")?
+        {
+            Self::replace_all(list);
+        }
+
+        Ok(())
+    }
+}
+
+impl RevokedSessions {
+    // 保存已退出会话的方法
+    pub async fn save() -> Result<(), Box<dyn std::error::Error>> {
+        save_archive(REVOKED_SESSIONS_FILE_PATH.as_str(), &Self::list()).await
+    }
+
+    // 加载已退出会话的方法
+    pub fn load() -> Result<(), BoxError> {
+        if let Some(list) = load_archive(REVOKED_SESSIONS_FILE_PATH.as_str(), "会话文件已损坏")?
+        {
+            Self::replace_all(list);
+        }
+
+        Ok(())
+    }
+}
+
+impl ModelCapabilities {
+    // 保存模型能力信息的方法
+    pub async fn save() -> Result<(), Box<dyn std::error::Error>> {
+        save_archive(MODEL_CAPABILITIES_FILE_PATH.as_str(), &Self::list()).await
+    }
+
+    // 加载模型能力信息的方法
+    pub fn load() -> Result<(), BoxError> {
+        if let Some(list) = load_archive(
+            MODEL_CAPABILITIES_FILE_PATH.as_str(),
+            "模型能力信息文件已损坏",
+        )? {
+            Self::replace_all(list);
+        }
+
+        Ok(())
+    }
+}
+
+impl AzureDeployments {
+    // 保存部署映射的方法
+    pub async fn save() -> Result<(), Box<dyn std::error::Error>> {
+        save_archive(AZURE_DEPLOYMENTS_FILE_PATH.as_str(), &Self::list()).await
+    }
+
+    // 加载部署映射的方法
+    pub fn load() -> Result<(), BoxError> {
+        if let Some(list) =
+            load_archive(AZURE_DEPLOYMENTS_FILE_PATH.as_str(), "部署映射文件已损坏")?
+        {
+            Self::replace_all(list);
+        }
 
         Ok(())
     }
@@ -0,0 +1,124 @@
+use parking_lot::RwLock;
+use rkyv::{Archive, Deserialize as RkyvDeserialize, Serialize as RkyvSerialize};
+use serde::Serialize;
+use std::{collections::HashMap, sync::LazyLock};
+
+use crate::app::lazy::{CONVERSATIONS_LIMIT, CONVERSATION_MAX_MESSAGES};
+
+// 服务端保存的会话历史，按调用方凭证的哈希区分所有者
+#[derive(Clone, Serialize, Archive, RkyvDeserialize, RkyvSerialize)]
+#[archive(check_bytes)]
+pub struct Conversation {
+    pub id: String,
+    #[serde(skip)]
+    pub owner: String,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub message_count: usize,
+    // 列表中不返回消息内容
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub messages: Vec<ConversationMessage>,
+}
+
+#[derive(Clone, Serialize, Archive, RkyvDeserialize, RkyvSerialize)]
+#[archive(check_bytes)]
+pub struct ConversationMessage {
+    pub role: String,
+    pub content: String,
+    pub created_at: i64,
+}
+
+static CONVERSATIONS: LazyLock<RwLock<HashMap<(String, String), Conversation>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+pub struct Conversations;
+
+impl Conversations {
+    pub fn messages(owner: &str, id: &str) -> Vec<ConversationMessage> {
+        CONVERSATIONS
+            .read()
+            .get(&(owner.to_string(), id.to_string()))
+            .map(|conversation| conversation.messages.clone())
+            .unwrap_or_default()
+    }
+
+    // 追加消息，超出 CONVERSATION_MAX_MESSAGES 时删除最早的消息，
+    // 会话数超出 CONVERSATIONS_LIMIT 时删除最久未更新的会话
+    pub fn append(owner: &str, id: &str, messages: Vec<ConversationMessage>) {
+        let now = chrono::Utc::now().timestamp();
+        let key = (owner.to_string(), id.to_string());
+        let mut conversations = CONVERSATIONS.write();
+
+        if !conversations.contains_key(&key) && conversations.len() >= *CONVERSATIONS_LIMIT {
+            if let Some(oldest) = conversations
+                .iter()
+                .min_by_key(|(_, conversation)| conversation.updated_at)
+                .map(|(key, _)| key.clone())
+            {
+                conversations.remove(&oldest);
+            }
+        }
+
+        let conversation = conversations.entry(key).or_insert_with(|| Conversation {
+            id: id.to_string(),
+            owner: owner.to_string(),
+            created_at: now,
+            updated_at: now,
+            message_count: 0,
+            messages: Vec::new(),
+        });
+        conversation.messages.extend(messages);
+        let excess = conversation
+            .messages
+            .len()
+            .saturating_sub(*CONVERSATION_MAX_MESSAGES);
+        conversation.messages.drain(..excess);
+        conversation.message_count = conversation.messages.len();
+        conversation.updated_at = now;
+    }
+
+    // 按更新时间从新到旧，不含消息内容
+    pub fn list_by_owner(owner: &str) -> Vec<Conversation> {
+        let mut conversations: Vec<_> = CONVERSATIONS
+            .read()
+            .values()
+            .filter(|conversation| conversation.owner == owner)
+            .map(|conversation| Conversation {
+                messages: Vec::new(),
+                ..conversation.clone()
+            })
+            .collect();
+        conversations.sort_unstable_by(|a, b| b.updated_at.cmp(&a.updated_at));
+        conversations
+    }
+
+    pub fn get(owner: &str, id: &str) -> Option<Conversation> {
+        CONVERSATIONS
+            .read()
+            .get(&(owner.to_string(), id.to_string()))
+            .cloned()
+    }
+
+    pub fn remove(owner: &str, id: &str) -> bool {
+        CONVERSATIONS
+            .write()
+            .remove(&(owner.to_string(), id.to_string()))
+            .is_some()
+    }
+
+    pub(super) fn list() -> Vec<Conversation> {
+        CONVERSATIONS.read().values().cloned().collect()
+    }
+
+    pub(super) fn replace_all(list: Vec<Conversation>) {
+        *CONVERSATIONS.write() = list
+            .into_iter()
+            .map(|conversation| {
+                (
+                    (conversation.owner.clone(), conversation.id.clone()),
+                    conversation,
+                )
+            })
+            .collect();
+    }
+}
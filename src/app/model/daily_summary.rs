@@ -0,0 +1,77 @@
+use parking_lot::RwLock;
+use rkyv::{Archive, Deserialize as RkyvDeserialize, Serialize as RkyvSerialize};
+use serde::Serialize;
+use std::{collections::BTreeMap, sync::LazyLock};
+
+use crate::app::lazy::DAILY_SUMMARIES_LIMIT;
+
+// 按本地日期汇总的请求日志，避免统计查询反复扫描全部日志
+#[derive(Clone, Serialize, Archive, RkyvDeserialize, RkyvSerialize)]
+#[archive(check_bytes)]
+pub struct DailySummary {
+    // YYYY-MM-DD
+    pub date: String,
+    pub generated_at: i64,
+    pub requests: u64,
+    pub failures: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub cost: f64,
+    // 按请求数从高到低
+    pub models: Vec<UsageEntry>,
+    // 使用 API key 的请求按 key ID 区分，其余按 token 的用户 ID 区分
+    pub users: Vec<UsageEntry>,
+}
+
+#[derive(Clone, Serialize, Archive, RkyvDeserialize, RkyvSerialize)]
+#[archive(check_bytes)]
+pub struct UsageEntry {
+    pub name: String,
+    pub requests: u64,
+    pub failures: u64,
+    pub cost: f64,
+}
+
+static SUMMARIES: LazyLock<RwLock<BTreeMap<String, DailySummary>>> =
+    LazyLock::new(|| RwLock::new(BTreeMap::new()));
+
+pub struct DailySummaries;
+
+impl DailySummaries {
+    #[inline]
+    pub fn is_enabled() -> bool {
+        *DAILY_SUMMARIES_LIMIT > 0
+    }
+
+    // 同一日期重新生成时覆盖，超出 DAILY_SUMMARIES_LIMIT 时删除最早的日期
+    pub fn insert(summary: DailySummary) {
+        let mut summaries = SUMMARIES.write();
+        summaries.insert(summary.date.clone(), summary);
+        while summaries.len() > *DAILY_SUMMARIES_LIMIT {
+            summaries.pop_first();
+        }
+    }
+
+    pub fn get(date: &str) -> Option<DailySummary> {
+        SUMMARIES.read().get(date).cloned()
+    }
+
+    pub fn contains(date: &str) -> bool {
+        SUMMARIES.read().contains_key(date)
+    }
+
+    pub(super) fn list() -> Vec<DailySummary> {
+        SUMMARIES.read().values().cloned().collect()
+    }
+
+    pub(super) fn replace_all(list: Vec<DailySummary>) {
+        let mut summaries = SUMMARIES.write();
+        *summaries = list
+            .into_iter()
+            .map(|summary| (summary.date.clone(), summary))
+            .collect();
+        while summaries.len() > *DAILY_SUMMARIES_LIMIT {
+            summaries.pop_first();
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use parking_lot::RwLock;
+use rkyv::{Archive, Deserialize as RkyvDeserialize, Serialize as RkyvSerialize};
+use serde::Serialize;
+use std::sync::LazyLock;
+
+use crate::app::constant::INVITE_CODE_PREFIX;
+
+// 管理员生成的邀请码，兑换后为用户签发 API key
+#[derive(Clone, Serialize, Archive, RkyvDeserialize, RkyvSerialize)]
+#[archive(check_bytes)]
+pub struct InviteCode {
+    pub code: String,
+    // 兑换得到的 API key 绑定的租户
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    pub created_at: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<i64>,
+    pub max_uses: u32,
+    // 已兑换的用户名
+    pub redeemed_by: Vec<String>,
+}
+
+impl InviteCode {
+    pub fn is_available(&self) -> bool {
+        self.redeemed_by.len() < self.max_uses as usize
+            && self
+                .expires_at
+                .is_none_or(|expires_at| expires_at > chrono::Utc::now().timestamp())
+    }
+}
+
+static INVITE_CODES: LazyLock<RwLock<Vec<InviteCode>>> = LazyLock::new(|| RwLock::new(Vec::new()));
+
+pub struct InviteCodes;
+
+impl InviteCodes {
+    pub fn create(
+        tenant: Option<String>,
+        note: Option<String>,
+        expires_at: Option<i64>,
+        max_uses: u32,
+    ) -> InviteCode {
+        let invite = InviteCode {
+            code: format!(
+                "{}{}",
+                INVITE_CODE_PREFIX,
+                &uuid::Uuid::new_v4().simple().to_string()[..16]
+            ),
+            tenant,
+            note,
+            created_at: chrono::Utc::now().timestamp(),
+            expires_at,
+            max_uses,
+            redeemed_by: Vec::new(),
+        };
+        INVITE_CODES.write().push(invite.clone());
+        invite
+    }
+
+    pub fn list() -> Vec<InviteCode> {
+        INVITE_CODES.read().clone()
+    }
+
+    pub fn remove(code: &str) -> bool {
+        let mut codes = INVITE_CODES.write();
+        let len = codes.len();
+        codes.retain(|invite| invite.code != code);
+        codes.len() != len
+    }
+
+    // 在同一把锁内检查并占用一次使用次数，并发兑换不会超出 max_uses
+    pub fn redeem(code: &str, user: &str) -> Option<InviteCode> {
+        let mut codes = INVITE_CODES.write();
+        let invite = codes
+            .iter_mut()
+            .find(|invite| invite.code == code && invite.is_available())?;
+        invite.redeemed_by.push(user.to_string());
+        Some(invite.clone())
+    }
+
+    // 撤销一次兑换，注册未能保存时使用
+    pub fn unredeem(code: &str, user: &str) {
+        let mut codes = INVITE_CODES.write();
+        if let Some(invite) = codes.iter_mut().find(|invite| invite.code == code) {
+            if let Some(pos) = invite.redeemed_by.iter().rposition(|name| name == user) {
+                invite.redeemed_by.remove(pos);
+            }
+        }
+    }
+
+    pub(super) fn replace_all(list: Vec<InviteCode>) {
+        *INVITE_CODES.write() = list;
+    }
+}
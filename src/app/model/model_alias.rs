@@ -0,0 +1,68 @@
+use parking_lot::RwLock;
+use rkyv::{Archive, Deserialize as RkyvDeserialize, Serialize as RkyvSerialize};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::LazyLock};
+
+// 客户端使用的模型名称到实际模型的映射
+#[derive(Clone, Serialize, Deserialize, Archive, RkyvDeserialize, RkyvSerialize)]
+#[archive(check_bytes)]
+pub struct ModelAlias {
+    pub alias: String,
+    pub model: String,
+    // 已弃用模型的重定向，响应中会通过 x-model-redirected 告知客户端
+    #[serde(default)]
+    pub deprecated: bool,
+}
+
+// 映射结果
+pub struct ResolvedModel {
+    pub model: String,
+    pub deprecated: bool,
+}
+
+static MODEL_ALIASES: LazyLock<RwLock<HashMap<String, ModelAlias>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+pub struct ModelAliases;
+
+impl ModelAliases {
+    // 只映射一次，不做链式解析；带 -online 后缀时映射去掉后缀的名称
+    pub fn resolve(model: &str) -> Option<ResolvedModel> {
+        let aliases = MODEL_ALIASES.read();
+        if aliases.is_empty() {
+            return None;
+        }
+        if let Some(alias) = aliases.get(model) {
+            return Some(ResolvedModel {
+                model: alias.model.clone(),
+                deprecated: alias.deprecated,
+            });
+        }
+        let base = model.strip_suffix("-online")?;
+        aliases.get(base).map(|alias| ResolvedModel {
+            model: format!("{}-online", alias.model),
+            deprecated: alias.deprecated,
+        })
+    }
+
+    pub fn list() -> Vec<ModelAlias> {
+        let mut aliases: Vec<_> = MODEL_ALIASES.read().values().cloned().collect();
+        aliases.sort_unstable_by(|a, b| a.alias.cmp(&b.alias));
+        aliases
+    }
+
+    pub fn set(alias: ModelAlias) {
+        MODEL_ALIASES.write().insert(alias.alias.clone(), alias);
+    }
+
+    pub fn remove(alias: &str) -> bool {
+        MODEL_ALIASES.write().remove(alias).is_some()
+    }
+
+    pub fn replace_all(list: Vec<ModelAlias>) {
+        *MODEL_ALIASES.write() = list
+            .into_iter()
+            .map(|alias| (alias.alias.clone(), alias))
+            .collect();
+    }
+}
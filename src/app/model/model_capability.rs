@@ -0,0 +1,77 @@
+use parking_lot::RwLock;
+use rkyv::{Archive, Deserialize as RkyvDeserialize, Serialize as RkyvSerialize};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::LazyLock};
+
+// 模型能力信息，未设置的字段不返回，供下游路由选择模型
+#[derive(Clone, Default, Serialize, Deserialize, Archive, RkyvDeserialize, RkyvSerialize)]
+#[archive(check_bytes)]
+pub struct Capabilities {
+    // 上下文窗口大小，单位为 token
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_window: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub supports_vision: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub supports_tools: Option<bool>,
+    // 默认最大输出 token 数
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+}
+
+impl Capabilities {
+    pub fn is_empty(&self) -> bool {
+        self.context_window.is_none()
+            && self.supports_vision.is_none()
+            && self.supports_tools.is_none()
+            && self.max_tokens.is_none()
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Archive, RkyvDeserialize, RkyvSerialize)]
+#[archive(check_bytes)]
+pub struct ModelCapability {
+    pub model: String,
+    pub capabilities: Capabilities,
+}
+
+static MODEL_CAPABILITIES: LazyLock<RwLock<HashMap<String, Capabilities>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+pub struct ModelCapabilities;
+
+impl ModelCapabilities {
+    pub fn get(model: &str) -> Option<Capabilities> {
+        MODEL_CAPABILITIES.read().get(model).cloned()
+    }
+
+    pub fn list() -> Vec<ModelCapability> {
+        let mut list: Vec<_> = MODEL_CAPABILITIES
+            .read()
+            .iter()
+            .map(|(model, capabilities)| ModelCapability {
+                model: model.clone(),
+                capabilities: capabilities.clone(),
+            })
+            .collect();
+        list.sort_unstable_by(|a, b| a.model.cmp(&b.model));
+        list
+    }
+
+    pub fn set(entry: ModelCapability) {
+        MODEL_CAPABILITIES
+            .write()
+            .insert(entry.model, entry.capabilities);
+    }
+
+    pub fn remove(model: &str) -> bool {
+        MODEL_CAPABILITIES.write().remove(model).is_some()
+    }
+
+    pub fn replace_all(list: Vec<ModelCapability>) {
+        *MODEL_CAPABILITIES.write() = list
+            .into_iter()
+            .map(|entry| (entry.model, entry.capabilities))
+            .collect();
+    }
+}
@@ -0,0 +1,69 @@
+use parking_lot::RwLock;
+use rkyv::{Archive, Deserialize as RkyvDeserialize, Serialize as RkyvSerialize};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::LazyLock};
+
+// 单个用户的模型访问策略
+#[derive(Clone, Serialize, Deserialize, Archive, RkyvDeserialize, RkyvSerialize)]
+#[archive(check_bytes)]
+pub struct UserModelPolicy {
+    pub user_id: String,
+    // 为空时允许所有模型
+    #[serde(default)]
+    pub allow: Vec<String>,
+    #[serde(default)]
+    pub deny: Vec<String>,
+}
+
+impl UserModelPolicy {
+    // deny 优先于 allow
+    pub fn permits(&self, model: &str) -> bool {
+        if self.deny.iter().any(|m| m == model) {
+            return false;
+        }
+        self.allow.is_empty() || self.allow.iter().any(|m| m == model)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.allow.is_empty() && self.deny.is_empty()
+    }
+}
+
+// 以用户 ID 为键的策略表
+static MODEL_POLICIES: LazyLock<RwLock<HashMap<String, UserModelPolicy>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+pub struct ModelPolicies;
+
+impl ModelPolicies {
+    pub fn get(user_id: &str) -> Option<UserModelPolicy> {
+        MODEL_POLICIES.read().get(user_id).cloned()
+    }
+
+    pub fn list() -> Vec<UserModelPolicy> {
+        let mut policies: Vec<_> = MODEL_POLICIES.read().values().cloned().collect();
+        policies.sort_unstable_by(|a, b| a.user_id.cmp(&b.user_id));
+        policies
+    }
+
+    // 设置策略，allow 与 deny 都为空时移除该用户的策略
+    pub fn set(policy: UserModelPolicy) {
+        let mut policies = MODEL_POLICIES.write();
+        if policy.is_empty() {
+            policies.remove(&policy.user_id);
+        } else {
+            policies.insert(policy.user_id.clone(), policy);
+        }
+    }
+
+    pub fn remove(user_id: &str) -> bool {
+        MODEL_POLICIES.write().remove(user_id).is_some()
+    }
+
+    pub fn replace_all(list: Vec<UserModelPolicy>) {
+        *MODEL_POLICIES.write() = list
+            .into_iter()
+            .map(|policy| (policy.user_id.clone(), policy))
+            .collect();
+    }
+}
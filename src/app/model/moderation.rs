@@ -0,0 +1,138 @@
+use parking_lot::RwLock;
+use regex::{Regex, RegexBuilder};
+use rkyv::{Archive, Deserialize as RkyvDeserialize, Serialize as RkyvSerialize};
+use serde::{Deserialize, Serialize};
+use std::sync::LazyLock;
+
+// 替换命中内容的文本
+const REDACTED: &str = "[REDACTED]";
+
+#[derive(
+    Clone, Copy, PartialEq, Serialize, Deserialize, Archive, RkyvDeserialize, RkyvSerialize,
+)]
+#[archive(check_bytes)]
+#[serde(rename_all = "lowercase")]
+pub enum ModerationAction {
+    // 拒绝请求或截断输出
+    Block,
+    // 将命中的内容替换为 [REDACTED]
+    Redact,
+    // 只记录日志
+    Log,
+}
+
+#[derive(
+    Clone, Copy, Default, PartialEq, Serialize, Deserialize, Archive, RkyvDeserialize, RkyvSerialize,
+)]
+#[archive(check_bytes)]
+#[serde(rename_all = "lowercase")]
+pub enum ModerationScope {
+    #[default]
+    All,
+    Prompt,
+    Output,
+}
+
+impl ModerationScope {
+    fn covers(self, stage: Self) -> bool {
+        self == Self::All || self == stage
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Archive, RkyvDeserialize, RkyvSerialize)]
+#[archive(check_bytes)]
+pub struct ModerationRule {
+    // 添加时未提供则自动生成
+    #[serde(default)]
+    pub id: String,
+    pub pattern: String,
+    // 为 false 时按关键词匹配，不区分大小写
+    #[serde(default)]
+    pub regex: bool,
+    pub action: ModerationAction,
+    #[serde(default)]
+    pub scope: ModerationScope,
+}
+
+impl ModerationRule {
+    pub fn compile(&self) -> Result<Regex, regex::Error> {
+        if self.regex {
+            Regex::new(&self.pattern)
+        } else {
+            RegexBuilder::new(&regex::escape(&self.pattern))
+                .case_insensitive(true)
+                .build()
+        }
+    }
+}
+
+static MODERATION_RULES: LazyLock<RwLock<Vec<(ModerationRule, Regex)>>> =
+    LazyLock::new(|| RwLock::new(Vec::new()));
+
+pub struct ModerationRules;
+
+impl ModerationRules {
+    pub fn list() -> Vec<ModerationRule> {
+        MODERATION_RULES
+            .read()
+            .iter()
+            .map(|(rule, _)| rule.clone())
+            .collect()
+    }
+
+    // 相同 id 的规则会被替换
+    pub fn set(rule: ModerationRule) -> Result<(), regex::Error> {
+        let regex = rule.compile()?;
+        let mut rules = MODERATION_RULES.write();
+        match rules.iter_mut().find(|(r, _)| r.id == rule.id) {
+            Some(entry) => *entry = (rule, regex),
+            None => rules.push((rule, regex)),
+        }
+        Ok(())
+    }
+
+    pub fn remove(id: &str) -> bool {
+        let mut rules = MODERATION_RULES.write();
+        let before = rules.len();
+        rules.retain(|(rule, _)| rule.id != id);
+        rules.len() < before
+    }
+
+    // 按顺序应用规则，命中 block 规则时返回其 id
+    pub fn apply(text: &mut String, stage: ModerationScope) -> Result<(), String> {
+        let rules = MODERATION_RULES.read();
+        for (rule, regex) in rules.iter().filter(|(rule, _)| rule.scope.covers(stage)) {
+            if !regex.is_match(text) {
+                continue;
+            }
+            match rule.action {
+                ModerationAction::Block => {
+                    tracing::warn!("内容命中审核规则 {}，已拦截", rule.id);
+                    return Err(rule.id.clone());
+                }
+                ModerationAction::Redact => {
+                    tracing::info!("内容命中审核规则 {}，已替换", rule.id);
+                    *text = regex.replace_all(text, REDACTED).into_owned();
+                }
+                ModerationAction::Log => {
+                    tracing::warn!("内容命中审核规则 {}", rule.id);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // 无法编译的规则会被跳过
+    pub(super) fn replace_all(list: Vec<ModerationRule>) {
+        *MODERATION_RULES.write() = list
+            .into_iter()
+            .filter_map(|rule| match rule.compile() {
+                Ok(regex) => Some((rule, regex)),
+                Err(e) => {
+                    tracing::warn!("跳过无效的审核规则 {}: {}", rule.id, e);
+                    None
+                }
+            })
+            .collect();
+    }
+}
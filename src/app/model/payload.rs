@@ -0,0 +1,70 @@
+use parking_lot::RwLock;
+use rkyv::{Archive, Deserialize as RkyvDeserialize, Serialize as RkyvSerialize};
+use serde::Serialize;
+use std::{collections::VecDeque, sync::LazyLock};
+
+use crate::app::lazy::PAYLOADS_LIMIT;
+
+// 按日志 ID 保存的请求消息与最终回复，保存前已脱敏并截断
+#[derive(Clone, Serialize, Archive, RkyvDeserialize, RkyvSerialize)]
+#[archive(check_bytes)]
+pub struct Payload {
+    pub log_id: u64,
+    pub timestamp: i64,
+    // 发送给上游的消息列表（JSON）
+    pub request: String,
+    // 未完整结束的请求没有回复
+    pub response: Option<String>,
+    // 请求或回复超出 PAYLOAD_MAX_BYTES 被截断
+    pub truncated: bool,
+}
+
+static PAYLOADS: LazyLock<RwLock<VecDeque<Payload>>> =
+    LazyLock::new(|| RwLock::new(VecDeque::new()));
+
+pub struct Payloads;
+
+impl Payloads {
+    // 超出 PAYLOADS_LIMIT 时删除最早的记录
+    pub fn push(payload: Payload) {
+        let mut payloads = PAYLOADS.write();
+        payloads.push_back(payload);
+        while payloads.len() > *PAYLOADS_LIMIT {
+            payloads.pop_front();
+        }
+    }
+
+    pub fn set_response(log_id: u64, response: String, truncated: bool) {
+        if let Some(payload) = PAYLOADS
+            .write()
+            .iter_mut()
+            .rev()
+            .find(|payload| payload.log_id == log_id)
+        {
+            payload.response = Some(response);
+            payload.truncated |= truncated;
+        }
+    }
+
+    // 清空日志后 ID 会重新计数，取最新的一条
+    pub fn get(log_id: u64) -> Option<Payload> {
+        PAYLOADS
+            .read()
+            .iter()
+            .rev()
+            .find(|payload| payload.log_id == log_id)
+            .cloned()
+    }
+
+    pub(super) fn list() -> Vec<Payload> {
+        PAYLOADS.read().iter().cloned().collect()
+    }
+
+    pub(super) fn replace_all(list: Vec<Payload>) {
+        let mut payloads = PAYLOADS.write();
+        *payloads = list.into();
+        while payloads.len() > *PAYLOADS_LIMIT {
+            payloads.pop_front();
+        }
+    }
+}
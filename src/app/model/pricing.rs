@@ -0,0 +1,158 @@
+use parking_lot::RwLock;
+use rkyv::{Archive, Deserialize as RkyvDeserialize, Serialize as RkyvSerialize};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::LazyLock};
+
+use crate::common::utils::extract_user_id;
+
+// 模型单价，单位为美元每百万 token
+#[derive(Clone, Serialize, Deserialize, Archive, RkyvDeserialize, RkyvSerialize)]
+#[archive(check_bytes)]
+pub struct ModelPrice {
+    pub model: String,
+    pub input: f64,
+    pub output: f64,
+}
+
+static MODEL_PRICES: LazyLock<RwLock<HashMap<String, ModelPrice>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+pub struct ModelPrices;
+
+impl ModelPrices {
+    pub fn get(model: &str) -> Option<ModelPrice> {
+        MODEL_PRICES.read().get(model).cloned()
+    }
+
+    pub fn list() -> Vec<ModelPrice> {
+        let mut prices: Vec<_> = MODEL_PRICES.read().values().cloned().collect();
+        prices.sort_unstable_by(|a, b| a.model.cmp(&b.model));
+        prices
+    }
+
+    pub fn set(price: ModelPrice) {
+        MODEL_PRICES.write().insert(price.model.clone(), price);
+    }
+
+    pub fn remove(model: &str) -> bool {
+        MODEL_PRICES.write().remove(model).is_some()
+    }
+
+    pub(super) fn replace_all(list: Vec<ModelPrice>) {
+        *MODEL_PRICES.write() = list
+            .into_iter()
+            .map(|price| (price.model.clone(), price))
+            .collect();
+    }
+}
+
+// 单次请求估算的用量与费用
+#[derive(Clone, Default, Serialize)]
+#[cfg_attr(feature = "client", derive(serde::Deserialize))]
+pub struct CostInfo {
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+    // 未配置单价的模型为0
+    pub cost: f64,
+}
+
+impl CostInfo {
+    pub fn estimate(model: &str, prompt_tokens: u32, completion_tokens: u32) -> Self {
+        let cost = ModelPrices::get(model).map_or(0.0, |price| {
+            (prompt_tokens as f64 * price.input + completion_tokens as f64 * price.output)
+                / 1_000_000.0
+        });
+        Self {
+            prompt_tokens,
+            completion_tokens,
+            cost,
+        }
+    }
+}
+
+// 按 token 累计的消费
+#[derive(Clone, Default, Serialize, Archive, RkyvDeserialize, RkyvSerialize)]
+#[archive(check_bytes)]
+pub struct SpendRecord {
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub token: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<String>,
+    pub requests: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub cost: f64,
+}
+
+impl SpendRecord {
+    fn add(&mut self, other: &Self) {
+        self.requests += other.requests;
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.cost += other.cost;
+    }
+}
+
+static SPEND_LEDGER: LazyLock<RwLock<HashMap<String, SpendRecord>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+pub struct SpendLedger;
+
+impl SpendLedger {
+    pub fn record(token: &str, cost: &CostInfo) {
+        let mut ledger = SPEND_LEDGER.write();
+        let record = ledger
+            .entry(token.to_string())
+            .or_insert_with(|| SpendRecord {
+                token: token.to_string(),
+                user_id: extract_user_id(token),
+                ..Default::default()
+            });
+        record.requests += 1;
+        record.prompt_tokens += cost.prompt_tokens as u64;
+        record.completion_tokens += cost.completion_tokens as u64;
+        record.cost += cost.cost;
+    }
+
+    // 按费用从高到低排列
+    pub fn by_token() -> Vec<SpendRecord> {
+        let mut records: Vec<_> = SPEND_LEDGER.read().values().cloned().collect();
+        records.sort_unstable_by(|a, b| b.cost.total_cmp(&a.cost));
+        records
+    }
+
+    // 同一用户的多个 token 合并统计，返回的记录不含 token
+    pub fn by_user() -> Vec<SpendRecord> {
+        let mut users: HashMap<String, SpendRecord> = HashMap::new();
+        for record in SPEND_LEDGER.read().values() {
+            let Some(ref user_id) = record.user_id else {
+                continue;
+            };
+            users
+                .entry(user_id.clone())
+                .or_insert_with(|| SpendRecord {
+                    user_id: Some(user_id.clone()),
+                    ..Default::default()
+                })
+                .add(record);
+        }
+        let mut records: Vec<_> = users.into_values().collect();
+        records.sort_unstable_by(|a, b| b.cost.total_cmp(&a.cost));
+        records
+    }
+
+    pub fn clear() {
+        SPEND_LEDGER.write().clear();
+    }
+
+    pub(super) fn list() -> Vec<SpendRecord> {
+        SPEND_LEDGER.read().values().cloned().collect()
+    }
+
+    pub(super) fn replace_all(list: Vec<SpendRecord>) {
+        *SPEND_LEDGER.write() = list
+            .into_iter()
+            .map(|record| (record.token.clone(), record))
+            .collect();
+    }
+}
@@ -0,0 +1,282 @@
+use parking_lot::RwLock;
+use rkyv::{Archive, Deserialize as RkyvDeserialize, Serialize as RkyvSerialize};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::LazyLock};
+
+use crate::chat::model::{Message, MessageContent, Role};
+
+// 服务端保存的提示词模板，内容中的 {{name}} 在请求时替换为变量值
+#[derive(Clone, Serialize, Deserialize, Archive, RkyvDeserialize, RkyvSerialize)]
+#[archive(check_bytes)]
+pub struct PromptTemplate {
+    pub name: String,
+    // 渲染为系统消息，为空时只使用 messages
+    #[serde(default)]
+    pub content: String,
+    #[serde(default)]
+    pub variables: Vec<TemplateVariable>,
+    // 依次渲染为对话消息，放在 content 之后、客户端的消息之前
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub messages: Vec<TemplateMessage>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Archive, RkyvDeserialize, RkyvSerialize)]
+#[archive(check_bytes)]
+pub struct TemplateMessage {
+    // system、user 或 assistant
+    pub role: String,
+    pub content: String,
+}
+
+fn parse_role(role: &str) -> Option<Role> {
+    match role {
+        "system" => Some(Role::System),
+        "user" => Some(Role::User),
+        "assistant" => Some(Role::Assistant),
+        _ => None,
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Archive, RkyvDeserialize, RkyvSerialize)]
+#[archive(check_bytes)]
+pub struct TemplateVariable {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    // 有默认值时请求可以不提供
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+}
+
+// 返回内容中依次出现的占位符名称，未闭合的 {{ 按原文处理
+fn placeholders(content: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut rest = content;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        names.push(rest[start + 2..start + 2 + len].trim());
+        rest = &rest[start + 2 + len + 2..];
+    }
+    names
+}
+
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+impl PromptTemplate {
+    // 变量名只能包含字母、数字、下划线和连字符，且内容与消息中的占位符必须全部声明
+    pub fn validate(&self) -> Result<(), String> {
+        if self.content.trim().is_empty() && self.messages.is_empty() {
+            return Err("模板内容为空".to_string());
+        }
+        for message in &self.messages {
+            if parse_role(&message.role).is_none() {
+                return Err(format!("无效的消息角色: {}", message.role));
+            }
+        }
+        for (i, variable) in self.variables.iter().enumerate() {
+            if !is_valid_name(&variable.name) {
+                return Err(format!("无效的变量名: {}", variable.name));
+            }
+            if self.variables[..i].iter().any(|v| v.name == variable.name) {
+                return Err(format!("重复的变量: {}", variable.name));
+            }
+        }
+        let texts = std::iter::once(&self.content).chain(self.messages.iter().map(|m| &m.content));
+        for name in texts.flat_map(|text| placeholders(text)) {
+            if !self.variables.iter().any(|v| v.name == name) {
+                return Err(format!("未声明的变量: {}", name));
+            }
+        }
+        Ok(())
+    }
+
+    // 渲染为消息列表，content 不为空时作为第一条系统消息
+    pub fn render(&self, vars: &HashMap<String, String>) -> Result<Vec<Message>, String> {
+        if let Some(name) = vars
+            .keys()
+            .find(|name| !self.variables.iter().any(|v| &v.name == *name))
+        {
+            return Err(format!("unknown variable '{}'", name));
+        }
+
+        let mut values = HashMap::with_capacity(self.variables.len());
+        for variable in &self.variables {
+            let value = vars
+                .get(&variable.name)
+                .or(variable.default.as_ref())
+                .ok_or_else(|| format!("missing variable '{}'", variable.name))?;
+            values.insert(variable.name.as_str(), value.as_str());
+        }
+
+        let mut messages = Vec::with_capacity(self.messages.len() + 1);
+        if !self.content.trim().is_empty() {
+            messages.push(Message {
+                role: Role::System,
+                content: MessageContent::Text(substitute(&self.content, &values)),
+            });
+        }
+        for message in &self.messages {
+            let role = parse_role(&message.role)
+                .ok_or_else(|| format!("invalid role '{}'", message.role))?;
+            messages.push(Message {
+                role,
+                content: MessageContent::Text(substitute(&message.content, &values)),
+            });
+        }
+        Ok(messages)
+    }
+}
+
+// 只替换一次，变量值中的 {{ }} 保持原样
+fn substitute(text: &str, values: &HashMap<&str, &str>) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        output.push_str(&rest[..start]);
+        let name = rest[start + 2..start + 2 + len].trim();
+        output.push_str(values.get(name).copied().unwrap_or_default());
+        rest = &rest[start + 2 + len + 2..];
+    }
+    output.push_str(rest);
+    output
+}
+
+static PROMPT_TEMPLATES: LazyLock<RwLock<HashMap<String, PromptTemplate>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+pub struct PromptTemplates;
+
+impl PromptTemplates {
+    pub fn get(name: &str) -> Option<PromptTemplate> {
+        PROMPT_TEMPLATES.read().get(name).cloned()
+    }
+
+    pub fn list() -> Vec<PromptTemplate> {
+        let mut templates: Vec<_> = PROMPT_TEMPLATES.read().values().cloned().collect();
+        templates.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+        templates
+    }
+
+    pub fn set(template: PromptTemplate) {
+        PROMPT_TEMPLATES
+            .write()
+            .insert(template.name.clone(), template);
+    }
+
+    pub fn remove(name: &str) -> bool {
+        PROMPT_TEMPLATES.write().remove(name).is_some()
+    }
+
+    pub(super) fn replace_all(list: Vec<PromptTemplate>) {
+        *PROMPT_TEMPLATES.write() = list
+            .into_iter()
+            .map(|template| (template.name.clone(), template))
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variable(name: &str, default: Option<&str>) -> TemplateVariable {
+        TemplateVariable {
+            name: name.to_string(),
+            description: None,
+            default: default.map(str::to_string),
+        }
+    }
+
+    fn text(message: &Message) -> &str {
+        match message.content {
+            MessageContent::Text(ref text) => text,
+            MessageContent::Vision(_) => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_substitute() {
+        let values = HashMap::from([("name", "Alice"), ("lang", "{{name}}")]);
+
+        assert_eq!(substitute("Hello, {{name}}!", &values), "Hello, Alice!");
+        assert_eq!(substitute("{{ name }}{{name}}", &values), "AliceAlice");
+        // 变量值中的占位符不会再次替换
+        assert_eq!(substitute("in {{lang}}", &values), "in {{name}}");
+        assert_eq!(substitute("{{missing}}.", &values), ".");
+        // 未闭合的 {{ 按原文保留
+        assert_eq!(substitute("{{name}} {{name", &values), "Alice {{name");
+        assert_eq!(substitute("no placeholders", &values), "no placeholders");
+    }
+
+    #[test]
+    fn test_placeholders() {
+        assert_eq!(placeholders("{{a}} and {{ b }} then {{c"), vec!["a", "b"]);
+        assert!(placeholders("plain text").is_empty());
+    }
+
+    #[test]
+    fn test_render() {
+        let template = PromptTemplate {
+            name: "translate".to_string(),
+            content: "Translate into {{lang}}.".to_string(),
+            variables: vec![variable("lang", Some("English")), variable("text", None)],
+            messages: vec![TemplateMessage {
+                role: "user".to_string(),
+                content: "{{text}}".to_string(),
+            }],
+        };
+        assert!(template.validate().is_ok());
+
+        let vars = HashMap::from([("text".to_string(), "你好".to_string())]);
+        let messages = template.render(&vars).unwrap();
+        assert_eq!(messages.len(), 2);
+        assert!(matches!(messages[0].role, Role::System));
+        assert_eq!(text(&messages[0]), "Translate into English.");
+        assert!(matches!(messages[1].role, Role::User));
+        assert_eq!(text(&messages[1]), "你好");
+
+        let vars = HashMap::from([
+            ("text".to_string(), "你好".to_string()),
+            ("lang".to_string(), "French".to_string()),
+        ]);
+        let messages = template.render(&vars).unwrap();
+        assert_eq!(text(&messages[0]), "Translate into French.");
+
+        assert_eq!(
+            template.render(&HashMap::new()).err().as_deref(),
+            Some("missing variable 'text'")
+        );
+        let vars = HashMap::from([
+            ("text".to_string(), "你好".to_string()),
+            ("tone".to_string(), "formal".to_string()),
+        ]);
+        assert_eq!(
+            template.render(&vars).err().as_deref(),
+            Some("unknown variable 'tone'")
+        );
+    }
+
+    #[test]
+    fn test_validate_rejects_undeclared_variables() {
+        let template = PromptTemplate {
+            name: "greet".to_string(),
+            content: "Hello, {{name}}".to_string(),
+            variables: Vec::new(),
+            messages: Vec::new(),
+        };
+        assert_eq!(
+            template.validate().err().as_deref(),
+            Some("未声明的变量: name")
+        );
+    }
+}
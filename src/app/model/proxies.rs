@@ -3,7 +3,11 @@ use serde::{Serialize, Serializer};
 use serde::{Deserialize, Deserializer};
 // use rkyv::{Archive, Deserialize as RkyvDeserialize, Serialize as RkyvSerialize};
 
-use crate::app::constant::COMMA_STRING;
+use crate::app::{
+    constant::COMMA_STRING,
+    lazy::{UPSTREAM_CONNECT_TIMEOUT, UPSTREAM_READ_TIMEOUT},
+};
+use std::time::Duration;
 
 #[derive(Clone, Default, PartialEq)]
 pub enum Proxies {
@@ -67,15 +71,30 @@ impl Proxies {
         }
     }
 
-    pub fn get_client(&self) -> Client {
+    /// 转换为与 PROXIES 环境变量相同格式的字符串，可由 from_str 还原
+    pub fn to_env_string(&self) -> String {
         match self {
-            Proxies::No => Client::builder().no_proxy().build().unwrap(),
-            Proxies::System => Client::new(),
+            Proxies::No => "no".to_string(),
+            Proxies::System => "system".to_string(),
+            Proxies::List(urls) => urls.join(COMMA_STRING),
+        }
+    }
+
+    pub fn get_client(&self) -> Client {
+        let builder = match self {
+            Proxies::No => Client::builder().no_proxy(),
+            Proxies::System => Client::builder(),
             Proxies::List(list) => {
                 // 使用第一个代理（已经确保是有效的）
                 let proxy = Proxy::all(list[0].clone()).unwrap();
-                Client::builder().proxy(proxy).build().unwrap()
+                Client::builder().proxy(proxy)
             }
+        };
+
+        let mut builder = builder.connect_timeout(Duration::from_secs(*UPSTREAM_CONNECT_TIMEOUT));
+        if *UPSTREAM_READ_TIMEOUT > 0 {
+            builder = builder.read_timeout(Duration::from_secs(*UPSTREAM_READ_TIMEOUT));
         }
+        builder.build().unwrap()
     }
 }
@@ -0,0 +1,103 @@
+use parking_lot::RwLock;
+use rkyv::{Archive, Deserialize as RkyvDeserialize, Serialize as RkyvSerialize};
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::LazyLock,
+};
+
+use crate::app::lazy::QUALITY_SAMPLES_LIMIT;
+
+// 按比例抽样的上游回复质量指标
+#[derive(Clone, Serialize, Archive, RkyvDeserialize, RkyvSerialize)]
+#[archive(check_bytes)]
+pub struct QualitySample {
+    pub timestamp: i64,
+    pub model: String,
+    // 回复的字符数
+    pub length: u32,
+    pub completion_tokens: u32,
+    // 按文字系统粗略判断的语言
+    pub language: String,
+    // 回复开头是否匹配拒答模式
+    pub refusal: bool,
+}
+
+// 一个时间段内的抽样汇总
+#[derive(Serialize)]
+#[cfg_attr(feature = "client", derive(serde::Deserialize))]
+pub struct QualityBucket {
+    pub start: i64,
+    pub samples: u64,
+    pub avg_length: f64,
+    pub avg_completion_tokens: f64,
+    pub refusals: u64,
+    pub refusal_rate: f64,
+    pub languages: BTreeMap<String, u64>,
+}
+
+static SAMPLES: LazyLock<RwLock<VecDeque<QualitySample>>> =
+    LazyLock::new(|| RwLock::new(VecDeque::new()));
+
+pub struct QualitySamples;
+
+impl QualitySamples {
+    // 超出 QUALITY_SAMPLES_LIMIT 时删除最早的记录
+    pub fn push(sample: QualitySample) {
+        let mut samples = SAMPLES.write();
+        samples.push_back(sample);
+        while samples.len() > *QUALITY_SAMPLES_LIMIT {
+            samples.pop_front();
+        }
+    }
+
+    // 按 bucket 秒对齐分组汇总 since 之后的记录，按时间从早到晚
+    pub fn trend(model: Option<&str>, bucket: i64, since: i64) -> Vec<QualityBucket> {
+        let mut buckets: BTreeMap<i64, QualityBucket> = BTreeMap::new();
+
+        for sample in SAMPLES.read().iter() {
+            if sample.timestamp < since || model.is_some_and(|model| sample.model != model) {
+                continue;
+            }
+            let start = sample.timestamp - sample.timestamp.rem_euclid(bucket);
+            let entry = buckets.entry(start).or_insert_with(|| QualityBucket {
+                start,
+                samples: 0,
+                avg_length: 0.0,
+                avg_completion_tokens: 0.0,
+                refusals: 0,
+                refusal_rate: 0.0,
+                languages: BTreeMap::new(),
+            });
+            entry.samples += 1;
+            // 先累加总量，最后再换算为平均值
+            entry.avg_length += sample.length as f64;
+            entry.avg_completion_tokens += sample.completion_tokens as f64;
+            entry.refusals += sample.refusal as u64;
+            *entry.languages.entry(sample.language.clone()).or_default() += 1;
+        }
+
+        buckets
+            .into_values()
+            .map(|mut bucket| {
+                let samples = bucket.samples as f64;
+                bucket.avg_length /= samples;
+                bucket.avg_completion_tokens /= samples;
+                bucket.refusal_rate = bucket.refusals as f64 / samples;
+                bucket
+            })
+            .collect()
+    }
+
+    pub(super) fn list() -> Vec<QualitySample> {
+        SAMPLES.read().iter().cloned().collect()
+    }
+
+    pub(super) fn replace_all(list: Vec<QualitySample>) {
+        let mut samples = SAMPLES.write();
+        *samples = list.into();
+        while samples.len() > *QUALITY_SAMPLES_LIMIT {
+            samples.pop_front();
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use parking_lot::RwLock;
+use rkyv::{Archive, Deserialize as RkyvDeserialize, Serialize as RkyvSerialize};
+use serde::Serialize;
+use std::{collections::HashMap, sync::LazyLock};
+
+// 定期从上游同步的账户快速请求额度
+#[derive(Clone, Serialize, Archive, RkyvDeserialize, RkyvSerialize)]
+#[archive(check_bytes)]
+pub struct QuotaSnapshot {
+    #[serde(skip)]
+    pub token: String,
+    pub synced_at: i64,
+    pub fast_requests: u32,
+    // 没有上限时不存在
+    pub max_fast_requests: Option<u32>,
+}
+
+impl QuotaSnapshot {
+    #[inline]
+    pub fn is_exhausted(&self) -> bool {
+        self.max_fast_requests
+            .is_some_and(|max| self.fast_requests >= max)
+    }
+}
+
+static SNAPSHOTS: LazyLock<RwLock<HashMap<String, QuotaSnapshot>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+pub struct QuotaSnapshots;
+
+impl QuotaSnapshots {
+    pub fn insert(snapshot: QuotaSnapshot) {
+        SNAPSHOTS.write().insert(snapshot.token.clone(), snapshot);
+    }
+
+    pub fn get(token: &str) -> Option<QuotaSnapshot> {
+        SNAPSHOTS.read().get(token).cloned()
+    }
+
+    // 最近一次同步时快速请求已用完
+    pub fn is_exhausted(token: &str) -> bool {
+        SNAPSHOTS
+            .read()
+            .get(token)
+            .is_some_and(QuotaSnapshot::is_exhausted)
+    }
+
+    // 删除已不在号池中的 token 的快照
+    pub fn retain(tokens: &[String]) {
+        SNAPSHOTS.write().retain(|token, _| tokens.contains(token));
+    }
+
+    pub fn list() -> Vec<QuotaSnapshot> {
+        SNAPSHOTS.read().values().cloned().collect()
+    }
+
+    pub fn replace_all(list: Vec<QuotaSnapshot>) {
+        *SNAPSHOTS.write() = list
+            .into_iter()
+            .map(|snapshot| (snapshot.token.clone(), snapshot))
+            .collect();
+    }
+}
@@ -0,0 +1,100 @@
+use parking_lot::RwLock;
+use rkyv::{Archive, Deserialize as RkyvDeserialize, Serialize as RkyvSerialize};
+use serde::Serialize;
+use std::{fmt::Write as _, sync::LazyLock};
+
+use crate::app::lazy::REPORTS_LIMIT;
+
+// 定期生成的使用报告，统计区间为生成前的 24 小时
+#[derive(Clone, Serialize, Archive, RkyvDeserialize, RkyvSerialize)]
+#[archive(check_bytes)]
+pub struct Report {
+    pub id: u64,
+    pub generated_at: i64,
+    pub period_start: i64,
+    pub period_end: i64,
+    pub requests: u64,
+    pub errors: u64,
+    pub cost: f64,
+    // 按请求数从高到低
+    pub top_models: Vec<ModelUsage>,
+    pub tokens_total: u64,
+    // 被黑名单拦截的 token 数
+    pub tokens_blocked: u64,
+    pub markdown: String,
+}
+
+#[derive(Clone, Serialize, Archive, RkyvDeserialize, RkyvSerialize)]
+#[archive(check_bytes)]
+pub struct ModelUsage {
+    pub model: String,
+    pub requests: u64,
+    pub errors: u64,
+}
+
+impl Report {
+    pub fn render_markdown(&mut self) {
+        let time = |timestamp: i64| {
+            chrono::DateTime::from_timestamp(timestamp, 0)
+                .map(|time| {
+                    time.with_timezone(&chrono::Local)
+                        .format("%Y-%m-%d %H:%M")
+                        .to_string()
+                })
+                .unwrap_or_default()
+        };
+
+        let mut markdown = format!(
+            "# 使用报告\n\n统计区间: {} ~ {}\n\n",
+            time(self.period_start),
+            time(self.period_end)
+        );
+        let error_rate = if self.requests > 0 {
+            self.errors as f64 * 100.0 / self.requests as f64
+        } else {
+            0.0
+        };
+        let _ = write!(
+            markdown,
+            "| 项目 | 数值 |\n| --- | --- |\n| 请求数 | {} |\n| 失败数 | {} ({:.1}%) |\n| 估算费用 | ${:.4} |\n| token 总数 | {} |\n| 已拉黑 token | {} |\n",
+            self.requests, self.errors, error_rate, self.cost, self.tokens_total, self.tokens_blocked
+        );
+
+        if !self.top_models.is_empty() {
+            markdown.push_str("\n## 模型排行\n\n| 模型 | 请求数 | 失败数 |\n| --- | --- | --- |\n");
+            for usage in &self.top_models {
+                let _ = writeln!(
+                    markdown,
+                    "| {} | {} | {} |",
+                    usage.model, usage.requests, usage.errors
+                );
+            }
+        }
+
+        self.markdown = markdown;
+    }
+}
+
+static REPORTS: LazyLock<RwLock<Vec<Report>>> = LazyLock::new(|| RwLock::new(Vec::new()));
+
+pub struct Reports;
+
+impl Reports {
+    // 分配 id 并加入历史记录，超出 REPORTS_LIMIT 时删除最早的报告
+    pub fn push(mut report: Report) -> Report {
+        let mut reports = REPORTS.write();
+        report.id = reports.last().map_or(1, |report| report.id + 1);
+        reports.push(report.clone());
+        let excess = reports.len().saturating_sub(*REPORTS_LIMIT);
+        reports.drain(..excess);
+        report
+    }
+
+    pub fn list() -> Vec<Report> {
+        REPORTS.read().clone()
+    }
+
+    pub(super) fn replace_all(list: Vec<Report>) {
+        *REPORTS.write() = list;
+    }
+}
@@ -0,0 +1,43 @@
+use parking_lot::RwLock;
+use rkyv::{Archive, Deserialize as RkyvDeserialize, Serialize as RkyvSerialize};
+use std::sync::LazyLock;
+
+// 已退出的会话，保留到会话本身过期为止
+#[derive(Clone, Archive, RkyvDeserialize, RkyvSerialize)]
+#[archive(check_bytes)]
+pub struct RevokedSession {
+    pub nonce: String,
+    pub expires_at: i64,
+}
+
+static REVOKED_SESSIONS: LazyLock<RwLock<Vec<RevokedSession>>> =
+    LazyLock::new(|| RwLock::new(Vec::new()));
+
+pub struct RevokedSessions;
+
+impl RevokedSessions {
+    pub fn is_revoked(nonce: &str) -> bool {
+        REVOKED_SESSIONS
+            .read()
+            .iter()
+            .any(|session| session.nonce == nonce)
+    }
+
+    // 记录时顺便清理已过期的条目，过期的会话本身已无法通过校验
+    pub fn revoke(nonce: String, expires_at: i64) {
+        let now = chrono::Utc::now().timestamp();
+        let mut sessions = REVOKED_SESSIONS.write();
+        sessions.retain(|session| session.expires_at > now);
+        if !sessions.iter().any(|session| session.nonce == nonce) {
+            sessions.push(RevokedSession { nonce, expires_at });
+        }
+    }
+
+    pub fn list() -> Vec<RevokedSession> {
+        REVOKED_SESSIONS.read().clone()
+    }
+
+    pub(super) fn replace_all(list: Vec<RevokedSession>) {
+        *REVOKED_SESSIONS.write() = list;
+    }
+}
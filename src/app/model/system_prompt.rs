@@ -0,0 +1,120 @@
+use parking_lot::RwLock;
+use rkyv::{Archive, Deserialize as RkyvDeserialize, Serialize as RkyvSerialize};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::LazyLock};
+
+use crate::chat::model::{Message, MessageContent, Role};
+
+#[derive(
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    Serialize,
+    Deserialize,
+    Archive,
+    RkyvDeserialize,
+    RkyvSerialize,
+)]
+#[archive(check_bytes)]
+#[serde(rename_all = "lowercase")]
+pub enum PromptScope {
+    // API key 的所有者（未设置时为 key ID）或自有 token 的用户 ID
+    User,
+    // 实际请求的模型，不含 -online 后缀
+    Model,
+}
+
+#[derive(
+    Clone, Copy, Default, PartialEq, Serialize, Deserialize, Archive, RkyvDeserialize, RkyvSerialize,
+)]
+#[archive(check_bytes)]
+#[serde(rename_all = "lowercase")]
+pub enum PromptMode {
+    // 放在客户端的系统消息之前
+    #[default]
+    Prepend,
+    // 替换客户端的系统消息
+    Replace,
+    // 放在客户端的系统消息之后
+    Append,
+}
+
+// 按用户或模型注入的系统提示词，没有任何系统消息时代替 DEFAULT_INSTRUCTIONS
+#[derive(Clone, Serialize, Deserialize, Archive, RkyvDeserialize, RkyvSerialize)]
+#[archive(check_bytes)]
+pub struct SystemPrompt {
+    pub scope: PromptScope,
+    pub name: String,
+    pub content: String,
+    #[serde(default)]
+    pub mode: PromptMode,
+}
+
+impl SystemPrompt {
+    fn apply(&self, messages: &mut Vec<Message>) {
+        let message = Message {
+            role: Role::System,
+            content: MessageContent::Text(self.content.clone()),
+        };
+        match self.mode {
+            PromptMode::Prepend => messages.insert(0, message),
+            PromptMode::Replace => {
+                messages.retain(|message| message.role != Role::System);
+                messages.insert(0, message);
+            }
+            // 系统消息按出现顺序合并，放在最后即排在其他系统消息之后
+            PromptMode::Append => messages.push(message),
+        }
+    }
+}
+
+static SYSTEM_PROMPTS: LazyLock<RwLock<HashMap<(PromptScope, String), SystemPrompt>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+pub struct SystemPrompts;
+
+impl SystemPrompts {
+    pub fn list() -> Vec<SystemPrompt> {
+        let mut prompts: Vec<_> = SYSTEM_PROMPTS.read().values().cloned().collect();
+        prompts.sort_unstable_by(|a, b| (a.scope as u8, &a.name).cmp(&(b.scope as u8, &b.name)));
+        prompts
+    }
+
+    pub fn set(prompt: SystemPrompt) {
+        SYSTEM_PROMPTS
+            .write()
+            .insert((prompt.scope, prompt.name.clone()), prompt);
+    }
+
+    pub fn remove(scope: PromptScope, name: &str) -> bool {
+        SYSTEM_PROMPTS
+            .write()
+            .remove(&(scope, name.to_string()))
+            .is_some()
+    }
+
+    // 先应用模型的提示词，再应用用户的，因此用户的 prepend 排在最前、replace 覆盖模型的提示词
+    pub fn apply(model: &str, user: Option<&str>, messages: &mut Vec<Message>) {
+        let prompts = SYSTEM_PROMPTS.read();
+        if prompts.is_empty() {
+            return;
+        }
+        if let Some(prompt) = prompts.get(&(PromptScope::Model, model.to_string())) {
+            prompt.apply(messages);
+        }
+        if let Some(prompt) =
+            user.and_then(|user| prompts.get(&(PromptScope::User, user.to_string())))
+        {
+            prompt.apply(messages);
+        }
+    }
+
+    pub(super) fn replace_all(list: Vec<SystemPrompt>) {
+        *SYSTEM_PROMPTS.write() = list
+            .into_iter()
+            .map(|prompt| ((prompt.scope, prompt.name.clone()), prompt))
+            .collect();
+    }
+}
@@ -0,0 +1,108 @@
+use parking_lot::RwLock;
+use rkyv::{Archive, Deserialize as RkyvDeserialize, Serialize as RkyvSerialize};
+use serde::Serialize;
+use std::{collections::BTreeMap, sync::LazyLock};
+
+// 租户通过 /{id}/v1/... 访问，只能使用带有与 id 同名标签的 token 以及绑定到该租户的 API key
+#[derive(Clone, Serialize, Archive, RkyvDeserialize, RkyvSerialize)]
+#[archive(check_bytes)]
+pub struct Tenant {
+    pub id: String,
+    pub name: String,
+    pub created_at: i64,
+}
+
+static TENANTS: LazyLock<RwLock<BTreeMap<String, Tenant>>> =
+    LazyLock::new(|| RwLock::new(BTreeMap::new()));
+
+pub struct Tenants;
+
+impl Tenants {
+    // 用作路由前缀，只允许小写字母、数字、- 与 _
+    pub fn is_valid_id(id: &str) -> bool {
+        !id.is_empty()
+            && id.len() <= 32
+            && id
+                .bytes()
+                .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-' || b == b'_')
+    }
+
+    pub fn get(id: &str) -> Option<Tenant> {
+        TENANTS.read().get(id).cloned()
+    }
+
+    pub fn list() -> Vec<Tenant> {
+        TENANTS.read().values().cloned().collect()
+    }
+
+    pub fn set(tenant: Tenant) {
+        TENANTS.write().insert(tenant.id.clone(), tenant);
+    }
+
+    pub fn remove(id: &str) -> bool {
+        TENANTS.write().remove(id).is_some()
+    }
+
+    // 指定租户时 token 需带有该租户的标签，否则不能带有任何租户的标签
+    pub fn allows(tenant: Option<&str>, tags: &[String]) -> bool {
+        match tenant {
+            Some(tenant) => tags.iter().any(|tag| tag == tenant),
+            None => {
+                let tenants = TENANTS.read();
+                tenants.is_empty() || !tags.iter().any(|tag| tenants.contains_key(tag))
+            }
+        }
+    }
+
+    pub(super) fn replace_all(list: Vec<Tenant>) {
+        *TENANTS.write() = list
+            .into_iter()
+            .map(|tenant| (tenant.id.clone(), tenant))
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tags(tags: &[&str]) -> Vec<String> {
+        tags.iter().map(|tag| tag.to_string()).collect()
+    }
+
+    #[test]
+    fn test_is_valid_id() {
+        assert!(Tenants::is_valid_id("team-a_1"));
+        assert!(!Tenants::is_valid_id(""));
+        assert!(!Tenants::is_valid_id("Team")); // 大写字母
+        assert!(!Tenants::is_valid_id("team/a"));
+        assert!(!Tenants::is_valid_id(&"a".repeat(33)));
+    }
+
+    // 只有此测试修改全局的租户列表
+    #[test]
+    fn test_allows() {
+        Tenants::replace_all(Vec::new());
+        // 没有租户时全局请求可使用任意 token
+        assert!(Tenants::allows(None, &tags(&["team-a", "pro"])));
+
+        Tenants::set(Tenant {
+            id: "team-a".to_string(),
+            name: "Team A".to_string(),
+            created_at: 0,
+        });
+
+        // 租户请求只能使用带有该租户标签的 token
+        assert!(Tenants::allows(Some("team-a"), &tags(&["pro", "team-a"])));
+        assert!(!Tenants::allows(Some("team-a"), &tags(&["pro"])));
+        assert!(!Tenants::allows(Some("team-b"), &tags(&["team-a"])));
+
+        // 全局请求不能使用带有任何租户标签的 token
+        assert!(Tenants::allows(None, &tags(&["pro"])));
+        assert!(Tenants::allows(None, &[]));
+        assert!(!Tenants::allows(None, &tags(&["pro", "team-a"])));
+
+        Tenants::remove("team-a");
+        assert!(Tenants::allows(None, &tags(&["team-a"])));
+    }
+}
@@ -0,0 +1,83 @@
+use crate::{app::lazy::TOKEN_BLACKLIST_FILE, common::utils::extract_user_id};
+use parking_lot::RwLock;
+use std::sync::LazyLock;
+
+// 过短的条目会误伤大量 token
+const MIN_ENTRY_LEN: usize = 8;
+
+// 黑名单条目：token 子串或 JWT subject（用户 ID）
+static TOKEN_BLACKLIST: LazyLock<RwLock<Vec<String>>> = LazyLock::new(|| RwLock::new(Vec::new()));
+
+pub struct TokenBlacklist;
+
+impl TokenBlacklist {
+    pub fn is_blocked(token: &str) -> bool {
+        let entries = TOKEN_BLACKLIST.read();
+        if entries.is_empty() {
+            return false;
+        }
+        let user_id = extract_user_id(token);
+        entries
+            .iter()
+            .any(|entry| token.contains(entry.as_str()) || user_id.as_ref() == Some(entry))
+    }
+
+    pub fn list() -> Vec<String> {
+        TOKEN_BLACKLIST.read().clone()
+    }
+
+    // 返回新增的条目数，过短或重复的条目会被忽略
+    pub fn add(entries: Vec<String>) -> usize {
+        let mut blacklist = TOKEN_BLACKLIST.write();
+        let before = blacklist.len();
+        push_entries(&mut blacklist, entries);
+        blacklist.len() - before
+    }
+
+    // 返回移除的条目数
+    pub fn remove(entries: &[String]) -> usize {
+        let mut blacklist = TOKEN_BLACKLIST.write();
+        let before = blacklist.len();
+        blacklist.retain(|e| !entries.iter().any(|entry| entry.trim() == e));
+        before - blacklist.len()
+    }
+
+    // 从文件重新加载，每行一个条目，支持 # 注释
+    pub fn load() -> std::io::Result<usize> {
+        let content = match std::fs::read_to_string(TOKEN_BLACKLIST_FILE.as_str()) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e),
+        };
+
+        let mut entries = Vec::new();
+        push_entries(
+            &mut entries,
+            content
+                .lines()
+                .filter(|line| !line.trim_start().starts_with('#'))
+                .map(str::to_string),
+        );
+
+        let count = entries.len();
+        *TOKEN_BLACKLIST.write() = entries;
+        Ok(count)
+    }
+
+    pub fn save() -> std::io::Result<()> {
+        let mut content = Self::list().join("\n");
+        if !content.is_empty() {
+            content.push('\n');
+        }
+        std::fs::write(TOKEN_BLACKLIST_FILE.as_str(), content)
+    }
+}
+
+fn push_entries(blacklist: &mut Vec<String>, entries: impl IntoIterator<Item = String>) {
+    for entry in entries {
+        let entry = entry.trim();
+        if entry.len() >= MIN_ENTRY_LEN && !blacklist.iter().any(|e| e == entry) {
+            blacklist.push(entry.to_string());
+        }
+    }
+}
@@ -141,6 +141,16 @@ impl<'de> Deserialize<'de> for UsageCheck {
 }
 
 impl UsageCheck {
+    // 与 USAGE_CHECK 环境变量相同的格式，可由 from_str 还原
+    pub fn to_env_string(&self) -> String {
+        match self {
+            Self::None => "none".to_string(),
+            Self::Default => "default".to_string(),
+            Self::All => "all".to_string(),
+            Self::Custom(models) => models.join(COMMA_STRING),
+        }
+    }
+
     pub fn from_str(s: &str) -> Self {
         match s.trim().to_lowercase().as_str() {
             "none" | "disabled" => Self::None,
@@ -0,0 +1,79 @@
+use super::{
+    lazy::{QUOTA_SLOW_FALLBACK, QUOTA_SYNC_DELAY, QUOTA_SYNC_INTERVAL},
+    model::{AppState, QuotaSnapshot, QuotaSnapshots, TokenBlacklist},
+};
+use crate::common::utils::get_usage_profile;
+use std::{sync::Arc, time::Duration};
+use tokio::sync::Mutex;
+
+#[inline]
+pub fn is_enabled() -> bool {
+    *QUOTA_SYNC_INTERVAL > 0
+}
+
+// 额度用完的 token 全部不可选时是否改用这些 token 并切换到慢速池
+#[inline]
+pub fn slow_fallback() -> bool {
+    is_enabled() && *QUOTA_SLOW_FALLBACK
+}
+
+// 启动额度同步任务，启动时立即同步一次，之后按 QUOTA_SYNC_INTERVAL 定期同步
+pub fn init(state: Arc<Mutex<AppState>>) {
+    if !is_enabled() {
+        return;
+    }
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(*QUOTA_SYNC_INTERVAL * 60));
+        loop {
+            interval.tick().await;
+            sync(&state).await;
+        }
+    });
+}
+
+// 逐个查询号池中未被拉黑的 token 的用量，查询失败时保留上一次的快照
+async fn sync(state: &Mutex<AppState>) {
+    let tokens: Vec<String> = state
+        .lock()
+        .await
+        .token_infos
+        .iter()
+        .map(|info| info.token.clone())
+        .collect();
+
+    let mut synced = 0;
+    let mut exhausted = 0;
+    for (i, token) in tokens.iter().enumerate() {
+        if TokenBlacklist::is_blocked(token) {
+            continue;
+        }
+        if i > 0 {
+            tokio::time::sleep(Duration::from_millis(*QUOTA_SYNC_DELAY)).await;
+        }
+
+        let Some(usage) = get_usage_profile(token).await else {
+            continue;
+        };
+        let snapshot = QuotaSnapshot {
+            token: token.clone(),
+            synced_at: chrono::Utc::now().timestamp(),
+            fast_requests: usage.premium.num_requests,
+            max_fast_requests: usage.premium.max_requests,
+        };
+        synced += 1;
+        exhausted += snapshot.is_exhausted() as usize;
+        QuotaSnapshots::insert(snapshot);
+    }
+
+    QuotaSnapshots::retain(&tokens);
+    if let Err(e) = QuotaSnapshots::save().await {
+        tracing::warn!("保存额度快照失败: {}", e);
+    }
+    tracing::info!(
+        "已同步 {}/{} 个 token 的额度，其中 {} 个快速请求已用完",
+        synced,
+        tokens.len(),
+        exhausted
+    );
+}
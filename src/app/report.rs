@@ -0,0 +1,254 @@
+use super::{
+    lazy::{REPORT_TARGETS, REPORT_TIME},
+    model::{AppState, LogStatus, ModelUsage, Report, Reports, TokenBlacklist},
+};
+use crate::common::client::HTTP_CLIENT;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use chrono::{Local, NaiveTime, TimeZone as _};
+use std::{collections::HashMap, sync::Arc};
+use tokio::{
+    io::{AsyncBufReadExt as _, AsyncWriteExt as _, BufReader},
+    net::TcpStream,
+    sync::Mutex,
+};
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+// 报告中列出的模型数
+const TOP_MODELS: usize = 5;
+
+// 根据 REPORT_TIME 启动每日生成报告的后台任务
+pub fn init(state: Arc<Mutex<AppState>>) {
+    if REPORT_TIME.is_empty() {
+        return;
+    }
+
+    let Ok(time) = NaiveTime::parse_from_str(REPORT_TIME.trim(), "%H:%M") else {
+        tracing::warn!("无效的 REPORT_TIME: {}", *REPORT_TIME);
+        return;
+    };
+
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(until_next(time)).await;
+            let report = generate(&state).await;
+            deliver(&report).await;
+        }
+    });
+}
+
+// 距离下一个本地时间 time 的时长
+pub(super) fn until_next(time: NaiveTime) -> std::time::Duration {
+    let now = Local::now();
+    let mut date = now.date_naive();
+    loop {
+        if let Some(next) = Local
+            .from_local_datetime(&date.and_time(time))
+            .earliest()
+            .filter(|next| *next > now)
+        {
+            return (next - now).to_std().unwrap_or_default();
+        }
+        date = date.succ_opt().unwrap_or(date);
+    }
+}
+
+// 统计最近 24 小时的请求日志并保存到历史记录
+pub async fn generate(state: &Mutex<AppState>) -> Report {
+    let period_end = Local::now();
+    let period_start = period_end - chrono::Duration::hours(24);
+
+    let mut report = Report {
+        id: 0,
+        generated_at: period_end.timestamp(),
+        period_start: period_start.timestamp(),
+        period_end: period_end.timestamp(),
+        requests: 0,
+        errors: 0,
+        cost: 0.0,
+        top_models: Vec::new(),
+        tokens_total: 0,
+        tokens_blocked: 0,
+        markdown: String::new(),
+    };
+
+    let mut models: HashMap<String, ModelUsage> = HashMap::new();
+    {
+        let state = state.lock().await;
+        for log in state
+            .request_logs
+            .iter()
+            .filter(|log| log.timestamp >= period_start)
+        {
+            let failed = matches!(log.status, LogStatus::Failed);
+            report.requests += 1;
+            report.errors += failed as u64;
+            report.cost += log.cost.as_ref().map_or(0.0, |cost| cost.cost);
+
+            let usage = models
+                .entry(log.model.clone())
+                .or_insert_with(|| ModelUsage {
+                    model: log.model.clone(),
+                    requests: 0,
+                    errors: 0,
+                });
+            usage.requests += 1;
+            usage.errors += failed as u64;
+        }
+
+        report.tokens_total = state.token_infos.len() as u64;
+        report.tokens_blocked = state
+            .token_infos
+            .iter()
+            .filter(|info| TokenBlacklist::is_blocked(&info.token))
+            .count() as u64;
+    }
+
+    let mut top_models: Vec<_> = models.into_values().collect();
+    top_models.sort_unstable_by(|a, b| b.requests.cmp(&a.requests).then(a.model.cmp(&b.model)));
+    top_models.truncate(TOP_MODELS);
+    report.top_models = top_models;
+    report.render_markdown();
+
+    let report = Reports::push(report);
+    if let Err(e) = Reports::save().await {
+        tracing::warn!("保存报告失败: {}", e);
+    }
+    report
+}
+
+// 发送到 REPORT_TARGETS 中的全部目标，单个目标失败不影响其他目标
+pub async fn deliver(report: &Report) {
+    for target in REPORT_TARGETS.split(',').map(str::trim) {
+        if target.is_empty() {
+            continue;
+        }
+        let result = if target.starts_with("http://") || target.starts_with("https://") {
+            send_webhook(target, report).await
+        } else if target.starts_with("smtp://") {
+            send_mail(target, report).await
+        } else {
+            Err("不支持的目标".into())
+        };
+        match result {
+            Ok(()) => tracing::info!("报告 {} 已发送", report.id),
+            // 查询参数中可能包含密码，不写入日志
+            Err(e) => tracing::warn!(
+                "报告发送失败 {}: {}",
+                target.split('?').next().unwrap_or_default(),
+                e
+            ),
+        }
+    }
+}
+
+async fn send_webhook(url: &str, report: &Report) -> Result<(), BoxError> {
+    let client = HTTP_CLIENT.read().clone();
+    client
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(serde_json::to_string(report)?)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+// 明文 SMTP，适用于本地或内网的邮件中继
+// 格式: smtp://主机:端口?from=发件人&to=收件人&user=用户名&password=密码，多个收件人重复 to 参数
+async fn send_mail(target: &str, report: &Report) -> Result<(), BoxError> {
+    let url = url::Url::parse(target)?;
+    let host = url.host_str().ok_or("缺少主机")?;
+    let port = url.port().unwrap_or(25);
+
+    let mut from = None;
+    let mut to = Vec::new();
+    let mut user = None;
+    let mut password = None;
+    for (key, value) in url.query_pairs() {
+        match key.as_ref() {
+            "from" => from = Some(value.into_owned()),
+            "to" => to.push(value.into_owned()),
+            "user" => user = Some(value.into_owned()),
+            "password" => password = Some(value.into_owned()),
+            _ => {}
+        }
+    }
+    let from = from.ok_or("缺少 from")?;
+    if to.is_empty() {
+        return Err("缺少 to".into());
+    }
+
+    let stream = TcpStream::connect((host, port)).await?;
+    let mut smtp = Smtp {
+        stream: BufReader::new(stream),
+    };
+    smtp.expect(220).await?;
+    smtp.command("EHLO cursor-api", 250).await?;
+    if let Some(user) = user {
+        let credentials = format!("\0{}\0{}", user, password.unwrap_or_default());
+        smtp.command(&format!("AUTH PLAIN {}", STANDARD.encode(credentials)), 235)
+            .await?;
+    }
+    smtp.command(&format!("MAIL FROM:<{}>", from), 250).await?;
+    for to in &to {
+        smtp.command(&format!("RCPT TO:<{}>", to), 250).await?;
+    }
+    smtp.command("DATA", 354).await?;
+
+    let subject = format!("cursor-api 使用报告 {}", Local::now().format("%Y-%m-%d"));
+    let mut message = format!(
+        "From: <{}>\r\nTo: {}\r\nSubject: =?UTF-8?B?{}?=\r\nDate: {}\r\nMIME-Version: 1.0\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Transfer-Encoding: 8bit\r\n\r\n",
+        from,
+        to.iter()
+            .map(|to| format!("<{}>", to))
+            .collect::<Vec<_>>()
+            .join(", "),
+        STANDARD.encode(subject),
+        Local::now().to_rfc2822(),
+    );
+    // 以 . 开头的行需要再加一个 .
+    for line in report.markdown.lines() {
+        if line.starts_with('.') {
+            message.push('.');
+        }
+        message.push_str(line);
+        message.push_str("\r\n");
+    }
+    message.push_str(".\r\n");
+    smtp.stream.write_all(message.as_bytes()).await?;
+    smtp.expect(250).await?;
+
+    let _ = smtp.command("QUIT", 221).await;
+    Ok(())
+}
+
+struct Smtp {
+    stream: BufReader<TcpStream>,
+}
+
+impl Smtp {
+    async fn command(&mut self, command: &str, code: u16) -> Result<(), BoxError> {
+        self.stream.write_all(command.as_bytes()).await?;
+        self.stream.write_all(b"\r\n").await?;
+        self.expect(code).await
+    }
+
+    // 读取完整的响应，多行响应以 "250-" 形式续行
+    async fn expect(&mut self, code: u16) -> Result<(), BoxError> {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if self.stream.read_line(&mut line).await? == 0 {
+                return Err("连接已关闭".into());
+            }
+            if line.as_bytes().get(3) != Some(&b'-') {
+                break;
+            }
+        }
+        match line.get(..3).and_then(|c| c.parse::<u16>().ok()) {
+            Some(c) if c == code || (code == 250 && c == 251) => Ok(()),
+            _ => Err(format!("SMTP 错误: {}", line.trim_end()).into()),
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use super::constant::HEADER_NAME_REQUEST_ID;
+use axum::{
+    extract::Request,
+    http::{HeaderMap, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use uuid::Uuid;
+
+// 客户端提供的 ID 最长长度，超出或包含其他字符时重新生成
+const MAX_LENGTH: usize = 128;
+
+fn is_valid(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_LENGTH
+        && id
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.' | b':'))
+}
+
+// 沿用客户端的 X-Request-Id，没有或无效时生成新的 ID，写回请求头供处理函数读取，并在响应头中返回
+pub async fn assign(mut request: Request, next: Next) -> Response {
+    let id = request
+        .headers()
+        .get(HEADER_NAME_REQUEST_ID)
+        .and_then(|h| h.to_str().ok())
+        .filter(|id| is_valid(id))
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().simple().to_string());
+    let value = HeaderValue::from_str(&id).unwrap();
+
+    request
+        .headers_mut()
+        .insert(HEADER_NAME_REQUEST_ID, value.clone());
+    let mut response = next.run(request).await;
+    response.headers_mut().insert(HEADER_NAME_REQUEST_ID, value);
+    response
+}
+
+// 经过 assign 的请求总是带有 ID
+pub fn get(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(HEADER_NAME_REQUEST_ID)
+        .and_then(|h| h.to_str().ok())
+        .map(str::to_string)
+}
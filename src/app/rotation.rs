@@ -0,0 +1,111 @@
+use super::{
+    lazy::{CHECKSUM_ROTATION_INTERVAL, TOKEN_LIST_FILE},
+    model::{AppState, ChecksumRotation, ChecksumRotations, ROTATION_SCHEDULED},
+};
+use crate::common::utils::{generate_checksum_with_default, write_tokens};
+use std::{sync::Arc, time::Duration};
+use tokio::sync::Mutex;
+
+// 检查是否有到期 token 的间隔
+const CHECK_INTERVAL: Duration = Duration::from_secs(600);
+
+// 启动 checksum 的后台任务：每个整1000秒刷新时间戳，按 CHECKSUM_ROTATION_INTERVAL 定期重新生成
+pub fn init(state: Arc<Mutex<AppState>>) {
+    let state_for_refresh = state.clone();
+    tokio::spawn(async move {
+        loop {
+            // 获取当前时间戳
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs();
+
+            // 等待到下一个整1000秒
+            let next_reload = (now / 1000 + 1) * 1000;
+            tokio::time::sleep(Duration::from_secs(next_reload - now)).await;
+
+            state_for_refresh.lock().await.update_checksum();
+        }
+    });
+
+    if *CHECKSUM_ROTATION_INTERVAL == 0 {
+        return;
+    }
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        // 跳过立即触发的第一次
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            rotate_due(&state).await;
+        }
+    });
+}
+
+// 为到期的 token 重新生成 checksum，没有轮换记录的 token 视为到期
+async fn rotate_due(state: &Mutex<AppState>) {
+    let cutoff = chrono::Utc::now().timestamp() - (*CHECKSUM_ROTATION_INTERVAL * 3600) as i64;
+    let tokens: Vec<String> = state
+        .lock()
+        .await
+        .token_infos
+        .iter()
+        .filter(|info| ChecksumRotations::last_rotated(&info.token).is_none_or(|t| t <= cutoff))
+        .map(|info| info.token.clone())
+        .collect();
+
+    if !tokens.is_empty() {
+        let rotated = rotate(state, &tokens, ROTATION_SCHEDULED).await;
+        tracing::info!("已定期轮换 {} 个 token 的 checksum", rotated.len());
+    }
+}
+
+/// 为号池中的 token 重新生成 checksum，写入 token 文件并记录轮换历史
+///
+/// `tokens` 为空时轮换号池中的全部 token，不在号池中的 token 会被忽略
+pub async fn rotate(
+    state: &Mutex<AppState>,
+    tokens: &[String],
+    reason: &str,
+) -> Vec<ChecksumRotation> {
+    let timestamp = chrono::Utc::now().timestamp();
+    let (rotations, token_infos) = {
+        let mut state = state.lock().await;
+        let mut rotations = Vec::new();
+        for token_info in state
+            .token_infos
+            .iter_mut()
+            .filter(|info| tokens.is_empty() || tokens.contains(&info.token))
+        {
+            token_info.checksum = generate_checksum_with_default();
+            rotations.push(ChecksumRotation {
+                timestamp,
+                token: token_info.token.clone(),
+                alias: token_info.alias.clone(),
+                reason: reason.to_string(),
+            });
+        }
+        (rotations, state.token_infos.clone())
+    };
+
+    if rotations.is_empty() {
+        return rotations;
+    }
+
+    let result =
+        tokio::task::spawn_blocking(move || write_tokens(&token_infos, TOKEN_LIST_FILE.as_str()))
+            .await;
+    if !matches!(result, Ok(Ok(()))) {
+        tracing::warn!("保存更新后的 checksum 失败");
+    }
+
+    for rotation in &rotations {
+        ChecksumRotations::push(rotation.clone());
+    }
+    if let Err(e) = ChecksumRotations::save().await {
+        tracing::warn!("保存 checksum 轮换记录失败: {}", e);
+    }
+
+    rotations
+}
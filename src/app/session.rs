@@ -0,0 +1,132 @@
+use super::{
+    constant::{AUTHORIZATION_BEARER_PREFIX, SESSION_COOKIE_NAME},
+    lazy::{AUTH_TOKEN, SESSION_TTL},
+    model::RevokedSessions,
+};
+use crate::common::utils::url_for;
+use ::base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use axum::http::{
+    header::{AUTHORIZATION, COOKIE},
+    HeaderMap,
+};
+use rand::RngCore as _;
+use ring::hmac;
+use sha2::{Digest, Sha256};
+use std::sync::LazyLock;
+
+// 由 AUTH_TOKEN 派生签名密钥，修改 AUTH_TOKEN 后已签发的会话全部失效
+static KEY: LazyLock<hmac::Key> = LazyLock::new(|| {
+    let key = Sha256::digest(format!("session:{}", *AUTH_TOKEN).as_bytes());
+    hmac::Key::new(hmac::HMAC_SHA256, &key)
+});
+
+#[inline]
+pub fn is_enabled() -> bool {
+    *SESSION_TTL > 0
+}
+
+/// 签发新的会话，返回 cookie 值与过期时间戳
+///
+/// 格式为 `过期时间.随机数.签名`，随机数与签名均为 base64url 编码
+pub fn create() -> (String, i64) {
+    let expires_at = chrono::Utc::now().timestamp() + (*SESSION_TTL * 3600) as i64;
+    let mut nonce = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut nonce);
+
+    let payload = format!("{}.{}", expires_at, URL_SAFE_NO_PAD.encode(nonce));
+    let tag = hmac::sign(&KEY, payload.as_bytes());
+    (
+        format!("{}.{}", payload, URL_SAFE_NO_PAD.encode(tag.as_ref())),
+        expires_at,
+    )
+}
+
+// 签名正确、未过期且未退出的会话有效，返回过期时间戳与随机数
+fn verify(value: &str) -> Option<(i64, &str)> {
+    let (payload, tag) = value.rsplit_once('.')?;
+    let tag = URL_SAFE_NO_PAD.decode(tag).ok()?;
+    hmac::verify(&KEY, payload.as_bytes(), &tag).ok()?;
+
+    let (expires_at, nonce) = payload.split_once('.')?;
+    let expires_at = expires_at.parse::<i64>().ok()?;
+    (expires_at > chrono::Utc::now().timestamp() && !RevokedSessions::is_revoked(nonce))
+        .then_some((expires_at, nonce))
+}
+
+fn from_headers(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(';'))
+        .find_map(|pair| {
+            pair.trim()
+                .strip_prefix(SESSION_COOKIE_NAME)
+                .and_then(|v| v.strip_prefix('='))
+        })
+}
+
+// 请求携带的有效会话的过期时间戳
+pub fn expires_at(headers: &HeaderMap) -> Option<i64> {
+    if !is_enabled() {
+        return None;
+    }
+    from_headers(headers)
+        .and_then(verify)
+        .map(|(expires_at, _)| expires_at)
+}
+
+// 有效会话的标识，取随机数的前 8 位，审计日志据此区分不同的登录
+pub fn id(headers: &HeaderMap) -> Option<String> {
+    if !is_enabled() {
+        return None;
+    }
+    let (_, nonce) = verify(from_headers(headers)?)?;
+    Some(nonce.chars().take(8).collect())
+}
+
+// 退出时在服务端记录该会话，之后即使 cookie 被保留也无法再使用；请求未携带有效会话时返回 false
+pub fn revoke(headers: &HeaderMap) -> bool {
+    if !is_enabled() {
+        return false;
+    }
+    let Some((expires_at, nonce)) = from_headers(headers).and_then(verify) else {
+        return false;
+    };
+    RevokedSessions::revoke(nonce.to_string(), expires_at);
+    true
+}
+
+// 请求头中的 AUTH_TOKEN 或有效会话均视为管理员
+pub fn is_admin(headers: &HeaderMap) -> bool {
+    let bearer = headers
+        .get(AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix(AUTHORIZATION_BEARER_PREFIX));
+    bearer == Some(AUTH_TOKEN.as_str()) || expires_at(headers).is_some()
+}
+
+// 页面脚本无法读取，跨站请求不携带，启用 HTTPS 时仅通过 HTTPS 发送
+pub fn set_cookie(value: &str) -> String {
+    cookie(value, *SESSION_TTL * 3600)
+}
+
+pub fn clear_cookie() -> String {
+    cookie("", 0)
+}
+
+fn cookie(value: &str, max_age: u64) -> String {
+    #[allow(unused_mut)]
+    let mut cookie = format!(
+        "{}={}; Path={}; Max-Age={}; HttpOnly; SameSite=Strict",
+        SESSION_COOKIE_NAME,
+        value,
+        url_for("/"),
+        max_age
+    );
+    #[cfg(feature = "tls")]
+    if super::tls::is_enabled() {
+        cookie.push_str("; Secure");
+    }
+    cookie
+}
@@ -0,0 +1,122 @@
+use super::{
+    constant::{
+        CONTENT_TYPE_TEXT_CSS_WITH_UTF8, CONTENT_TYPE_TEXT_HTML_WITH_UTF8,
+        CONTENT_TYPE_TEXT_JS_WITH_UTF8, CONTENT_TYPE_TEXT_PLAIN_WITH_UTF8,
+    },
+    i18n::translate_page,
+    lazy::{STATIC_DIR, STATIC_MAX_AGE},
+};
+use axum::{
+    body::Body,
+    http::{
+        header::{CACHE_CONTROL, CONTENT_TYPE, ETAG, IF_NONE_MATCH},
+        HeaderMap, HeaderValue, StatusCode,
+    },
+    response::Response,
+};
+use sha2::{Digest, Sha256};
+use std::path::{Component, Path, PathBuf};
+
+pub fn is_enabled() -> bool {
+    !STATIC_DIR.is_empty()
+}
+
+fn content_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_default();
+    match extension.as_str() {
+        "html" | "htm" => CONTENT_TYPE_TEXT_HTML_WITH_UTF8,
+        "css" => CONTENT_TYPE_TEXT_CSS_WITH_UTF8,
+        "js" | "mjs" => CONTENT_TYPE_TEXT_JS_WITH_UTF8,
+        "txt" => CONTENT_TYPE_TEXT_PLAIN_WITH_UTF8,
+        "json" | "map" => "application/json",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "ico" => "image/x-icon",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        _ => "application/octet-stream",
+    }
+}
+
+// 只接受目录内的普通路径，拒绝 ..、绝对路径与隐藏文件，符号链接解析后同样不能离开目录
+fn resolve(relative: &str) -> Option<PathBuf> {
+    let relative = Path::new(relative);
+    let valid = relative.components().all(|component| match component {
+        Component::Normal(name) => name.to_str().is_some_and(|name| !name.starts_with('.')),
+        _ => false,
+    });
+    if !valid || relative.as_os_str().is_empty() {
+        return None;
+    }
+
+    let root = Path::new(STATIC_DIR.as_str()).canonicalize().ok()?;
+    let path = root.join(relative).canonicalize().ok()?;
+    (path.starts_with(&root) && path.is_file()).then_some(path)
+}
+
+// 从 STATIC_DIR 读取文件，不存在时返回 None 以回退到内置文件
+// ETag 为内容的哈希，客户端携带相同的 If-None-Match 时返回 304
+pub async fn serve(relative: &str, headers: &HeaderMap) -> Option<Response> {
+    if !is_enabled() {
+        return None;
+    }
+    let path = resolve(relative)?;
+    let content = match tokio::fs::read(&path).await {
+        Ok(content) => content,
+        Err(e) => {
+            tracing::warn!("读取静态文件 {} 失败: {}", path.display(), e);
+            return None;
+        }
+    };
+
+    let hash = Sha256::digest(&content);
+    let etag = format!("\"{}\"", hex::encode(&hash[..16]));
+    // 页面需要每次校验，其他资源在有效期内直接使用缓存
+    let cache_control = if content_type(&path) == CONTENT_TYPE_TEXT_HTML_WITH_UTF8 {
+        "no-cache".to_string()
+    } else {
+        format!("public, max-age={}", *STATIC_MAX_AGE)
+    };
+
+    let not_modified = headers
+        .get(IF_NONE_MATCH)
+        .and_then(|h| h.to_str().ok())
+        .is_some_and(|value| {
+            value
+                .split(',')
+                .any(|tag| tag.trim() == "*" || tag.trim().trim_start_matches("W/") == etag)
+        });
+
+    let builder = Response::builder()
+        .header(ETAG, HeaderValue::from_str(&etag).unwrap())
+        .header(
+            CACHE_CONTROL,
+            HeaderValue::from_str(&cache_control).unwrap(),
+        );
+    let response = if not_modified {
+        builder.status(StatusCode::NOT_MODIFIED).body(Body::empty())
+    } else {
+        builder
+            .header(CONTENT_TYPE, content_type(&path))
+            .body(Body::from(content))
+    };
+    response.ok()
+}
+
+// 内置页面，STATIC_DIR 中有同名文件时使用该文件（不做翻译）
+pub async fn page(file_name: &str, headers: &HeaderMap, embedded: &'static str) -> Response {
+    if let Some(response) = serve(file_name, headers).await {
+        return response;
+    }
+    Response::builder()
+        .header(CONTENT_TYPE, CONTENT_TYPE_TEXT_HTML_WITH_UTF8)
+        .body(Body::from(translate_page(embedded).into_owned()))
+        .unwrap()
+}
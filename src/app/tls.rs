@@ -0,0 +1,78 @@
+use super::lazy::{HTTP_REDIRECT_PORT, TLS_CERT_PATH, TLS_KEY_PATH};
+use axum::{
+    http::{
+        header::{HOST, LOCATION},
+        HeaderMap, StatusCode, Uri,
+    },
+    response::{IntoResponse, Response},
+    Router,
+};
+use axum_server::tls_rustls::RustlsConfig;
+use std::{net::SocketAddr, sync::Once};
+
+pub fn is_enabled() -> bool {
+    !TLS_CERT_PATH.is_empty() && !TLS_KEY_PATH.is_empty()
+}
+
+// 以 HTTPS 提供服务，设置了 HTTP_REDIRECT_PORT 时同时监听该端口并重定向到 HTTPS
+// 监听多个地址时只重定向到第一个启动的地址
+pub async fn serve(addr: &str, app: Router) -> std::io::Result<()> {
+    static REDIRECT: Once = Once::new();
+
+    let addr: SocketAddr = addr
+        .parse()
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+    let config = RustlsConfig::from_pem_file(TLS_CERT_PATH.as_str(), TLS_KEY_PATH.as_str()).await?;
+
+    if *HTTP_REDIRECT_PORT != 0 {
+        REDIRECT.call_once(|| {
+            tokio::spawn(serve_redirect(addr.port()));
+        });
+    }
+
+    tracing::info!("HTTPS 运行在 {}", addr);
+    axum_server::bind_rustls(addr, config)
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+        .await
+}
+
+async fn serve_redirect(https_port: u16) {
+    let app = Router::new().fallback(move |headers: HeaderMap, uri: Uri| async move {
+        redirect_to_https(&headers, &uri, https_port)
+    });
+
+    let addr = SocketAddr::from(([0, 0, 0, 0], *HTTP_REDIRECT_PORT));
+    let listener = match tokio::net::TcpListener::bind(addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            tracing::error!("HTTP 重定向端口绑定失败: {}", e);
+            return;
+        }
+    };
+
+    tracing::info!("HTTP 重定向运行在端口 {}", *HTTP_REDIRECT_PORT);
+    if let Err(e) = axum::serve(listener, app).await {
+        tracing::error!("HTTP 重定向服务错误: {}", e);
+    }
+}
+
+fn redirect_to_https(headers: &HeaderMap, uri: &Uri, https_port: u16) -> Response {
+    let Some(host) = headers.get(HOST).and_then(|h| h.to_str().ok()) else {
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+
+    // 去掉原端口，IPv6 地址中的冒号不受影响
+    let host = host
+        .rsplit_once(':')
+        .filter(|(_, port)| port.bytes().all(|b| b.is_ascii_digit()))
+        .map_or(host, |(host, _)| host);
+    let path = uri.path_and_query().map_or("/", |p| p.as_str());
+
+    let location = if https_port == 443 {
+        format!("https://{}{}", host, path)
+    } else {
+        format!("https://{}:{}{}", host, https_port, path)
+    };
+
+    (StatusCode::PERMANENT_REDIRECT, [(LOCATION, location)]).into_response()
+}
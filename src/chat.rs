@@ -1,10 +1,25 @@
 pub mod adapter;
 pub mod aiserver;
+pub mod cache;
+pub mod completions;
 pub mod config;
 pub mod constant;
+pub mod conversation;
 pub mod error;
-// pub mod middleware;
+pub mod fault;
+pub mod idempotency;
+pub mod images;
+pub mod json_mode;
+pub mod mock;
+pub mod middleware;
 pub mod model;
+pub mod moderation;
+pub mod payload;
+pub mod priority;
+pub mod public_pool;
+pub mod quality;
+pub mod queue;
+pub mod reconcile;
 pub mod route;
 pub mod service;
 pub mod stream;
@@ -1,5 +1,5 @@
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
-use image::guess_format;
+use image::{guess_format, imageops::FilterType, ImageFormat};
 use prost::Message as _;
 use reqwest::Client;
 use uuid::Uuid;
@@ -17,14 +17,17 @@ use super::{
     aiserver::v1::{
         conversation_message, image_proto, AzureState, ChatExternalLink, ConversationMessage, ExplicitContext, GetChatRequest, ImageProto, ModelDetails
     },
-    constant::{ERR_UNSUPPORTED_GIF, ERR_UNSUPPORTED_IMAGE_FORMAT, LONG_CONTEXT_MODELS},
-    model::{Message, MessageContent, Role},
+    constant::{
+        ERR_IMAGE_TOO_LARGE, ERR_UNSUPPORTED_GIF, ERR_UNSUPPORTED_IMAGE_FORMAT,
+        IMAGE_LOW_DETAIL_DIMENSION, IMAGE_MAX_DIMENSION, IMAGE_MAX_SIZE, LONG_CONTEXT_MODELS,
+    },
+    model::{ImageUrl, Message, MessageContent, Role},
 };
 
-async fn process_chat_inputs(
+pub async fn process_chat_inputs(
     inputs: Vec<Message>,
     disable_vision: bool,
-) -> (String, Vec<ConversationMessage>, Vec<String>) {
+) -> Result<(String, Vec<ConversationMessage>, Vec<String>), ImageError> {
     // 收集 system 指令
     let instructions = inputs
         .iter()
@@ -61,7 +64,7 @@ async fn process_chat_inputs(
 
     // 处理空对话情况
     if chat_inputs.is_empty() {
-        return (
+        return Ok((
             instructions,
             vec![ConversationMessage {
                 text: EMPTY_STRING.into(),
@@ -98,7 +101,7 @@ async fn process_chat_inputs(
                 conversation_summary: None,
             }],
             vec![],
-        );
+        ));
     }
 
     // 处理 WebReferences 开头的 assistant 消息
@@ -178,20 +181,15 @@ async fn process_chat_inputs(
                             }
                         }
                         "image_url" => {
-                            if !disable_vision {
-                                if let Some(image_url) = &content.image_url {
-                                    let url = image_url.url.clone();
-                                    let client = HTTP_CLIENT.read().clone();
-                                    let result = tokio::spawn(async move {
-                                        fetch_image_data(&url, client).await
-                                    });
-                                    if let Ok(Ok((image_data, dimensions))) = result.await {
-                                        images.push(ImageProto {
-                                            data: image_data,
-                                            dimension: dimensions,
-                                        });
-                                    }
-                                }
+                            // 模型不支持或全局禁用图片时忽略图片
+                            if disable_vision || AppConfig::get_vision_ability().is_none() {
+                                continue;
+                            }
+                            if let Some(image_url) = &content.image_url {
+                                let image = fetch_image_data(image_url)
+                                    .await
+                                    .map_err(|e| ImageError(e.to_string()))?;
+                                images.push(image);
                             }
                         }
                         _ => {}
@@ -266,40 +264,61 @@ async fn process_chat_inputs(
         }
     }
 
-    (instructions, messages, urls)
+    Ok((instructions, messages, urls))
+}
+
+// 图片处理失败，属于请求内容的问题
+#[derive(Debug)]
+pub struct ImageError(pub String);
+
+impl std::fmt::Display for ImageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
 }
 
+impl std::error::Error for ImageError {}
+
 async fn fetch_image_data(
-    url: &str,
-    client: Client,
-) -> Result<(Vec<u8>, Option<image_proto::Dimension>), Box<dyn std::error::Error + Send + Sync>> {
+    image_url: &ImageUrl,
+) -> Result<ImageProto, Box<dyn std::error::Error + Send + Sync>> {
     // 在进入异步操作前获取并释放锁
     let vision_ability = AppConfig::get_vision_ability();
+    let url = image_url.url.as_str();
 
-    match vision_ability {
-        VisionAbility::None => Err("图片功能已禁用".into()),
+    let image_data = match vision_ability {
+        VisionAbility::None => return Err("图片功能已禁用".into()),
 
         VisionAbility::Base64 => {
             if !url.starts_with("data:image/") {
                 return Err("仅支持 base64 编码的图片".into());
             }
-            process_base64_image(url)
+            decode_base64_image(url)?
         }
 
         VisionAbility::All => {
             if url.starts_with("data:image/") {
-                process_base64_image(url)
+                decode_base64_image(url)?
             } else {
-                process_http_image(url, client).await
+                let client = HTTP_CLIENT.read().clone();
+                download_image(url, client).await?
             }
         }
-    }
+    };
+
+    // detail 为 low 时使用更小的尺寸
+    let max_dimension = if image_url.detail.as_deref() == Some("low") {
+        IMAGE_LOW_DETAIL_DIMENSION
+    } else {
+        IMAGE_MAX_DIMENSION
+    };
+
+    // 解码和缩放比较耗时，放到阻塞线程池中进行
+    tokio::task::spawn_blocking(move || process_image(image_data, max_dimension)).await?
 }
 
-// 处理 base64 编码的图片
-fn process_base64_image(
-    url: &str,
-) -> Result<(Vec<u8>, Option<image_proto::Dimension>), Box<dyn std::error::Error + Send + Sync>> {
+// 解码 base64 编码的图片
+fn decode_base64_image(url: &str) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
     let parts: Vec<&str> = url.split("base64,").collect();
     if parts.len() != 2 {
         return Err("无效的 base64 图片格式".into());
@@ -316,45 +335,49 @@ fn process_base64_image(
         return Err(ERR_UNSUPPORTED_IMAGE_FORMAT.into());
     }
 
-    let image_data = BASE64.decode(parts[1])?;
-
-    // 检查是否为动态 GIF
-    if format.contains("gif") {
-        if let Ok(frames) = gif::DecodeOptions::new().read_info(std::io::Cursor::new(&image_data)) {
-            if frames.into_iter().count() > 1 {
-                return Err(ERR_UNSUPPORTED_GIF.into());
-            }
-        }
+    let image_data = BASE64.decode(parts[1].trim())?;
+    if image_data.len() > IMAGE_MAX_SIZE {
+        return Err(ERR_IMAGE_TOO_LARGE.into());
     }
 
-    // 获取图片尺寸
-    let dimensions = if let Ok(img) = image::load_from_memory(&image_data) {
-        Some(image_proto::Dimension {
-            width: img.width() as i32,
-            height: img.height() as i32,
-        })
-    } else {
-        None
-    };
-
-    Ok((image_data, dimensions))
+    Ok(image_data)
 }
 
-// 处理 HTTP 图片 URL
-async fn process_http_image(
+// 下载 HTTP 图片
+async fn download_image(
     url: &str,
     client: Client,
-) -> Result<(Vec<u8>, Option<image_proto::Dimension>), Box<dyn std::error::Error + Send + Sync>> {
-    let response = client.get(url).send().await?;
-    let image_data = response.bytes().await?.to_vec();
-    let format = guess_format(&image_data)?;
+) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+    let response = client.get(url).send().await?.error_for_status()?;
+
+    if response
+        .content_length()
+        .map_or(false, |len| len > IMAGE_MAX_SIZE as u64)
+    {
+        return Err(ERR_IMAGE_TOO_LARGE.into());
+    }
+
+    let image_data = response.bytes().await?;
+    if image_data.len() > IMAGE_MAX_SIZE {
+        return Err(ERR_IMAGE_TOO_LARGE.into());
+    }
+
+    Ok(image_data.to_vec())
+}
+
+// 校验图片格式，必要时缩放并重新编码
+fn process_image(
+    image_data: Vec<u8>,
+    max_dimension: u32,
+) -> Result<ImageProto, Box<dyn std::error::Error + Send + Sync>> {
+    let format = guess_format(&image_data).map_err(|_| ERR_UNSUPPORTED_IMAGE_FORMAT)?;
 
     // 检查图片格式
     match format {
-        image::ImageFormat::Png | image::ImageFormat::Jpeg | image::ImageFormat::WebP => {
+        ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::WebP => {
             // 这些格式都支持
         }
-        image::ImageFormat::Gif => {
+        ImageFormat::Gif => {
             if let Ok(frames) =
                 gif::DecodeOptions::new().read_info(std::io::Cursor::new(&image_data))
             {
@@ -366,17 +389,36 @@ async fn process_http_image(
         _ => return Err(ERR_UNSUPPORTED_IMAGE_FORMAT.into()),
     }
 
-    // 获取图片尺寸
-    let dimensions = if let Ok(img) = image::load_from_memory_with_format(&image_data, format) {
-        Some(image_proto::Dimension {
-            width: img.width() as i32,
-            height: img.height() as i32,
-        })
+    let img = image::load_from_memory_with_format(&image_data, format)?;
+
+    // 尺寸在限制内时原样发送
+    if img.width() <= max_dimension && img.height() <= max_dimension {
+        return Ok(ImageProto {
+            dimension: Some(image_proto::Dimension {
+                width: img.width() as i32,
+                height: img.height() as i32,
+            }),
+            data: image_data,
+        });
+    }
+
+    // 等比缩放，JPEG 保持原格式，其余转换为 PNG
+    let img = img.resize(max_dimension, max_dimension, FilterType::Triangle);
+    let output_format = if format == ImageFormat::Jpeg {
+        ImageFormat::Jpeg
     } else {
-        None
+        ImageFormat::Png
     };
+    let mut data = Vec::new();
+    img.write_to(&mut std::io::Cursor::new(&mut data), output_format)?;
 
-    Ok((image_data, dimensions))
+    Ok(ImageProto {
+        dimension: Some(image_proto::Dimension {
+            width: img.width() as i32,
+            height: img.height() as i32,
+        }),
+        data,
+    })
 }
 
 pub async fn encode_chat_message(
@@ -385,6 +427,7 @@ pub async fn encode_chat_message(
     disable_vision: bool,
     enable_slow_pool: bool,
     is_search: bool,
+    conversation_id: Option<String>,
 ) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
     // 在进入异步操作前获取并释放锁
     let enable_slow_pool = {
@@ -395,7 +438,7 @@ pub async fn encode_chat_message(
         }
     };
 
-    let (instructions, messages, urls) = process_chat_inputs(inputs, disable_vision).await;
+    let (instructions, messages, urls) = process_chat_inputs(inputs, disable_vision).await?;
 
     let explicit_context = if !instructions.trim().is_empty() {
         Some(ExplicitContext {
@@ -442,7 +485,7 @@ pub async fn encode_chat_message(
         summary_up_until_index: None,
         allow_long_file_scan: Some(false),
         is_bash: Some(false),
-        conversation_id: Uuid::new_v4().to_string(),
+        conversation_id: conversation_id.unwrap_or_else(|| Uuid::new_v4().to_string()),
         can_handle_filenames_after_language_ids: Some(true),
         use_web: if is_search {
             Some("full_search".to_string())
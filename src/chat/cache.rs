@@ -0,0 +1,94 @@
+use crate::app::lazy::{RESPONSE_CACHE_CAPACITY, RESPONSE_CACHE_TTL};
+use parking_lot::Mutex;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    sync::LazyLock,
+    time::{Duration, Instant},
+};
+
+use super::model::Message;
+
+pub type CacheKey = [u8; 32];
+
+struct CacheEntry {
+    text: String,
+    expires_at: Instant,
+    last_used: u64,
+}
+
+// 按最近使用淘汰的响应缓存
+struct ResponseCache {
+    entries: HashMap<CacheKey, CacheEntry>,
+    tick: u64,
+}
+
+static RESPONSE_CACHE: LazyLock<Mutex<ResponseCache>> = LazyLock::new(|| {
+    Mutex::new(ResponseCache {
+        entries: HashMap::new(),
+        tick: 0,
+    })
+});
+
+pub fn is_enabled() -> bool {
+    *RESPONSE_CACHE_TTL > 0 && *RESPONSE_CACHE_CAPACITY > 0
+}
+
+// 以模型和规范化后的消息计算缓存键
+pub fn cache_key(model: &str, messages: &[Message]) -> Option<CacheKey> {
+    let messages = serde_json::to_string(messages).ok()?;
+    let mut hasher = Sha256::new();
+    hasher.update(model.as_bytes());
+    hasher.update([0]);
+    hasher.update(messages.as_bytes());
+    Some(hasher.finalize().into())
+}
+
+pub fn get(key: &CacheKey) -> Option<String> {
+    let mut cache = RESPONSE_CACHE.lock();
+    cache.tick += 1;
+    let tick = cache.tick;
+
+    match cache.entries.get_mut(key) {
+        Some(entry) if entry.expires_at > Instant::now() => {
+            entry.last_used = tick;
+            Some(entry.text.clone())
+        }
+        Some(_) => {
+            cache.entries.remove(key);
+            None
+        }
+        None => None,
+    }
+}
+
+pub fn insert(key: CacheKey, text: String) {
+    let mut cache = RESPONSE_CACHE.lock();
+    cache.tick += 1;
+    let tick = cache.tick;
+    let now = Instant::now();
+
+    if !cache.entries.contains_key(&key) && cache.entries.len() >= *RESPONSE_CACHE_CAPACITY {
+        // 先清理过期条目，仍然已满时淘汰最久未使用的条目
+        cache.entries.retain(|_, entry| entry.expires_at > now);
+        if cache.entries.len() >= *RESPONSE_CACHE_CAPACITY {
+            if let Some(oldest) = cache
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| *key)
+            {
+                cache.entries.remove(&oldest);
+            }
+        }
+    }
+
+    cache.entries.insert(
+        key,
+        CacheEntry {
+            text,
+            expires_at: now + Duration::from_secs(*RESPONSE_CACHE_TTL),
+            last_used: tick,
+        },
+    );
+}
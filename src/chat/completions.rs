@@ -0,0 +1,202 @@
+use super::{
+    model::{ChatResponse, Message, MessageContent, Role, Usage},
+    service::{handle_chat, ChatQuery},
+};
+use crate::{
+    app::{
+        constant::HEADER_NAME_NON_STREAM_KEEPALIVE,
+        model::{AppState, ChatRequest},
+    },
+    common::model::{error::ChatError, ApiStatus, ErrorResponse},
+};
+use axum::{
+    body::Body,
+    extract::{ConnectInfo, Query, State},
+    http::{
+        header::{CONTENT_LENGTH, CONTENT_TYPE},
+        HeaderMap, StatusCode,
+    },
+    response::Response,
+    Json,
+};
+use serde::{de::IgnoredAny, Deserialize, Serialize};
+use std::{collections::HashMap, net::SocketAddr, sync::Arc};
+use tokio::sync::Mutex;
+
+// 旧版文本补全请求，prompt 作为单条用户消息发送
+#[derive(Deserialize)]
+pub struct CompletionRequest {
+    pub model: String,
+    pub prompt: Prompt,
+    #[serde(default)]
+    pub stream: bool,
+    #[serde(default)]
+    pub n: Option<u32>,
+    // 为 true 时回复文本以 prompt 开头
+    #[serde(default)]
+    pub echo: bool,
+    // 其余参数（包括 max_tokens）与对话接口一样只用于告知客户端
+    #[serde(flatten)]
+    pub extra: HashMap<String, Option<IgnoredAny>>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum Prompt {
+    Text(String),
+    Batch(Vec<String>),
+}
+
+#[derive(Serialize)]
+pub struct CompletionResponse {
+    pub id: String,
+    pub object: &'static str,
+    pub created: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    pub choices: Vec<CompletionChoice>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<Usage>,
+}
+
+#[derive(Serialize)]
+pub struct CompletionChoice {
+    pub text: String,
+    pub index: i32,
+    pub logprobs: Option<()>,
+    pub finish_reason: Option<String>,
+}
+
+// 转换为对话请求交由对话接口处理，再将回复转换为 text_completion 格式
+pub async fn handle_completions(
+    State(state): State<Arc<Mutex<AppState>>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    mut headers: HeaderMap,
+    Json(request): Json<CompletionRequest>,
+) -> Result<Response<Body>, (StatusCode, Json<ErrorResponse>)> {
+    if request.stream {
+        return Err(invalid_request(
+            "stream is not supported, use /v1/chat/completions instead",
+        ));
+    }
+
+    let prompt = match request.prompt {
+        Prompt::Text(prompt) => prompt,
+        Prompt::Batch(mut prompts) if prompts.len() == 1 => prompts.remove(0),
+        Prompt::Batch(_) => return Err(invalid_request("prompt must be a single string")),
+    };
+    if prompt.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ChatError::EmptyMessages.to_json()),
+        ));
+    }
+
+    let chat_request = ChatRequest {
+        model: request.model,
+        messages: vec![Message {
+            role: Role::User,
+            content: MessageContent::Text(prompt.clone()),
+        }],
+        stream: false,
+        stream_options: None,
+        n: request.n,
+        logprobs: None,
+        top_logprobs: None,
+        response_format: None,
+        conversation_id: None,
+        slow_pool: None,
+        token_tag: None,
+        template: None,
+        template_vars: HashMap::new(),
+        metadata: HashMap::new(),
+        tenant: None,
+        extra: request.extra,
+    };
+
+    // 保活响应在 JSON 前插入空白字符，无法再转换格式
+    headers.remove(HEADER_NAME_NON_STREAM_KEEPALIVE);
+
+    let response = handle_chat(
+        State(state),
+        ConnectInfo(addr),
+        Query(ChatQuery::default()),
+        headers,
+        Json(chat_request),
+    )
+    .await?;
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = axum::body::to_bytes(body, usize::MAX)
+        .await
+        .map_err(|e| request_failed(e.to_string()))?;
+    if !parts.status.is_success() {
+        return Ok(Response::from_parts(parts, Body::from(bytes)));
+    }
+
+    let response: ChatResponse =
+        serde_json::from_slice(&bytes).map_err(|e| request_failed(e.to_string()))?;
+    let completion = CompletionResponse {
+        id: response.id.replacen("chatcmpl-", "cmpl-", 1),
+        object: "text_completion",
+        created: response.created,
+        model: response.model,
+        choices: response
+            .choices
+            .into_iter()
+            .map(|choice| {
+                let text = choice.message.map(|m| content_text(m.content));
+                CompletionChoice {
+                    text: match (request.echo, text) {
+                        (true, Some(text)) => format!("{}{}", prompt, text),
+                        (true, None) => prompt.clone(),
+                        (false, text) => text.unwrap_or_default(),
+                    },
+                    index: choice.index,
+                    logprobs: None,
+                    finish_reason: choice.finish_reason,
+                }
+            })
+            .collect(),
+        usage: response.usage,
+    };
+
+    parts.headers.remove(CONTENT_LENGTH);
+    parts
+        .headers
+        .insert(CONTENT_TYPE, "application/json".parse().unwrap());
+    Ok(Response::from_parts(
+        parts,
+        Body::from(serde_json::to_string(&completion).unwrap()),
+    ))
+}
+
+fn content_text(content: MessageContent) -> String {
+    match content {
+        MessageContent::Text(text) => text,
+        MessageContent::Vision(contents) => contents
+            .into_iter()
+            .filter_map(|content| content.text)
+            .collect::<Vec<String>>()
+            .join("\n"),
+    }
+}
+
+fn invalid_request(message: &str) -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(ErrorResponse {
+            status: ApiStatus::Failed,
+            code: Some(400),
+            error: Some("Invalid request".to_string()),
+            message: Some(message.to_string()),
+        }),
+    )
+}
+
+fn request_failed(error: String) -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(ChatError::RequestFailed(error).to_json()),
+    )
+}
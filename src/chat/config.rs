@@ -1,4 +1,4 @@
-use crate::AppConfig;
+use crate::app::model::AppConfig;
 
 include!(concat!(env!("OUT_DIR"), "/key.rs"));
 
@@ -10,8 +10,21 @@ def_pub_const!(
     ERR_UNSUPPORTED_IMAGE_FORMAT,
     "不支持的图片格式，仅支持 PNG、JPEG、WEBP 和非动态 GIF"
 );
+def_pub_const!(ERR_IMAGE_TOO_LARGE, "图片过大，最大支持 20MB");
 def_pub_const!(ERR_NODATA, "No data");
 
+// 图片大小限制(字节)
+pub const IMAGE_MAX_SIZE: usize = 20 * 1024 * 1024;
+// 图片最大边长，超出时等比缩放
+pub const IMAGE_MAX_DIMENSION: u32 = 2048;
+// detail 为 low 时的最大边长
+pub const IMAGE_LOW_DETAIL_DIMENSION: u32 = 512;
+
+// 请求 metadata 的限制，与 OpenAI 一致
+pub const METADATA_MAX_KEYS: usize = 16;
+pub const METADATA_MAX_KEY_LENGTH: usize = 64;
+pub const METADATA_MAX_VALUE_LENGTH: usize = 512;
+
 const MODEL_OBJECT: &str = "model";
 const CREATED: &i64 = &1706659200;
 
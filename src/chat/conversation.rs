@@ -0,0 +1,165 @@
+use super::model::{Message, MessageContent, Role};
+use crate::app::{
+    lazy::CONVERSATION_HISTORY,
+    model::{ConversationMessage, Conversations},
+};
+use parking_lot::Mutex;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    sync::LazyLock,
+    time::{Duration, Instant},
+};
+use uuid::Uuid;
+
+// 超过上限时淘汰最久未使用的会话
+const CAPACITY: usize = 10_000;
+// 会话闲置超过该时长后重新生成上游会话 ID
+const IDLE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+struct Entry {
+    upstream_id: String,
+    last_used: Instant,
+}
+
+// 以调用方凭证与客户端会话 ID 的哈希为键，不同调用方的同名会话互不影响
+static CONVERSATIONS: LazyLock<Mutex<HashMap<[u8; 32], Entry>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn key(scope: &str, conversation_id: &str) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(scope.as_bytes());
+    hasher.update([0]);
+    hasher.update(conversation_id.as_bytes());
+    hasher.finalize().into()
+}
+
+// 返回客户端会话对应的上游会话 ID，首次出现或已过期时生成新的 ID
+pub fn upstream_id(scope: &str, conversation_id: &str) -> String {
+    let key = key(scope, conversation_id);
+    let now = Instant::now();
+    let mut conversations = CONVERSATIONS.lock();
+
+    if let Some(entry) = conversations.get_mut(&key) {
+        if now.duration_since(entry.last_used) < IDLE_TTL {
+            entry.last_used = now;
+            return entry.upstream_id.clone();
+        }
+    }
+
+    if !conversations.contains_key(&key) && conversations.len() >= CAPACITY {
+        conversations.retain(|_, entry| now.duration_since(entry.last_used) < IDLE_TTL);
+        if conversations.len() >= CAPACITY {
+            if let Some(oldest) = conversations
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| *key)
+            {
+                conversations.remove(&oldest);
+            }
+        }
+    }
+
+    let upstream_id = Uuid::new_v4().to_string();
+    conversations.insert(
+        key,
+        Entry {
+            upstream_id: upstream_id.clone(),
+            last_used: now,
+        },
+    );
+    upstream_id
+}
+
+#[inline]
+pub fn history_enabled() -> bool {
+    *CONVERSATION_HISTORY
+}
+
+// 会话历史的所有者，使用调用方凭证的哈希，不保存凭证本身
+pub fn owner(scope: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(scope.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+// 一轮对话中需要写入会话历史的内容
+pub struct Turn {
+    owner: String,
+    id: String,
+    messages: Vec<ConversationMessage>,
+}
+
+/// 将保存的历史消息插入到请求消息中（位于开头的系统消息之后），返回本轮待保存的内容
+///
+/// 系统消息不会写入历史，图片只保留其中的文本
+pub fn begin_turn(scope: &str, id: &str, messages: &mut Vec<Message>) -> Turn {
+    let owner = owner(scope);
+    let now = chrono::Utc::now().timestamp();
+
+    let new_messages = messages
+        .iter()
+        .filter(|message| message.role != Role::System)
+        .map(|message| ConversationMessage {
+            role: role_name(&message.role).to_string(),
+            content: content_text(&message.content),
+            created_at: now,
+        })
+        .collect();
+
+    let history = Conversations::messages(&owner, id);
+    if !history.is_empty() {
+        let position = messages
+            .iter()
+            .position(|message| message.role != Role::System)
+            .unwrap_or(messages.len());
+        messages.splice(
+            position..position,
+            history.into_iter().map(|message| Message {
+                role: match message.role.as_str() {
+                    "assistant" => Role::Assistant,
+                    _ => Role::User,
+                },
+                content: MessageContent::Text(message.content),
+            }),
+        );
+    }
+
+    Turn {
+        owner,
+        id: id.to_string(),
+        messages: new_messages,
+    }
+}
+
+impl Turn {
+    // 写入本轮的消息与助手回复
+    pub fn finish(&self, reply: &str) {
+        let mut messages = self.messages.clone();
+        messages.push(ConversationMessage {
+            role: role_name(&Role::Assistant).to_string(),
+            content: reply.to_string(),
+            created_at: chrono::Utc::now().timestamp(),
+        });
+        Conversations::append(&self.owner, &self.id, messages);
+    }
+}
+
+fn role_name(role: &Role) -> &'static str {
+    match role {
+        Role::System => "system",
+        Role::User => "user",
+        Role::Assistant => "assistant",
+    }
+}
+
+fn content_text(content: &MessageContent) -> String {
+    match content {
+        MessageContent::Text(text) => text.clone(),
+        MessageContent::Vision(contents) => contents
+            .iter()
+            .filter_map(|content| content.text.as_deref())
+            .collect::<Vec<_>>()
+            .join("\n"),
+    }
+}
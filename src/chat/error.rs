@@ -1,5 +1,8 @@
-use super::aiserver::v1::ErrorDetails;
-use crate::common::model::{ApiStatus, ErrorResponse as CommonErrorResponse};
+use super::aiserver::v1::{error_details, ErrorDetails};
+use crate::{
+    app::i18n::Text,
+    common::model::{ApiStatus, ErrorResponse as CommonErrorResponse},
+};
 use base64::{engine::general_purpose::STANDARD_NO_PAD, Engine as _};
 use prost::Message as _;
 use reqwest::StatusCode;
@@ -42,31 +45,53 @@ pub struct ErrorDetail {
 // }
 
 impl ChatError {
+    fn details(&self) -> Option<ErrorDetails> {
+        self.error.details.first().and_then(|detail| {
+            STANDARD_NO_PAD
+                .decode(&detail.value)
+                .ok()
+                .map(bytes::Bytes::from)
+                .and_then(|buf| ErrorDetails::decode(buf).ok())
+        })
+    }
+
+    // 上游因 checksum 失效拒绝请求，表现为客户端过旧或错误说明中提到 checksum
+    pub fn is_checksum_error(&self) -> bool {
+        self.kind() == ErrorKind::InvalidChecksum
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        self.details()
+            .as_ref()
+            .map_or(ErrorKind::Unknown, ErrorKind::classify)
+    }
+
     pub fn to_error_response(self) -> ErrorResponse {
         if self.error.details.is_empty() {
             return ErrorResponse {
                 status: 500,
                 code: "unknown".to_string(),
+                kind: ErrorKind::Unknown,
                 error: None,
             };
         }
 
-        let error_details = self.error.details.first().and_then(|detail| {
-            STANDARD_NO_PAD
-                .decode(&detail.value)
-                .ok()
-                .map(bytes::Bytes::from)
-                .and_then(|buf| ErrorDetails::decode(buf).ok())
-        });
-
-        let status = error_details
+        let error_details = self.details();
+        let kind = error_details
             .as_ref()
-            .map(|details| details.status_code())
-            .unwrap_or(500);
+            .map_or(ErrorKind::Unknown, ErrorKind::classify);
+
+        let status = kind.status_code().unwrap_or_else(|| {
+            error_details
+                .as_ref()
+                .map(|details| details.status_code())
+                .unwrap_or(500)
+        });
 
         ErrorResponse {
             status,
             code: self.error.code,
+            kind,
             error: error_details
                 .and_then(|details| details.details)
                 .map(|custom_details| Error {
@@ -77,14 +102,141 @@ impl ChatError {
     }
 }
 
+// 上游错误的分类，已知的类别以独立的错误码与状态码返回给客户端
+#[derive(Clone, Copy, PartialEq)]
+pub enum ErrorKind {
+    // 账号的用量已用完
+    QuotaExceeded,
+    RateLimited,
+    // 模型不存在、已下线或当前账号无权使用
+    ModelDisabled,
+    // checksum 失效或客户端版本过旧
+    InvalidChecksum,
+    // 所在地区无法使用
+    RegionBlocked,
+    // token 无效或已过期
+    Unauthorized,
+    Unknown,
+}
+
+impl ErrorKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorKind::QuotaExceeded => "quota_exceeded",
+            ErrorKind::RateLimited => "rate_limited",
+            ErrorKind::ModelDisabled => "model_disabled",
+            ErrorKind::InvalidChecksum => "invalid_checksum",
+            ErrorKind::RegionBlocked => "region_blocked",
+            ErrorKind::Unauthorized => "upstream_unauthorized",
+            ErrorKind::Unknown => "upstream_error",
+        }
+    }
+
+    // 未知类别沿用上游错误类型对应的状态码
+    fn status_code(self) -> Option<u16> {
+        match self {
+            ErrorKind::QuotaExceeded | ErrorKind::RateLimited => Some(429),
+            ErrorKind::ModelDisabled => Some(400),
+            // 与客户端无关，是服务使用的 token 或 checksum 被上游拒绝
+            ErrorKind::InvalidChecksum => Some(502),
+            ErrorKind::RegionBlocked => Some(403),
+            ErrorKind::Unauthorized => Some(401),
+            ErrorKind::Unknown => None,
+        }
+    }
+
+    // 先按错误说明中的关键字匹配，上游常以通用错误类型返回这些错误
+    fn classify(details: &ErrorDetails) -> ErrorKind {
+        use error_details::Error;
+
+        let text = details
+            .details
+            .as_ref()
+            .map(|custom| format!("{} {}", custom.title, custom.detail).to_lowercase())
+            .unwrap_or_default();
+        let mentions = |keywords: &[&str]| keywords.iter().any(|keyword| text.contains(keyword));
+
+        if mentions(&["region", "country"])
+            && mentions(&["not available", "unavailable", "not supported", "blocked"])
+        {
+            return ErrorKind::RegionBlocked;
+        }
+        if mentions(&["checksum"]) {
+            return ErrorKind::InvalidChecksum;
+        }
+        if mentions(&["usage limit", "quota", "out of fast", "fast requests"]) {
+            return ErrorKind::QuotaExceeded;
+        }
+        if mentions(&["rate limit", "too many requests"]) {
+            return ErrorKind::RateLimited;
+        }
+        if mentions(&["model"])
+            && mentions(&[
+                "disabled",
+                "not available",
+                "not supported",
+                "not enabled",
+                "deprecated",
+            ])
+        {
+            return ErrorKind::ModelDisabled;
+        }
+
+        match Error::try_from(details.error) {
+            Ok(Error::OutdatedClient) => ErrorKind::InvalidChecksum,
+            Ok(
+                Error::FreeUserUsageLimit
+                | Error::ProUserUsageLimit
+                | Error::OpenaiAccountLimitExceeded
+                | Error::ResourceExhausted,
+            ) => ErrorKind::QuotaExceeded,
+            Ok(
+                Error::FreeUserRateLimitExceeded
+                | Error::ProUserRateLimitExceeded
+                | Error::OpenaiRateLimitExceeded
+                | Error::GenericRateLimitExceeded
+                | Error::Gpt4VisionPreviewRateLimit
+                | Error::ApiKeyRateLimit,
+            ) => ErrorKind::RateLimited,
+            Ok(Error::BadModelName | Error::Deprecated | Error::ProUserOnly) => {
+                ErrorKind::ModelDisabled
+            }
+            Ok(
+                Error::BadApiKey
+                | Error::InvalidAuthId
+                | Error::AuthTokenNotFound
+                | Error::AuthTokenExpired
+                | Error::Unauthorized
+                | Error::NotLoggedIn,
+            ) => ErrorKind::Unauthorized,
+            _ => ErrorKind::Unknown,
+        }
+    }
+}
+
 #[derive(Serialize)]
 pub struct ErrorResponse {
     pub status: u16,
     pub code: String,
+    #[serde(skip)]
+    pub kind: ErrorKind,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<Error>,
 }
 
+// 写入日志 error 字段的结构化错误
+#[derive(Serialize)]
+struct LoggedError<'a> {
+    code: &'static str,
+    status: u16,
+    // 上游返回的原始错误码
+    upstream: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    details: Option<&'a str>,
+}
+
 #[derive(Serialize)]
 pub struct Error {
     pub message: String,
@@ -108,7 +260,31 @@ impl ErrorResponse {
         )
     }
 
+    pub fn to_log(&self) -> String {
+        serde_json::to_string(&LoggedError {
+            code: self.kind.as_str(),
+            status: self.status,
+            upstream: &self.code,
+            message: self.error.as_ref().map(|error| error.message.as_str()),
+            details: self.error.as_ref().map(|error| error.details.as_str()),
+        })
+        .unwrap_or_else(|_| self.native_code())
+    }
+
+    // 已知类别的 error 为分类错误码，message 为上游的错误说明
     pub fn to_common(self) -> CommonErrorResponse {
+        if self.kind != ErrorKind::Unknown {
+            return CommonErrorResponse {
+                status: ApiStatus::Error,
+                code: Some(self.status),
+                error: Some(self.kind.as_str().to_string()),
+                message: Some(self.error.map_or_else(
+                    || self.code.replace("_", " "),
+                    |error| format!("{}: {}", error.message, error.details),
+                )),
+            };
+        }
+
         CommonErrorResponse {
             status: ApiStatus::Error,
             code: Some(self.status),
@@ -128,6 +304,17 @@ pub enum StreamError {
     EmptyStream,
 }
 
+impl StreamError {
+    // 返回给客户端的说明，上游错误另行转换
+    pub fn message(&self) -> Option<String> {
+        match self {
+            StreamError::ChatError(_) => None,
+            StreamError::DataLengthLessThan5 => Some(Text::StreamDataTooShort.get().to_string()),
+            StreamError::EmptyStream => Some(Text::StreamEmpty.get().to_string()),
+        }
+    }
+}
+
 impl std::fmt::Display for StreamError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -0,0 +1,182 @@
+use super::aiserver::v1::{error_details, CustomErrorDetails, ErrorDetails, StreamChatResponse};
+use crate::app::{
+    constant::HEADER_NAME_FAULT_INJECT,
+    lazy::{
+        ENABLE_FAULT_INJECTION, FAULT_MALFORMED_PERCENT, FAULT_RATE_LIMIT_PERCENT,
+        FAULT_STREAM_ERROR_PERCENT, FAULT_TIMEOUT_PERCENT,
+    },
+};
+use axum::http::{HeaderMap, Response};
+use base64::{engine::general_purpose::STANDARD_NO_PAD, Engine as _};
+use bytes::Bytes;
+use parking_lot::RwLock;
+use prost::Message as _;
+use rand::Rng as _;
+use serde::Serialize;
+use std::{convert::Infallible, sync::LazyLock};
+
+// 可注入的上游故障
+#[derive(Clone, Copy, PartialEq)]
+pub enum Fault {
+    // 等待 SERVICE_TIMEOUT 后按超时处理
+    Timeout,
+    // 上游返回限流错误
+    RateLimit,
+    // 返回部分内容后中断
+    StreamError,
+    // 无法解析的帧
+    Malformed,
+}
+
+impl Fault {
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Self::Timeout => "timeout",
+            Self::RateLimit => "rate_limit",
+            Self::StreamError => "stream_error",
+            Self::Malformed => "malformed",
+        }
+    }
+
+    pub fn from_str_name(s: &str) -> Option<Self> {
+        match s {
+            "timeout" => Some(Self::Timeout),
+            "rate_limit" => Some(Self::RateLimit),
+            "stream_error" => Some(Self::StreamError),
+            "malformed" => Some(Self::Malformed),
+            _ => None,
+        }
+    }
+}
+
+// 各类故障的注入概率(百分比)
+#[derive(Clone, Copy, Serialize)]
+pub struct FaultRates {
+    pub timeout: usize,
+    pub rate_limit: usize,
+    pub stream_error: usize,
+    pub malformed: usize,
+}
+
+impl FaultRates {
+    fn from_env() -> Self {
+        Self {
+            timeout: *FAULT_TIMEOUT_PERCENT,
+            rate_limit: *FAULT_RATE_LIMIT_PERCENT,
+            stream_error: *FAULT_STREAM_ERROR_PERCENT,
+            malformed: *FAULT_MALFORMED_PERCENT,
+        }
+    }
+}
+
+static RATES: LazyLock<RwLock<FaultRates>> = LazyLock::new(|| RwLock::new(FaultRates::from_env()));
+
+#[inline]
+pub fn is_enabled() -> bool {
+    *ENABLE_FAULT_INJECTION
+}
+
+pub fn rates() -> FaultRates {
+    *RATES.read()
+}
+
+pub fn set_rates(rates: FaultRates) {
+    *RATES.write() = rates;
+}
+
+// 恢复为环境变量中的配置
+pub fn reset_rates() {
+    set_rates(FaultRates::from_env());
+}
+
+/// 决定本次请求注入的故障，请求头 x-fault-inject 优先于配置的概率
+pub fn pick(headers: &HeaderMap) -> Option<Fault> {
+    if !is_enabled() {
+        return None;
+    }
+    if let Some(value) = headers
+        .get(HEADER_NAME_FAULT_INJECT)
+        .and_then(|v| v.to_str().ok())
+    {
+        return Fault::from_str_name(value);
+    }
+
+    let rates = rates();
+    let mut rng = rand::thread_rng();
+    [
+        (Fault::Timeout, rates.timeout),
+        (Fault::RateLimit, rates.rate_limit),
+        (Fault::StreamError, rates.stream_error),
+        (Fault::Malformed, rates.malformed),
+    ]
+    .into_iter()
+    .find(|&(_, rate)| rate > 0 && rng.gen_range(0..100) < rate)
+    .map(|(fault, _)| fault)
+}
+
+/// 代替上游的响应，每个数据块单独返回；超时故障不产生响应，返回 `None`
+pub fn response(fault: Fault) -> Option<reqwest::Response> {
+    let chunks = match fault {
+        Fault::Timeout => return None,
+        Fault::RateLimit => vec![error_frame(
+            error_details::Error::GenericRateLimitExceeded,
+            "Too many requests",
+        )],
+        Fault::StreamError => {
+            let content = frame(
+                0,
+                &StreamChatResponse {
+                    text: "故障注入: 模拟的部分回复".to_string(),
+                    ..Default::default()
+                }
+                .encode_to_vec(),
+            );
+            vec![
+                Bytes::from(vec![0; 5]),
+                Bytes::from(content),
+                error_frame(error_details::Error::Unspecified, "Injected stream error"),
+            ]
+        }
+        Fault::Malformed => {
+            let mut first = vec![0; 5];
+            first.extend(frame(0, &[0xff; 8]));
+            // 声明的长度超过实际数据，帧永远不完整
+            let mut truncated = vec![0];
+            truncated.extend_from_slice(&64u32.to_be_bytes());
+            truncated.extend_from_slice(&[0xff; 3]);
+            vec![Bytes::from(first), Bytes::from(truncated)]
+        }
+    };
+
+    let body = reqwest::Body::wrap_stream(futures::stream::iter(
+        chunks.into_iter().map(Ok::<_, Infallible>),
+    ));
+    Some(Response::new(body).into())
+}
+
+fn frame(msg_type: u8, data: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(5 + data.len());
+    bytes.push(msg_type);
+    bytes.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    bytes.extend_from_slice(data);
+    bytes
+}
+
+// 与上游错误相同格式的 JSON 帧
+fn error_frame(error: error_details::Error, title: &str) -> Bytes {
+    let details = ErrorDetails {
+        error: error as i32,
+        details: Some(CustomErrorDetails {
+            title: title.to_string(),
+            detail: "故障注入".to_string(),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+    .encode_to_vec();
+    let json = format!(
+        r#"{{"error":{{"code":"fault_injected","details":[{{"value":"{}"}}]}}}}"#,
+        STANDARD_NO_PAD.encode(details)
+    );
+    Bytes::from(frame(2, json.as_bytes()))
+}
@@ -0,0 +1,339 @@
+use crate::{
+    app::{
+        constant::{HEADER_NAME_IDEMPOTENCY_KEY, HEADER_NAME_IDEMPOTENT_REPLAYED},
+        lazy::{IDEMPOTENCY_CAPACITY, IDEMPOTENCY_TTL},
+    },
+    common::model::{ApiStatus, ErrorResponse},
+};
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header::AUTHORIZATION, HeaderMap, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use bytes::Bytes;
+use futures::StreamExt as _;
+use parking_lot::Mutex;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, LazyLock,
+    },
+    time::{Duration, Instant},
+};
+
+// 客户端提供的键最长长度，超出时拒绝请求
+const MAX_KEY_LENGTH: usize = 255;
+
+type Key = [u8; 32];
+
+enum State {
+    // 首次请求仍在进行中
+    Pending,
+    Completed {
+        status: StatusCode,
+        headers: HeaderMap,
+        body: Bytes,
+    },
+}
+
+struct Entry {
+    fingerprint: Key,
+    state: State,
+    expires_at: Instant,
+}
+
+// 以 200 发出但以错误结束的响应（流式错误片段、保活响应中的错误等）由对话服务标记，
+// 作为响应扩展返回，标记的响应不保存，客户端可以使用同一个键重试
+#[derive(Clone, Default)]
+pub struct ResponseFailure(Arc<AtomicBool>);
+
+impl ResponseFailure {
+    pub fn mark(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    fn is_marked(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+static ENTRIES: LazyLock<Mutex<HashMap<Key, Entry>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+pub fn is_enabled() -> bool {
+    *IDEMPOTENCY_TTL > 0 && *IDEMPOTENCY_CAPACITY > 0
+}
+
+fn digest(parts: &[&[u8]]) -> Key {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update(part);
+        hasher.update([0]);
+    }
+    hasher.finalize().into()
+}
+
+fn error(status: StatusCode, error: &str, message: &str) -> Response {
+    (
+        status,
+        Json(ErrorResponse {
+            status: ApiStatus::Failed,
+            code: Some(status.as_u16()),
+            error: Some(error.to_string()),
+            message: Some(message.to_string()),
+        }),
+    )
+        .into_response()
+}
+
+// 带 Idempotency-Key 的请求在有效期内只执行一次，重复的请求直接回放首次的响应，流式响应整体回放
+pub async fn apply(request: Request, next: Next) -> Response {
+    if !is_enabled() {
+        return next.run(request).await;
+    }
+    let Some(idempotency_key) = request
+        .headers()
+        .get(HEADER_NAME_IDEMPOTENCY_KEY)
+        .map(|h| h.as_bytes().to_vec())
+    else {
+        return next.run(request).await;
+    };
+    if idempotency_key.is_empty() || idempotency_key.len() > MAX_KEY_LENGTH {
+        return error(
+            StatusCode::BAD_REQUEST,
+            "Invalid idempotency key",
+            "Idempotency-Key 不能为空且长度不能超过255",
+        );
+    }
+
+    // 不同调用者的键互不影响
+    let caller = request
+        .headers()
+        .get(AUTHORIZATION)
+        .map(HeaderValue::as_bytes)
+        .unwrap_or_default();
+    let key = digest(&[caller, request.uri().path().as_bytes(), &idempotency_key]);
+
+    let (parts, body) = request.into_parts();
+    let body = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(body) => body,
+        Err(_) => {
+            return error(
+                StatusCode::BAD_REQUEST,
+                "Invalid request body",
+                "无法读取请求体",
+            )
+        }
+    };
+    let fingerprint = digest(&[&body]);
+
+    {
+        let mut entries = ENTRIES.lock();
+        let now = Instant::now();
+        if let Some(entry) = entries.get(&key).filter(|entry| entry.expires_at > now) {
+            if entry.fingerprint != fingerprint {
+                return error(
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    "Idempotency key reused",
+                    "该 Idempotency-Key 已用于内容不同的请求",
+                );
+            }
+            return match entry.state {
+                State::Pending => error(
+                    StatusCode::CONFLICT,
+                    "Request in progress",
+                    "使用该 Idempotency-Key 的请求仍在处理中",
+                ),
+                State::Completed {
+                    status,
+                    ref headers,
+                    ref body,
+                } => {
+                    tracing::debug!("回放幂等请求的响应");
+                    let mut response = Response::new(Body::from(body.clone()));
+                    *response.status_mut() = status;
+                    *response.headers_mut() = headers.clone();
+                    response.headers_mut().insert(
+                        HEADER_NAME_IDEMPOTENT_REPLAYED,
+                        HeaderValue::from_static("true"),
+                    );
+                    response
+                }
+            };
+        }
+
+        // 先清理过期条目，仍然已满时淘汰最早过期的条目
+        if entries.len() >= *IDEMPOTENCY_CAPACITY {
+            entries.retain(|_, entry| entry.expires_at > now);
+            if entries.len() >= *IDEMPOTENCY_CAPACITY {
+                if let Some(oldest) = entries
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.expires_at)
+                    .map(|(key, _)| *key)
+                {
+                    entries.remove(&oldest);
+                }
+            }
+        }
+        entries.insert(
+            key,
+            Entry {
+                fingerprint,
+                state: State::Pending,
+                expires_at: now + Duration::from_secs(*IDEMPOTENCY_TTL),
+            },
+        );
+    }
+
+    let recorder = Recorder {
+        key,
+        completed: false,
+    };
+    let response = next.run(Request::from_parts(parts, Body::from(body))).await;
+    record(recorder, response)
+}
+
+// 响应体完整结束后保存响应，失败的请求不保存，客户端可以使用同一个键重试
+fn record(recorder: Recorder, response: Response) -> Response {
+    if !response.status().is_success() {
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let status = parts.status;
+    let headers = parts.headers.clone();
+    let failure = parts.extensions.get::<ResponseFailure>().cloned();
+    let stream = futures::stream::unfold(
+        (
+            body.into_data_stream(),
+            Vec::new(),
+            Some((recorder, headers)),
+        ),
+        move |(mut stream, mut buffer, recorder)| {
+            let failure = failure.clone();
+            async move {
+                match stream.next().await {
+                    Some(Ok(chunk)) => {
+                        buffer.extend_from_slice(&chunk);
+                        Some((Ok(chunk), (stream, buffer, recorder)))
+                    }
+                    Some(Err(e)) => Some((Err(e), (stream, buffer, None))),
+                    None => {
+                        // 以错误结束的响应丢弃 recorder，进行中的条目随之移除
+                        if let Some((mut recorder, headers)) = recorder
+                            .filter(|_| !failure.as_ref().is_some_and(ResponseFailure::is_marked))
+                        {
+                            recorder.complete(status, headers, Bytes::from(buffer));
+                        }
+                        None
+                    }
+                }
+            }
+        },
+    );
+    Response::from_parts(parts, Body::from_stream(stream))
+}
+
+// 响应未能完整结束（出错或客户端断开）时移除进行中的条目
+struct Recorder {
+    key: Key,
+    completed: bool,
+}
+
+impl Recorder {
+    fn complete(&mut self, status: StatusCode, headers: HeaderMap, body: Bytes) {
+        self.completed = true;
+        if let Some(entry) = ENTRIES.lock().get_mut(&self.key) {
+            entry.state = State::Completed {
+                status,
+                headers,
+                body,
+            };
+        }
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        if !self.completed {
+            ENTRIES.lock().remove(&self.key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pending(key: Key) {
+        ENTRIES.lock().insert(
+            key,
+            Entry {
+                fingerprint: [0; 32],
+                state: State::Pending,
+                expires_at: Instant::now() + Duration::from_secs(60),
+            },
+        );
+    }
+
+    fn stream_response(body: &'static str, failure: Option<ResponseFailure>) -> Response {
+        let mut response = Response::new(Body::from(body));
+        if let Some(failure) = failure {
+            response.extensions_mut().insert(failure);
+        }
+        response
+    }
+
+    #[tokio::test]
+    async fn test_completed_stream_is_replayed() {
+        let key = digest(&[b"test_completed_stream_is_replayed".as_slice()]);
+        pending(key);
+        let response = record(
+            Recorder {
+                key,
+                completed: false,
+            },
+            stream_response(
+                "data: {}\n\ndata: [DONE]\n\n",
+                Some(ResponseFailure::default()),
+            ),
+        );
+        axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            ENTRIES.lock().get(&key).map(|entry| &entry.state),
+            Some(State::Completed { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_stream_ending_in_error_is_not_replayed() {
+        let key = digest(&[b"test_stream_ending_in_error_is_not_replayed".as_slice()]);
+        pending(key);
+        let failure = ResponseFailure::default();
+        let response = record(
+            Recorder {
+                key,
+                completed: false,
+            },
+            stream_response(
+                "data: {\"choices\":[{\"finish_reason\":\"error\"}]}\n\ndata: [DONE]\n\n",
+                Some(failure.clone()),
+            ),
+        );
+        // 对话服务在发出错误片段时标记响应
+        failure.mark();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+
+        assert!(body.ends_with(b"data: [DONE]\n\n"));
+        assert!(ENTRIES.lock().get(&key).is_none());
+    }
+}
@@ -0,0 +1,164 @@
+use crate::{
+    app::{
+        constant::{API_KEY_SCOPE_CHAT, AUTHORIZATION_BEARER_PREFIX},
+        lazy::{AUTH_TOKEN, IMAGE_API_BASE, IMAGE_API_KEY},
+        log_sink,
+        model::{ApiKeys, AppConfig, AppState, LogStatus, RequestLog, TimingInfo},
+        request_id,
+    },
+    common::{
+        client::HTTP_CLIENT,
+        model::{error::ChatError, ErrorResponse},
+        utils::client_ip,
+    },
+};
+use axum::{
+    body::{Body, Bytes},
+    extract::{ConnectInfo, State},
+    http::{
+        header::{AUTHORIZATION, CONTENT_TYPE},
+        HeaderMap, StatusCode,
+    },
+    response::Response,
+    Json,
+};
+use serde::Deserialize;
+use std::{net::SocketAddr, sync::Arc, time::Instant};
+use tokio::sync::Mutex;
+
+// 仅用于记录日志，其余字段原样转发
+#[derive(Deserialize)]
+struct ImageRequest {
+    #[serde(default)]
+    model: Option<String>,
+}
+
+#[inline]
+pub fn is_enabled() -> bool {
+    !IMAGE_API_BASE.is_empty()
+}
+
+// 图片生成：未配置 IMAGE_API_BASE 时返回 501，否则转发到外部服务并记录日志
+pub async fn handle_images_generations(
+    State(state): State<Arc<Mutex<AppState>>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Response<Body>, (StatusCode, Json<ErrorResponse>)> {
+    let auth_header = headers
+        .get(AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix(AUTHORIZATION_BEARER_PREFIX))
+        .ok_or((
+            StatusCode::UNAUTHORIZED,
+            Json(ChatError::Unauthorized.to_json()),
+        ))?;
+
+    // 外部服务的费用由部署者承担，只允许使用号池的令牌
+    let client_ip = client_ip(&headers, addr);
+    let api_key = ApiKeys::authenticate(auth_header);
+    if let Some(ref api_key) = api_key {
+        if !api_key.has_scope(API_KEY_SCOPE_CHAT) {
+            return Err((
+                StatusCode::FORBIDDEN,
+                Json(ChatError::Unauthorized.to_json()),
+            ));
+        }
+        if !api_key.allows_ip(client_ip) {
+            return Err((
+                StatusCode::FORBIDDEN,
+                Json(ChatError::IpNotAllowed(client_ip.to_string()).to_json()),
+            ));
+        }
+    } else if auth_header != AUTH_TOKEN.as_str()
+        && !(AppConfig::is_share() && auth_header == AppConfig::get_share_token().as_str())
+    {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(ChatError::Unauthorized.to_json()),
+        ));
+    }
+
+    if !is_enabled() {
+        return Err((
+            StatusCode::NOT_IMPLEMENTED,
+            Json(ChatError::ImagesUnsupported.to_json()),
+        ));
+    }
+
+    let model = serde_json::from_slice::<ImageRequest>(&body)
+        .ok()
+        .and_then(|request| request.model)
+        .unwrap_or_default();
+    let request_time = chrono::Local::now();
+    let start = Instant::now();
+
+    let request = HTTP_CLIENT
+        .read()
+        .post(format!(
+            "{}/images/generations",
+            IMAGE_API_BASE.trim_end_matches('/')
+        ))
+        .bearer_auth(IMAGE_API_KEY.as_str())
+        .header(CONTENT_TYPE, "application/json")
+        .body(body);
+    let result = request.send().await;
+
+    let (response, error) = match result {
+        Ok(response) => {
+            let status = response.status();
+            let content_type = response.headers().get(CONTENT_TYPE).cloned();
+            match response.bytes().await {
+                Ok(bytes) => {
+                    let error = (!status.is_success())
+                        .then(|| format!("Image API returned {}", status.as_u16()));
+                    let mut builder = Response::builder().status(status);
+                    if let Some(content_type) = content_type {
+                        builder = builder.header(CONTENT_TYPE, content_type);
+                    }
+                    (Ok(builder.body(Body::from(bytes)).unwrap()), error)
+                }
+                Err(e) => (Err(e.to_string()), Some(e.to_string())),
+            }
+        }
+        Err(e) => (Err(e.to_string()), Some(e.to_string())),
+    };
+
+    record_log(
+        &state,
+        RequestLog {
+            timing: TimingInfo {
+                total: start.elapsed().as_secs_f64(),
+                first: None,
+                upstream: None,
+            },
+            status: if error.is_some() {
+                LogStatus::Failed
+            } else {
+                LogStatus::Success
+            },
+            error,
+            api_key: api_key.map(|api_key| api_key.id),
+            ..RequestLog::rejected(0, request_time, model, request_id::get(&headers))
+        },
+    )
+    .await;
+
+    response.map_err(|e| {
+        (
+            StatusCode::BAD_GATEWAY,
+            Json(ChatError::RequestFailed(e).to_json()),
+        )
+    })
+}
+
+async fn record_log(state: &Mutex<AppState>, mut log: RequestLog) {
+    let mut state = state.lock().await;
+    log.id = state.next_log_id();
+    if matches!(log.status, LogStatus::Failed) {
+        state.error_requests += 1;
+    }
+    log_sink::submit(&log);
+    state.push_log(log);
+    state.total_requests += 1;
+}
@@ -0,0 +1,256 @@
+use crate::app::{lazy::RESPONSE_FORMAT_STRICTNESS, model::ResponseFormat};
+use serde::{de::IgnoredAny, Deserialize};
+use std::collections::HashMap;
+
+const NO_EXTRA_TEXT: &str =
+    "Do not include any explanation, markdown formatting or code fences before or after it.";
+
+// json_schema 中用于校验的部分，只检查顶层类型与必需字段
+#[derive(Deserialize, Default)]
+struct SchemaRequirements {
+    #[serde(rename = "type", default)]
+    kind: Option<String>,
+    #[serde(default)]
+    required: Vec<String>,
+}
+
+// RESPONSE_FORMAT_STRICTNESS 为 off 时只注入指令，按原样返回回复
+#[inline]
+pub fn validation_enabled() -> bool {
+    RESPONSE_FORMAT_STRICTNESS.as_str() != "off"
+}
+
+// 注入到系统消息中的格式要求，text 格式不需要
+pub fn instruction(format: &ResponseFormat) -> Option<String> {
+    match format {
+        ResponseFormat::Text => None,
+        ResponseFormat::JsonObject => Some(format!(
+            "Respond only with a single valid JSON object. {}",
+            NO_EXTRA_TEXT
+        )),
+        ResponseFormat::JsonSchema { json_schema } => {
+            let mut instruction = format!(
+                "Respond only with a single valid JSON value that conforms to the JSON schema \"{}\"",
+                json_schema.name
+            );
+            if let Some(ref description) = json_schema.description {
+                instruction.push_str(&format!(" ({})", description));
+            }
+            instruction.push_str(". ");
+            instruction.push_str(NO_EXTRA_TEXT);
+            if let Some(ref schema) = json_schema.schema {
+                instruction.push_str("\n\nJSON schema:\n");
+                instruction.push_str(&serde_json::to_string(schema).unwrap_or_default());
+            }
+            Some(instruction)
+        }
+    }
+}
+
+/// 校验完整的回复，不符合要求时依次尝试去掉代码块标记、截取首尾括号之间的内容
+///
+/// 均无法通过校验时，strict 模式返回错误原因，其余模式返回原回复
+pub fn finalize(format: &ResponseFormat, text: String) -> Result<String, String> {
+    let requirements = match format {
+        ResponseFormat::Text => return Ok(text),
+        ResponseFormat::JsonObject => SchemaRequirements {
+            kind: Some("object".to_string()),
+            required: Vec::new(),
+        },
+        ResponseFormat::JsonSchema { json_schema } => json_schema
+            .schema
+            .as_ref()
+            .and_then(|schema| serde_json::to_string(schema).ok())
+            .and_then(|schema| serde_json::from_str(&schema).ok())
+            .unwrap_or_default(),
+    };
+
+    // 通过校验的候选内容，与原回复相同时为 None
+    let result = {
+        let trimmed = text.trim();
+        let unfenced = strip_code_fence(trimmed);
+        let candidates = [
+            Some(trimmed),
+            unfenced,
+            extract_outermost(unfenced.unwrap_or(trimmed)),
+        ];
+
+        let mut error = None;
+        let mut valid = None;
+        for candidate in candidates.into_iter().flatten() {
+            match validate(candidate, &requirements) {
+                Ok(()) => {
+                    valid = Some((candidate != text).then(|| candidate.to_string()));
+                    break;
+                }
+                Err(e) => {
+                    error.get_or_insert(e);
+                }
+            }
+        }
+        valid.ok_or_else(|| error.unwrap_or_default())
+    };
+
+    match result {
+        Ok(None) => Ok(text),
+        Ok(Some(repaired)) => {
+            tracing::debug!("已修复 JSON 模式的回复");
+            Ok(repaired)
+        }
+        Err(error) => {
+            tracing::warn!("回复不是有效的 JSON: {}", error);
+            if RESPONSE_FORMAT_STRICTNESS.as_str() == "strict" {
+                return Err(error);
+            }
+            Ok(text)
+        }
+    }
+}
+
+fn validate(text: &str, requirements: &SchemaRequirements) -> Result<(), String> {
+    // 未声明类型但有必需字段时同样按对象校验
+    let is_object = match requirements.kind.as_deref() {
+        Some(kind) => kind == "object",
+        None => !requirements.required.is_empty(),
+    };
+    if !is_object {
+        return serde_json::from_str::<IgnoredAny>(text)
+            .map(|_| ())
+            .map_err(|e| e.to_string());
+    }
+
+    let object =
+        serde_json::from_str::<HashMap<String, IgnoredAny>>(text).map_err(|e| e.to_string())?;
+    match requirements
+        .required
+        .iter()
+        .find(|key| !object.contains_key(key.as_str()))
+    {
+        Some(key) => Err(format!("missing required property '{}'", key)),
+        None => Ok(()),
+    }
+}
+
+// 去掉 ```json ... ``` 代码块标记
+fn strip_code_fence(text: &str) -> Option<&str> {
+    let rest = text.strip_prefix("```")?;
+    let rest = &rest[rest.find('\n')? + 1..];
+    Some(rest.trim_end().strip_suffix("```")?.trim())
+}
+
+// 截取第一个 { 或 [ 到与之对应的最后一个 } 或 ] 之间的内容
+fn extract_outermost(text: &str) -> Option<&str> {
+    let start = text.find(['{', '['])?;
+    let close = if text[start..].starts_with('{') {
+        '}'
+    } else {
+        ']'
+    };
+    let end = text.rfind(close)?;
+    (end > start).then(|| &text[start..=end])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(json: &str) -> ResponseFormat {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_strip_code_fence() {
+        assert_eq!(
+            strip_code_fence("```json\n{\"a\":1}\n```"),
+            Some("{\"a\":1}")
+        );
+        assert_eq!(strip_code_fence("```\n[1, 2]\n```  "), Some("[1, 2]"));
+        assert_eq!(strip_code_fence("{\"a\":1}"), None); // 没有代码块
+        assert_eq!(strip_code_fence("```json\n{\"a\":1}"), None); // 缺少结尾标记
+        assert_eq!(strip_code_fence("```{\"a\":1}```"), None); // 缺少换行
+    }
+
+    #[test]
+    fn test_extract_outermost() {
+        assert_eq!(
+            extract_outermost("Here it is: {\"a\": {\"b\": 1}} hope this helps"),
+            Some("{\"a\": {\"b\": 1}}")
+        );
+        assert_eq!(extract_outermost("result: [1, [2]] done"), Some("[1, [2]]"));
+        assert_eq!(extract_outermost("no json here"), None);
+        assert_eq!(extract_outermost("} then {"), None);
+    }
+
+    #[test]
+    fn test_finalize_text_unchanged() {
+        let text = "```json\n{}\n```".to_string();
+        assert_eq!(finalize(&ResponseFormat::Text, text.clone()), Ok(text));
+    }
+
+    #[test]
+    fn test_finalize_keeps_valid_response() {
+        let text = "{\"a\": 1}".to_string();
+        assert_eq!(
+            finalize(&ResponseFormat::JsonObject, text.clone()),
+            Ok(text)
+        );
+    }
+
+    #[test]
+    fn test_finalize_repairs_response() {
+        assert_eq!(
+            finalize(
+                &ResponseFormat::JsonObject,
+                "```json\n{\"a\": 1}\n```".to_string()
+            ),
+            Ok("{\"a\": 1}".to_string())
+        );
+        assert_eq!(
+            finalize(
+                &ResponseFormat::JsonObject,
+                "Sure! {\"a\": 1} Anything else?".to_string()
+            ),
+            Ok("{\"a\": 1}".to_string())
+        );
+        assert_eq!(
+            finalize(
+                &ResponseFormat::JsonObject,
+                "```json\nSure! {\"a\": 1}\n```".to_string()
+            ),
+            Ok("{\"a\": 1}".to_string())
+        );
+    }
+
+    #[test]
+    fn test_finalize_checks_required_properties() {
+        let response_format = format(
+            r#"{"type":"json_schema","json_schema":{"name":"person","schema":{"type":"object","required":["name"]}}}"#,
+        );
+        let ResponseFormat::JsonSchema { ref json_schema } = response_format else {
+            unreachable!();
+        };
+        let requirements: SchemaRequirements =
+            serde_json::from_str(&serde_json::to_string(&json_schema.schema).unwrap()).unwrap();
+
+        assert!(validate("{\"name\": \"a\"}", &requirements).is_ok());
+        assert_eq!(
+            validate("{\"age\": 1}", &requirements),
+            Err("missing required property 'name'".to_string())
+        );
+        assert!(validate("[1]", &requirements).is_err());
+        assert_eq!(
+            finalize(&response_format, "Result: {\"name\": \"a\"}".to_string()),
+            Ok("{\"name\": \"a\"}".to_string())
+        );
+    }
+
+    #[test]
+    fn test_validate_non_object_schema() {
+        let requirements = SchemaRequirements {
+            kind: Some("array".to_string()),
+            required: Vec::new(),
+        };
+        assert!(validate("[1, 2]", &requirements).is_ok());
+        assert!(validate("[1, 2", &requirements).is_err());
+    }
+}
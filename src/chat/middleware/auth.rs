@@ -1,23 +1,58 @@
-use crate::app::{constant::AUTHORIZATION_BEARER_PREFIX, lazy::AUTH_TOKEN};
+use crate::{
+    app::{constant::AUTHORIZATION_BEARER_PREFIX, lazy::AUTH_TOKEN},
+    common::model::{error::ChatError, ApiStatus, ErrorResponse},
+};
 use axum::{
     body::Body,
-    http::{header::AUTHORIZATION, Request, StatusCode},
+    extract::FromRequestParts,
+    http::{header::AUTHORIZATION, request::Parts, HeaderMap, Request, StatusCode},
     middleware::Next,
     response::Response,
+    Json,
 };
 
-// 认证中间件函数
-pub async fn auth_middleware(request: Request<Body>, next: Next) -> Result<Response, StatusCode> {
-    let auth_header = request
-        .headers()
+fn bearer(headers: &HeaderMap) -> Option<&str> {
+    headers
         .get(AUTHORIZATION)
         .and_then(|h| h.to_str().ok())
         .and_then(|h| h.strip_prefix(AUTHORIZATION_BEARER_PREFIX))
-        .ok_or(StatusCode::UNAUTHORIZED)?;
+}
 
-    if auth_header != AUTH_TOKEN.as_str() {
+// 认证中间件函数
+pub async fn auth_middleware(request: Request<Body>, next: Next) -> Result<Response, StatusCode> {
+    if bearer(request.headers()) != Some(AUTH_TOKEN.as_str()) {
         return Err(StatusCode::UNAUTHORIZED);
     }
 
     Ok(next.run(request).await)
 }
+
+// 管理接口的认证，请求头需携带 AUTH_TOKEN，放在处理函数参数中即可生效
+pub struct AdminAuth;
+
+impl<S: Send + Sync> FromRequestParts<S> for AdminAuth {
+    type Rejection = (StatusCode, Json<ErrorResponse>);
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        if bearer(&parts.headers) != Some(AUTH_TOKEN.as_str()) {
+            return Err((
+                StatusCode::UNAUTHORIZED,
+                Json(ChatError::Unauthorized.to_json()),
+            ));
+        }
+        Ok(Self)
+    }
+}
+
+// 管理接口参数校验失败时的响应
+pub fn bad_request(message: impl Into<String>) -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(ErrorResponse {
+            status: ApiStatus::Failed,
+            code: Some(400),
+            error: Some("Invalid request".to_string()),
+            message: Some(message.into()),
+        }),
+    )
+}
@@ -0,0 +1,61 @@
+use super::{
+    aiserver::v1::StreamChatResponse,
+    model::{Message, MessageContent, Role},
+};
+use crate::app::lazy::MOCK_UPSTREAM;
+use axum::http::Response;
+use bytes::Bytes;
+use prost::Message as _;
+use std::convert::Infallible;
+
+#[inline]
+pub fn is_enabled() -> bool {
+    *MOCK_UPSTREAM
+}
+
+/// 模拟的回复内容，由模型名与最后一条用户消息决定，相同的请求总是得到相同的回复
+pub fn reply(model: &str, messages: &[Message]) -> String {
+    let prompt = messages
+        .iter()
+        .rev()
+        .find(|message| message.role == Role::User)
+        .map(|message| match message.content {
+            MessageContent::Text(ref text) => text.clone(),
+            MessageContent::Vision(ref parts) => parts
+                .iter()
+                .filter_map(|part| part.text.as_deref())
+                .collect::<Vec<_>>()
+                .join(" "),
+        })
+        .unwrap_or_default();
+    format!("Mock response from {model}: {prompt}")
+}
+
+/// 代替上游的响应：开始帧、按单词切分的内容帧与结束帧，每帧单独返回以经过完整的流式处理
+pub fn response(reply: &str) -> reqwest::Response {
+    let mut chunks = vec![Bytes::from(frame(0, &[]))];
+    chunks.extend(reply.split_inclusive(' ').map(|text| {
+        Bytes::from(frame(
+            0,
+            &StreamChatResponse {
+                text: text.to_string(),
+                ..Default::default()
+            }
+            .encode_to_vec(),
+        ))
+    }));
+    chunks.push(Bytes::from(frame(2, b"{}")));
+
+    let body = reqwest::Body::wrap_stream(futures::stream::iter(
+        chunks.into_iter().map(Ok::<_, Infallible>),
+    ));
+    Response::new(body).into()
+}
+
+fn frame(msg_type: u8, data: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(5 + data.len());
+    bytes.push(msg_type);
+    bytes.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    bytes.extend_from_slice(data);
+    bytes
+}
@@ -1,13 +1,14 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 #[serde(untagged)]
 pub enum MessageContent {
     Text(String),
     Vision(Vec<VisionMessageContent>),
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct VisionMessageContent {
     #[serde(rename = "type")]
     pub content_type: String,
@@ -17,20 +18,20 @@ pub struct VisionMessageContent {
     pub image_url: Option<ImageUrl>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct ImageUrl {
     pub url: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub detail: Option<String>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Message {
     pub role: Role,
     pub content: MessageContent,
 }
 
-#[derive(Serialize, Deserialize, PartialEq)]
+#[derive(Serialize, Deserialize, PartialEq, Clone)]
 pub enum Role {
     #[serde(rename = "system", alias = "developer")]
     System,
@@ -40,7 +41,7 @@ pub enum Role {
     Assistant,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 pub struct ChatResponse {
     pub id: String,
     pub object: String,
@@ -50,19 +51,25 @@ pub struct ChatResponse {
     pub choices: Vec<Choice>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub usage: Option<Usage>,
+    // 回显请求中允许记录的 metadata，流式响应只在结束片段中返回
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<HashMap<String, String>>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 pub struct Choice {
     pub index: i32,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub message: Option<Message>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub delta: Option<Delta>,
+    // 上游不提供 logprobs，始终为 null，供严格校验响应结构的客户端使用
+    #[serde(default)]
+    pub logprobs: Option<()>,
     pub finish_reason: Option<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 pub struct Delta {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub role: Option<Role>,
@@ -70,11 +77,19 @@ pub struct Delta {
     pub content: Option<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 pub struct Usage {
     pub prompt_tokens: u32,
     pub completion_tokens: u32,
     pub total_tokens: u32,
+    // 仅 o1 系列模型返回
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub completion_tokens_details: Option<CompletionTokensDetails>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CompletionTokensDetails {
+    pub reasoning_tokens: u32,
 }
 
 // 模型定义
@@ -86,10 +101,15 @@ pub struct Model {
     pub owned_by: &'static str,
 }
 
-use super::constant::USAGE_CHECK_MODELS;
-use crate::app::model::{AppConfig, UsageCheck};
+use super::constant::{O1, O1_MINI, O1_PREVIEW, USAGE_CHECK_MODELS};
+use crate::app::model::{AppConfig, Capabilities, ModelCapabilities, UsageCheck};
 
 impl Model {
+    // o1 系列模型只能完整返回，用量中需要包含 reasoning_tokens
+    pub fn is_o1(&self) -> bool {
+        matches!(self.id, O1 | O1_MINI | O1_PREVIEW)
+    }
+
     pub fn is_usage_check(&self, usage_check: Option<UsageCheck>) -> bool {
         match usage_check.unwrap_or(AppConfig::get_usage_check()) {
             UsageCheck::None => false,
@@ -100,8 +120,31 @@ impl Model {
     }
 }
 
+// 模型列表中的条目，capabilities 为扩展字段，仅在配置了模型能力信息时返回
+#[derive(Serialize)]
+pub struct ModelEntry {
+    pub id: &'static str,
+    pub created: &'static i64,
+    pub object: &'static str,
+    pub owned_by: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub capabilities: Option<Capabilities>,
+}
+
+impl From<&Model> for ModelEntry {
+    fn from(model: &Model) -> Self {
+        Self {
+            id: model.id,
+            created: model.created,
+            object: model.object,
+            owned_by: model.owned_by,
+            capabilities: ModelCapabilities::get(model.id).filter(|c| !c.is_empty()),
+        }
+    }
+}
+
 #[derive(Serialize)]
 pub struct ModelsResponse {
     pub object: &'static str,
-    pub data: &'static [Model],
+    pub data: Vec<ModelEntry>,
 }
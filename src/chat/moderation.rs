@@ -0,0 +1,100 @@
+use crate::{
+    app::{
+        lazy::{MODERATION_API_KEY, MODERATION_API_URL, SERVICE_TIMEOUT},
+        model::{ModerationRules, ModerationScope},
+    },
+    common::client::HTTP_CLIENT,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, time::Duration};
+
+use super::model::{Message, MessageContent};
+
+#[derive(Serialize)]
+struct ModerationApiRequest<'a> {
+    input: &'a str,
+}
+
+// 兼容 OpenAI /v1/moderations 的响应格式
+#[derive(Deserialize)]
+struct ModerationApiResponse {
+    results: Vec<ModerationApiResult>,
+}
+
+#[derive(Deserialize)]
+struct ModerationApiResult {
+    flagged: bool,
+    #[serde(default)]
+    categories: HashMap<String, bool>,
+}
+
+// 应用规则后再调用外部审核接口，返回拦截原因
+pub async fn check_prompt(messages: &mut [Message]) -> Result<(), String> {
+    let mut texts = Vec::new();
+    for message in messages.iter_mut() {
+        match message.content {
+            MessageContent::Text(ref mut text) => {
+                apply_rules(text, ModerationScope::Prompt)?;
+                texts.push(text.as_str());
+            }
+            MessageContent::Vision(ref mut contents) => {
+                for text in contents.iter_mut().filter_map(|c| c.text.as_mut()) {
+                    apply_rules(text, ModerationScope::Prompt)?;
+                    texts.push(text.as_str());
+                }
+            }
+        }
+    }
+
+    if MODERATION_API_URL.is_empty() || texts.is_empty() {
+        return Ok(());
+    }
+
+    // 接口不可用时放行，避免影响正常请求
+    match call_api(&texts.join("\n")).await {
+        Ok(Some(categories)) => Err(format!("flagged by moderation API ({})", categories)),
+        Ok(None) => Ok(()),
+        Err(e) => {
+            tracing::warn!("调用审核接口失败: {}", e);
+            Ok(())
+        }
+    }
+}
+
+// 流式输出按片段检查，跨片段的内容可能无法命中
+pub fn check_output(text: &mut String) -> Result<(), String> {
+    apply_rules(text, ModerationScope::Output)
+}
+
+fn apply_rules(text: &mut String, stage: ModerationScope) -> Result<(), String> {
+    ModerationRules::apply(text, stage).map_err(|id| format!("matched rule '{}'", id))
+}
+
+// 被标记时返回命中的类别
+async fn call_api(input: &str) -> Result<Option<String>, reqwest::Error> {
+    let client = HTTP_CLIENT.read().clone();
+    let mut request = client
+        .post(MODERATION_API_URL.as_str())
+        .timeout(Duration::from_secs(*SERVICE_TIMEOUT))
+        .json(&ModerationApiRequest { input });
+    if !MODERATION_API_KEY.is_empty() {
+        request = request.bearer_auth(MODERATION_API_KEY.as_str());
+    }
+
+    let response: ModerationApiResponse = request.send().await?.error_for_status()?.json().await?;
+
+    Ok(response
+        .results
+        .into_iter()
+        .find(|result| result.flagged)
+        .map(|result| {
+            let mut categories: Vec<_> = result
+                .categories
+                .into_iter()
+                .filter(|(_, flagged)| *flagged)
+                .map(|(category, _)| category)
+                .collect();
+            categories.sort_unstable();
+            categories.join(",")
+        }))
+}
@@ -0,0 +1,67 @@
+use super::model::Message;
+use crate::app::{
+    lazy::{PAYLOAD_ARCHIVE, PAYLOAD_MAX_BYTES, PAYLOAD_REDACT_PATTERN},
+    model::{Payload, Payloads},
+};
+use regex::Regex;
+use std::sync::LazyLock;
+
+// 常见的 API key、Bearer/JWT 令牌与邮箱地址
+const DEFAULT_REDACT_PATTERN: &str = r"sk-[A-Za-z0-9_-]{16,}|Bearer\s+[A-Za-z0-9._~+/=-]{16,}|eyJ[A-Za-z0-9_-]+\.[A-Za-z0-9_-]+\.[A-Za-z0-9_-]+|[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}";
+const REDACTED: &str = "[REDACTED]";
+
+static REDACT_REGEX: LazyLock<Option<Regex>> = LazyLock::new(|| {
+    let pattern = if PAYLOAD_REDACT_PATTERN.is_empty() {
+        DEFAULT_REDACT_PATTERN
+    } else {
+        PAYLOAD_REDACT_PATTERN.as_str()
+    };
+    Regex::new(pattern)
+        .inspect_err(|e| tracing::warn!("脱敏规则无效，已改用内置规则: {}", e))
+        .or_else(|_| Regex::new(DEFAULT_REDACT_PATTERN))
+        .ok()
+});
+
+#[inline]
+pub fn is_enabled() -> bool {
+    *PAYLOAD_ARCHIVE
+}
+
+// 记录发送给上游的消息，需在消息被消耗之前调用
+pub fn record_request(log_id: u64, messages: &[Message]) {
+    let Ok(request) = serde_json::to_string(messages) else {
+        return;
+    };
+    let (request, truncated) = sanitize(&request);
+    Payloads::push(Payload {
+        log_id,
+        timestamp: chrono::Utc::now().timestamp(),
+        request,
+        response: None,
+        truncated,
+    });
+}
+
+// 记录完整结束的回复
+pub fn record_response(log_id: u64, text: &str) {
+    let (response, truncated) = sanitize(text);
+    Payloads::set_response(log_id, response, truncated);
+}
+
+// 先脱敏再按 PAYLOAD_MAX_BYTES 截断，返回是否被截断
+fn sanitize(text: &str) -> (String, bool) {
+    let mut text = match REDACT_REGEX.as_ref() {
+        Some(regex) => regex.replace_all(text, REDACTED).into_owned(),
+        None => text.to_string(),
+    };
+    if text.len() <= *PAYLOAD_MAX_BYTES {
+        return (text, false);
+    }
+
+    let mut end = *PAYLOAD_MAX_BYTES;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    text.truncate(end);
+    (text, true)
+}
@@ -0,0 +1,153 @@
+use crate::app::{
+    lazy::{
+        DISPATCH_LIMIT, DISPATCH_LIMIT_HIGH, DISPATCH_LIMIT_LOW, DISPATCH_LIMIT_NORMAL,
+        DISPATCH_QUEUE_TIMEOUT,
+    },
+    model::AppState,
+};
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, LazyLock,
+    },
+    time::{Duration, Instant},
+};
+use tokio::sync::{Mutex, Notify};
+
+// 请求的优先级，数值越小越优先
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Tier {
+    // 使用 AUTH_TOKEN 的管理员请求
+    High = 0,
+    // API key、共享 token 与自有 token 的请求
+    Normal = 1,
+    // 使用公共号池的请求
+    Low = 2,
+}
+
+pub const TIERS: [Tier; 3] = [Tier::High, Tier::Normal, Tier::Low];
+
+impl Tier {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Tier::High => "high",
+            Tier::Normal => "normal",
+            Tier::Low => "low",
+        }
+    }
+
+    // 该等级单独的并发上限，为0时只受 DISPATCH_LIMIT 限制
+    fn limit(self) -> usize {
+        match self {
+            Tier::High => *DISPATCH_LIMIT_HIGH,
+            Tier::Normal => *DISPATCH_LIMIT_NORMAL,
+            Tier::Low => *DISPATCH_LIMIT_LOW,
+        }
+    }
+
+    fn below_limit(self, state: &AppState) -> bool {
+        let limit = self.limit();
+        limit == 0 || (state.dispatching[self as usize] as usize) < limit
+    }
+}
+
+// 各等级正在排队的请求数
+static WAITING: [AtomicUsize; 3] = [
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+];
+// 有请求结束或离开队列时唤醒排队的请求重新检查
+static RELEASED: LazyLock<Notify> = LazyLock::new(Notify::new);
+
+pub fn is_enabled() -> bool {
+    *DISPATCH_LIMIT > 0 || TIERS.iter().any(|tier| tier.limit() > 0)
+}
+
+pub fn waiting(tier: Tier) -> usize {
+    WAITING[tier as usize].load(Ordering::SeqCst)
+}
+
+// 总并发与该等级的上限都未达到，且没有可以发出的更高优先级请求在排队
+fn can_dispatch(state: &AppState, tier: Tier) -> bool {
+    let total: u64 = state.dispatching.iter().sum();
+    if *DISPATCH_LIMIT > 0 && total as usize >= *DISPATCH_LIMIT {
+        return false;
+    }
+    tier.below_limit(state)
+        && TIERS
+            .iter()
+            .take_while(|higher| **higher < tier)
+            .all(|higher| waiting(*higher) == 0 || !higher.below_limit(state))
+}
+
+// 离开队列时减少排队计数，请求被中途取消时同样生效
+struct Waiting(Tier);
+
+impl Drop for Waiting {
+    fn drop(&mut self) {
+        WAITING[self.0 as usize].fetch_sub(1, Ordering::SeqCst);
+        RELEASED.notify_waiters();
+    }
+}
+
+// 请求结束时释放占用的并发数
+pub struct Permit {
+    state: Arc<Mutex<AppState>>,
+    tier: Tier,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        let state = self.state.clone();
+        let tier = self.tier;
+        tokio::spawn(async move {
+            let mut state = state.lock().await;
+            let dispatching = &mut state.dispatching[tier as usize];
+            *dispatching = dispatching.saturating_sub(1);
+            drop(state);
+            RELEASED.notify_waiters();
+        });
+    }
+}
+
+// 按优先级等待发出请求的许可，未启用时返回 Some(None)，等待超时返回 None
+pub async fn acquire(state: &Arc<Mutex<AppState>>, tier: Tier) -> Option<Option<Permit>> {
+    if !is_enabled() {
+        return Some(None);
+    }
+
+    let deadline = Instant::now() + Duration::from_secs(*DISPATCH_QUEUE_TIMEOUT);
+    let mut waiting = None;
+    loop {
+        // 先注册通知再检查，避免错过检查之后的释放
+        let released = RELEASED.notified();
+        tokio::pin!(released);
+        released.as_mut().enable();
+
+        {
+            let mut state_guard = state.lock().await;
+            if can_dispatch(&state_guard, tier) {
+                state_guard.dispatching[tier as usize] += 1;
+                drop(state_guard);
+                drop(waiting);
+                return Some(Some(Permit {
+                    state: state.clone(),
+                    tier,
+                }));
+            }
+        }
+
+        if waiting.is_none() {
+            tracing::debug!("并发已达上限，{} 优先级的请求进入等待", tier.as_str());
+            WAITING[tier as usize].fetch_add(1, Ordering::SeqCst);
+            waiting = Some(Waiting(tier));
+        }
+
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() || tokio::time::timeout(remaining, released).await.is_err() {
+            tracing::debug!("等待发出请求超时");
+            return None;
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use crate::app::{
+    backoff,
+    lazy::{PUBLIC_POOL_DAILY_LIMIT, PUBLIC_POOL_ENABLED},
+    lease,
+    model::{AppState, Tenants, TokenBlacklist},
+};
+use chrono::NaiveDate;
+use std::{collections::HashMap, sync::LazyLock, time::Instant};
+use tokio::sync::Mutex;
+
+// 每个用户当天通过公共号池发起的请求数
+static USER_USAGE: LazyLock<parking_lot::Mutex<HashMap<String, (NaiveDate, usize)>>> =
+    LazyLock::new(|| parking_lot::Mutex::new(HashMap::new()));
+
+// 每个公共 token 最近一次被分配的时间
+static LAST_USED: LazyLock<parking_lot::Mutex<HashMap<String, Instant>>> =
+    LazyLock::new(|| parking_lot::Mutex::new(HashMap::new()));
+
+#[inline]
+pub fn is_enabled() -> bool {
+    *PUBLIC_POOL_ENABLED
+}
+
+/// 占用用户当天的一次配额，已用完时返回 `false`
+pub fn try_consume(user: &str) -> bool {
+    let today = chrono::Local::now().date_naive();
+    let mut usage = USER_USAGE.lock();
+    let (date, count) = usage.entry(user.to_string()).or_insert((today, 0));
+    if *date != today {
+        *date = today;
+        *count = 0;
+    }
+    if *PUBLIC_POOL_DAILY_LIMIT > 0 && *count >= *PUBLIC_POOL_DAILY_LIMIT {
+        return false;
+    }
+    *count += 1;
+    true
+}
+
+/// 用户当天剩余的配额，未启用公共号池或不限制时返回 `None`
+pub fn remaining(user: &str) -> Option<usize> {
+    if !is_enabled() || *PUBLIC_POOL_DAILY_LIMIT == 0 {
+        return None;
+    }
+    let today = chrono::Local::now().date_naive();
+    let used = USER_USAGE
+        .lock()
+        .get(user)
+        .filter(|(date, _)| *date == today)
+        .map_or(0, |(_, count)| *count);
+    Some(PUBLIC_POOL_DAILY_LIMIT.saturating_sub(used))
+}
+
+/// 归还一次配额，用于没有选到 token 的请求
+pub fn refund(user: &str) {
+    if let Some((_, count)) = USER_USAGE.lock().get_mut(user) {
+        *count = count.saturating_sub(1);
+    }
+}
+
+/// 在公共 token 中选择最久未被分配的一个，使请求均匀分摊到各个 token
+pub async fn select(state: &Mutex<AppState>) -> Option<(String, String)> {
+    let mut candidates: Vec<(String, String)> = state
+        .lock()
+        .await
+        .token_infos
+        .iter()
+        .filter(|info| {
+            info.is_public
+                && Tenants::allows(None, &info.tags)
+                && !TokenBlacklist::is_blocked(&info.token)
+                && !backoff::is_cooling_down(&info.token)
+        })
+        .map(|info| (info.token.clone(), info.checksum.clone()))
+        .collect();
+
+    // 从未分配过的 token 排在最前
+    {
+        let last_used = LAST_USED.lock();
+        candidates.sort_by_key(|(token, _)| last_used.get(token).copied());
+    }
+
+    for (token, checksum) in candidates {
+        if lease::try_acquire(&token).await {
+            LAST_USED.lock().insert(token.clone(), Instant::now());
+            return Some((token, checksum));
+        }
+    }
+    None
+}
@@ -0,0 +1,79 @@
+use crate::app::{
+    lazy::{QUALITY_REFUSAL_PATTERN, QUALITY_SAMPLE_PERCENT},
+    model::{QualitySample, QualitySamples},
+};
+use rand::Rng as _;
+use regex::{Regex, RegexBuilder};
+use std::sync::LazyLock;
+
+// 常见的拒答开头
+const DEFAULT_REFUSAL_PATTERN: &str = r"^\s*(I'm sorry|I am sorry|Sorry,|I can't|I cannot|I'm unable|I am unable|I won't|As an AI|抱歉|对不起|很抱歉|我无法|我不能)";
+// 只检查回复开头的字符数
+const REFUSAL_CHECK_CHARS: usize = 200;
+
+static REFUSAL_REGEX: LazyLock<Option<Regex>> = LazyLock::new(|| {
+    let pattern = if QUALITY_REFUSAL_PATTERN.is_empty() {
+        DEFAULT_REFUSAL_PATTERN
+    } else {
+        QUALITY_REFUSAL_PATTERN.as_str()
+    };
+    RegexBuilder::new(pattern)
+        .case_insensitive(true)
+        .build()
+        .inspect_err(|e| tracing::warn!("拒答模式无效，已禁用拒答检测: {}", e))
+        .ok()
+});
+
+#[inline]
+pub fn is_enabled() -> bool {
+    *QUALITY_SAMPLE_PERCENT > 0
+}
+
+// 按 QUALITY_SAMPLE_PERCENT 决定本次请求是否抽样
+pub fn should_sample() -> bool {
+    is_enabled() && rand::thread_rng().gen_range(0..100) < *QUALITY_SAMPLE_PERCENT
+}
+
+// 记录一条完整回复的质量指标
+pub fn record(model: &str, text: &str, completion_tokens: u32) {
+    let head: String = text.chars().take(REFUSAL_CHECK_CHARS).collect();
+    QualitySamples::push(QualitySample {
+        timestamp: chrono::Utc::now().timestamp(),
+        model: model.to_string(),
+        length: u32::try_from(text.chars().count()).unwrap_or(u32::MAX),
+        completion_tokens,
+        language: detect_language(text).to_string(),
+        refusal: REFUSAL_REGEX
+            .as_ref()
+            .is_some_and(|regex| regex.is_match(&head)),
+    });
+}
+
+// 按出现最多的文字系统判断语言，含假名的汉字文本视为日语
+fn detect_language(text: &str) -> &'static str {
+    let (mut han, mut kana, mut hangul, mut cyrillic, mut arabic, mut latin) = (0, 0, 0, 0, 0, 0);
+    for c in text.chars() {
+        match c {
+            '\u{4E00}'..='\u{9FFF}' | '\u{3400}'..='\u{4DBF}' => han += 1,
+            '\u{3040}'..='\u{30FF}' => kana += 1,
+            '\u{AC00}'..='\u{D7AF}' | '\u{1100}'..='\u{11FF}' => hangul += 1,
+            '\u{0400}'..='\u{04FF}' => cyrillic += 1,
+            '\u{0600}'..='\u{06FF}' => arabic += 1,
+            c if c.is_ascii_alphabetic() => latin += 1,
+            _ => {}
+        }
+    }
+
+    let counts = [
+        ("zh", han + kana),
+        ("ko", hangul),
+        ("ru", cyrillic),
+        ("ar", arabic),
+        ("en", latin),
+    ];
+    match counts.iter().max_by_key(|(_, count)| *count) {
+        Some(&(_, 0)) | None => "other",
+        Some(&("zh", _)) if kana > 0 => "ja",
+        Some(&(language, _)) => language,
+    }
+}
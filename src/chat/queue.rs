@@ -0,0 +1,75 @@
+use crate::app::lazy::{TOKEN_QUEUE_SIZE, TOKEN_QUEUE_TIMEOUT};
+use axum::{
+    http::{header::RETRY_AFTER, HeaderValue, StatusCode},
+    response::Response,
+};
+use std::{
+    future::Future,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+// 等待期间重新尝试选择 token 的间隔
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+// 没有可用 token 时建议客户端重试的间隔(秒)
+const RETRY_AFTER_SECS: &str = "5";
+
+static WAITING: AtomicUsize = AtomicUsize::new(0);
+
+pub fn is_enabled() -> bool {
+    *TOKEN_QUEUE_SIZE > 0 && *TOKEN_QUEUE_TIMEOUT > 0
+}
+
+// 离开队列时释放位置
+struct Slot;
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        WAITING.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+// 排队等待直到 select 返回结果，未启用、队列已满或超时时返回 None
+pub async fn wait_for<T, F, Fut>(mut select: F) -> Option<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Option<T>>,
+{
+    if !is_enabled() {
+        return None;
+    }
+    if WAITING
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| {
+            (n < *TOKEN_QUEUE_SIZE).then_some(n + 1)
+        })
+        .is_err()
+    {
+        tracing::debug!("等待队列已满");
+        return None;
+    }
+    let _slot = Slot;
+
+    tracing::debug!("没有可用的 token，进入等待队列");
+    let deadline = Instant::now() + Duration::from_secs(*TOKEN_QUEUE_TIMEOUT);
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            tracing::debug!("等待 token 超时");
+            return None;
+        }
+        tokio::time::sleep(POLL_INTERVAL.min(remaining)).await;
+        if let Some(selected) = select().await {
+            return Some(selected);
+        }
+    }
+}
+
+// 没有可用 token 时返回 503，附带 Retry-After
+pub async fn add_retry_after(mut response: Response) -> Response {
+    if response.status() == StatusCode::SERVICE_UNAVAILABLE {
+        response
+            .headers_mut()
+            .insert(RETRY_AFTER, HeaderValue::from_static(RETRY_AFTER_SECS));
+    }
+    response
+}
@@ -0,0 +1,88 @@
+use crate::{
+    app::{
+        lazy::{USAGE_RECONCILE, USAGE_RECONCILE_TOLERANCE},
+        model::{AppState, UsageReconciliation},
+    },
+    common::{model::userinfo::UsageProfile, utils::get_usage_profile},
+};
+use std::{sync::Arc, time::Duration};
+use tokio::sync::Mutex;
+
+// 上游用量统计存在延迟，等待一段时间后再查询
+const SETTLE_DELAY: Duration = Duration::from_secs(5);
+
+pub fn is_enabled() -> bool {
+    *USAGE_RECONCILE
+}
+
+// 请求前的用量快照
+pub async fn snapshot(auth_token: &str) -> Option<UsageProfile> {
+    if !is_enabled() {
+        return None;
+    }
+    get_usage_profile(auth_token).await
+}
+
+// 在后台查询请求后的用量，与本地估算的 token 数对账并写入日志
+pub fn spawn(
+    state: Arc<Mutex<AppState>>,
+    log_id: u64,
+    auth_token: String,
+    before: UsageProfile,
+    counted_tokens: u32,
+) {
+    tokio::spawn(async move {
+        tokio::time::sleep(SETTLE_DELAY).await;
+
+        let Some(after) = get_usage_profile(&auth_token).await else {
+            tracing::debug!("对账时获取用量失败: {}", log_id);
+            return;
+        };
+
+        let reconciliation = reconcile(&before, &after, counted_tokens);
+        if reconciliation.discrepancy {
+            tracing::warn!(
+                "请求 {} 用量差异较大: 估算 {} tokens，上游 {} tokens",
+                log_id,
+                reconciliation.counted_tokens,
+                reconciliation.reported_tokens
+            );
+        }
+
+        let mut state = state.lock().await;
+        if let Some(log) = state
+            .request_logs
+            .iter_mut()
+            .rev()
+            .find(|log| log.id == log_id)
+        {
+            log.reconciliation = Some(reconciliation);
+        }
+    });
+}
+
+fn reconcile(
+    before: &UsageProfile,
+    after: &UsageProfile,
+    counted_tokens: u32,
+) -> UsageReconciliation {
+    let delta = |before: u32, after: u32| after.saturating_sub(before);
+
+    let reported_tokens = delta(before.premium.num_tokens, after.premium.num_tokens)
+        + delta(before.standard.num_tokens, after.standard.num_tokens)
+        + delta(before.unknown.num_tokens, after.unknown.num_tokens);
+    let reported_requests = delta(before.premium.num_requests, after.premium.num_requests)
+        + delta(before.standard.num_requests, after.standard.num_requests)
+        + delta(before.unknown.num_requests, after.unknown.num_requests);
+
+    // 差值超过估算值的一定比例时标记
+    let diff = reported_tokens.abs_diff(counted_tokens) as u64;
+    let discrepancy = diff * 100 > counted_tokens.max(1) as u64 * *USAGE_RECONCILE_TOLERANCE;
+
+    UsageReconciliation {
+        counted_tokens,
+        reported_tokens,
+        reported_requests,
+        discrepancy,
+    }
+}
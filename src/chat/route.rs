@@ -1,12 +1,17 @@
 mod logs;
-pub use logs::{handle_logs, handle_logs_post};
+pub use logs::{
+    handle_logs, handle_logs_cleanup, handle_logs_post, handle_logs_stream, LogsQuery,
+    LogsResponse,
+};
 mod health;
 pub use health::{handle_health, handle_root};
 mod tokens;
 pub use tokens::{
-    handle_add_tokens, handle_basic_calibration, handle_delete_tokens, handle_get_checksum,
-    handle_get_hash, handle_get_timestamp_header, handle_get_tokens, handle_reload_tokens,
-    handle_tokens_page, handle_update_tokens,
+    handle_add_tokens, handle_basic_calibration, handle_delete_tokens, handle_export_tokens,
+    handle_get_checksum, handle_get_deleted_tokens, handle_get_hash, handle_get_timestamp_header,
+    handle_get_tokens, handle_import_tokens, handle_reload_tokens, handle_restore_token,
+    handle_soft_delete_token, handle_token_meta, handle_tokens_page, handle_update_tokens,
+    handle_validate_tokens, TokenInfoResponse,
 };
 mod profile;
 pub use profile::handle_user_info;
@@ -17,3 +22,54 @@ pub use config::{
 };
 mod api;
 pub use api::handle_api_page;
+mod debug;
+pub use debug::handle_debug_echo;
+mod model_policies;
+pub use model_policies::handle_model_policies;
+mod token_blacklist;
+pub use token_blacklist::handle_token_blacklist;
+mod model_aliases;
+pub use model_aliases::handle_model_aliases;
+mod pricing;
+pub use pricing::{handle_model_prices, handle_spend};
+mod api_keys;
+pub use api_keys::handle_api_keys;
+mod runtime;
+pub use runtime::handle_runtime;
+mod audit_logs;
+pub use audit_logs::handle_audit_logs;
+mod prompt_templates;
+pub use prompt_templates::handle_prompt_templates;
+mod moderation;
+pub use moderation::handle_moderation_rules;
+mod reports;
+pub use reports::handle_reports;
+mod stats;
+pub use stats::{
+    handle_daily_stats, handle_latency_stats, handle_token_stats, LatencyPercentiles, ModelLatency,
+    TokenStats,
+};
+mod conversations;
+pub use conversations::handle_conversations;
+mod quality;
+pub use quality::handle_quality_trend;
+mod faults;
+pub use faults::handle_faults;
+mod checksums;
+pub use checksums::handle_checksums;
+mod session;
+pub use session::handle_session;
+mod payloads;
+pub use payloads::handle_payload;
+mod tenants;
+pub use tenants::handle_tenants;
+mod system_prompts;
+pub use system_prompts::handle_system_prompts;
+mod model_capabilities;
+pub use model_capabilities::handle_model_capabilities;
+mod azure_deployments;
+pub use azure_deployments::handle_azure_deployments;
+mod me;
+pub use me::handle_me;
+mod invite_codes;
+pub use invite_codes::{handle_invite_codes, handle_register};
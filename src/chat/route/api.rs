@@ -1,26 +1,35 @@
-use axum::response::{IntoResponse, Response};
+use axum::{
+    body::Body,
+    http::HeaderMap,
+    response::{IntoResponse, Response},
+};
 use reqwest::header::CONTENT_TYPE;
 
-use crate::{
-    app::constant::{
+use crate::app::{
+    constant::{
         CONTENT_TYPE_TEXT_HTML_WITH_UTF8, CONTENT_TYPE_TEXT_PLAIN_WITH_UTF8, ROUTE_API_PATH,
     },
-    AppConfig, PageContent,
+    model::{AppConfig, PageContent},
+    static_dir,
 };
 
-pub async fn handle_api_page() -> impl IntoResponse {
+pub async fn handle_api_page(headers: HeaderMap) -> impl IntoResponse {
     match AppConfig::get_page_content(ROUTE_API_PATH).unwrap_or_default() {
-        PageContent::Default => Response::builder()
-            .header(CONTENT_TYPE, CONTENT_TYPE_TEXT_HTML_WITH_UTF8)
-            .body(include_str!("../../../static/api.min.html").to_string())
-            .unwrap(),
+        PageContent::Default => {
+            static_dir::page(
+                "api.html",
+                &headers,
+                include_str!("../../../static/api.min.html"),
+            )
+            .await
+        }
         PageContent::Text(content) => Response::builder()
             .header(CONTENT_TYPE, CONTENT_TYPE_TEXT_PLAIN_WITH_UTF8)
-            .body(content.clone())
+            .body(Body::from(content.clone()))
             .unwrap(),
         PageContent::Html(content) => Response::builder()
             .header(CONTENT_TYPE, CONTENT_TYPE_TEXT_HTML_WITH_UTF8)
-            .body(content.clone())
+            .body(Body::from(content.clone()))
             .unwrap(),
     }
 }
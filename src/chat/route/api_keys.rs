@@ -0,0 +1,156 @@
+use crate::{
+    app::{
+        constant::API_KEY_SCOPE_CHAT,
+        model::{is_valid_ip_rule, ApiKey, ApiKeys, AuditActor, AuditLogs, Tenants},
+    },
+    chat::middleware::{bad_request, AdminAuth},
+    common::model::{ApiStatus, ErrorResponse},
+};
+use axum::{http::StatusCode, Json};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+pub struct ApiKeysRequest {
+    pub action: String,
+    // create 时使用
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub owner: Option<String>,
+    // 绑定的租户 ID，不填表示使用全局号池
+    #[serde(default)]
+    pub tenant: Option<String>,
+    // 有效期(秒)，不填表示永不过期
+    #[serde(default)]
+    pub expires_in: Option<u64>,
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    // 允许的来源 IP 或 CIDR，不填表示不限制
+    #[serde(default)]
+    pub allowed_ips: Vec<String>,
+    // revoke 与 delete 时使用
+    #[serde(default)]
+    pub id: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ApiKeysResponse {
+    pub status: ApiStatus,
+    pub keys: Vec<ApiKey>,
+    // 新建的明文密钥，只返回这一次
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+const KNOWN_SCOPES: [&str; 1] = [API_KEY_SCOPE_CHAT];
+
+pub async fn handle_api_keys(
+    _admin: AdminAuth,
+    actor: AuditActor,
+    Json(request): Json<ApiKeysRequest>,
+) -> Result<Json<ApiKeysResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let before = ApiKeys::list();
+
+    let (key, message) = match request.action.as_str() {
+        "list" => (None, None),
+
+        "create" => {
+            let name = request
+                .name
+                .filter(|name| !name.is_empty())
+                .ok_or_else(|| bad_request("缺少 name"))?;
+            if let Some(scope) = request
+                .scopes
+                .iter()
+                .find(|scope| !KNOWN_SCOPES.contains(&scope.as_str()))
+            {
+                return Err(bad_request(format!("未知权限范围: {}", scope)));
+            }
+            if let Some(rule) = request
+                .allowed_ips
+                .iter()
+                .find(|rule| !is_valid_ip_rule(rule))
+            {
+                return Err(bad_request(format!("无效的 IP 或 CIDR: {}", rule)));
+            }
+            let tenant = request.tenant.filter(|tenant| !tenant.is_empty());
+            if let Some(ref tenant) = tenant {
+                if Tenants::get(tenant).is_none() {
+                    return Err(bad_request(format!("租户不存在: {}", tenant)));
+                }
+            }
+            let expires_at = request
+                .expires_in
+                .map(|secs| chrono::Utc::now().timestamp().saturating_add(secs as i64));
+
+            let allowed_ips = request
+                .allowed_ips
+                .iter()
+                .map(|rule| rule.trim().to_string())
+                .collect();
+            let (_, key) = ApiKeys::create(
+                name,
+                request.owner,
+                tenant,
+                expires_at,
+                request.scopes,
+                allowed_ips,
+            );
+            (Some(key), Some("API key 已创建".to_string()))
+        }
+
+        "revoke" | "delete" => {
+            let id = request.id.ok_or_else(|| bad_request("缺少 id"))?;
+            let found = if request.action == "revoke" {
+                ApiKeys::revoke(&id)
+            } else {
+                ApiKeys::remove(&id)
+            };
+            if !found {
+                return Err(bad_request("API key 不存在"));
+            }
+            let message = if request.action == "revoke" {
+                "API key 已吊销"
+            } else {
+                "API key 已删除"
+            };
+            (None, Some(message.to_string()))
+        }
+
+        _ => return Err(bad_request("无效的操作类型")),
+    };
+
+    if request.action != "list" {
+        if let Err(e) = ApiKeys::save().await {
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    status: ApiStatus::Failed,
+                    code: Some(500),
+                    error: Some("保存 API key 失败".to_string()),
+                    message: Some(e.to_string()),
+                }),
+            ));
+        }
+    }
+
+    let after = ApiKeys::list();
+    if request.action != "list" {
+        AuditLogs::record(
+            &actor,
+            &format!("api_keys.{}", request.action),
+            AuditLogs::snapshot(&before),
+            AuditLogs::snapshot(&after),
+        )
+        .await;
+    }
+
+    Ok(Json(ApiKeysResponse {
+        status: ApiStatus::Success,
+        keys: after,
+        key,
+        message,
+    }))
+}
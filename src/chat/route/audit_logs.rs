@@ -0,0 +1,86 @@
+use crate::{
+    app::{
+        model::{AuditActor, AuditLog, AuditLogs},
+        session,
+    },
+    chat::middleware::AdminAuth,
+    common::{
+        model::{ApiStatus, ErrorResponse, NormalResponse},
+        utils::client_ip,
+    },
+};
+use axum::{
+    extract::{ConnectInfo, FromRequestParts},
+    http::{request::Parts, StatusCode},
+    Json,
+};
+use serde::Deserialize;
+use std::net::SocketAddr;
+
+// 操作者由认证方式决定，不信任客户端提供的名称：
+// AUTH_TOKEN 记为 admin，网页会话记为 session:会话标识
+impl<S: Send + Sync> FromRequestParts<S> for AuditActor {
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let name = if AdminAuth::from_request_parts(parts, state).await.is_ok() {
+            "admin".to_string()
+        } else if let Some(id) = session::id(&parts.headers) {
+            format!("session:{}", id)
+        } else {
+            "unknown".to_string()
+        };
+        let ip = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map_or_else(
+                || "unknown".to_string(),
+                |ConnectInfo(addr)| client_ip(&parts.headers, *addr).to_string(),
+            );
+        Ok(Self { name, ip })
+    }
+}
+
+#[derive(Deserialize, Default)]
+pub struct AuditLogsRequest {
+    // 按操作前缀筛选，如 tokens 或 tokens.add
+    #[serde(default)]
+    pub action: Option<String>,
+    #[serde(default)]
+    pub actor: Option<String>,
+    // 只返回最近的若干条
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+// 按时间从新到旧返回
+pub async fn handle_audit_logs(
+    _admin: AdminAuth,
+    request: Option<Json<AuditLogsRequest>>,
+) -> Result<Json<NormalResponse<Vec<AuditLog>>>, (StatusCode, Json<ErrorResponse>)> {
+    let request = request.map(|Json(request)| request).unwrap_or_default();
+
+    let logs: Vec<AuditLog> = AuditLogs::list()
+        .into_iter()
+        .rev()
+        .filter(|log| {
+            request
+                .action
+                .as_deref()
+                .is_none_or(|action| log.action.starts_with(action))
+        })
+        .filter(|log| {
+            request
+                .actor
+                .as_deref()
+                .is_none_or(|actor| log.actor == actor)
+        })
+        .take(request.limit.unwrap_or(usize::MAX))
+        .collect();
+
+    Ok(Json(NormalResponse {
+        status: ApiStatus::Success,
+        data: Some(logs),
+        message: None,
+    }))
+}
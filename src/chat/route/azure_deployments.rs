@@ -0,0 +1,93 @@
+use crate::{
+    app::model::{AuditActor, AuditLogs, AzureDeployment, AzureDeployments},
+    chat::{
+        constant::AVAILABLE_MODELS,
+        middleware::{bad_request, AdminAuth},
+    },
+    common::model::{ApiStatus, ErrorResponse, NormalResponse},
+};
+use axum::{http::StatusCode, Json};
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct AzureDeploymentRequest {
+    pub action: String,
+    #[serde(default)]
+    pub deployment: Option<String>,
+    // set 时使用，映射到的实际模型
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+pub async fn handle_azure_deployments(
+    _admin: AdminAuth,
+    actor: AuditActor,
+    Json(request): Json<AzureDeploymentRequest>,
+) -> Result<Json<NormalResponse<Vec<AzureDeployment>>>, (StatusCode, Json<ErrorResponse>)> {
+    let before = AzureDeployments::list();
+
+    let message = match request.action.as_str() {
+        "get" => None,
+
+        "set" | "delete" => {
+            let deployment = request
+                .deployment
+                .map(|deployment| deployment.trim().to_string())
+                .filter(|deployment| !deployment.is_empty())
+                .ok_or_else(|| bad_request("缺少 deployment".to_string()))?;
+
+            let message = if request.action == "set" {
+                let model = request
+                    .model
+                    .map(|model| model.trim().to_string())
+                    .filter(|model| !model.is_empty())
+                    .ok_or_else(|| bad_request("缺少 model".to_string()))?;
+
+                // 拒绝未知模型，避免部署指向无法使用的模型
+                if !AVAILABLE_MODELS.iter().any(|m| m.id == model) && !model.starts_with("claude") {
+                    return Err(bad_request(format!("未知模型: {}", model)));
+                }
+
+                AzureDeployments::set(AzureDeployment { deployment, model });
+                "部署映射已更新"
+            } else if AzureDeployments::remove(&deployment) {
+                "部署映射已删除"
+            } else {
+                "该部署不存在"
+            };
+
+            if let Err(e) = AzureDeployments::save().await {
+                return Err((
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ErrorResponse {
+                        status: ApiStatus::Failed,
+                        code: Some(500),
+                        error: Some("保存部署映射失败".to_string()),
+                        message: Some(e.to_string()),
+                    }),
+                ));
+            }
+
+            Some(message.to_string())
+        }
+
+        _ => return Err(bad_request("无效的操作类型".to_string())),
+    };
+
+    let after = AzureDeployments::list();
+    if request.action != "get" {
+        AuditLogs::record(
+            &actor,
+            &format!("azure_deployments.{}", request.action),
+            AuditLogs::snapshot(&before),
+            AuditLogs::snapshot(&after),
+        )
+        .await;
+    }
+
+    Ok(Json(NormalResponse {
+        status: ApiStatus::Success,
+        data: Some(after),
+        message,
+    }))
+}
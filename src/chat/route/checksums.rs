@@ -0,0 +1,81 @@
+use crate::{
+    app::{
+        model::{
+            AppState, AuditActor, AuditLogs, ChecksumRotation, ChecksumRotations, ROTATION_MANUAL,
+        },
+        rotation,
+    },
+    chat::middleware::AdminAuth,
+    common::model::{ApiStatus, ErrorResponse, NormalResponse},
+};
+use axum::{extract::State, http::StatusCode, Json};
+use serde::Deserialize;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+// history 时默认返回的最多条数
+const DEFAULT_HISTORY_LIMIT: usize = 100;
+
+#[derive(Deserialize)]
+pub struct ChecksumsRequest {
+    pub action: String,
+    // history 时仅返回该 token 的记录
+    #[serde(default)]
+    pub token: Option<String>,
+    // rotate 时要轮换的 token，为空时轮换全部
+    #[serde(default)]
+    pub tokens: Option<Vec<String>>,
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+pub async fn handle_checksums(
+    State(state): State<Arc<Mutex<AppState>>>,
+    _admin: AdminAuth,
+    actor: AuditActor,
+    Json(request): Json<ChecksumsRequest>,
+) -> Result<Json<NormalResponse<Vec<ChecksumRotation>>>, (StatusCode, Json<ErrorResponse>)> {
+    match request.action.as_str() {
+        "history" => Ok(Json(NormalResponse {
+            status: ApiStatus::Success,
+            data: Some(ChecksumRotations::recent(
+                request.token.as_deref(),
+                request.limit.unwrap_or(DEFAULT_HISTORY_LIMIT),
+            )),
+            message: None,
+        })),
+
+        // 立即为指定的 token 重新生成 checksum
+        "rotate" => {
+            let tokens = request.tokens.unwrap_or_default();
+            let rotations = rotation::rotate(&state, &tokens, ROTATION_MANUAL).await;
+
+            if !rotations.is_empty() {
+                AuditLogs::record(
+                    &actor,
+                    "checksums.rotate",
+                    None,
+                    AuditLogs::snapshot(&rotations.len()),
+                )
+                .await;
+            }
+
+            let message = format!("已轮换 {} 个 token 的 checksum", rotations.len());
+            Ok(Json(NormalResponse {
+                status: ApiStatus::Success,
+                data: Some(rotations),
+                message: Some(message),
+            }))
+        }
+
+        _ => Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                status: ApiStatus::Failed,
+                code: Some(400),
+                error: Some("Invalid request".to_string()),
+                message: Some("无效的操作类型".to_string()),
+            }),
+        )),
+    }
+}
@@ -5,9 +5,10 @@ use crate::{
         },
         lazy::{AUTH_TOKEN, KEY_PREFIX},
         model::{AppConfig, BuildKeyRequest, BuildKeyResponse, PageContent, UsageCheckModelType},
+        static_dir,
     },
     chat::config::{key_config, KeyConfig},
-    common::utils::{to_base64, token_to_tokeninfo},
+    common::utils::{to_base64, token_to_tokeninfo, url_for},
 };
 use axum::{
     body::Body,
@@ -29,34 +30,56 @@ pub async fn handle_env_example() -> impl IntoResponse {
 }
 
 // 配置页面处理函数
-pub async fn handle_config_page() -> impl IntoResponse {
+pub async fn handle_config_page(headers: HeaderMap) -> impl IntoResponse {
     match AppConfig::get_page_content(ROUTE_CONFIG_PATH).unwrap_or_default() {
-        PageContent::Default => Response::builder()
-            .header(CONTENT_TYPE, CONTENT_TYPE_TEXT_HTML_WITH_UTF8)
-            .body(include_str!("../../../static/config.min.html").to_string())
-            .unwrap(),
+        PageContent::Default => {
+            static_dir::page(
+                "config.html",
+                &headers,
+                include_str!("../../../static/config.min.html"),
+            )
+            .await
+        }
         PageContent::Text(content) => Response::builder()
             .header(CONTENT_TYPE, CONTENT_TYPE_TEXT_PLAIN_WITH_UTF8)
-            .body(content.clone())
+            .body(Body::from(content.clone()))
             .unwrap(),
         PageContent::Html(content) => Response::builder()
             .header(CONTENT_TYPE, CONTENT_TYPE_TEXT_HTML_WITH_UTF8)
-            .body(content.clone())
+            .body(Body::from(content.clone()))
             .unwrap(),
     }
 }
 
-pub async fn handle_static(Path(path): Path<String>) -> impl IntoResponse {
+fn is_customized(path: &str) -> bool {
+    let route = match path {
+        "shared-styles.css" => ROUTE_SHARED_STYLES_PATH,
+        "shared.js" => ROUTE_SHARED_JS_PATH,
+        _ => return false,
+    };
+    !matches!(
+        AppConfig::get_page_content(route).unwrap_or_default(),
+        PageContent::Default
+    )
+}
+
+pub async fn handle_static(Path(path): Path<String>, headers: HeaderMap) -> impl IntoResponse {
+    // 配置接口设置的内容优先，其次是 STATIC_DIR 中的文件
+    if !is_customized(&path) {
+        if let Some(response) = static_dir::serve(&path, &headers).await {
+            return response;
+        }
+    }
     match path.as_str() {
         "shared-styles.css" => {
             match AppConfig::get_page_content(ROUTE_SHARED_STYLES_PATH).unwrap_or_default() {
                 PageContent::Default => Response::builder()
                     .header(CONTENT_TYPE, CONTENT_TYPE_TEXT_CSS_WITH_UTF8)
-                    .body(include_str!("../../../static/shared-styles.min.css").to_string())
+                    .body(Body::from(include_str!("../../../static/shared-styles.min.css")))
                     .unwrap(),
                 PageContent::Text(content) | PageContent::Html(content) => Response::builder()
                     .header(CONTENT_TYPE, CONTENT_TYPE_TEXT_CSS_WITH_UTF8)
-                    .body(content.clone())
+                    .body(Body::from(content.clone()))
                     .unwrap(),
             }
         }
@@ -64,17 +87,17 @@ pub async fn handle_static(Path(path): Path<String>) -> impl IntoResponse {
             match AppConfig::get_page_content(ROUTE_SHARED_JS_PATH).unwrap_or_default() {
                 PageContent::Default => Response::builder()
                     .header(CONTENT_TYPE, CONTENT_TYPE_TEXT_JS_WITH_UTF8)
-                    .body(include_str!("../../../static/shared.min.js").to_string())
+                    .body(Body::from(include_str!("../../../static/shared.min.js")))
                     .unwrap(),
                 PageContent::Text(content) | PageContent::Html(content) => Response::builder()
                     .header(CONTENT_TYPE, CONTENT_TYPE_TEXT_JS_WITH_UTF8)
-                    .body(content.clone())
+                    .body(Body::from(content.clone()))
                     .unwrap(),
             }
         }
         _ => Response::builder()
             .status(StatusCode::NOT_FOUND)
-            .body("Not found".to_string())
+            .body(Body::from("Not found"))
             .unwrap(),
     }
 }
@@ -100,7 +123,7 @@ pub async fn handle_about() -> impl IntoResponse {
     match AppConfig::get_page_content(ROUTE_ABOUT_PATH).unwrap_or_default() {
         PageContent::Default => Response::builder()
             .status(StatusCode::TEMPORARY_REDIRECT)
-            .header(LOCATION, ROUTE_README_PATH)
+            .header(LOCATION, url_for(ROUTE_README_PATH))
             .body(Body::empty())
             .unwrap(),
         PageContent::Text(content) => Response::builder()
@@ -114,19 +137,23 @@ pub async fn handle_about() -> impl IntoResponse {
     }
 }
 
-pub async fn handle_build_key_page() -> impl IntoResponse {
+pub async fn handle_build_key_page(headers: HeaderMap) -> impl IntoResponse {
     match AppConfig::get_page_content(ROUTE_BUILD_KEY_PATH).unwrap_or_default() {
-        PageContent::Default => Response::builder()
-            .header(CONTENT_TYPE, CONTENT_TYPE_TEXT_HTML_WITH_UTF8)
-            .body(include_str!("../../../static/build_key.min.html").to_string())
-            .unwrap(),
+        PageContent::Default => {
+            static_dir::page(
+                "build_key.html",
+                &headers,
+                include_str!("../../../static/build_key.min.html"),
+            )
+            .await
+        }
         PageContent::Text(content) => Response::builder()
             .header(CONTENT_TYPE, CONTENT_TYPE_TEXT_PLAIN_WITH_UTF8)
-            .body(content.clone())
+            .body(Body::from(content.clone()))
             .unwrap(),
         PageContent::Html(content) => Response::builder()
             .header(CONTENT_TYPE, CONTENT_TYPE_TEXT_HTML_WITH_UTF8)
-            .body(content.clone())
+            .body(Body::from(content.clone()))
             .unwrap(),
     }
 }
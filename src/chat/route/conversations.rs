@@ -0,0 +1,92 @@
+use crate::{
+    app::{
+        constant::AUTHORIZATION_BEARER_PREFIX,
+        model::{Conversation, Conversations},
+    },
+    chat::{conversation, middleware::bad_request},
+    common::model::{error::ChatError, ApiStatus, ErrorResponse, NormalResponse},
+};
+use axum::{
+    http::{header::AUTHORIZATION, HeaderMap, StatusCode},
+    Json,
+};
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct ConversationsRequest {
+    pub action: String,
+    // get 与 delete 时必填
+    #[serde(default)]
+    pub id: Option<String>,
+}
+
+// 使用与对话接口相同的凭证，只能访问该凭证下的会话
+pub async fn handle_conversations(
+    headers: HeaderMap,
+    Json(request): Json<ConversationsRequest>,
+) -> Result<Json<NormalResponse<Vec<Conversation>>>, (StatusCode, Json<ErrorResponse>)> {
+    let auth_header = headers
+        .get(AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix(AUTHORIZATION_BEARER_PREFIX))
+        .filter(|h| !h.is_empty())
+        .ok_or((
+            StatusCode::UNAUTHORIZED,
+            Json(ChatError::Unauthorized.to_json()),
+        ))?;
+
+    if !conversation::history_enabled() {
+        return Err(bad_request("未启用会话历史"));
+    }
+
+    let owner = conversation::owner(auth_header);
+    let id = request
+        .id
+        .as_deref()
+        .map(str::trim)
+        .filter(|id| !id.is_empty());
+
+    match request.action.as_str() {
+        "list" => Ok(Json(NormalResponse {
+            status: ApiStatus::Success,
+            data: Some(Conversations::list_by_owner(&owner)),
+            message: None,
+        })),
+
+        "get" => {
+            let id = id.ok_or_else(|| bad_request("缺少会话 ID"))?;
+            let conversation = Conversations::get(&owner, id).ok_or((
+                StatusCode::NOT_FOUND,
+                Json(ErrorResponse {
+                    status: ApiStatus::Failed,
+                    code: Some(404),
+                    error: Some("Conversation not found".to_string()),
+                    message: Some("会话不存在".to_string()),
+                }),
+            ))?;
+
+            Ok(Json(NormalResponse {
+                status: ApiStatus::Success,
+                data: Some(vec![conversation]),
+                message: None,
+            }))
+        }
+
+        "delete" => {
+            let id = id.ok_or_else(|| bad_request("缺少会话 ID"))?;
+            let message = if Conversations::remove(&owner, id) {
+                "会话已删除"
+            } else {
+                "会话不存在"
+            };
+
+            Ok(Json(NormalResponse {
+                status: ApiStatus::Success,
+                data: None,
+                message: Some(message.to_string()),
+            }))
+        }
+
+        _ => Err(bad_request("无效的操作类型")),
+    }
+}
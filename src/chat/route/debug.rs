@@ -0,0 +1,153 @@
+use crate::{
+    app::model::{AppConfig, ChatRequest},
+    chat::{
+        adapter::{process_chat_inputs, ImageError},
+        aiserver::v1::conversation_message::MessageType,
+        config::KeyConfig,
+        constant::{AVAILABLE_MODELS, LONG_CONTEXT_MODELS},
+        middleware::AdminAuth,
+        moderation,
+    },
+    common::{
+        model::{error::ChatError, ErrorResponse},
+        utils::estimate_tokens,
+    },
+};
+use axum::{http::StatusCode, Json};
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub struct DebugEchoResponse {
+    pub model: String,
+    pub upstream_model: String,
+    pub model_supported: bool,
+    pub is_search: bool,
+    pub slow_pool: bool,
+    pub long_context: bool,
+    pub stream: bool,
+    pub ignored_params: Vec<String>,
+    pub disable_vision: bool,
+    pub instructions: String,
+    pub messages: Vec<DebugEchoMessage>,
+    pub external_links: Vec<String>,
+    pub estimated_tokens: u32,
+}
+
+#[derive(Serialize)]
+pub struct DebugEchoMessage {
+    pub role: &'static str,
+    pub text: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub images: Vec<DebugEchoImage>,
+}
+
+#[derive(Serialize)]
+pub struct DebugEchoImage {
+    pub size: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub width: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<i32>,
+}
+
+// 返回代理解析后的请求内容，不调用上游
+pub async fn handle_debug_echo(
+    _admin: AdminAuth,
+    Json(mut request): Json<ChatRequest>,
+) -> Result<Json<DebugEchoResponse>, (StatusCode, Json<ErrorResponse>)> {
+    request.apply_template().map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ChatError::InvalidTemplate(e).to_json()),
+        )
+    })?;
+
+    if request.messages.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ChatError::EmptyMessages.to_json()),
+        ));
+    }
+
+    if let Err(reason) = moderation::check_prompt(&mut request.messages).await {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ChatError::ContentBlocked(reason).to_json()),
+        ));
+    }
+
+    // 与聊天接口相同的模型映射规则
+    request.apply_slow_pool_suffix();
+    let is_search = request.model.ends_with("-online");
+    let upstream_model = if is_search {
+        request.model[..request.model.len() - 7].to_string()
+    } else {
+        request.model.clone()
+    };
+    let model_supported = AVAILABLE_MODELS.iter().any(|m| m.id == upstream_model)
+        || AppConfig::get_allow_claude() && request.model.starts_with("claude");
+
+    let key_config = KeyConfig::new_with_global();
+    let disable_vision = key_config.disable_vision();
+    let slow_pool = request
+        .slow_pool
+        .unwrap_or_else(|| key_config.enable_slow_pool());
+    let ignored_params = request
+        .ignored_params()
+        .into_iter()
+        .map(str::to_string)
+        .collect();
+
+    let (instructions, messages, external_links) =
+        process_chat_inputs(request.messages, disable_vision)
+            .await
+            .map_err(|ImageError(e)| {
+                (
+                    StatusCode::BAD_REQUEST,
+                    Json(ChatError::InvalidImage(e).to_json()),
+                )
+            })?;
+
+    let estimated_tokens = estimate_tokens(&instructions)
+        + messages
+            .iter()
+            .map(|message| estimate_tokens(&message.text))
+            .sum::<u32>();
+
+    let messages = messages
+        .into_iter()
+        .map(|message| DebugEchoMessage {
+            role: if message.r#type == MessageType::Human as i32 {
+                "user"
+            } else {
+                "assistant"
+            },
+            text: message.text,
+            images: message
+                .images
+                .iter()
+                .map(|image| DebugEchoImage {
+                    size: image.data.len(),
+                    width: image.dimension.as_ref().map(|d| d.width),
+                    height: image.dimension.as_ref().map(|d| d.height),
+                })
+                .collect(),
+        })
+        .collect();
+
+    Ok(Json(DebugEchoResponse {
+        long_context: LONG_CONTEXT_MODELS.contains(&upstream_model.as_str()),
+        model: request.model,
+        upstream_model,
+        model_supported,
+        is_search,
+        slow_pool,
+        stream: request.stream,
+        ignored_params,
+        disable_vision,
+        instructions,
+        messages,
+        external_links,
+        estimated_tokens,
+    }))
+}
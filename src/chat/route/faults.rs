@@ -0,0 +1,95 @@
+use crate::{
+    app::model::{AuditActor, AuditLogs},
+    chat::{
+        fault::{self, FaultRates},
+        middleware::AdminAuth,
+    },
+    common::model::{ApiStatus, ErrorResponse, NormalResponse},
+};
+use axum::{http::StatusCode, Json};
+use serde::Deserialize;
+
+// 未提供的字段保持不变，全部为空时仅返回当前概率
+#[derive(Deserialize, Default)]
+pub struct FaultsRequest {
+    #[serde(default)]
+    pub timeout: Option<usize>,
+    #[serde(default)]
+    pub rate_limit: Option<usize>,
+    #[serde(default)]
+    pub stream_error: Option<usize>,
+    #[serde(default)]
+    pub malformed: Option<usize>,
+    // 先恢复为环境变量中的配置，再应用其余字段
+    #[serde(default)]
+    pub reset: bool,
+}
+
+pub async fn handle_faults(
+    _admin: AdminAuth,
+    actor: AuditActor,
+    request: Option<Json<FaultsRequest>>,
+) -> Result<Json<NormalResponse<FaultRates>>, (StatusCode, Json<ErrorResponse>)> {
+    let request = request.map(|Json(request)| request).unwrap_or_default();
+
+    let rates = [
+        request.timeout,
+        request.rate_limit,
+        request.stream_error,
+        request.malformed,
+    ];
+    if rates.iter().flatten().any(|&rate| rate > 100) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                status: ApiStatus::Failed,
+                code: Some(400),
+                error: Some("Invalid request".to_string()),
+                message: Some("概率必须在 0-100 之间".to_string()),
+            }),
+        ));
+    }
+
+    let changed = request.reset || rates.iter().any(Option::is_some);
+    if !changed {
+        return Ok(Json(NormalResponse {
+            status: ApiStatus::Success,
+            data: Some(fault::rates()),
+            message: None,
+        }));
+    }
+
+    let before = AuditLogs::snapshot(&fault::rates());
+    if request.reset {
+        fault::reset_rates();
+    }
+    let mut current = fault::rates();
+    if let Some(rate) = request.timeout {
+        current.timeout = rate;
+    }
+    if let Some(rate) = request.rate_limit {
+        current.rate_limit = rate;
+    }
+    if let Some(rate) = request.stream_error {
+        current.stream_error = rate;
+    }
+    if let Some(rate) = request.malformed {
+        current.malformed = rate;
+    }
+    fault::set_rates(current);
+
+    tracing::info!("故障注入概率已修改");
+    AuditLogs::record(
+        &actor,
+        "faults.update",
+        before,
+        AuditLogs::snapshot(&current),
+    )
+    .await;
+
+    Ok(Json(NormalResponse {
+        status: ApiStatus::Success,
+        data: Some(current),
+        message: Some("设置已生效，重启后恢复为环境变量中的配置".to_string()),
+    }))
+}
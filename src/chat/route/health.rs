@@ -10,18 +10,32 @@ use crate::{
             ROUTE_TOKENS_GET_PATH, ROUTE_TOKENS_PATH, ROUTE_TOKENS_UPDATE_PATH,
             ROUTE_USER_INFO_PATH,
         },
-        lazy::{get_start_time, AUTH_TOKEN, ROUTE_CHAT_PATH, ROUTE_MODELS_PATH},
-        model::{AppConfig, AppState, PageContent},
+        ip_filter,
+        lazy::{
+            get_start_time, AUTH_TOKEN, ROUTE_CHAT_PATH, ROUTE_CHAT_WS_PATH,
+            ROUTE_COMPLETIONS_PATH, ROUTE_IMAGES_GENERATIONS_PATH, ROUTE_MODELS_PATH,
+        },
+        model::{
+            AppConfig, AppState, LogStatus, PageContent, RequestLog, TokenBlacklist, TokenInfo,
+        },
+    },
+    chat::{
+        constant::AVAILABLE_MODELS,
+        priority::{self, TIERS},
+        stream::heartbeat_frames,
     },
-    chat::constant::AVAILABLE_MODELS,
     common::model::{
-        health::{CpuInfo, HealthCheckResponse, MemoryInfo, SystemInfo, SystemStats},
+        health::{
+            CpuInfo, DispatchStats, HealthCheckResponse, HealthModels, MemoryInfo, ModelStats,
+            RecentStats, SystemInfo, SystemStats, TokenPoolStats,
+        },
         ApiStatus,
     },
+    common::utils::{extract_exp, url_for},
 };
 use axum::{
     body::Body,
-    extract::State,
+    extract::{Query, State},
     http::{
         header::{CONTENT_TYPE, LOCATION},
         HeaderMap, StatusCode,
@@ -31,15 +45,22 @@ use axum::{
 };
 use chrono::Local;
 use reqwest::header::AUTHORIZATION;
-use std::sync::Arc;
+use serde::Deserialize;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 use sysinfo::{CpuRefreshKind, MemoryRefreshKind, RefreshKind, System};
 use tokio::sync::Mutex;
 
+// 健康检查中展示的 IP 数量
+const TOP_IPS_LIMIT: usize = 20;
+
 pub async fn handle_root() -> impl IntoResponse {
     match AppConfig::get_page_content(ROUTE_ROOT_PATH).unwrap_or_default() {
         PageContent::Default => Response::builder()
             .status(StatusCode::TEMPORARY_REDIRECT)
-            .header(LOCATION, ROUTE_HEALTH_PATH)
+            .header(LOCATION, url_for(ROUTE_HEALTH_PATH))
             .body(Body::empty())
             .unwrap(),
         PageContent::Text(content) => Response::builder()
@@ -53,8 +74,16 @@ pub async fn handle_root() -> impl IntoResponse {
     }
 }
 
+#[derive(Deserialize)]
+pub struct HealthQuery {
+    // 为 true 时 models 返回旧的名称数组
+    #[serde(default)]
+    pub flat_models: bool,
+}
+
 pub async fn handle_health(
     State(state): State<Arc<Mutex<AppState>>>,
+    Query(query): Query<HealthQuery>,
     headers: HeaderMap,
 ) -> Json<HealthCheckResponse> {
     let start_time = get_start_time();
@@ -74,7 +103,7 @@ pub async fn handle_health(
                 .with_cpu(CpuRefreshKind::everything()),
         );
 
-        std::thread::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
+        tokio::time::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL).await;
 
         // 刷新 CPU 和内存信息
         sys.refresh_memory();
@@ -95,6 +124,22 @@ pub async fn handle_health(
             started: start_time.to_string(),
             total_requests: state.total_requests,
             active_requests: state.active_requests,
+            heartbeat_frames: heartbeat_frames(),
+            ips: ip_filter::top_ips(TOP_IPS_LIMIT),
+            last_hour: recent_stats(&state.request_logs),
+            token_pool: token_pool_stats(&state.token_infos, &state.request_logs),
+            dispatch: if priority::is_enabled() {
+                TIERS
+                    .iter()
+                    .map(|tier| DispatchStats {
+                        tier: tier.as_str(),
+                        dispatching: state.dispatching[*tier as usize],
+                        waiting: priority::waiting(*tier) as u64,
+                    })
+                    .collect()
+            } else {
+                Vec::new()
+            },
             system: SystemInfo {
                 memory: MemoryInfo {
                     rss: memory, // 物理内存使用量(字节)
@@ -108,14 +153,23 @@ pub async fn handle_health(
         None
     };
 
+    let models = if query.flat_models {
+        HealthModels::Flat(AVAILABLE_MODELS.iter().map(|m| m.id).collect())
+    } else {
+        HealthModels::Stats(model_stats(&state.lock().await.request_logs))
+    };
+
     Json(HealthCheckResponse {
         status: ApiStatus::Healthy,
         version: PKG_VERSION,
         uptime,
         stats,
-        models: AVAILABLE_MODELS.iter().map(|m| m.id).collect::<Vec<_>>(),
+        models,
         endpoints: vec![
             ROUTE_CHAT_PATH.as_str(),
+            ROUTE_CHAT_WS_PATH.as_str(),
+            ROUTE_COMPLETIONS_PATH.as_str(),
+            ROUTE_IMAGES_GENERATIONS_PATH.as_str(),
             ROUTE_MODELS_PATH.as_str(),
             ROUTE_TOKENS_PATH,
             ROUTE_TOKENS_GET_PATH,
@@ -138,3 +192,108 @@ pub async fn handle_health(
         ],
     })
 }
+
+#[derive(Default)]
+struct ModelStatsAcc {
+    requests: u64,
+    failed: u64,
+    succeeded: u64,
+    total_time: f64,
+}
+
+// 联网搜索的 -online 模型计入对应的基础模型
+fn model_stats(logs: &[RequestLog]) -> Vec<ModelStats> {
+    let since = Local::now() - chrono::Duration::hours(24);
+    let mut acc: HashMap<&str, ModelStatsAcc> = HashMap::new();
+
+    for log in logs.iter().rev().take_while(|log| log.timestamp >= since) {
+        let model = log.model.strip_suffix("-online").unwrap_or(&log.model);
+        let entry = acc.entry(model).or_default();
+        entry.requests += 1;
+        match log.status {
+            LogStatus::Success => {
+                entry.succeeded += 1;
+                entry.total_time += log.timing.total;
+            }
+            LogStatus::Failed => entry.failed += 1,
+            LogStatus::Pending | LogStatus::Cancelled => {}
+        }
+    }
+
+    AVAILABLE_MODELS
+        .iter()
+        .map(|m| {
+            let stats = acc.remove(m.id).unwrap_or_default();
+            let finished = stats.succeeded + stats.failed;
+            ModelStats {
+                id: m.id,
+                requests_24h: stats.requests,
+                error_rate: if finished > 0 {
+                    stats.failed as f64 / finished as f64
+                } else {
+                    0.0
+                },
+                avg_latency: (stats.succeeded > 0)
+                    .then(|| stats.total_time / stats.succeeded as f64),
+            }
+        })
+        .collect()
+}
+
+fn recent_stats(logs: &[RequestLog]) -> RecentStats {
+    let since = Local::now() - chrono::Duration::hours(1);
+    let mut stats = RecentStats::default();
+    let mut total_time = 0.0;
+    let mut total_first = 0.0;
+    let mut first_count = 0u64;
+
+    for log in logs.iter().rev().take_while(|log| log.timestamp >= since) {
+        stats.requests += 1;
+        match log.status {
+            LogStatus::Pending => stats.pending += 1,
+            LogStatus::Success => {
+                stats.success += 1;
+                total_time += log.timing.total;
+                if let Some(first) = log.timing.first {
+                    total_first += first;
+                    first_count += 1;
+                }
+            }
+            LogStatus::Failed => stats.failed += 1,
+            LogStatus::Cancelled => stats.cancelled += 1,
+        }
+    }
+
+    let finished = stats.success + stats.failed;
+    if finished > 0 {
+        stats.error_rate = stats.failed as f64 / finished as f64;
+    }
+    stats.avg_latency = (stats.success > 0).then(|| total_time / stats.success as f64);
+    stats.avg_first_token = (first_count > 0).then(|| total_first / first_count as f64);
+    stats
+}
+
+fn token_pool_stats(token_infos: &[TokenInfo], logs: &[RequestLog]) -> TokenPoolStats {
+    let now = chrono::Utc::now().timestamp();
+    let in_flight: HashSet<&str> = logs
+        .iter()
+        .filter(|log| matches!(log.status, LogStatus::Pending))
+        .map(|log| log.token_info.token.as_str())
+        .collect();
+
+    let mut stats = TokenPoolStats::default();
+    for info in token_infos {
+        stats.total += 1;
+        if in_flight.contains(info.token.as_str()) {
+            stats.pending += 1;
+        }
+        if TokenBlacklist::is_blocked(&info.token) {
+            stats.blocked += 1;
+        } else if extract_exp(&info.token).is_some_and(|exp| exp <= now) {
+            stats.expired += 1;
+        } else {
+            stats.active += 1;
+        }
+    }
+    stats
+}
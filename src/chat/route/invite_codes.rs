@@ -0,0 +1,237 @@
+use crate::{
+    app::{
+        constant::INVITE_CODE_PREFIX,
+        lazy::INVITE_REGISTRATION,
+        model::{
+            ApiKey, ApiKeys, AuditActor, AuditLogs, InviteCode, InviteCodes, RegisterError, Tenants,
+        },
+    },
+    chat::middleware::{bad_request, AdminAuth},
+    common::model::{ApiStatus, ErrorResponse, NormalResponse},
+};
+use axum::{http::StatusCode, Json};
+use serde::{Deserialize, Serialize};
+
+// 单次最多生成的邀请码数量
+const MAX_CREATE_COUNT: usize = 100;
+
+#[derive(Deserialize)]
+pub struct InviteCodesRequest {
+    pub action: String,
+    // create 时使用
+    #[serde(default)]
+    pub count: Option<usize>,
+    #[serde(default)]
+    pub max_uses: Option<u32>,
+    // 有效期(秒)，不填表示永不过期
+    #[serde(default)]
+    pub expires_in: Option<u64>,
+    #[serde(default)]
+    pub tenant: Option<String>,
+    #[serde(default)]
+    pub note: Option<String>,
+    // delete 时使用
+    #[serde(default)]
+    pub code: Option<String>,
+}
+
+fn error_response(
+    status: StatusCode,
+    error: &str,
+    message: String,
+) -> (StatusCode, Json<ErrorResponse>) {
+    (
+        status,
+        Json(ErrorResponse {
+            status: ApiStatus::Failed,
+            code: Some(status.as_u16()),
+            error: Some(error.to_string()),
+            message: Some(message),
+        }),
+    )
+}
+
+// 邀请码可直接兑换 API key，审计快照与日志中只保留前缀
+fn masked(code: &str) -> String {
+    format!(
+        "{}...",
+        code.chars()
+            .take(INVITE_CODE_PREFIX.len() + 4)
+            .collect::<String>()
+    )
+}
+
+fn snapshot(codes: &[InviteCode]) -> Option<String> {
+    let codes: Vec<InviteCode> = codes
+        .iter()
+        .map(|invite| InviteCode {
+            code: masked(&invite.code),
+            ..invite.clone()
+        })
+        .collect();
+    AuditLogs::snapshot(&codes)
+}
+
+pub async fn handle_invite_codes(
+    _admin: AdminAuth,
+    actor: AuditActor,
+    Json(request): Json<InviteCodesRequest>,
+) -> Result<Json<NormalResponse<Vec<InviteCode>>>, (StatusCode, Json<ErrorResponse>)> {
+    let before = InviteCodes::list();
+
+    let message = match request.action.as_str() {
+        "list" => None,
+
+        "create" => {
+            let count = request.count.unwrap_or(1);
+            if count == 0 || count > MAX_CREATE_COUNT {
+                return Err(bad_request(format!(
+                    "count 需在 1 到 {} 之间",
+                    MAX_CREATE_COUNT
+                )));
+            }
+            let max_uses = request.max_uses.unwrap_or(1);
+            if max_uses == 0 {
+                return Err(bad_request("max_uses 不能为 0".to_string()));
+            }
+            let tenant = request.tenant.filter(|tenant| !tenant.is_empty());
+            if let Some(ref tenant) = tenant {
+                if Tenants::get(tenant).is_none() {
+                    return Err(bad_request(format!("租户不存在: {}", tenant)));
+                }
+            }
+            let note = request.note.filter(|note| !note.is_empty());
+            let expires_at = request
+                .expires_in
+                .map(|secs| chrono::Utc::now().timestamp().saturating_add(secs as i64));
+
+            for _ in 0..count {
+                InviteCodes::create(tenant.clone(), note.clone(), expires_at, max_uses);
+            }
+            Some(format!("已生成 {} 个邀请码", count))
+        }
+
+        "delete" => {
+            let code = request
+                .code
+                .ok_or_else(|| bad_request("缺少 code".to_string()))?;
+            if !InviteCodes::remove(&code) {
+                return Err(bad_request("邀请码不存在".to_string()));
+            }
+            Some("邀请码已删除".to_string())
+        }
+
+        _ => return Err(bad_request("无效的操作类型".to_string())),
+    };
+
+    if request.action != "list" {
+        if let Err(e) = InviteCodes::save().await {
+            return Err(error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "保存邀请码失败",
+                e.to_string(),
+            ));
+        }
+    }
+
+    let after = InviteCodes::list();
+    if request.action != "list" {
+        AuditLogs::record(
+            &actor,
+            &format!("invite_codes.{}", request.action),
+            snapshot(&before),
+            snapshot(&after),
+        )
+        .await;
+    }
+
+    Ok(Json(NormalResponse {
+        status: ApiStatus::Success,
+        data: Some(after),
+        message,
+    }))
+}
+
+#[derive(Deserialize)]
+pub struct RegisterRequest {
+    pub code: String,
+    // 用户名，作为 API key 的名称与 owner
+    pub name: String,
+}
+
+#[derive(Serialize)]
+pub struct RegisterResponse {
+    pub status: ApiStatus,
+    pub api_key: ApiKey,
+    // 明文密钥，只返回这一次
+    pub key: String,
+}
+
+// 用户名只允许字母、数字、-、_ 与 .
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 64
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.'))
+}
+
+pub async fn handle_register(
+    Json(request): Json<RegisterRequest>,
+) -> Result<Json<RegisterResponse>, (StatusCode, Json<ErrorResponse>)> {
+    if !*INVITE_REGISTRATION {
+        return Err(error_response(
+            StatusCode::NOT_FOUND,
+            "Not found",
+            "未启用邀请码注册".to_string(),
+        ));
+    }
+
+    let name = request.name.trim();
+    if !is_valid_name(name) {
+        return Err(bad_request("无效的用户名".to_string()));
+    }
+    let (invite, api_key, key) =
+        ApiKeys::register(name, request.code.trim()).map_err(|e| match e {
+            RegisterError::NameTaken => {
+                error_response(StatusCode::CONFLICT, "Conflict", "用户名已存在".to_string())
+            }
+            RegisterError::InvalidInvite => bad_request("邀请码无效、已过期或已用完".to_string()),
+        })?;
+
+    // 任一文件保存失败时撤销本次注册，避免重启后邀请码可以再次兑换
+    if let Err(e) = InviteCodes::save().await {
+        rollback(&invite.code, name, &api_key.id, false).await;
+        return Err(error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "保存邀请码失败",
+            e.to_string(),
+        ));
+    }
+    if let Err(e) = ApiKeys::save().await {
+        rollback(&invite.code, name, &api_key.id, true).await;
+        return Err(error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "保存 API key 失败",
+            e.to_string(),
+        ));
+    }
+    tracing::info!("用户 {} 使用邀请码 {} 注册", name, masked(&invite.code));
+
+    Ok(Json(RegisterResponse {
+        status: ApiStatus::Success,
+        api_key,
+        key,
+    }))
+}
+
+// 撤销兑换并移除新建的 API key，已写入文件的邀请码同步恢复
+async fn rollback(code: &str, name: &str, api_key_id: &str, invite_saved: bool) {
+    InviteCodes::unredeem(code, name);
+    ApiKeys::remove(api_key_id);
+    if invite_saved {
+        if let Err(e) = InviteCodes::save().await {
+            tracing::error!("恢复邀请码失败: {}", e);
+        }
+    }
+}
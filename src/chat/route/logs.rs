@@ -4,14 +4,16 @@ use crate::{
             AUTHORIZATION_BEARER_PREFIX, CONTENT_TYPE_TEXT_HTML_WITH_UTF8,
             CONTENT_TYPE_TEXT_PLAIN_WITH_UTF8, ROUTE_LOGS_PATH,
         },
-        lazy::AUTH_TOKEN,
-        model::{AppConfig, AppState, PageContent, RequestLog},
+        log_stream,
+        model::{AppConfig, AppState, AuditActor, AuditLogs, LogStatus, PageContent, RequestLog},
+        session, static_dir,
     },
+    chat::middleware::AdminAuth,
     common::{model::ApiStatus, utils::extract_token},
 };
 use axum::{
     body::Body,
-    extract::State,
+    extract::{Query, State},
     http::{
         header::{AUTHORIZATION, CONTENT_TYPE},
         HeaderMap, StatusCode,
@@ -19,19 +21,24 @@ use axum::{
     response::{IntoResponse, Response},
     Json,
 };
-use chrono::Local;
-use std::sync::Arc;
-use tokio::sync::Mutex;
+use chrono::{DateTime, Local, NaiveDate, TimeZone as _};
+use serde::Deserialize;
+use std::{sync::Arc, time::Duration};
+use tokio::sync::{broadcast::error::RecvError, Mutex};
+
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(15);
 
 // 日志处理
-pub async fn handle_logs() -> impl IntoResponse {
+pub async fn handle_logs(headers: HeaderMap) -> impl IntoResponse {
     match AppConfig::get_page_content(ROUTE_LOGS_PATH).unwrap_or_default() {
-        PageContent::Default => Response::builder()
-            .header(CONTENT_TYPE, CONTENT_TYPE_TEXT_HTML_WITH_UTF8)
-            .body(Body::from(
-                include_str!("../../../static/logs.min.html").to_string(),
-            ))
-            .unwrap(),
+        PageContent::Default => {
+            static_dir::page(
+                "logs.html",
+                &headers,
+                include_str!("../../../static/logs.min.html"),
+            )
+            .await
+        }
         PageContent::Text(content) => Response::builder()
             .header(CONTENT_TYPE, CONTENT_TYPE_TEXT_PLAIN_WITH_UTF8)
             .body(Body::from(content.clone()))
@@ -43,63 +50,261 @@ pub async fn handle_logs() -> impl IntoResponse {
     }
 }
 
+#[derive(Deserialize)]
+#[cfg_attr(feature = "client", derive(serde::Serialize, Default))]
+pub struct LogsQuery {
+    // 从1开始
+    pub page: Option<usize>,
+    // 不指定时返回全部
+    pub page_size: Option<usize>,
+    pub model: Option<String>,
+    pub status: Option<String>,
+    // RFC3339 时间或 YYYY-MM-DD 日期，包含边界
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub alias: Option<String>,
+    // 响应头 X-Request-Id 中的值
+    pub request_id: Option<String>,
+}
+
+// 解析后的筛选条件
+struct LogsFilter {
+    model: Option<String>,
+    status: Option<LogStatus>,
+    from: Option<DateTime<Local>>,
+    to: Option<DateTime<Local>>,
+    // 别名对应的 token
+    tokens: Option<Vec<String>>,
+    request_id: Option<String>,
+}
+
+impl LogsFilter {
+    fn new(query: &LogsQuery, state: &AppState) -> Result<Self, StatusCode> {
+        let status = match query.status.as_deref() {
+            Some(status) => Some(LogStatus::from_str_name(status).ok_or(StatusCode::BAD_REQUEST)?),
+            None => None,
+        };
+        let from = match query.from.as_deref() {
+            Some(from) => Some(parse_log_time(from, false).ok_or(StatusCode::BAD_REQUEST)?),
+            None => None,
+        };
+        let to = match query.to.as_deref() {
+            Some(to) => Some(parse_log_time(to, true).ok_or(StatusCode::BAD_REQUEST)?),
+            None => None,
+        };
+
+        // 日志中不保存别名，通过当前的 token 列表反查
+        let tokens = query.alias.as_deref().map(|alias| {
+            state
+                .token_infos
+                .iter()
+                .filter(|info| info.alias.as_deref() == Some(alias))
+                .map(|info| info.token.clone())
+                .collect()
+        });
+
+        Ok(Self {
+            model: query.model.clone(),
+            status,
+            from,
+            to,
+            tokens,
+            request_id: query.request_id.clone(),
+        })
+    }
+
+    fn matches(&self, log: &RequestLog) -> bool {
+        self.model.as_ref().is_none_or(|model| &log.model == model)
+            && self
+                .status
+                .as_ref()
+                .is_none_or(|status| status.as_str_name() == log.status.as_str_name())
+            && self.from.is_none_or(|from| log.timestamp >= from)
+            && self.to.is_none_or(|to| log.timestamp <= to)
+            && self
+                .tokens
+                .as_ref()
+                .is_none_or(|tokens| tokens.contains(&log.token_info.token))
+            && self
+                .request_id
+                .as_ref()
+                .is_none_or(|id| log.request_id.as_ref() == Some(id))
+    }
+}
+
+// 日期形式的 to 取当天结束时刻
+fn parse_log_time(s: &str, end_of_day: bool) -> Option<DateTime<Local>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(s) {
+        return Some(time.with_timezone(&Local));
+    }
+    let date = NaiveDate::parse_from_str(s, "%Y-%m-%d").ok()?;
+    let time = if end_of_day {
+        date.and_hms_milli_opt(23, 59, 59, 999)?
+    } else {
+        date.and_hms_opt(0, 0, 0)?
+    };
+    Local.from_local_datetime(&time).earliest()
+}
+
+// 筛选并分页，返回筛选后的总数
+fn filter_logs<'a>(
+    logs: impl Iterator<Item = &'a RequestLog>,
+    filter: &LogsFilter,
+    query: &LogsQuery,
+) -> (Vec<RequestLog>, usize) {
+    let filtered: Vec<&RequestLog> = logs.filter(|log| filter.matches(log)).collect();
+    let total_count = filtered.len();
+
+    let logs = match query.page_size.filter(|&size| size > 0) {
+        Some(page_size) => {
+            let page = query.page.unwrap_or(1).max(1);
+            filtered
+                .into_iter()
+                .skip((page - 1).saturating_mul(page_size))
+                .take(page_size)
+                .cloned()
+                .collect()
+        }
+        None => filtered.into_iter().cloned().collect(),
+    };
+
+    (logs, total_count)
+}
+
 pub async fn handle_logs_post(
     State(state): State<Arc<Mutex<AppState>>>,
+    Query(query): Query<LogsQuery>,
     headers: HeaderMap,
 ) -> Result<Json<LogsResponse>, StatusCode> {
-    let auth_token = AUTH_TOKEN.as_str();
-
-    // 获取认证头
-    let auth_header = headers
-        .get(AUTHORIZATION)
-        .and_then(|h| h.to_str().ok())
-        .and_then(|h| h.strip_prefix(AUTHORIZATION_BEARER_PREFIX))
-        .ok_or(StatusCode::UNAUTHORIZED)?;
-
     let state = state.lock().await;
+    let filter = LogsFilter::new(&query, &state)?;
 
-    // 如果是管理员token,返回所有日志
-    if auth_header == auth_token {
+    // 如果是管理员token或网页会话,返回所有日志
+    if session::is_admin(&headers) {
+        let (logs, total_count) = filter_logs(state.request_logs.iter(), &filter, &query);
         return Ok(Json(LogsResponse {
             status: ApiStatus::Success,
             total: state.total_requests,
+            total_count,
             active: Some(state.active_requests),
             error: Some(state.error_requests),
-            logs: state.request_logs.clone(),
+            logs,
             timestamp: Local::now().to_string(),
         }));
     }
 
+    // 获取认证头
+    let auth_header = headers
+        .get(AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix(AUTHORIZATION_BEARER_PREFIX))
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
     // 解析 token
     let token_part = extract_token(auth_header).ok_or(StatusCode::UNAUTHORIZED)?;
 
     // 否则筛选出token匹配的日志
-    let filtered_logs: Vec<RequestLog> = state
+    let user_logs: Vec<&RequestLog> = state
         .request_logs
         .iter()
         .filter(|log| log.token_info.token == token_part)
-        .cloned()
         .collect();
 
     // 如果没有匹配的日志,返回未授权错误
-    if filtered_logs.is_empty() {
+    if user_logs.is_empty() {
         return Err(StatusCode::UNAUTHORIZED);
     }
 
+    let total = user_logs.len() as u64;
+    let (logs, total_count) = filter_logs(user_logs.into_iter(), &filter, &query);
+
     Ok(Json(LogsResponse {
         status: ApiStatus::Success,
-        total: filtered_logs.len() as u64,
+        total,
+        total_count,
         active: None,
         error: None,
-        logs: filtered_logs,
+        logs,
         timestamp: Local::now().to_string(),
     }))
 }
 
+// 实时推送新建与结束的请求日志，每条为一个 log 事件，客户端按 id 更新
+// IP 过滤拒绝的请求按时间间隔汇总为 denied 事件
+// 积压过多被跳过时发送 lagged 事件，客户端应重新拉取完整日志
+pub async fn handle_logs_stream(headers: HeaderMap) -> Result<Response, StatusCode> {
+    if !session::is_admin(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let receiver = log_stream::subscribe();
+    let body = futures::stream::unfold(receiver, |mut receiver| async move {
+        let event = match tokio::time::timeout(KEEPALIVE_INTERVAL, receiver.recv()).await {
+            Ok(Ok((event, data))) => format!("event: {}\ndata: {}\n\n", event, data),
+            Ok(Err(RecvError::Lagged(skipped))) => {
+                format!("event: lagged\ndata: {}\n\n", skipped)
+            }
+            Ok(Err(RecvError::Closed)) => return None,
+            // 定期发送注释行，避免空闲连接被代理断开
+            Err(_) => ": keepalive\n\n".to_string(),
+        };
+        Some((Ok::<_, std::convert::Infallible>(event), receiver))
+    });
+
+    Ok(Response::builder()
+        .header("Cache-Control", "no-cache")
+        .header("Connection", "keep-alive")
+        .header(CONTENT_TYPE, "text/event-stream")
+        .body(Body::from_stream(body))
+        .unwrap())
+}
+
+// 按保留策略立即清理日志并写入文件
+pub async fn handle_logs_cleanup(
+    State(state): State<Arc<Mutex<AppState>>>,
+    _admin: AdminAuth,
+    actor: AuditActor,
+) -> Result<Json<LogsCleanupResponse>, StatusCode> {
+    let mut state = state.lock().await;
+    let before = state.request_logs.len();
+    let removed = state.prune_logs();
+
+    if let Err(e) = state.save_logs().await {
+        tracing::error!("保存日志失败: {}", e);
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    let remaining = state.request_logs.len();
+    drop(state);
+    AuditLogs::record(
+        &actor,
+        "logs.cleanup",
+        AuditLogs::snapshot(&before),
+        AuditLogs::snapshot(&remaining),
+    )
+    .await;
+
+    Ok(Json(LogsCleanupResponse {
+        status: ApiStatus::Success,
+        removed,
+        remaining,
+    }))
+}
+
+#[derive(serde::Serialize)]
+pub struct LogsCleanupResponse {
+    pub status: ApiStatus,
+    pub removed: usize,
+    pub remaining: usize,
+}
+
 #[derive(serde::Serialize)]
+#[cfg_attr(feature = "client", derive(serde::Deserialize))]
 pub struct LogsResponse {
     pub status: ApiStatus,
     pub total: u64,
+    // 筛选后的条数，用于分页
+    pub total_count: usize,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub active: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
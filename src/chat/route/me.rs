@@ -0,0 +1,185 @@
+use crate::{
+    app::{
+        backoff,
+        constant::AUTHORIZATION_BEARER_PREFIX,
+        lazy::AUTH_TOKEN,
+        model::{
+            ApiKey, ApiKeys, AppConfig, AppState, LogStatus, QuotaSnapshots, RequestLog, Tenants,
+            TokenBlacklist,
+        },
+    },
+    chat::public_pool,
+    common::{
+        model::{error::ChatError, ApiStatus, ErrorResponse, NormalResponse},
+        utils::{extract_token, extract_user_id},
+    },
+};
+use axum::{
+    extract::State,
+    http::{header::AUTHORIZATION, HeaderMap, StatusCode},
+    Json,
+};
+use chrono::{Local, TimeDelta};
+use serde::Serialize;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+// 用量统计的天数
+const USAGE_DAYS: i64 = 30;
+
+// 调用者自己的账户概览
+#[derive(Serialize)]
+pub struct Me {
+    // api_key 或 token
+    pub kind: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<String>,
+    // 使用 API key 时为该 key 的记录，不含哈希
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<ApiKey>,
+    pub usage: UsageSummary,
+    // 调用者当前可以使用的 token 数
+    pub active_tokens: usize,
+    pub quota: QuotaStanding,
+    // API key 已吊销或已过期，或 token 在黑名单中
+    pub banned: bool,
+}
+
+// 最近 30 天的用量，基于内存中保留的请求日志
+#[derive(Serialize, Default)]
+pub struct UsageSummary {
+    pub requests: u64,
+    pub failures: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub cost: f64,
+}
+
+#[derive(Serialize, Default)]
+pub struct QuotaStanding {
+    // 公共号池今日剩余的请求数，未启用或不限制时不存在
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub public_pool_remaining: Option<usize>,
+    // 直接使用 token 时根据额度快照或缓存的账户资料计算的剩余快速请求数
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remaining_fast_requests: Option<u32>,
+}
+
+pub async fn handle_me(
+    State(state): State<Arc<Mutex<AppState>>>,
+    headers: HeaderMap,
+) -> Result<Json<NormalResponse<Me>>, (StatusCode, Json<ErrorResponse>)> {
+    let unauthorized = || {
+        (
+            StatusCode::UNAUTHORIZED,
+            Json(ChatError::Unauthorized.to_json()),
+        )
+    };
+    let auth_header = headers
+        .get(AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix(AUTHORIZATION_BEARER_PREFIX))
+        .ok_or_else(unauthorized)?;
+
+    // 管理员与共享令牌不对应具体的用户
+    if auth_header == AUTH_TOKEN.as_str()
+        || (AppConfig::is_share() && auth_header == AppConfig::get_share_token().as_str())
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                status: ApiStatus::Failed,
+                code: Some(400),
+                error: Some("Invalid request".to_string()),
+                message: Some("AUTH_TOKEN 与共享令牌没有对应的用户".to_string()),
+            }),
+        ));
+    }
+
+    let since = Local::now() - TimeDelta::days(USAGE_DAYS);
+    let state = state.lock().await;
+    let recent_logs = state
+        .request_logs
+        .iter()
+        .filter(|log| log.timestamp >= since);
+
+    let me = if let Some(api_key) = ApiKeys::find(auth_header) {
+        let usage = summarize(
+            recent_logs.filter(|log| log.api_key.as_deref() == Some(api_key.id.as_str())),
+        );
+        let active_tokens = state
+            .token_infos
+            .iter()
+            .filter(|info| {
+                Tenants::allows(api_key.tenant.as_deref(), &info.tags)
+                    && !TokenBlacklist::is_blocked(&info.token)
+                    && !backoff::is_cooling_down(&info.token)
+            })
+            .count();
+        let pool_user = api_key.owner.as_ref().unwrap_or(&api_key.id);
+        Me {
+            kind: "api_key",
+            user_id: api_key.owner.clone(),
+            usage,
+            active_tokens,
+            quota: QuotaStanding {
+                public_pool_remaining: public_pool::remaining(pool_user),
+                remaining_fast_requests: None,
+            },
+            banned: !api_key.is_active(),
+            api_key: Some(api_key),
+        }
+    } else {
+        let token = extract_token(auth_header).ok_or_else(unauthorized)?;
+        let user_id = extract_user_id(&token).ok_or_else(unauthorized)?;
+        let banned = TokenBlacklist::is_blocked(&token);
+        let profile = state
+            .token_infos
+            .iter()
+            .find(|info| info.token == token)
+            .and_then(|info| info.profile.as_ref());
+        let remaining_fast_requests = match QuotaSnapshots::get(&token) {
+            Some(snapshot) => snapshot
+                .max_fast_requests
+                .map(|max| max.saturating_sub(snapshot.fast_requests)),
+            None => profile.and_then(|profile| {
+                let premium = &profile.usage.premium;
+                premium
+                    .max_requests
+                    .map(|max| max.saturating_sub(premium.num_requests))
+            }),
+        };
+        Me {
+            kind: "token",
+            user_id: Some(user_id),
+            api_key: None,
+            usage: summarize(recent_logs.filter(|log| log.token_info.token == token)),
+            active_tokens: usize::from(!banned),
+            quota: QuotaStanding {
+                public_pool_remaining: None,
+                remaining_fast_requests,
+            },
+            banned,
+        }
+    };
+
+    Ok(Json(NormalResponse {
+        status: ApiStatus::Success,
+        data: Some(me),
+        message: None,
+    }))
+}
+
+fn summarize<'a>(logs: impl Iterator<Item = &'a RequestLog>) -> UsageSummary {
+    let mut usage = UsageSummary::default();
+    for log in logs {
+        usage.requests += 1;
+        usage.failures += matches!(log.status, LogStatus::Failed) as u64;
+        if let Some(ref cost) = log.cost {
+            usage.prompt_tokens += cost.prompt_tokens as u64;
+            usage.completion_tokens += cost.completion_tokens as u64;
+            usage.cost += cost.cost;
+        }
+    }
+    usage
+}
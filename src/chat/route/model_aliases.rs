@@ -0,0 +1,103 @@
+use crate::{
+    app::model::{AuditActor, AuditLogs, ModelAlias, ModelAliases},
+    chat::{
+        constant::AVAILABLE_MODELS,
+        middleware::{bad_request, AdminAuth},
+    },
+    common::model::{ApiStatus, ErrorResponse, NormalResponse},
+};
+use axum::{http::StatusCode, Json};
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct ModelAliasRequest {
+    pub action: String,
+    #[serde(default)]
+    pub alias: Option<String>,
+    // set 时使用，映射到的实际模型
+    #[serde(default)]
+    pub model: Option<String>,
+    // set 时使用，标记为已弃用模型的重定向
+    #[serde(default)]
+    pub deprecated: bool,
+}
+
+pub async fn handle_model_aliases(
+    _admin: AdminAuth,
+    actor: AuditActor,
+    Json(request): Json<ModelAliasRequest>,
+) -> Result<Json<NormalResponse<Vec<ModelAlias>>>, (StatusCode, Json<ErrorResponse>)> {
+    let before = ModelAliases::list();
+
+    let message = match request.action.as_str() {
+        "get" => None,
+
+        "set" | "delete" => {
+            let alias = request
+                .alias
+                .map(|alias| alias.trim().to_string())
+                .filter(|alias| !alias.is_empty())
+                .ok_or_else(|| bad_request("缺少 alias".to_string()))?;
+
+            let message = if request.action == "set" {
+                let model = request
+                    .model
+                    .map(|model| model.trim().to_string())
+                    .filter(|model| !model.is_empty())
+                    .ok_or_else(|| bad_request("缺少 model".to_string()))?;
+
+                // 拒绝未知模型，避免别名指向无法使用的模型
+                if !AVAILABLE_MODELS.iter().any(|m| m.id == model) && !model.starts_with("claude") {
+                    return Err(bad_request(format!("未知模型: {}", model)));
+                }
+                if alias == model {
+                    return Err(bad_request("别名与模型相同".to_string()));
+                }
+
+                ModelAliases::set(ModelAlias {
+                    alias,
+                    model,
+                    deprecated: request.deprecated,
+                });
+                "模型别名已更新"
+            } else if ModelAliases::remove(&alias) {
+                "模型别名已删除"
+            } else {
+                "该别名不存在"
+            };
+
+            if let Err(e) = ModelAliases::save().await {
+                return Err((
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ErrorResponse {
+                        status: ApiStatus::Failed,
+                        code: Some(500),
+                        error: Some("保存模型别名失败".to_string()),
+                        message: Some(e.to_string()),
+                    }),
+                ));
+            }
+
+            Some(message.to_string())
+        }
+
+        _ => return Err(bad_request("无效的操作类型".to_string())),
+    };
+
+    let after = ModelAliases::list();
+    if request.action != "get" {
+        AuditLogs::record(
+            &actor,
+            &format!("model_aliases.{}", request.action),
+            AuditLogs::snapshot(&before),
+            AuditLogs::snapshot(&after),
+        )
+        .await;
+    }
+
+    Ok(Json(NormalResponse {
+        status: ApiStatus::Success,
+        data: Some(after),
+        message,
+    }))
+}
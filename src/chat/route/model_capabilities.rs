@@ -0,0 +1,100 @@
+use crate::{
+    app::model::{AuditActor, AuditLogs, Capabilities, ModelCapabilities, ModelCapability},
+    chat::middleware::{bad_request, AdminAuth},
+    common::model::{ApiStatus, ErrorResponse, NormalResponse},
+};
+use axum::{http::StatusCode, Json};
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct ModelCapabilityRequest {
+    pub action: String,
+    #[serde(default)]
+    pub model: Option<String>,
+    // set 时使用
+    #[serde(default)]
+    pub capabilities: Capabilities,
+    // import 时使用，整体替换现有配置，可直接提交导出的 JSON 文件内容
+    #[serde(default)]
+    pub models: Option<Vec<ModelCapability>>,
+}
+
+pub async fn handle_model_capabilities(
+    _admin: AdminAuth,
+    actor: AuditActor,
+    Json(request): Json<ModelCapabilityRequest>,
+) -> Result<Json<NormalResponse<Vec<ModelCapability>>>, (StatusCode, Json<ErrorResponse>)> {
+    let before = ModelCapabilities::list();
+
+    let message = match request.action.as_str() {
+        "get" => None,
+
+        "set" | "delete" | "import" => {
+            let message = match request.action.as_str() {
+                "import" => {
+                    let models = request.models.ok_or_else(|| bad_request("缺少 models"))?;
+                    if models.iter().any(|entry| entry.model.trim().is_empty()) {
+                        return Err(bad_request("model 不能为空"));
+                    }
+                    ModelCapabilities::replace_all(models);
+                    "模型能力信息已导入"
+                }
+                action => {
+                    let model = request
+                        .model
+                        .map(|model| model.trim().to_string())
+                        .filter(|model| !model.is_empty())
+                        .ok_or_else(|| bad_request("缺少 model"))?;
+
+                    if action == "set" {
+                        if request.capabilities.is_empty() {
+                            return Err(bad_request("缺少 capabilities"));
+                        }
+                        ModelCapabilities::set(ModelCapability {
+                            model,
+                            capabilities: request.capabilities,
+                        });
+                        "模型能力信息已更新"
+                    } else if ModelCapabilities::remove(&model) {
+                        "模型能力信息已删除"
+                    } else {
+                        "该模型没有设置能力信息"
+                    }
+                }
+            };
+
+            if let Err(e) = ModelCapabilities::save().await {
+                return Err((
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ErrorResponse {
+                        status: ApiStatus::Failed,
+                        code: Some(500),
+                        error: Some("保存模型能力信息失败".to_string()),
+                        message: Some(e.to_string()),
+                    }),
+                ));
+            }
+
+            Some(message.to_string())
+        }
+
+        _ => return Err(bad_request("无效的操作类型")),
+    };
+
+    let after = ModelCapabilities::list();
+    if request.action != "get" {
+        AuditLogs::record(
+            &actor,
+            &format!("model_capabilities.{}", request.action),
+            AuditLogs::snapshot(&before),
+            AuditLogs::snapshot(&after),
+        )
+        .await;
+    }
+
+    Ok(Json(NormalResponse {
+        status: ApiStatus::Success,
+        data: Some(after),
+        message,
+    }))
+}
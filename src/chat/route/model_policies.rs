@@ -0,0 +1,99 @@
+use crate::{
+    app::model::{AuditActor, AuditLogs, ModelPolicies, UserModelPolicy},
+    chat::{
+        constant::AVAILABLE_MODELS,
+        middleware::{bad_request, AdminAuth},
+    },
+    common::model::{ApiStatus, ErrorResponse, NormalResponse},
+};
+use axum::{http::StatusCode, Json};
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct ModelPolicyRequest {
+    pub action: String,
+    #[serde(default)]
+    pub user_id: Option<String>,
+    #[serde(default)]
+    pub allow: Vec<String>,
+    #[serde(default)]
+    pub deny: Vec<String>,
+}
+
+pub async fn handle_model_policies(
+    _admin: AdminAuth,
+    actor: AuditActor,
+    Json(request): Json<ModelPolicyRequest>,
+) -> Result<Json<NormalResponse<Vec<UserModelPolicy>>>, (StatusCode, Json<ErrorResponse>)> {
+    match request.action.as_str() {
+        "get" => {
+            let policies = match request.user_id {
+                Some(user_id) => ModelPolicies::get(&user_id).into_iter().collect(),
+                None => ModelPolicies::list(),
+            };
+            Ok(Json(NormalResponse {
+                status: ApiStatus::Success,
+                data: Some(policies),
+                message: None,
+            }))
+        }
+
+        "set" | "delete" => {
+            let before = ModelPolicies::list();
+            let user_id = request
+                .user_id
+                .filter(|id| !id.is_empty())
+                .ok_or_else(|| bad_request("缺少 user_id".to_string()))?;
+
+            let message = if request.action == "set" {
+                // 拒绝未知模型，避免拼写错误导致策略静默失效
+                if let Some(model) = request.allow.iter().chain(&request.deny).find(|model| {
+                    !AVAILABLE_MODELS.iter().any(|m| m.id == model.as_str())
+                        && !model.starts_with("claude")
+                }) {
+                    return Err(bad_request(format!("未知模型: {}", model)));
+                }
+
+                ModelPolicies::set(UserModelPolicy {
+                    user_id,
+                    allow: request.allow,
+                    deny: request.deny,
+                });
+                "模型策略已更新"
+            } else if ModelPolicies::remove(&user_id) {
+                "模型策略已删除"
+            } else {
+                "该用户没有模型策略"
+            };
+
+            if let Err(e) = ModelPolicies::save().await {
+                return Err((
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ErrorResponse {
+                        status: ApiStatus::Failed,
+                        code: Some(500),
+                        error: Some("保存模型策略失败".to_string()),
+                        message: Some(e.to_string()),
+                    }),
+                ));
+            }
+
+            let after = ModelPolicies::list();
+            AuditLogs::record(
+                &actor,
+                &format!("model_policies.{}", request.action),
+                AuditLogs::snapshot(&before),
+                AuditLogs::snapshot(&after),
+            )
+            .await;
+
+            Ok(Json(NormalResponse {
+                status: ApiStatus::Success,
+                data: Some(after),
+                message: Some(message.to_string()),
+            }))
+        }
+
+        _ => Err(bad_request("无效的操作类型".to_string())),
+    }
+}
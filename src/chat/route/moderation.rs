@@ -0,0 +1,93 @@
+use crate::{
+    app::model::{AuditActor, AuditLogs, ModerationRule, ModerationRules},
+    chat::middleware::{bad_request, AdminAuth},
+    common::model::{ApiStatus, ErrorResponse, NormalResponse},
+};
+use axum::{http::StatusCode, Json};
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct ModerationRuleRequest {
+    pub action: String,
+    // set 时使用
+    #[serde(default)]
+    pub rule: Option<ModerationRule>,
+    // delete 时使用
+    #[serde(default)]
+    pub id: Option<String>,
+}
+
+pub async fn handle_moderation_rules(
+    _admin: AdminAuth,
+    actor: AuditActor,
+    Json(request): Json<ModerationRuleRequest>,
+) -> Result<Json<NormalResponse<Vec<ModerationRule>>>, (StatusCode, Json<ErrorResponse>)> {
+    let before = ModerationRules::list();
+
+    let message = match request.action.as_str() {
+        "get" => None,
+
+        "set" | "delete" => {
+            let message = if request.action == "set" {
+                let mut rule = request
+                    .rule
+                    .ok_or_else(|| bad_request("缺少 rule".to_string()))?;
+                if rule.pattern.is_empty() {
+                    return Err(bad_request("缺少 pattern".to_string()));
+                }
+                rule.id = rule.id.trim().to_string();
+                if rule.id.is_empty() {
+                    rule.id = uuid::Uuid::new_v4().simple().to_string()[..8].to_string();
+                }
+
+                ModerationRules::set(rule)
+                    .map_err(|e| bad_request(format!("无效的正则表达式: {}", e)))?;
+                "审核规则已更新"
+            } else {
+                let id = request
+                    .id
+                    .map(|id| id.trim().to_string())
+                    .filter(|id| !id.is_empty())
+                    .ok_or_else(|| bad_request("缺少 id".to_string()))?;
+                if ModerationRules::remove(&id) {
+                    "审核规则已删除"
+                } else {
+                    "该规则不存在"
+                }
+            };
+
+            if let Err(e) = ModerationRules::save().await {
+                return Err((
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ErrorResponse {
+                        status: ApiStatus::Failed,
+                        code: Some(500),
+                        error: Some("保存审核规则失败".to_string()),
+                        message: Some(e.to_string()),
+                    }),
+                ));
+            }
+
+            Some(message.to_string())
+        }
+
+        _ => return Err(bad_request("无效的操作类型".to_string())),
+    };
+
+    let after = ModerationRules::list();
+    if request.action != "get" {
+        AuditLogs::record(
+            &actor,
+            &format!("moderation.{}", request.action),
+            AuditLogs::snapshot(&before),
+            AuditLogs::snapshot(&after),
+        )
+        .await;
+    }
+
+    Ok(Json(NormalResponse {
+        status: ApiStatus::Success,
+        data: Some(after),
+        message,
+    }))
+}
@@ -0,0 +1,46 @@
+use crate::{
+    app::{
+        model::{Payload, Payloads},
+        session,
+    },
+    chat::payload,
+    common::model::{error::ChatError, ApiStatus, ErrorResponse, NormalResponse},
+};
+use axum::{
+    extract::Path,
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+
+// 按日志 ID 查询保存的请求消息与最终回复
+pub async fn handle_payload(
+    headers: HeaderMap,
+    Path(id): Path<u64>,
+) -> Result<Json<NormalResponse<Payload>>, (StatusCode, Json<ErrorResponse>)> {
+    if !session::is_admin(&headers) {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(ChatError::Unauthorized.to_json()),
+        ));
+    }
+
+    let payload = Payloads::get(id).ok_or((
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse {
+            status: ApiStatus::Failed,
+            code: Some(404),
+            error: Some("Payload not found".to_string()),
+            message: Some(if payload::is_enabled() {
+                "该日志没有保存的请求内容".to_string()
+            } else {
+                "未启用请求内容保存".to_string()
+            }),
+        }),
+    ))?;
+
+    Ok(Json(NormalResponse {
+        status: ApiStatus::Success,
+        data: Some(payload),
+        message: None,
+    }))
+}
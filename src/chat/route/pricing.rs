@@ -0,0 +1,136 @@
+use crate::{
+    app::model::{AuditActor, AuditLogs, ModelPrice, ModelPrices, SpendLedger, SpendRecord},
+    chat::middleware::{bad_request, AdminAuth},
+    common::model::{ApiStatus, ErrorResponse, NormalResponse},
+};
+use axum::{http::StatusCode, Json};
+use serde::Deserialize;
+
+fn save_error(error: &str, e: Box<dyn std::error::Error>) -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(ErrorResponse {
+            status: ApiStatus::Failed,
+            code: Some(500),
+            error: Some(error.to_string()),
+            message: Some(e.to_string()),
+        }),
+    )
+}
+
+#[derive(Deserialize)]
+pub struct ModelPriceRequest {
+    pub action: String,
+    #[serde(default)]
+    pub model: Option<String>,
+    // 美元每百万 token
+    #[serde(default)]
+    pub input: f64,
+    #[serde(default)]
+    pub output: f64,
+}
+
+pub async fn handle_model_prices(
+    _admin: AdminAuth,
+    actor: AuditActor,
+    Json(request): Json<ModelPriceRequest>,
+) -> Result<Json<NormalResponse<Vec<ModelPrice>>>, (StatusCode, Json<ErrorResponse>)> {
+    let before = ModelPrices::list();
+
+    let message = match request.action.as_str() {
+        "get" => None,
+
+        "set" | "delete" => {
+            let model = request
+                .model
+                .filter(|model| !model.is_empty())
+                .ok_or_else(|| bad_request("缺少 model"))?;
+
+            let message = if request.action == "set" {
+                if !(request.input >= 0.0 && request.output >= 0.0) {
+                    return Err(bad_request("单价不能为负数"));
+                }
+                ModelPrices::set(ModelPrice {
+                    model,
+                    input: request.input,
+                    output: request.output,
+                });
+                "模型单价已更新"
+            } else if ModelPrices::remove(&model) {
+                "模型单价已删除"
+            } else {
+                "该模型没有设置单价"
+            };
+
+            ModelPrices::save()
+                .await
+                .map_err(|e| save_error("保存模型单价失败", e))?;
+            Some(message.to_string())
+        }
+
+        _ => return Err(bad_request("无效的操作类型")),
+    };
+
+    let after = ModelPrices::list();
+    if request.action != "get" {
+        AuditLogs::record(
+            &actor,
+            &format!("model_prices.{}", request.action),
+            AuditLogs::snapshot(&before),
+            AuditLogs::snapshot(&after),
+        )
+        .await;
+    }
+
+    Ok(Json(NormalResponse {
+        status: ApiStatus::Success,
+        data: Some(after),
+        message,
+    }))
+}
+
+#[derive(Deserialize)]
+pub struct SpendRequest {
+    pub action: String,
+    // "token" 或 "user"
+    #[serde(default)]
+    pub group_by: Option<String>,
+}
+
+pub async fn handle_spend(
+    _admin: AdminAuth,
+    actor: AuditActor,
+    Json(request): Json<SpendRequest>,
+) -> Result<Json<NormalResponse<Vec<SpendRecord>>>, (StatusCode, Json<ErrorResponse>)> {
+    match request.action.as_str() {
+        "get" => {
+            let records = match request.group_by.as_deref() {
+                None | Some("token") => SpendLedger::by_token(),
+                Some("user") => SpendLedger::by_user(),
+                Some(_) => return Err(bad_request("无效的分组方式")),
+            };
+            Ok(Json(NormalResponse {
+                status: ApiStatus::Success,
+                data: Some(records),
+                message: None,
+            }))
+        }
+
+        "reset" => {
+            // 快照按用户汇总，不包含 token
+            let before = SpendLedger::by_user();
+            SpendLedger::clear();
+            SpendLedger::save()
+                .await
+                .map_err(|e| save_error("保存消费统计失败", e))?;
+            AuditLogs::record(&actor, "spend.reset", AuditLogs::snapshot(&before), None).await;
+            Ok(Json(NormalResponse {
+                status: ApiStatus::Success,
+                data: Some(Vec::new()),
+                message: Some("消费统计已清空".to_string()),
+            }))
+        }
+
+        _ => Err(bad_request("无效的操作类型")),
+    }
+}
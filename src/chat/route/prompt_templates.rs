@@ -0,0 +1,98 @@
+use crate::{
+    app::model::{
+        AuditActor, AuditLogs, PromptTemplate, PromptTemplates, TemplateMessage, TemplateVariable,
+    },
+    chat::middleware::{bad_request, AdminAuth},
+    common::model::{ApiStatus, ErrorResponse, NormalResponse},
+};
+use axum::{http::StatusCode, Json};
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct PromptTemplateRequest {
+    pub action: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    // set 时使用，{{name}} 为变量占位符
+    #[serde(default)]
+    pub content: Option<String>,
+    // set 时使用，内容与消息中的占位符必须全部声明
+    #[serde(default)]
+    pub variables: Vec<TemplateVariable>,
+    // set 时使用，content 与 messages 至少提供一个
+    #[serde(default)]
+    pub messages: Vec<TemplateMessage>,
+}
+
+pub async fn handle_prompt_templates(
+    _admin: AdminAuth,
+    actor: AuditActor,
+    Json(request): Json<PromptTemplateRequest>,
+) -> Result<Json<NormalResponse<Vec<PromptTemplate>>>, (StatusCode, Json<ErrorResponse>)> {
+    let before = PromptTemplates::list();
+
+    let message = match request.action.as_str() {
+        "get" => None,
+
+        "set" | "delete" => {
+            let name = request
+                .name
+                .map(|name| name.trim().to_string())
+                .filter(|name| !name.is_empty())
+                .ok_or_else(|| bad_request("缺少 name".to_string()))?;
+
+            let message = if request.action == "set" {
+                if request.content.is_none() && request.messages.is_empty() {
+                    return Err(bad_request("缺少 content".to_string()));
+                }
+                let template = PromptTemplate {
+                    name,
+                    content: request.content.unwrap_or_default(),
+                    variables: request.variables,
+                    messages: request.messages,
+                };
+                template.validate().map_err(bad_request)?;
+
+                PromptTemplates::set(template);
+                "提示词模板已更新"
+            } else if PromptTemplates::remove(&name) {
+                "提示词模板已删除"
+            } else {
+                "该模板不存在"
+            };
+
+            if let Err(e) = PromptTemplates::save().await {
+                return Err((
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ErrorResponse {
+                        status: ApiStatus::Failed,
+                        code: Some(500),
+                        error: Some("保存提示词模板失败".to_string()),
+                        message: Some(e.to_string()),
+                    }),
+                ));
+            }
+
+            Some(message.to_string())
+        }
+
+        _ => return Err(bad_request("无效的操作类型".to_string())),
+    };
+
+    let after = PromptTemplates::list();
+    if request.action != "get" {
+        AuditLogs::record(
+            &actor,
+            &format!("prompt_templates.{}", request.action),
+            AuditLogs::snapshot(&before),
+            AuditLogs::snapshot(&after),
+        )
+        .await;
+    }
+
+    Ok(Json(NormalResponse {
+        status: ApiStatus::Success,
+        data: Some(after),
+        message,
+    }))
+}
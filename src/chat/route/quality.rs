@@ -0,0 +1,48 @@
+use crate::{
+    app::model::{QualityBucket, QualitySamples},
+    chat::{middleware::AdminAuth, quality},
+    common::model::{ApiStatus, ErrorResponse, NormalResponse},
+};
+use axum::{extract::Query, http::StatusCode, Json};
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct QualityQuery {
+    pub model: Option<String>,
+    // hour 或 day，默认 hour
+    pub bucket: Option<String>,
+    // 统计最近多少小时，默认 168（7天）
+    pub hours: Option<u32>,
+}
+
+pub async fn handle_quality_trend(
+    _admin: AdminAuth,
+    Query(query): Query<QualityQuery>,
+) -> Result<Json<NormalResponse<Vec<QualityBucket>>>, (StatusCode, Json<ErrorResponse>)> {
+    let bucket = match query.bucket.as_deref() {
+        None | Some("hour") => 3600,
+        Some("day") => 86400,
+        Some(_) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse {
+                    status: ApiStatus::Failed,
+                    code: Some(400),
+                    error: Some("Invalid bucket".to_string()),
+                    message: Some("bucket 只能为 hour 或 day".to_string()),
+                }),
+            ));
+        }
+    };
+    let since = chrono::Utc::now().timestamp() - i64::from(query.hours.unwrap_or(168)) * 3600;
+
+    Ok(Json(NormalResponse {
+        status: ApiStatus::Success,
+        data: Some(QualitySamples::trend(
+            query.model.as_deref().filter(|model| !model.is_empty()),
+            bucket,
+            since,
+        )),
+        message: (!quality::is_enabled()).then(|| "未启用质量抽样".to_string()),
+    }))
+}
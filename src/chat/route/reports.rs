@@ -0,0 +1,65 @@
+use crate::{
+    app::{
+        model::{AppState, Report, Reports},
+        report,
+    },
+    chat::middleware::AdminAuth,
+    common::model::{ApiStatus, ErrorResponse, NormalResponse},
+};
+use axum::{extract::State, http::StatusCode, Json};
+use serde::Deserialize;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+#[derive(Deserialize)]
+pub struct ReportsRequest {
+    pub action: String,
+    // get 时返回的最多份数
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+pub async fn handle_reports(
+    State(state): State<Arc<Mutex<AppState>>>,
+    _admin: AdminAuth,
+    Json(request): Json<ReportsRequest>,
+) -> Result<Json<NormalResponse<Vec<Report>>>, (StatusCode, Json<ErrorResponse>)> {
+    match request.action.as_str() {
+        "get" => {
+            // 最新的报告在前
+            let mut reports = Reports::list();
+            reports.reverse();
+            if let Some(limit) = request.limit {
+                reports.truncate(limit);
+            }
+
+            Ok(Json(NormalResponse {
+                status: ApiStatus::Success,
+                data: Some(reports),
+                message: None,
+            }))
+        }
+
+        // 立即生成并发送一份报告
+        "generate" => {
+            let report = report::generate(&state).await;
+            report::deliver(&report).await;
+
+            Ok(Json(NormalResponse {
+                status: ApiStatus::Success,
+                data: Some(vec![report]),
+                message: Some("报告已生成".to_string()),
+            }))
+        }
+
+        _ => Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                status: ApiStatus::Failed,
+                code: Some(400),
+                error: Some("Invalid request".to_string()),
+                message: Some("无效的操作类型".to_string()),
+            }),
+        )),
+    }
+}
@@ -0,0 +1,165 @@
+use crate::{
+    app::{
+        lazy::LOG_LEVEL,
+        logging,
+        model::{AppConfig, AuditActor, AuditLogs},
+    },
+    chat::middleware::AdminAuth,
+    common::model::{ApiStatus, ErrorResponse, NormalResponse},
+};
+use axum::{http::StatusCode, Json};
+use serde::{Deserialize, Serialize};
+
+// 未提供的字段保持不变，全部为空时仅返回当前状态
+#[derive(Deserialize, Default)]
+pub struct RuntimeRequest {
+    // 格式与 RUST_LOG 相同
+    #[serde(default)]
+    pub log_level: Option<String>,
+    #[serde(default)]
+    pub debug: Option<bool>,
+    #[serde(default)]
+    pub enable_slow_pool: Option<bool>,
+    #[serde(default)]
+    pub enable_all_claude: Option<bool>,
+    #[serde(default)]
+    pub enable_dynamic_key: Option<bool>,
+    #[serde(default)]
+    pub include_web_references: Option<bool>,
+    #[serde(default)]
+    pub token_warmup: Option<bool>,
+    #[serde(default)]
+    pub token_warmup_required: Option<bool>,
+    // 先恢复默认值，再应用其余字段
+    #[serde(default)]
+    pub reset: bool,
+}
+
+#[derive(Serialize)]
+pub struct RuntimeSettings {
+    pub log_level: String,
+    pub debug: bool,
+    pub enable_slow_pool: bool,
+    pub enable_all_claude: bool,
+    pub enable_dynamic_key: bool,
+    pub include_web_references: bool,
+    pub token_warmup: bool,
+    pub token_warmup_required: bool,
+}
+
+impl RuntimeSettings {
+    fn current() -> Self {
+        Self {
+            log_level: logging::current_level(),
+            debug: AppConfig::get_debug(),
+            enable_slow_pool: AppConfig::get_slow_pool(),
+            enable_all_claude: AppConfig::get_allow_claude(),
+            enable_dynamic_key: AppConfig::get_dynamic_key(),
+            include_web_references: AppConfig::get_web_refs(),
+            token_warmup: AppConfig::get_token_warmup(),
+            token_warmup_required: AppConfig::get_token_warmup_required(),
+        }
+    }
+}
+
+pub async fn handle_runtime(
+    _admin: AdminAuth,
+    actor: AuditActor,
+    request: Option<Json<RuntimeRequest>>,
+) -> Result<Json<NormalResponse<RuntimeSettings>>, (StatusCode, Json<ErrorResponse>)> {
+    let request = request.map(|Json(request)| request).unwrap_or_default();
+
+    // 先校验日志级别，避免只应用了部分修改
+    if let Some(ref level) = request.log_level {
+        if let Err(e) = tracing_subscriber::EnvFilter::try_new(level) {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse {
+                    status: ApiStatus::Failed,
+                    code: Some(400),
+                    error: Some("Invalid request".to_string()),
+                    message: Some(format!("无效的日志级别: {}", e)),
+                }),
+            ));
+        }
+    }
+
+    let changed = request.reset
+        || request.log_level.is_some()
+        || request.debug.is_some()
+        || request.enable_slow_pool.is_some()
+        || request.enable_all_claude.is_some()
+        || request.enable_dynamic_key.is_some()
+        || request.include_web_references.is_some()
+        || request.token_warmup.is_some()
+        || request.token_warmup_required.is_some();
+
+    let before = if changed {
+        AuditLogs::snapshot(&RuntimeSettings::current())
+    } else {
+        None
+    };
+
+    if request.reset {
+        let _ = logging::set_level(&LOG_LEVEL);
+        AppConfig::reset_debug();
+        AppConfig::reset_slow_pool();
+        AppConfig::reset_allow_claude();
+        AppConfig::reset_dynamic_key();
+        AppConfig::reset_web_refs();
+        AppConfig::reset_token_warmup();
+        AppConfig::reset_token_warmup_required();
+    }
+
+    if let Some(ref level) = request.log_level {
+        if let Err(e) = logging::set_level(level) {
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    status: ApiStatus::Failed,
+                    code: Some(500),
+                    error: Some(format!("日志级别修改失败: {}", e)),
+                    message: None,
+                }),
+            ));
+        }
+    }
+    if let Some(value) = request.debug {
+        AppConfig::update_debug(value);
+    }
+    if let Some(value) = request.enable_slow_pool {
+        AppConfig::update_slow_pool(value);
+    }
+    if let Some(value) = request.enable_all_claude {
+        AppConfig::update_allow_claude(value);
+    }
+    if let Some(value) = request.enable_dynamic_key {
+        AppConfig::update_dynamic_key(value);
+    }
+    if let Some(value) = request.include_web_references {
+        AppConfig::update_web_refs(value);
+    }
+    if let Some(value) = request.token_warmup {
+        AppConfig::update_token_warmup(value);
+    }
+    if let Some(value) = request.token_warmup_required {
+        AppConfig::update_token_warmup_required(value);
+    }
+
+    let mut message = None;
+    if changed {
+        tracing::info!("运行时设置已修改");
+        let after = AuditLogs::snapshot(&RuntimeSettings::current());
+        AuditLogs::record(&actor, "runtime.update", before, after).await;
+        message = Some(match AppConfig::save_config() {
+            Ok(()) => "设置已生效".to_string(),
+            Err(e) => format!("设置已生效，但保存失败: {}", e),
+        });
+    }
+
+    Ok(Json(NormalResponse {
+        status: ApiStatus::Success,
+        data: Some(RuntimeSettings::current()),
+        message,
+    }))
+}
@@ -0,0 +1,139 @@
+use crate::{
+    app::{
+        constant::AUTHORIZATION_BEARER_PREFIX,
+        lazy::AUTH_TOKEN,
+        model::{AuditActor, AuditLogs, RevokedSessions},
+        session,
+    },
+    common::model::{error::ChatError, ApiStatus, ErrorResponse, NormalResponse},
+};
+use axum::{
+    http::{
+        header::{AUTHORIZATION, SET_COOKIE},
+        HeaderMap, HeaderValue, StatusCode,
+    },
+    Json,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+pub struct SessionRequest {
+    pub action: String,
+}
+
+#[derive(Serialize)]
+pub struct SessionInfo {
+    pub authenticated: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<i64>,
+}
+
+// 用 AUTH_TOKEN 换取网页会话，之后页面无需再携带令牌
+pub async fn handle_session(
+    headers: HeaderMap,
+    actor: AuditActor,
+    Json(request): Json<SessionRequest>,
+) -> Result<(HeaderMap, Json<NormalResponse<SessionInfo>>), (StatusCode, Json<ErrorResponse>)> {
+    let mut response_headers = HeaderMap::new();
+
+    match request.action.as_str() {
+        "status" => {
+            let expires_at = session::expires_at(&headers);
+            Ok((
+                response_headers,
+                Json(NormalResponse {
+                    status: ApiStatus::Success,
+                    data: Some(SessionInfo {
+                        authenticated: expires_at.is_some(),
+                        expires_at,
+                    }),
+                    message: None,
+                }),
+            ))
+        }
+
+        "login" => {
+            // 验证 AUTH_TOKEN
+            let auth_header = headers
+                .get(AUTHORIZATION)
+                .and_then(|h| h.to_str().ok())
+                .and_then(|h| h.strip_prefix(AUTHORIZATION_BEARER_PREFIX))
+                .ok_or((
+                    StatusCode::UNAUTHORIZED,
+                    Json(ChatError::Unauthorized.to_json()),
+                ))?;
+
+            if auth_header != AUTH_TOKEN.as_str() {
+                return Err((
+                    StatusCode::UNAUTHORIZED,
+                    Json(ChatError::Unauthorized.to_json()),
+                ));
+            }
+
+            if !session::is_enabled() {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    Json(ErrorResponse {
+                        status: ApiStatus::Failed,
+                        code: Some(400),
+                        error: Some("会话登录未启用".to_string()),
+                        message: Some("SESSION_TTL 为0".to_string()),
+                    }),
+                ));
+            }
+
+            let (value, expires_at) = session::create();
+            if let Ok(cookie) = HeaderValue::from_str(&session::set_cookie(&value)) {
+                response_headers.insert(SET_COOKIE, cookie);
+            }
+            AuditLogs::record(&actor, "session.login", None, None).await;
+
+            Ok((
+                response_headers,
+                Json(NormalResponse {
+                    status: ApiStatus::Success,
+                    data: Some(SessionInfo {
+                        authenticated: true,
+                        expires_at: Some(expires_at),
+                    }),
+                    message: Some("已登录".to_string()),
+                }),
+            ))
+        }
+
+        // 退出时清除浏览器中的 cookie，并将该会话记入 RevokedSessions，服务端在会话过期前拒绝再次使用
+        "logout" => {
+            if let Ok(cookie) = HeaderValue::from_str(&session::clear_cookie()) {
+                response_headers.insert(SET_COOKIE, cookie);
+            }
+            if session::revoke(&headers) {
+                if let Err(e) = RevokedSessions::save().await {
+                    tracing::error!("保存已退出的会话失败: {}", e);
+                }
+                AuditLogs::record(&actor, "session.logout", None, None).await;
+            }
+
+            Ok((
+                response_headers,
+                Json(NormalResponse {
+                    status: ApiStatus::Success,
+                    data: Some(SessionInfo {
+                        authenticated: false,
+                        expires_at: None,
+                    }),
+                    message: Some("已退出".to_string()),
+                }),
+            ))
+        }
+
+        _ => Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                status: ApiStatus::Failed,
+                code: Some(400),
+                error: Some("Invalid request".to_string()),
+                message: Some("无效的操作类型".to_string()),
+            }),
+        )),
+    }
+}
@@ -0,0 +1,260 @@
+use crate::{
+    app::{
+        backoff,
+        daily_summary::{self, DATE_FORMAT},
+        model::{
+            AppState, DailySummaries, DailySummary, LogStatus, QuotaSnapshots, TokenBlacklist,
+        },
+    },
+    chat::middleware::AdminAuth,
+    common::model::{ApiStatus, ErrorResponse, NormalResponse},
+};
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::Arc};
+use tokio::sync::Mutex;
+
+// 单个 token 的使用概览
+#[derive(Serialize)]
+#[cfg_attr(feature = "client", derive(serde::Deserialize))]
+pub struct TokenStats {
+    pub token: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+    pub requests_today: u64,
+    pub failures_today: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_used: Option<DateTime<Local>>,
+    // 优先根据同步的额度快照计算，其次为缓存的账户资料，都没有或没有上限时不存在
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remaining_fast_requests: Option<u32>,
+    // 额度快照的同步时间
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quota_synced_at: Option<i64>,
+    pub blocked: bool,
+    // 被上游限流后剩余的冷却时间(秒)，冷却期内不参与号池的选择
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cooldown_remaining: Option<u64>,
+}
+
+#[derive(Default)]
+struct LogAggregate {
+    requests_today: u64,
+    failures_today: u64,
+    last_used: Option<DateTime<Local>>,
+}
+
+pub async fn handle_token_stats(
+    State(state): State<Arc<Mutex<AppState>>>,
+    _admin: AdminAuth,
+) -> Result<Json<NormalResponse<Vec<TokenStats>>>, (StatusCode, Json<ErrorResponse>)> {
+    // 今天本地时间零点
+    let today = Local::now()
+        .date_naive()
+        .and_hms_opt(0, 0, 0)
+        .and_then(|midnight| midnight.and_local_timezone(Local).earliest());
+
+    let state = state.lock().await;
+
+    // 按 token 汇总内存中的请求日志
+    let mut aggregates: HashMap<&str, LogAggregate> = HashMap::new();
+    for log in &state.request_logs {
+        let aggregate = aggregates.entry(log.token_info.token.as_str()).or_default();
+        if aggregate
+            .last_used
+            .is_none_or(|last_used| log.timestamp > last_used)
+        {
+            aggregate.last_used = Some(log.timestamp);
+        }
+        if today.is_some_and(|today| log.timestamp >= today) {
+            aggregate.requests_today += 1;
+            aggregate.failures_today += matches!(log.status, LogStatus::Failed) as u64;
+        }
+    }
+
+    let stats = state
+        .token_infos
+        .iter()
+        .map(|info| {
+            let aggregate = aggregates.remove(info.token.as_str()).unwrap_or_default();
+            let premium = info.profile.as_ref().map(|profile| &profile.usage.premium);
+            let snapshot = QuotaSnapshots::get(&info.token);
+            TokenStats {
+                token: info.token.clone(),
+                alias: info.alias.clone(),
+                requests_today: aggregate.requests_today,
+                failures_today: aggregate.failures_today,
+                last_used: aggregate.last_used,
+                remaining_fast_requests: match snapshot {
+                    Some(ref snapshot) => snapshot
+                        .max_fast_requests
+                        .map(|max| max.saturating_sub(snapshot.fast_requests)),
+                    None => premium.and_then(|premium| {
+                        premium
+                            .max_requests
+                            .map(|max| max.saturating_sub(premium.num_requests))
+                    }),
+                },
+                quota_synced_at: snapshot.map(|snapshot| snapshot.synced_at),
+                blocked: TokenBlacklist::is_blocked(&info.token),
+                cooldown_remaining: backoff::remaining(&info.token)
+                    .map(|remaining| remaining.as_secs().max(1)),
+            }
+        })
+        .collect();
+
+    Ok(Json(NormalResponse {
+        status: ApiStatus::Success,
+        data: Some(stats),
+        message: None,
+    }))
+}
+
+#[derive(Deserialize)]
+pub struct DailyStatsQuery {
+    // YYYY-MM-DD，默认为昨天
+    pub date: Option<String>,
+}
+
+// 查询某一天的用量汇总，当天的数据实时统计，之前的数据来自每日生成的汇总
+pub async fn handle_daily_stats(
+    State(state): State<Arc<Mutex<AppState>>>,
+    _admin: AdminAuth,
+    Query(query): Query<DailyStatsQuery>,
+) -> Result<Json<NormalResponse<DailySummary>>, (StatusCode, Json<ErrorResponse>)> {
+    let today = Local::now().date_naive();
+    let date = match query.date.as_deref() {
+        None => today.pred_opt().unwrap_or(today),
+        Some(date) => NaiveDate::parse_from_str(date, DATE_FORMAT).map_err(|_| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse {
+                    status: ApiStatus::Failed,
+                    code: Some(400),
+                    error: Some("Invalid date".to_string()),
+                    message: Some("date 格式应为 YYYY-MM-DD".to_string()),
+                }),
+            )
+        })?,
+    };
+
+    let summary = if date == today {
+        Some(daily_summary::summarize(&state, date).await)
+    } else {
+        DailySummaries::get(&date.format(DATE_FORMAT).to_string())
+    };
+    let summary = summary.ok_or((
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse {
+            status: ApiStatus::Failed,
+            code: Some(404),
+            error: Some("Summary not found".to_string()),
+            message: Some(if DailySummaries::is_enabled() {
+                "该日期没有用量汇总".to_string()
+            } else {
+                "未启用每日用量汇总".to_string()
+            }),
+        }),
+    ))?;
+
+    Ok(Json(NormalResponse {
+        status: ApiStatus::Success,
+        data: Some(summary),
+        message: None,
+    }))
+}
+
+// 耗时分布(秒)
+#[derive(Serialize)]
+#[cfg_attr(feature = "client", derive(serde::Deserialize))]
+pub struct LatencyPercentiles {
+    pub p50: f64,
+    pub p95: f64,
+}
+
+impl LatencyPercentiles {
+    fn from_samples(mut samples: Vec<f64>) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        samples.sort_unstable_by(f64::total_cmp);
+        // 最近秩法
+        let rank = |p: f64| samples[((p * samples.len() as f64).ceil() as usize).max(1) - 1];
+        Some(Self {
+            p50: rank(0.5),
+            p95: rank(0.95),
+        })
+    }
+}
+
+// 单个模型的请求耗时
+#[derive(Serialize)]
+#[cfg_attr(feature = "client", derive(serde::Deserialize))]
+pub struct ModelLatency {
+    pub model: String,
+    pub requests: usize,
+    // 从发出上游请求到收到响应头
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upstream: Option<LatencyPercentiles>,
+    // 从收到响应头到首个内容片段
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first: Option<LatencyPercentiles>,
+    // 从收到响应头到响应结束
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<LatencyPercentiles>,
+}
+
+#[derive(Default)]
+struct LatencySamples {
+    requests: usize,
+    upstream: Vec<f64>,
+    first: Vec<f64>,
+    total: Vec<f64>,
+}
+
+// 按模型统计内存中成功请求的耗时分布
+pub async fn handle_latency_stats(
+    State(state): State<Arc<Mutex<AppState>>>,
+    _admin: AdminAuth,
+) -> Result<Json<NormalResponse<Vec<ModelLatency>>>, (StatusCode, Json<ErrorResponse>)> {
+    let mut samples: HashMap<String, LatencySamples> = HashMap::new();
+    {
+        let state = state.lock().await;
+        for log in &state.request_logs {
+            if !matches!(log.status, LogStatus::Success) {
+                continue;
+            }
+            let entry = samples.entry(log.model.clone()).or_default();
+            entry.requests += 1;
+            entry.upstream.extend(log.timing.upstream);
+            entry.first.extend(log.timing.first);
+            // 流式请求结束前总用时为 0
+            if log.timing.total > 0.0 {
+                entry.total.push(log.timing.total);
+            }
+        }
+    }
+
+    let mut stats: Vec<ModelLatency> = samples
+        .into_iter()
+        .map(|(model, samples)| ModelLatency {
+            model,
+            requests: samples.requests,
+            upstream: LatencyPercentiles::from_samples(samples.upstream),
+            first: LatencyPercentiles::from_samples(samples.first),
+            total: LatencyPercentiles::from_samples(samples.total),
+        })
+        .collect();
+    stats.sort_unstable_by(|a, b| a.model.cmp(&b.model));
+
+    Ok(Json(NormalResponse {
+        status: ApiStatus::Success,
+        data: Some(stats),
+        message: None,
+    }))
+}
@@ -0,0 +1,93 @@
+use crate::{
+    app::model::{AuditActor, AuditLogs, PromptMode, PromptScope, SystemPrompt, SystemPrompts},
+    chat::middleware::{bad_request, AdminAuth},
+    common::model::{ApiStatus, ErrorResponse, NormalResponse},
+};
+use axum::{http::StatusCode, Json};
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct SystemPromptRequest {
+    pub action: String,
+    #[serde(default)]
+    pub scope: Option<PromptScope>,
+    #[serde(default)]
+    pub name: Option<String>,
+    // set 时使用
+    #[serde(default)]
+    pub content: Option<String>,
+    #[serde(default)]
+    pub mode: PromptMode,
+}
+
+pub async fn handle_system_prompts(
+    _admin: AdminAuth,
+    actor: AuditActor,
+    Json(request): Json<SystemPromptRequest>,
+) -> Result<Json<NormalResponse<Vec<SystemPrompt>>>, (StatusCode, Json<ErrorResponse>)> {
+    let before = SystemPrompts::list();
+
+    let message = match request.action.as_str() {
+        "get" => None,
+
+        "set" | "delete" => {
+            let scope = request.scope.ok_or_else(|| bad_request("缺少 scope"))?;
+            let name = request
+                .name
+                .map(|name| name.trim().to_string())
+                .filter(|name| !name.is_empty())
+                .ok_or_else(|| bad_request("缺少 name"))?;
+
+            let message = if request.action == "set" {
+                let content = request
+                    .content
+                    .filter(|content| !content.trim().is_empty())
+                    .ok_or_else(|| bad_request("缺少 content"))?;
+                SystemPrompts::set(SystemPrompt {
+                    scope,
+                    name,
+                    content,
+                    mode: request.mode,
+                });
+                "系统提示词已更新"
+            } else if SystemPrompts::remove(scope, &name) {
+                "系统提示词已删除"
+            } else {
+                "该系统提示词不存在"
+            };
+
+            if let Err(e) = SystemPrompts::save().await {
+                return Err((
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ErrorResponse {
+                        status: ApiStatus::Failed,
+                        code: Some(500),
+                        error: Some("保存系统提示词失败".to_string()),
+                        message: Some(e.to_string()),
+                    }),
+                ));
+            }
+
+            Some(message.to_string())
+        }
+
+        _ => return Err(bad_request("无效的操作类型")),
+    };
+
+    let after = SystemPrompts::list();
+    if request.action != "get" {
+        AuditLogs::record(
+            &actor,
+            &format!("system_prompts.{}", request.action),
+            AuditLogs::snapshot(&before),
+            AuditLogs::snapshot(&after),
+        )
+        .await;
+    }
+
+    Ok(Json(NormalResponse {
+        status: ApiStatus::Success,
+        data: Some(after),
+        message,
+    }))
+}
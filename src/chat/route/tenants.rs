@@ -0,0 +1,98 @@
+use crate::{
+    app::model::{AuditActor, AuditLogs, Tenant, Tenants},
+    chat::middleware::{bad_request, AdminAuth},
+    common::model::{ApiStatus, ErrorResponse, NormalResponse},
+};
+use axum::{http::StatusCode, Json};
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct TenantRequest {
+    pub action: String,
+    #[serde(default)]
+    pub id: Option<String>,
+    // set 时使用，不填时与 id 相同
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+pub async fn handle_tenants(
+    _admin: AdminAuth,
+    actor: AuditActor,
+    Json(request): Json<TenantRequest>,
+) -> Result<Json<NormalResponse<Vec<Tenant>>>, (StatusCode, Json<ErrorResponse>)> {
+    let before = Tenants::list();
+
+    let message = match request.action.as_str() {
+        "get" => None,
+
+        "set" | "delete" => {
+            let id = request
+                .id
+                .map(|id| id.trim().to_string())
+                .filter(|id| !id.is_empty())
+                .ok_or_else(|| bad_request("缺少 id".to_string()))?;
+
+            let message = if request.action == "set" {
+                if !Tenants::is_valid_id(&id) {
+                    return Err(bad_request(format!(
+                        "无效的租户 ID: {}，只能包含小写字母、数字、- 与 _",
+                        id
+                    )));
+                }
+                let name = request
+                    .name
+                    .map(|name| name.trim().to_string())
+                    .filter(|name| !name.is_empty())
+                    .unwrap_or_else(|| id.clone());
+                let created_at = Tenants::get(&id).map_or_else(
+                    || chrono::Utc::now().timestamp(),
+                    |tenant| tenant.created_at,
+                );
+                Tenants::set(Tenant {
+                    id,
+                    name,
+                    created_at,
+                });
+                "租户已更新"
+            } else if Tenants::remove(&id) {
+                "租户已删除"
+            } else {
+                "该租户不存在"
+            };
+
+            if let Err(e) = Tenants::save().await {
+                return Err((
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ErrorResponse {
+                        status: ApiStatus::Failed,
+                        code: Some(500),
+                        error: Some("保存租户失败".to_string()),
+                        message: Some(e.to_string()),
+                    }),
+                ));
+            }
+
+            Some(message.to_string())
+        }
+
+        _ => return Err(bad_request("无效的操作类型".to_string())),
+    };
+
+    let after = Tenants::list();
+    if request.action != "get" {
+        AuditLogs::record(
+            &actor,
+            &format!("tenants.{}", request.action),
+            AuditLogs::snapshot(&before),
+            AuditLogs::snapshot(&after),
+        )
+        .await;
+    }
+
+    Ok(Json(NormalResponse {
+        status: ApiStatus::Success,
+        data: Some(after),
+        message,
+    }))
+}
@@ -0,0 +1,83 @@
+use crate::{
+    app::model::{AuditActor, AuditLogs, TokenBlacklist},
+    chat::middleware::AdminAuth,
+    common::model::{ApiStatus, ErrorResponse, NormalResponse},
+};
+use axum::{http::StatusCode, Json};
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct TokenBlacklistRequest {
+    pub action: String,
+    #[serde(default)]
+    pub entries: Vec<String>,
+}
+
+pub async fn handle_token_blacklist(
+    _admin: AdminAuth,
+    actor: AuditActor,
+    Json(request): Json<TokenBlacklistRequest>,
+) -> Result<Json<NormalResponse<Vec<String>>>, (StatusCode, Json<ErrorResponse>)> {
+    let internal_error = |error: &str, e: std::io::Error| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                status: ApiStatus::Failed,
+                code: Some(500),
+                error: Some(error.to_string()),
+                message: Some(e.to_string()),
+            }),
+        )
+    };
+
+    let before = TokenBlacklist::list();
+
+    let message = match request.action.as_str() {
+        "get" => None,
+
+        "add" | "delete" => {
+            let message = if request.action == "add" {
+                format!("已添加 {} 个条目", TokenBlacklist::add(request.entries))
+            } else {
+                format!("已删除 {} 个条目", TokenBlacklist::remove(&request.entries))
+            };
+            TokenBlacklist::save().map_err(|e| internal_error("保存黑名单失败", e))?;
+            Some(message)
+        }
+
+        // 从文件重新加载，用于手动编辑文件之后
+        "reload" => {
+            let count = TokenBlacklist::load().map_err(|e| internal_error("加载黑名单失败", e))?;
+            Some(format!("已加载 {} 个条目", count))
+        }
+
+        _ => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse {
+                    status: ApiStatus::Failed,
+                    code: Some(400),
+                    error: Some("Invalid request".to_string()),
+                    message: Some("无效的操作类型".to_string()),
+                }),
+            ))
+        }
+    };
+
+    let after = TokenBlacklist::list();
+    if request.action != "get" {
+        AuditLogs::record(
+            &actor,
+            &format!("blacklist.{}", request.action),
+            AuditLogs::snapshot(&before),
+            AuditLogs::snapshot(&after),
+        )
+        .await;
+    }
+
+    Ok(Json(NormalResponse {
+        status: ApiStatus::Success,
+        data: Some(after),
+        message,
+    }))
+}
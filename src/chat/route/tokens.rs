@@ -1,26 +1,36 @@
 use crate::{
     app::{
         constant::{
-            AUTHORIZATION_BEARER_PREFIX, CONTENT_TYPE_TEXT_HTML_WITH_UTF8,
-            CONTENT_TYPE_TEXT_PLAIN_WITH_UTF8, ROUTE_TOKENS_PATH,
+            AUTHORIZATION_BEARER_PREFIX, COMMA, CONTENT_TYPE_TEXT_CSV_WITH_UTF8,
+            CONTENT_TYPE_TEXT_HTML_WITH_UTF8, CONTENT_TYPE_TEXT_PLAIN_WITH_UTF8, ROUTE_TOKENS_PATH,
         },
-        lazy::{AUTH_TOKEN, TOKEN_LIST_FILE},
+        lazy::{SERVICE_TIMEOUT, TOKEN_LIST_FILE, TOKEN_TRASH_FILE},
         model::{
-            AppConfig, AppState, PageContent, TokenAddRequestTokenInfo, TokenInfo,
-            TokenUpdateRequest, TokensDeleteRequest, TokensDeleteResponse,
+            AppConfig, AppState, AuditActor, AuditLogs, PageContent, TokenAddRequestTokenInfo,
+            TokenBlacklist, TokenExportInfo, TokenInfo, TokenMetaRequest, TokenUpdateRequest,
+            TokenWarmup, TokensDeleteRequest, TokensDeleteResponse, TokensImportResponse,
+            TokensTransferFormat, TokensTransferQuery,
         },
+        session, static_dir,
     },
+    chat::middleware::AdminAuth,
     common::{
-        model::{error::ChatError, ApiStatus, ErrorResponse},
+        model::{
+            error::ChatError, userinfo::TokenProfile, ApiStatus, ErrorResponse, NormalResponse,
+        },
         utils::{
-            extract_time, extract_time_ks, extract_user_id, generate_checksum_with_default,
-            generate_checksum_with_repair, generate_hash, generate_timestamp_header, load_tokens,
-            parse_token, validate_token, validate_token_and_checksum, write_tokens,
+            extract_time, extract_time_ks, extract_token, extract_user_id, format_time_ms,
+            generate_checksum_with_default, generate_checksum_with_repair, generate_hash,
+            generate_timestamp_header, get_token_profile, is_public_flag, load_token_file,
+            load_tokens, normalize_field, normalize_tags, parse_tags, parse_token,
+            validate_checksum, validate_token, validate_token_and_checksum, write_tokens,
+            PUBLIC_TOKEN_FLAG,
         },
     },
 };
 use axum::{
-    extract::{Query, State},
+    body::Body,
+    extract::{Path, Query, State},
     http::{
         header::{AUTHORIZATION, CONTENT_TYPE},
         HeaderMap,
@@ -28,6 +38,7 @@ use axum::{
     response::{IntoResponse, Response},
     Json,
 };
+use futures::StreamExt as _;
 use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
@@ -82,14 +93,8 @@ pub async fn handle_get_tokens(
     State(state): State<Arc<Mutex<AppState>>>,
     headers: HeaderMap,
 ) -> Result<Json<TokenInfoResponse>, StatusCode> {
-    // 验证 AUTH_TOKEN
-    let auth_header = headers
-        .get(AUTHORIZATION)
-        .and_then(|h| h.to_str().ok())
-        .and_then(|h| h.strip_prefix(AUTHORIZATION_BEARER_PREFIX))
-        .ok_or(StatusCode::UNAUTHORIZED)?;
-
-    if auth_header != AUTH_TOKEN.as_str() {
+    // 验证 AUTH_TOKEN 或网页会话
+    if !session::is_admin(&headers) {
         return Err(StatusCode::UNAUTHORIZED);
     }
 
@@ -105,6 +110,7 @@ pub async fn handle_get_tokens(
 }
 
 #[derive(Serialize)]
+#[cfg_attr(feature = "client", derive(serde::Deserialize))]
 pub struct TokenInfoResponse {
     pub status: ApiStatus,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -116,28 +122,17 @@ pub struct TokenInfoResponse {
 
 pub async fn handle_reload_tokens(
     State(state): State<Arc<Mutex<AppState>>>,
-    headers: HeaderMap,
+    _admin: AdminAuth,
+    actor: AuditActor,
 ) -> Result<Json<TokenInfoResponse>, StatusCode> {
-    // 验证 AUTH_TOKEN
-    let auth_header = headers
-        .get(AUTHORIZATION)
-        .and_then(|h| h.to_str().ok())
-        .and_then(|h| h.strip_prefix(AUTHORIZATION_BEARER_PREFIX))
-        .ok_or(StatusCode::UNAUTHORIZED)?;
-
-    if auth_header != AUTH_TOKEN.as_str() {
-        return Err(StatusCode::UNAUTHORIZED);
-    }
-
     // 重新加载 tokens
-    let tokens = load_tokens();
+    let tokens = tokio::task::spawn_blocking(load_tokens)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     let tokens_count = tokens.len();
 
     // 更新应用状态
-    {
-        let mut state = state.lock().await;
-        state.token_infos = tokens;
-    }
+    replace_tokens(&state, &actor, "tokens.reload", tokens).await;
 
     Ok(Json(TokenInfoResponse {
         status: ApiStatus::Success,
@@ -149,34 +144,21 @@ pub async fn handle_reload_tokens(
 
 pub async fn handle_update_tokens(
     State(state): State<Arc<Mutex<AppState>>>,
-    headers: HeaderMap,
+    _admin: AdminAuth,
+    actor: AuditActor,
     Json(request): Json<TokenUpdateRequest>,
 ) -> Result<Json<TokenInfoResponse>, StatusCode> {
-    // 验证 AUTH_TOKEN
-    let auth_header = headers
-        .get(AUTHORIZATION)
-        .and_then(|h| h.to_str().ok())
-        .and_then(|h| h.strip_prefix(AUTHORIZATION_BEARER_PREFIX))
-        .ok_or(StatusCode::UNAUTHORIZED)?;
-
-    if auth_header != AUTH_TOKEN.as_str() {
-        return Err(StatusCode::UNAUTHORIZED);
-    }
-
-    let token_list_file = TOKEN_LIST_FILE.as_str();
-
-    std::fs::write(&token_list_file, &request.tokens)
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    // 重新加载 tokens
-    let token_infos = load_tokens();
+    // 写入并重新加载 tokens
+    let token_infos = tokio::task::spawn_blocking(move || {
+        std::fs::write(TOKEN_LIST_FILE.as_str(), &request.tokens).map(|_| load_tokens())
+    })
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     let tokens_count = token_infos.len();
 
     // 更新应用状态
-    {
-        let mut state = state.lock().await;
-        state.token_infos = token_infos;
-    }
+    replace_tokens(&state, &actor, "tokens.update", token_infos).await;
 
     Ok(Json(TokenInfoResponse {
         status: ApiStatus::Success,
@@ -188,28 +170,10 @@ pub async fn handle_update_tokens(
 
 pub async fn handle_add_tokens(
     State(state): State<Arc<Mutex<AppState>>>,
-    headers: HeaderMap,
+    _admin: AdminAuth,
+    actor: AuditActor,
     Json(request): Json<Vec<TokenAddRequestTokenInfo>>,
 ) -> Result<Json<TokenInfoResponse>, (StatusCode, Json<ErrorResponse>)> {
-    // 验证 AUTH_TOKEN
-    let auth_header = headers
-        .get(AUTHORIZATION)
-        .and_then(|h| h.to_str().ok())
-        .and_then(|h| h.strip_prefix(AUTHORIZATION_BEARER_PREFIX))
-        .ok_or((
-            StatusCode::UNAUTHORIZED,
-            Json(ChatError::Unauthorized.to_json()),
-        ))?;
-
-    if auth_header != AUTH_TOKEN.as_str() {
-        return Err((
-            StatusCode::UNAUTHORIZED,
-            Json(ChatError::Unauthorized.to_json()),
-        ));
-    }
-
-    let token_list_file = TOKEN_LIST_FILE.as_str();
-
     // 获取当前的 tokens 并创建新的 token_infos
     let mut token_infos = {
         let state = state.lock().await;
@@ -226,7 +190,10 @@ pub async fn handle_add_tokens(
     // 处理新的tokens
     for token_info in request {
         let parsed_token = parse_token(&token_info.token);
-        if !existing_tokens.contains(parsed_token.as_str()) && validate_token(&parsed_token) {
+        if !existing_tokens.contains(parsed_token.as_str())
+            && validate_token(&parsed_token)
+            && !TokenBlacklist::is_blocked(&parsed_token)
+        {
             new_tokens.push(TokenInfo {
                 token: parsed_token,
                 // 如果提供了checksum就使用提供的，否则生成新的
@@ -235,11 +202,19 @@ pub async fn handle_add_tokens(
                     .as_deref()
                     .map(generate_checksum_with_repair)
                     .unwrap_or_else(generate_checksum_with_default),
+                alias: token_info.alias.as_deref().and_then(normalize_field),
                 profile: None,
+                warmup: None,
+                is_public: token_info.is_public,
+                note: token_info.note.as_deref().and_then(normalize_field),
+                contact: token_info.contact.as_deref().and_then(normalize_field),
+                tags: normalize_tags(&token_info.tags),
             });
         }
     }
 
+    let rejected = warmup_tokens(&mut new_tokens).await;
+
     // 如果有新tokens才进行后续操作
     if !new_tokens.is_empty() {
         // 预分配足够的容量
@@ -247,32 +222,28 @@ pub async fn handle_add_tokens(
         token_infos.extend(new_tokens);
 
         // 写入文件
-        write_tokens(&token_infos, token_list_file).map_err(|_| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    status: ApiStatus::Error,
-                    code: None,
-                    error: Some("Failed to update token list file".to_string()),
-                    message: Some("无法更新token list文件".to_string()),
-                }),
-            )
-        })?;
+        let token_infos = write_tokens_blocking(token_infos).await?;
 
         // 获取最终的tokens数量（在更新状态之前）
         let tokens_count = token_infos.len();
 
         // 更新应用状态
-        {
-            let mut state = state.lock().await;
-            state.token_infos = token_infos;
-        }
+        replace_tokens(&state, &actor, "tokens.add", token_infos).await;
+
+        let message = if rejected.is_empty() {
+            "New tokens have been added and reloaded".to_string()
+        } else {
+            format!(
+                "New tokens have been added and reloaded, {} rejected by warmup",
+                rejected.len()
+            )
+        };
 
         Ok(Json(TokenInfoResponse {
             status: ApiStatus::Success,
             tokens: None,
             tokens_count,
-            message: Some("New tokens have been added and reloaded".to_string()),
+            message: Some(message),
         }))
     } else {
         // 如果没有新tokens，使用原始数量
@@ -289,32 +260,13 @@ pub async fn handle_add_tokens(
 
 pub async fn handle_delete_tokens(
     State(state): State<Arc<Mutex<AppState>>>,
-    headers: HeaderMap,
+    _admin: AdminAuth,
+    actor: AuditActor,
     Json(request): Json<TokensDeleteRequest>,
 ) -> Result<Json<TokensDeleteResponse>, (StatusCode, Json<ErrorResponse>)> {
-    // 验证 AUTH_TOKEN
-    let auth_header = headers
-        .get(AUTHORIZATION)
-        .and_then(|h| h.to_str().ok())
-        .and_then(|h| h.strip_prefix(AUTHORIZATION_BEARER_PREFIX))
-        .ok_or((
-            StatusCode::UNAUTHORIZED,
-            Json(ChatError::Unauthorized.to_json()),
-        ))?;
-
-    if auth_header != AUTH_TOKEN.as_str() {
-        return Err((
-            StatusCode::UNAUTHORIZED,
-            Json(ChatError::Unauthorized.to_json()),
-        ));
-    }
-
     let token_infos = state.lock().await.token_infos.clone();
     let original_count = token_infos.len(); // 提前存储原始长度
 
-    // 获取token_list文件路径
-    let token_list_file = TOKEN_LIST_FILE.as_str();
-
     // 创建要删除的tokens的HashSet，提高查找效率
     let tokens_to_delete: std::collections::HashSet<_> = request.tokens.iter().collect();
 
@@ -335,28 +287,26 @@ pub async fn handle_delete_tokens(
     // 预分配容量并过滤掉要删除的tokens
     let estimated_capacity = original_count.saturating_sub(tokens_to_delete.len());
     let mut filtered_token_infos = Vec::with_capacity(estimated_capacity);
+    let mut removed_token_infos = Vec::new();
 
     // 一次性过滤tokens
     for info in token_infos {
-        if !tokens_to_delete.contains(&info.token) {
+        if tokens_to_delete.contains(&info.token) {
+            removed_token_infos.push(info);
+        } else {
             filtered_token_infos.push(info);
         }
     }
 
     // 如果有tokens被删除才进行更新操作
-    if filtered_token_infos.len() < original_count {
+    if !removed_token_infos.is_empty() {
+        // 先写入回收站，避免 token 丢失
+        if request.soft {
+            move_to_trash(removed_token_infos).await?;
+        }
+
         // 写入文件
-        write_tokens(&filtered_token_infos, token_list_file).map_err(|_| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    status: ApiStatus::Error,
-                    code: None,
-                    error: Some("Failed to update token list file".to_string()),
-                    message: Some("无法更新token list文件".to_string()),
-                }),
-            )
-        })?;
+        let filtered_token_infos = write_tokens_blocking(filtered_token_infos).await?;
 
         // 如果需要的话计算 updated_tokens
         let updated_tokens = if request.expectation.needs_updated_tokens() {
@@ -371,10 +321,12 @@ pub async fn handle_delete_tokens(
         };
 
         // 更新状态
-        {
-            let mut state = state.lock().await;
-            state.token_infos = filtered_token_infos;
-        }
+        let action = if request.soft {
+            "tokens.soft_delete"
+        } else {
+            "tokens.delete"
+        };
+        replace_tokens(&state, &actor, action, filtered_token_infos).await;
 
         Ok(Json(TokensDeleteResponse {
             status: ApiStatus::Success,
@@ -400,19 +352,571 @@ pub async fn handle_delete_tokens(
     }
 }
 
-pub async fn handle_tokens_page() -> impl IntoResponse {
+// 回收站中的 token 列表，与 token list 文件格式相同
+pub async fn handle_get_deleted_tokens(
+    headers: HeaderMap,
+) -> Result<Json<TokenInfoResponse>, (StatusCode, Json<ErrorResponse>)> {
+    if !session::is_admin(&headers) {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(ChatError::Unauthorized.to_json()),
+        ));
+    }
+
+    let tokens = load_deleted_tokens().await?;
+    let tokens_count = tokens.len();
+
+    Ok(Json(TokenInfoResponse {
+        status: ApiStatus::Success,
+        tokens: Some(tokens),
+        tokens_count,
+        message: None,
+    }))
+}
+
+// 将指定别名的 token 移入回收站，不再参与号池选择
+pub async fn handle_soft_delete_token(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Path(alias): Path<String>,
+    headers: HeaderMap,
+    actor: AuditActor,
+) -> Result<Json<NormalResponse<usize>>, (StatusCode, Json<ErrorResponse>)> {
+    let owner = token_owner(&headers)?;
+
+    let token_infos = state.lock().await.token_infos.clone();
+    let (removed, kept): (Vec<_>, Vec<_>) = token_infos
+        .into_iter()
+        .partition(|info| matches_alias(info, &alias, owner.as_deref()));
+    if removed.is_empty() {
+        return Err(token_not_found());
+    }
+    let count = removed.len();
+
+    move_to_trash(removed).await?;
+    let kept = write_tokens_blocking(kept).await?;
+    replace_tokens(&state, &actor, "tokens.soft_delete", kept).await;
+
+    Ok(Json(NormalResponse {
+        status: ApiStatus::Success,
+        data: Some(count),
+        message: Some(format!("已将 {count} 个 token 移入回收站")),
+    }))
+}
+
+// 从回收站恢复指定别名的 token，号池中已存在的同一 token 保持不变
+pub async fn handle_restore_token(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Path(alias): Path<String>,
+    headers: HeaderMap,
+    actor: AuditActor,
+) -> Result<Json<NormalResponse<usize>>, (StatusCode, Json<ErrorResponse>)> {
+    let owner = token_owner(&headers)?;
+
+    let (restored, remaining): (Vec<_>, Vec<_>) = load_deleted_tokens()
+        .await?
+        .into_iter()
+        .partition(|info| matches_alias(info, &alias, owner.as_deref()));
+    if restored.is_empty() {
+        return Err(token_not_found());
+    }
+    let count = restored.len();
+
+    let mut token_infos = state.lock().await.token_infos.clone();
+    for info in restored {
+        if !token_infos
+            .iter()
+            .any(|existing| existing.token == info.token)
+        {
+            token_infos.push(info);
+        }
+    }
+
+    let token_infos = write_tokens_blocking(token_infos).await?;
+    replace_tokens(&state, &actor, "tokens.restore", token_infos).await;
+    write_token_file_blocking(remaining, TOKEN_TRASH_FILE.as_str()).await?;
+
+    Ok(Json(NormalResponse {
+        status: ApiStatus::Success,
+        data: Some(count),
+        message: Some(format!("已恢复 {count} 个 token")),
+    }))
+}
+
+#[derive(Deserialize)]
+pub struct TokensValidateRequest {
+    // 为空时检查全部可操作的 token
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    // 是否请求一次账户资料，验证 token 在上游是否可用
+    #[serde(default)]
+    pub probe: bool,
+    #[serde(default)]
+    pub concurrency: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub struct TokenVerdict {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<String>,
+    pub token_valid: bool,
+    pub checksum_valid: bool,
+    // 仅在请求 probe 且本地校验通过时返回
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub probe: Option<TokenWarmup>,
+    pub valid: bool,
+}
+
+// 同时进行的上游探测数
+const DEFAULT_VALIDATE_CONCURRENCY: usize = 4;
+const MAX_VALIDATE_CONCURRENCY: usize = 16;
+
+// 批量校验 token 与 checksum 的格式，可选地探测上游，按号池中的顺序返回结果
+pub async fn handle_validate_tokens(
+    State(state): State<Arc<Mutex<AppState>>>,
+    headers: HeaderMap,
+    Json(request): Json<TokensValidateRequest>,
+) -> Result<Json<NormalResponse<Vec<TokenVerdict>>>, (StatusCode, Json<ErrorResponse>)> {
+    let owner = token_owner(&headers)?;
+
+    let token_infos: Vec<TokenInfo> = state
+        .lock()
+        .await
+        .token_infos
+        .iter()
+        .filter(|info| owner.as_ref().is_none_or(|owner| *owner == info.token))
+        .filter(|info| {
+            request.aliases.is_empty()
+                || info
+                    .alias
+                    .as_ref()
+                    .is_some_and(|alias| request.aliases.contains(alias))
+        })
+        .cloned()
+        .collect();
+    if token_infos.is_empty() && !request.aliases.is_empty() {
+        return Err(token_not_found());
+    }
+
+    let concurrency = request
+        .concurrency
+        .unwrap_or(DEFAULT_VALIDATE_CONCURRENCY)
+        .clamp(1, MAX_VALIDATE_CONCURRENCY);
+    let probe_upstream = request.probe;
+    let verdicts: Vec<TokenVerdict> = futures::stream::iter(token_infos)
+        .map(|info| async move {
+            let token_valid = validate_token(&info.token);
+            let checksum_valid = validate_checksum(&info.checksum);
+            let probe = if probe_upstream && token_valid && checksum_valid {
+                Some(warmup_token(&info.token).await.1)
+            } else {
+                None
+            };
+            TokenVerdict {
+                valid: token_valid
+                    && checksum_valid
+                    && probe.as_ref().is_none_or(|probe| probe.success),
+                user_id: extract_user_id(&info.token),
+                alias: info.alias,
+                token_valid,
+                checksum_valid,
+                probe,
+            }
+        })
+        .buffered(concurrency)
+        .collect()
+        .await;
+
+    let invalid = verdicts.iter().filter(|verdict| !verdict.valid).count();
+    Ok(Json(NormalResponse {
+        status: ApiStatus::Success,
+        message: Some(format!(
+            "共检查 {} 个 token，其中 {} 个无效",
+            verdicts.len(),
+            invalid
+        )),
+        data: Some(verdicts),
+    }))
+}
+
+// 管理员可以操作任意 token，返回 None；其他调用者只能操作与自己 Bearer token 相同的 token
+fn token_owner(headers: &HeaderMap) -> Result<Option<String>, (StatusCode, Json<ErrorResponse>)> {
+    if session::is_admin(headers) {
+        return Ok(None);
+    }
+    headers
+        .get(AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix(AUTHORIZATION_BEARER_PREFIX))
+        .and_then(extract_token)
+        .map(Some)
+        .ok_or((
+            StatusCode::UNAUTHORIZED,
+            Json(ChatError::Unauthorized.to_json()),
+        ))
+}
+
+fn matches_alias(info: &TokenInfo, alias: &str, owner: Option<&str>) -> bool {
+    info.alias.as_deref() == Some(alias) && owner.is_none_or(|owner| owner == info.token)
+}
+
+// 不区分别名不存在与无权操作，避免泄露其他用户的 token 别名
+fn token_not_found() -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse {
+            status: ApiStatus::Failed,
+            code: Some(404),
+            error: Some("Token not found".to_string()),
+            message: Some("没有可操作的该别名的 token".to_string()),
+        }),
+    )
+}
+
+async fn load_deleted_tokens() -> Result<Vec<TokenInfo>, (StatusCode, Json<ErrorResponse>)> {
+    tokio::task::spawn_blocking(|| load_token_file(TOKEN_TRASH_FILE.as_str()))
+        .await
+        .map_err(|_| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    status: ApiStatus::Error,
+                    code: None,
+                    error: Some("Failed to read token trash file".to_string()),
+                    message: Some("无法读取回收站文件".to_string()),
+                }),
+            )
+        })
+}
+
+// 移入回收站，回收站中已有的同一 token 被替换为最新的信息
+async fn move_to_trash(
+    token_infos: Vec<TokenInfo>,
+) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    let mut deleted = load_deleted_tokens().await?;
+    deleted.retain(|info| {
+        !token_infos
+            .iter()
+            .any(|removed| removed.token == info.token)
+    });
+    deleted.extend(token_infos);
+    write_token_file_blocking(deleted, TOKEN_TRASH_FILE.as_str())
+        .await
+        .map(|_| ())
+}
+
+pub async fn handle_token_meta(
+    State(state): State<Arc<Mutex<AppState>>>,
+    _admin: AdminAuth,
+    actor: AuditActor,
+    Json(request): Json<TokenMetaRequest>,
+) -> Result<Json<TokenInfoResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let mut token_infos = state.lock().await.token_infos.clone();
+    let token = parse_token(&request.token);
+    let token_info = token_infos
+        .iter_mut()
+        .find(|info| info.token == token)
+        .ok_or((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                status: ApiStatus::Failed,
+                code: Some(400),
+                error: Some("Token not found".to_string()),
+                message: Some("token 不存在".to_string()),
+            }),
+        ))?;
+    token_info.note = request.note.as_deref().and_then(normalize_field);
+    token_info.contact = request.contact.as_deref().and_then(normalize_field);
+    if let Some(ref tags) = request.tags {
+        token_info.tags = normalize_tags(tags);
+    }
+
+    let token_infos = write_tokens_blocking(token_infos).await?;
+    let tokens_count = token_infos.len();
+
+    replace_tokens(&state, &actor, "tokens.meta", token_infos).await;
+
+    Ok(Json(TokenInfoResponse {
+        status: ApiStatus::Success,
+        tokens: None,
+        tokens_count,
+        message: Some("Token metadata has been updated".to_string()),
+    }))
+}
+
+pub async fn handle_import_tokens(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Query(query): Query<TokensTransferQuery>,
+    _admin: AdminAuth,
+    actor: AuditActor,
+    body: String,
+) -> Result<Json<TokensImportResponse>, (StatusCode, Json<ErrorResponse>)> {
+    // 解析导入内容
+    let entries = match query.format {
+        TokensTransferFormat::Json => serde_json::from_str::<Vec<TokenAddRequestTokenInfo>>(&body)
+            .map_err(|e| {
+                (
+                    StatusCode::BAD_REQUEST,
+                    Json(ErrorResponse {
+                        status: ApiStatus::Error,
+                        code: None,
+                        error: Some("Invalid JSON token list".to_string()),
+                        message: Some(e.to_string()),
+                    }),
+                )
+            })?,
+        TokensTransferFormat::Csv => parse_tokens_csv(&body),
+    };
+
+    let mut token_infos = {
+        let state = state.lock().await;
+        state.token_infos.clone()
+    };
+
+    // 已存在的 token 以及本次导入中重复的 token 都会被跳过
+    let mut known_tokens: std::collections::HashSet<String> =
+        token_infos.iter().map(|info| info.token.clone()).collect();
+    let mut new_tokens = Vec::with_capacity(entries.len());
+    let mut skipped_count = 0;
+    let mut invalid_tokens = Vec::new();
+
+    for entry in entries {
+        let parsed_token = parse_token(&entry.token);
+        if !validate_token(&parsed_token) || TokenBlacklist::is_blocked(&parsed_token) {
+            invalid_tokens.push(entry.token);
+            continue;
+        }
+        if !known_tokens.insert(parsed_token.clone()) {
+            skipped_count += 1;
+            continue;
+        }
+        new_tokens.push(TokenInfo {
+            token: parsed_token,
+            checksum: entry
+                .checksum
+                .as_deref()
+                .filter(|checksum| !checksum.is_empty())
+                .map(generate_checksum_with_repair)
+                .unwrap_or_else(generate_checksum_with_default),
+            alias: entry.alias.as_deref().and_then(normalize_field),
+            profile: None,
+            warmup: None,
+            is_public: entry.is_public,
+            note: entry.note.as_deref().and_then(normalize_field),
+            contact: entry.contact.as_deref().and_then(normalize_field),
+            tags: normalize_tags(&entry.tags),
+        });
+    }
+
+    // 预热失败被拒绝的 token 同样计入 invalid_tokens
+    invalid_tokens.extend(warmup_tokens(&mut new_tokens).await);
+
+    let imported_count = new_tokens.len();
+
+    // 一次性写入所有新 token
+    if imported_count > 0 {
+        token_infos.extend(new_tokens);
+        token_infos = write_tokens_blocking(token_infos).await?;
+    }
+
+    let tokens_count = token_infos.len();
+
+    if imported_count > 0 {
+        replace_tokens(&state, &actor, "tokens.import", token_infos).await;
+    }
+
+    Ok(Json(TokensImportResponse {
+        status: ApiStatus::Success,
+        imported_count,
+        skipped_count,
+        tokens_count,
+        invalid_tokens,
+    }))
+}
+
+// 启用预热时并发预热新 token，并记录结果与账户资料
+// 返回因预热失败被拒绝的 token（仅在要求预热成功时）
+async fn warmup_tokens(new_tokens: &mut Vec<TokenInfo>) -> Vec<String> {
+    if !AppConfig::get_token_warmup() || new_tokens.is_empty() {
+        return Vec::new();
+    }
+
+    let required = AppConfig::get_token_warmup_required();
+    let results =
+        futures::future::join_all(new_tokens.iter().map(|info| warmup_token(&info.token))).await;
+
+    let mut rejected = Vec::new();
+    let mut warmed = Vec::with_capacity(new_tokens.len());
+    for (mut info, (profile, warmup)) in new_tokens.drain(..).zip(results) {
+        if !warmup.success && required {
+            tracing::warn!("token 预热失败，已拒绝添加: {}", info.token);
+            rejected.push(info.token);
+            continue;
+        }
+        info.profile = profile;
+        info.warmup = Some(warmup);
+        warmed.push(info);
+    }
+    *new_tokens = warmed;
+
+    rejected
+}
+
+// 请求一次账户资料，验证 token 可用并预先建立到上游的连接
+async fn warmup_token(token: &str) -> (Option<TokenProfile>, TokenWarmup) {
+    let start = std::time::Instant::now();
+    let profile = tokio::time::timeout(
+        std::time::Duration::from_secs(*SERVICE_TIMEOUT),
+        get_token_profile(token),
+    )
+    .await
+    .ok()
+    .flatten();
+
+    let warmup = TokenWarmup {
+        success: profile.is_some(),
+        latency: format_time_ms(start.elapsed().as_secs_f64()),
+        checked_at: chrono::Local::now(),
+    };
+    (profile, warmup)
+}
+
+// 解析 CSV 格式的 token 列表: token,checksum,alias,public,note,contact,tags，checksum 之后的列可省略
+fn parse_tokens_csv(content: &str) -> Vec<TokenAddRequestTokenInfo> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut fields = line.split(COMMA).map(str::trim);
+            let token = fields.next()?;
+            // 跳过表头
+            if token.eq_ignore_ascii_case("token") {
+                return None;
+            }
+            Some(TokenAddRequestTokenInfo {
+                token: token.to_string(),
+                checksum: fields.next().map(str::to_string),
+                alias: fields.next().map(str::to_string),
+                is_public: fields.next().is_some_and(is_public_flag),
+                note: fields.next().map(str::to_string),
+                contact: fields.next().map(str::to_string),
+                tags: fields.next().map(parse_tags).unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+pub async fn handle_export_tokens(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Query(query): Query<TokensTransferQuery>,
+    _admin: AdminAuth,
+) -> Result<Response, StatusCode> {
+    let tokens: Vec<TokenExportInfo> = state
+        .lock()
+        .await
+        .token_infos
+        .iter()
+        .map(|info| TokenExportInfo {
+            token: info.token.clone(),
+            checksum: info.checksum.clone(),
+            alias: info.alias.clone(),
+            is_public: info.is_public,
+            note: info.note.clone(),
+            contact: info.contact.clone(),
+            tags: info.tags.clone(),
+        })
+        .collect();
+
+    match query.format {
+        TokensTransferFormat::Json => Ok(Json(tokens).into_response()),
+        TokensTransferFormat::Csv => {
+            let mut content = String::from("token,checksum,alias,public,note,contact,tags\n");
+            for info in &tokens {
+                content.push_str(&info.token);
+                content.push(COMMA);
+                content.push_str(&info.checksum);
+                content.push(COMMA);
+                content.push_str(info.alias.as_deref().unwrap_or_default());
+                content.push(COMMA);
+                if info.is_public {
+                    content.push_str(PUBLIC_TOKEN_FLAG);
+                }
+                content.push(COMMA);
+                content.push_str(info.note.as_deref().unwrap_or_default());
+                content.push(COMMA);
+                content.push_str(info.contact.as_deref().unwrap_or_default());
+                content.push(COMMA);
+                content.push_str(&info.tags.join(" "));
+                content.push('\n');
+            }
+
+            Ok(([(CONTENT_TYPE, CONTENT_TYPE_TEXT_CSV_WITH_UTF8)], content).into_response())
+        }
+    }
+}
+
+// 替换内存中的 token 列表并记录审计日志
+async fn replace_tokens(
+    state: &Mutex<AppState>,
+    actor: &AuditActor,
+    action: &str,
+    token_infos: Vec<TokenInfo>,
+) {
+    let after = AuditLogs::token_snapshot(&token_infos);
+    let before = {
+        let mut state = state.lock().await;
+        let before = AuditLogs::token_snapshot(&state.token_infos);
+        state.token_infos = token_infos;
+        before
+    };
+    AuditLogs::record(actor, action, before, after).await;
+}
+
+// 在阻塞线程池中写入 token list 文件，成功后归还 token 列表
+async fn write_tokens_blocking(
+    token_infos: Vec<TokenInfo>,
+) -> Result<Vec<TokenInfo>, (StatusCode, Json<ErrorResponse>)> {
+    write_token_file_blocking(token_infos, TOKEN_LIST_FILE.as_str()).await
+}
+
+async fn write_token_file_blocking(
+    token_infos: Vec<TokenInfo>,
+    file_path: &'static str,
+) -> Result<Vec<TokenInfo>, (StatusCode, Json<ErrorResponse>)> {
+    tokio::task::spawn_blocking(move || write_tokens(&token_infos, file_path).map(|_| token_infos))
+        .await
+        .ok()
+        .and_then(Result::ok)
+        .ok_or((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                status: ApiStatus::Error,
+                code: None,
+                error: Some("Failed to update token list file".to_string()),
+                message: Some("无法更新token list文件".to_string()),
+            }),
+        ))
+}
+
+pub async fn handle_tokens_page(headers: HeaderMap) -> impl IntoResponse {
     match AppConfig::get_page_content(ROUTE_TOKENS_PATH).unwrap_or_default() {
-        PageContent::Default => Response::builder()
-            .header(CONTENT_TYPE, CONTENT_TYPE_TEXT_HTML_WITH_UTF8)
-            .body(include_str!("../../../static/tokens.min.html").to_string())
-            .unwrap(),
+        PageContent::Default => {
+            static_dir::page(
+                "tokens.html",
+                &headers,
+                include_str!("../../../static/tokens.min.html"),
+            )
+            .await
+        }
         PageContent::Text(content) => Response::builder()
             .header(CONTENT_TYPE, CONTENT_TYPE_TEXT_PLAIN_WITH_UTF8)
-            .body(content.clone())
+            .body(Body::from(content.clone()))
             .unwrap(),
         PageContent::Html(content) => Response::builder()
             .header(CONTENT_TYPE, CONTENT_TYPE_TEXT_HTML_WITH_UTF8)
-            .body(content.clone())
+            .body(Body::from(content.clone()))
             .unwrap(),
     }
 }
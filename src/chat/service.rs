@@ -1,69 +1,912 @@
 use crate::{
     app::{
+        backoff,
         constant::{
-            AUTHORIZATION_BEARER_PREFIX, FINISH_REASON_STOP, OBJECT_CHAT_COMPLETION,
-            OBJECT_CHAT_COMPLETION_CHUNK,
+            API_KEY_SCOPE_CHAT, AUTHORIZATION_BEARER_PREFIX, FALSE, FINISH_REASON_CONTENT_FILTER,
+            FINISH_REASON_ERROR, FINISH_REASON_STOP, HEADER_NAME_API_KEY,
+            HEADER_NAME_IGNORED_PARAMS, HEADER_NAME_MODEL_REDIRECTED,
+            HEADER_NAME_NON_STREAM_KEEPALIVE, HEADER_NAME_PUBLIC_POOL, HEADER_NAME_STREAM_PRELUDE,
+            HEADER_NAME_TOKEN_ALIAS, HEADER_NAME_TOKEN_TAG, OBJECT_CHAT_COMPLETION,
+            OBJECT_CHAT_COMPLETION_CHUNK, TRUE,
         },
-        lazy::{AUTH_TOKEN, KEY_PREFIX, KEY_PREFIX_LEN, REQUEST_LOGS_LIMIT, SERVICE_TIMEOUT},
+        i18n::{Locale, Text},
+        lazy::{
+            AUTH_TOKEN, CHAT_MAX_CHOICES, KEY_PREFIX, KEY_PREFIX_LEN, LOGPROBS_REJECT,
+            NON_STREAM_KEEPALIVE_AFTER, NON_STREAM_KEEPALIVE_INTERVAL, SERVICE_TIMEOUT,
+            STREAM_PRELUDE_CLIENTS,
+        },
+        lease, log_sink, log_stream,
         model::{
-            AppConfig, AppState, ChatRequest, LogStatus, RequestLog, TimingInfo, TokenInfo,
-            UsageCheck,
+            ApiKeys, AppConfig, AppState, AzureDeployments, Cancellation, ChatRequest, CostInfo,
+            LogStatus, ModelAliases, ModelPolicies, QuotaSnapshots, RequestLog, ResponseFormat,
+            SpendLedger, SystemPrompts, Tenants, TimingInfo, TokenBlacklist, TokenInfo, UsageCheck,
+            ROTATION_REJECTED,
         },
+        quota, request_id, rotation,
     },
     chat::{
+        adapter::ImageError,
+        cache,
         config::KeyConfig,
         constant::{AVAILABLE_MODELS, USAGE_CHECK_MODELS},
-        error::StreamError,
+        conversation,
+        error::{ErrorKind, StreamError},
+        fault,
+        idempotency::ResponseFailure,
+        json_mode, mock,
         model::{
-            ChatResponse, Choice, Delta, Message, MessageContent, ModelsResponse, Role, Usage,
+            ChatResponse, Choice, CompletionTokensDetails, Delta, Message, MessageContent, Model,
+            ModelEntry, ModelsResponse, Role, Usage,
         },
+        moderation, payload,
+        priority::{self, Permit, Tier},
+        public_pool, quality, queue, reconcile,
         stream::{StreamDecoder, StreamMessage},
     },
     common::{
-        client::build_client,
-        model::{error::ChatError, userinfo::MembershipType, ApiStatus, ErrorResponse},
+        client::{build_client, send_with_retry},
+        model::{
+            error::ChatError,
+            userinfo::{MembershipType, UsageProfile},
+            ApiStatus, ErrorResponse, NormalResponse,
+        },
         utils::{
-            format_time_ms, from_base64, get_token_profile, tokeninfo_to_token,
+            client_ip, estimate_tokens, extract_user_id, format_time_ms, from_base64,
+            generate_checksum_with_default, get_token_profile, tokeninfo_to_token,
             validate_token_and_checksum, TrimNewlines as _,
         },
     },
 };
 use axum::{
     body::Body,
-    extract::State,
+    extract::{
+        ws::{Message as WsMessage, WebSocket, WebSocketUpgrade},
+        ConnectInfo, Path, Query, State,
+    },
     http::{
-        header::{AUTHORIZATION, CONTENT_TYPE},
-        HeaderMap, StatusCode,
+        header::{AUTHORIZATION, CONTENT_TYPE, USER_AGENT},
+        HeaderMap, HeaderValue, StatusCode,
     },
     response::Response,
     Json,
 };
 use bytes::Bytes;
-use futures::StreamExt;
+use futures::{stream::BoxStream, StreamExt};
 use prost::Message as _;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
 use std::{
+    collections::HashMap,
     convert::Infallible,
+    net::{IpAddr, SocketAddr},
     sync::{atomic::AtomicBool, Arc},
 };
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, OnceCell};
+use tokio_util::sync::CancellationToken;
+use tracing::Instrument as _;
 use uuid::Uuid;
 
 // 模型列表处理
 pub async fn handle_models() -> Json<ModelsResponse> {
     Json(ModelsResponse {
         object: "list",
-        data: &AVAILABLE_MODELS,
+        data: AVAILABLE_MODELS.iter().map(ModelEntry::from).collect(),
     })
 }
 
+#[derive(Deserialize, Default)]
+pub struct ChatQuery {
+    // 为 true 时流式响应使用具名事件（delta、done、error）
+    #[serde(default)]
+    pub sse_events: bool,
+}
+
 // 聊天处理函数的签名
 pub async fn handle_chat(
     State(state): State<Arc<Mutex<AppState>>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Query(query): Query<ChatQuery>,
+    headers: HeaderMap,
+    Json(mut request): Json<ChatRequest>,
+) -> Result<Response<Body>, (StatusCode, Json<ErrorResponse>)> {
+    let response_id = format!("chatcmpl-{}", Uuid::new_v4().simple());
+
+    request.apply_token_tag_suffix();
+    request.apply_slow_pool_suffix();
+    request.apply_template().map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ChatError::InvalidTemplate(e).to_json()),
+        )
+    })?;
+    request.apply_response_format();
+
+    // 先将客户端使用的别名映射为实际模型，再进行校验；已弃用的模型通过响应头告知原名称
+    let mut redirected_from = None;
+    if let Some(resolved) = ModelAliases::resolve(&request.model) {
+        tracing::debug!("模型别名 {} 映射为 {}", request.model, resolved.model);
+        let requested = std::mem::replace(&mut request.model, resolved.model);
+        if resolved.deprecated {
+            redirected_from = Some(requested);
+        }
+    }
+
+    // n > 1 时每个回复各发起一次上游请求
+    let choices = request.n.unwrap_or(1);
+    if choices == 0 || choices > *CHAT_MAX_CHOICES {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(
+                ChatError::InvalidChoices(format!("must be between 1 and {}", *CHAT_MAX_CHOICES))
+                    .to_json(),
+            ),
+        ));
+    }
+    // 多个回复无法写入同一个上游会话
+    if choices > 1 && request.conversation_id.is_some() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(
+                ChatError::InvalidChoices("cannot be combined with conversation_id".to_string())
+                    .to_json(),
+            ),
+        ));
+    }
+
+    if *LOGPROBS_REJECT && request.wants_logprobs() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ChatError::LogprobsUnsupported.to_json()),
+        ));
+    }
+
+    // 每个请求一个 span，关联该请求产生的所有日志
+    let request_id = request_id::get(&headers).unwrap_or_default();
+    let span = tracing::info_span!(
+        "chat",
+        id = %response_id,
+        request_id = %request_id,
+        model = %request.model,
+        stream = request.stream,
+        user = tracing::field::Empty,
+        log_id = tracing::field::Empty,
+    );
+
+    // 上游不支持的参数通过响应头告知客户端
+    let ignored_params = request.ignored_params().join(",");
+
+    // 客户端主动开启时，耗时较长的非流式请求改用保活响应
+    let keepalive = !request.stream
+        && *NON_STREAM_KEEPALIVE_AFTER > 0
+        && headers
+            .get(HEADER_NAME_NON_STREAM_KEEPALIVE)
+            .is_some_and(|v| v.as_bytes() == TRUE.as_bytes());
+
+    let sse_events = query.sse_events && request.stream;
+
+    // 部分客户端需要立即收到首个片段才认为连接正常，此时先发送只含角色的前导片段
+    let prelude = (request.stream && stream_prelude_enabled(&headers))
+        .then(|| (response_id.clone(), request.model.clone()));
+
+    // 状态码已发送后才出现的错误通过响应扩展告知中间件
+    let failure = ResponseFailure::default();
+
+    let client_ip = client_ip(&headers, addr);
+    let chat = if choices > 1 {
+        futures::future::Either::Left(process_choices(
+            state,
+            headers,
+            client_ip,
+            request,
+            response_id,
+            failure.clone(),
+        ))
+    } else {
+        futures::future::Either::Right(process_chat(
+            state,
+            headers,
+            client_ip,
+            request,
+            ResponseSlot {
+                response_id,
+                index: 0,
+                dispatch: Arc::new(DispatchSlot::new()),
+                failure: failure.clone(),
+            },
+        ))
+    }
+    .instrument(span);
+    let result = if keepalive {
+        with_keepalive(chat, failure.clone()).await
+    } else if let Some((response_id, model)) = prelude {
+        Ok(with_prelude(
+            chat,
+            response_id,
+            model,
+            request_id.clone(),
+            failure.clone(),
+        ))
+    } else {
+        chat.await
+    };
+    let mut response = match result {
+        Ok(response) if sse_events => with_sse_events(response),
+        Err(error) if sse_events => sse_error(error, &request_id),
+        result => result?,
+    };
+    if !ignored_params.is_empty() {
+        if let Ok(value) = HeaderValue::from_str(&ignored_params) {
+            response
+                .headers_mut()
+                .insert(HEADER_NAME_IGNORED_PARAMS, value);
+        }
+    }
+    if let Some(value) = redirected_from.and_then(|model| HeaderValue::from_str(&model).ok()) {
+        response
+            .headers_mut()
+            .insert(HEADER_NAME_MODEL_REDIRECTED, value);
+    }
+    response.extensions_mut().insert(failure);
+    Ok(response)
+}
+
+// 使用服务端模板的对话接口，template_id 必填，渲染出的消息放在客户端消息之前
+pub async fn handle_chat_template(
+    state: State<Arc<Mutex<AppState>>>,
+    addr: ConnectInfo<SocketAddr>,
+    query: Query<ChatQuery>,
     headers: HeaderMap,
     Json(request): Json<ChatRequest>,
 ) -> Result<Response<Body>, (StatusCode, Json<ErrorResponse>)> {
+    if request.template.is_none() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ChatError::InvalidTemplate("template_id is required".to_string()).to_json()),
+        ));
+    }
+    handle_chat(state, addr, query, headers, Json(request)).await
+}
+
+// 租户路由，只使用该租户的 token 与 API key
+pub async fn handle_tenant_models(
+    Path(tenant): Path<String>,
+) -> Result<Json<ModelsResponse>, (StatusCode, Json<ErrorResponse>)> {
+    if Tenants::get(&tenant).is_none() {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(ChatError::TenantNotFound(tenant).to_json()),
+        ));
+    }
+    Ok(handle_models().await)
+}
+
+pub async fn handle_tenant_chat(
+    Path(tenant): Path<String>,
+    state: State<Arc<Mutex<AppState>>>,
+    addr: ConnectInfo<SocketAddr>,
+    query: Query<ChatQuery>,
+    headers: HeaderMap,
+    Json(mut request): Json<ChatRequest>,
+) -> Result<Response<Body>, (StatusCode, Json<ErrorResponse>)> {
+    if Tenants::get(&tenant).is_none() {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(ChatError::TenantNotFound(tenant).to_json()),
+        ));
+    }
+    request.tenant = Some(tenant);
+    handle_chat(state, addr, query, headers, Json(request)).await
+}
+
+// Azure OpenAI 风格的对话接口，模型由部署名称决定，请求体中的 model 被忽略
+// 查询参数 api-version 只为兼容客户端，不影响处理；认证可使用 api-key 请求头代替 Authorization
+pub async fn handle_azure_chat(
+    Path(deployment): Path<String>,
+    state: State<Arc<Mutex<AppState>>>,
+    addr: ConnectInfo<SocketAddr>,
+    query: Query<ChatQuery>,
+    mut headers: HeaderMap,
+    Json(mut request): Json<ChatRequest>,
+) -> Result<Response<Body>, (StatusCode, Json<ErrorResponse>)> {
+    if !headers.contains_key(AUTHORIZATION) {
+        if let Some(value) = headers
+            .get(HEADER_NAME_API_KEY)
+            .and_then(|h| h.to_str().ok())
+            .and_then(|key| {
+                HeaderValue::from_str(&format!("{AUTHORIZATION_BEARER_PREFIX}{}", key.trim())).ok()
+            })
+        {
+            headers.insert(AUTHORIZATION, value);
+        }
+    }
+    request.model = AzureDeployments::resolve(&deployment);
+    handle_chat(state, addr, query, headers, Json(request)).await
+}
+
+// 客户端断开后 handler 与响应体都会被 drop，此时中止后台的请求任务
+struct AbortOnDrop<T>(tokio::task::JoinHandle<T>);
+
+impl<T> Drop for AbortOnDrop<T> {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+impl<T> std::future::Future for AbortOnDrop<T> {
+    type Output = Result<T, tokio::task::JoinError>;
+
+    fn poll(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Self::Output> {
+        std::pin::Pin::new(&mut self.0).poll(cx)
+    }
+}
+
+// 超过阈值仍未完成时先返回 200，定期发送空白字符，完成后再发送完整的 JSON
+// 此时错误只能以 JSON 的形式返回，状态码写入 code 字段
+async fn with_keepalive<F>(
+    chat: F,
+    failure: ResponseFailure,
+) -> Result<Response<Body>, (StatusCode, Json<ErrorResponse>)>
+where
+    F: std::future::Future<Output = Result<Response<Body>, (StatusCode, Json<ErrorResponse>)>>
+        + Send
+        + 'static,
+{
+    let join_error = |e: tokio::task::JoinError| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ChatError::RequestFailed(e.to_string()).to_json()),
+        )
+    };
+
+    let mut handle = AbortOnDrop(tokio::spawn(Locale::current().scope(chat)));
+    if let Ok(result) = tokio::time::timeout(
+        std::time::Duration::from_secs(*NON_STREAM_KEEPALIVE_AFTER),
+        &mut handle,
+    )
+    .await
+    {
+        return result.unwrap_or_else(|e| Err(join_error(e)));
+    }
+
+    tracing::debug!("非流式请求耗时较长，开始发送保活空白");
+
+    let interval = std::time::Duration::from_secs(*NON_STREAM_KEEPALIVE_INTERVAL);
+    let body = futures::stream::unfold(Some(handle), move |handle| {
+        let failure = failure.clone();
+        async move {
+            let mut handle = handle?;
+            tokio::select! {
+                result = &mut handle => {
+                    let bytes = match result.unwrap_or_else(|e| Err(join_error(e))) {
+                        Ok(response) => axum::body::to_bytes(response.into_body(), usize::MAX)
+                            .await
+                            .unwrap_or_default(),
+                        Err((status, Json(mut error))) => {
+                            failure.mark();
+                            error.code.get_or_insert(status.as_u16());
+                            Bytes::from(serde_json::to_string(&error).unwrap_or_default())
+                        }
+                    };
+                    Some((Ok::<_, Infallible>(bytes), None))
+                }
+                _ = tokio::time::sleep(interval) => Some((Ok(Bytes::from_static(b" ")), Some(handle))),
+            }
+        }
+    });
+
+    Ok(Response::builder()
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from_stream(body))
+        .unwrap())
+}
+
+// 请求头 x-stream-prelude 优先，否则按 User-Agent 匹配配置的客户端
+fn stream_prelude_enabled(headers: &HeaderMap) -> bool {
+    match headers
+        .get(HEADER_NAME_STREAM_PRELUDE)
+        .and_then(|v| v.to_str().ok())
+    {
+        Some(value) if value.eq_ignore_ascii_case(TRUE) => return true,
+        Some(value) if value.eq_ignore_ascii_case(FALSE) => return false,
+        _ => {}
+    }
+    if STREAM_PRELUDE_CLIENTS.is_empty() {
+        return false;
+    }
+    let user_agent = headers
+        .get(USER_AGENT)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    STREAM_PRELUDE_CLIENTS
+        .iter()
+        .any(|client| client == "*" || user_agent.contains(client.as_str()))
+}
+
+// 在收到上游的第一个字节前立即返回只含角色的片段，之后转发实际的流式响应
+// 此时状态码已发送，错误以 error 事件返回
+fn with_prelude<F>(
+    chat: F,
+    response_id: String,
+    model: String,
+    request_id: String,
+    failure: ResponseFailure,
+) -> Response<Body>
+where
+    F: std::future::Future<Output = Result<Response<Body>, (StatusCode, Json<ErrorResponse>)>>
+        + Send
+        + 'static,
+{
+    let prelude = ChatResponse {
+        id: response_id,
+        object: OBJECT_CHAT_COMPLETION_CHUNK.to_string(),
+        created: chrono::Utc::now().timestamp(),
+        model: Some(model),
+        choices: vec![Choice {
+            index: 0,
+            message: None,
+            delta: Some(Delta {
+                role: Some(Role::Assistant),
+                content: Some(String::new()),
+            }),
+            logprobs: None,
+            finish_reason: None,
+        }],
+        usage: None,
+        metadata: None,
+    };
+    let prelude = Bytes::from(format!(
+        "data: {}\n\n",
+        serde_json::to_string(&prelude).unwrap()
+    ));
+
+    let body = futures::stream::once(futures::future::ready(Ok(prelude))).chain(
+        futures::stream::once(chat).flat_map(move |result| match result {
+            Ok(response) => response.into_body().into_data_stream().boxed(),
+            Err((_, Json(error))) => {
+                failure.mark();
+                futures::stream::once(futures::future::ready(Ok(Bytes::from(error_event(
+                    &error,
+                    &request_id,
+                )))))
+                .boxed()
+            }
+        }),
+    );
+
+    Response::builder()
+        .header("Cache-Control", "no-cache")
+        .header("Connection", "keep-alive")
+        .header(CONTENT_TYPE, "text/event-stream")
+        .body(Body::from_stream(body))
+        .unwrap()
+}
+
+// 为每个 SSE 事件加上事件名，[DONE] 为 done，其余为 delta，已带事件名的保持不变
+fn with_sse_events(response: Response<Body>) -> Response<Body> {
+    let (parts, body) = response.into_parts();
+    let body = body.into_data_stream().scan(Vec::new(), |buffer, chunk| {
+        let chunk = chunk.map(|chunk| {
+            buffer.extend_from_slice(&chunk);
+            let mut output = Vec::with_capacity(buffer.len() + 16);
+            while let Some(pos) = buffer.windows(2).position(|w| w == b"\n\n") {
+                let event: Vec<u8> = buffer.drain(..pos + 2).collect();
+                if !event.starts_with(b"event: ") {
+                    output.extend_from_slice(if event.starts_with(b"data: [DONE]") {
+                        b"event: done\n"
+                    } else {
+                        b"event: delta\n"
+                    });
+                }
+                output.extend_from_slice(&event);
+            }
+            Bytes::from(output)
+        });
+        futures::future::ready(Some(chunk))
+    });
+    Response::from_parts(parts, Body::from_stream(body))
+}
+
+// 以 error 事件返回错误，保留原状态码
+fn sse_error(
+    (status, Json(error)): (StatusCode, Json<ErrorResponse>),
+    request_id: &str,
+) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, "text/event-stream")
+        .body(Body::from(error_event(&error, request_id)))
+        .unwrap()
+}
+
+// 错误事件附带请求 ID，客户端反馈问题时可据此查找日志
+fn error_event(error: &ErrorResponse, request_id: &str) -> String {
+    #[derive(Serialize)]
+    struct ErrorEvent<'a> {
+        #[serde(flatten)]
+        error: &'a ErrorResponse,
+        request_id: &'a str,
+    }
+
+    format!(
+        "event: error\ndata: {}\n\n",
+        serde_json::to_string(&ErrorEvent { error, request_id }).unwrap_or_default()
+    )
+}
+
+// WebSocket 传输，每条文本消息为一个 ChatRequest，回复按 SSE 片段逐帧推送
+pub async fn handle_chat_ws(
+    State(state): State<Arc<Mutex<AppState>>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let locale = Locale::current();
+    ws.on_upgrade(move |socket| locale.scope(serve_chat_ws(socket, state, addr, headers)))
+}
+
+async fn serve_chat_ws(
+    mut socket: WebSocket,
+    state: Arc<Mutex<AppState>>,
+    addr: SocketAddr,
+    headers: HeaderMap,
+) {
+    while let Some(Ok(message)) = socket.recv().await {
+        let request = match message {
+            WsMessage::Text(text) => serde_json::from_str::<ChatRequest>(text.as_str()),
+            WsMessage::Close(_) => break,
+            _ => continue,
+        };
+
+        let result = match request {
+            Ok(mut request) => {
+                request.stream = true;
+                handle_chat(
+                    State(state.clone()),
+                    ConnectInfo(addr),
+                    Query(ChatQuery::default()),
+                    headers.clone(),
+                    Json(request),
+                )
+                .await
+            }
+            Err(e) => Err((
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse {
+                    status: ApiStatus::Failed,
+                    code: Some(400),
+                    error: Some("Invalid request".to_string()),
+                    message: Some(e.to_string()),
+                }),
+            )),
+        };
+
+        let sent = match result {
+            Ok(response) => forward_sse_frames(&mut socket, response.into_body()).await,
+            Err((_, Json(error))) => socket
+                .send(WsMessage::Text(
+                    serde_json::to_string(&error).unwrap_or_default().into(),
+                ))
+                .await
+                .is_ok(),
+        };
+
+        if !sent {
+            break;
+        }
+    }
+}
+
+// 复用 SSE 响应体，将每个事件的 data 字段作为一帧发送，包括结尾的 [DONE]
+async fn forward_sse_frames(socket: &mut WebSocket, body: Body) -> bool {
+    let mut stream = body.into_data_stream();
+    let mut buffer = Vec::new();
+
+    while let Some(Ok(chunk)) = stream.next().await {
+        buffer.extend_from_slice(&chunk);
+
+        while let Some(pos) = buffer.windows(2).position(|w| w == b"\n\n") {
+            let event: Vec<u8> = buffer.drain(..pos + 2).collect();
+            for line in event.split(|&b| b == b'\n') {
+                if let Some(data) = line.strip_prefix(b"data: ") {
+                    let frame = String::from_utf8_lossy(data).into_owned();
+                    if socket.send(WsMessage::Text(frame.into())).await.is_err() {
+                        return false;
+                    }
+                }
+            }
+        }
+    }
+
+    true
+}
+
+// 按 chatcmpl id 取消进行中的请求，只有发起请求的令牌或 AUTH_TOKEN 可以取消
+pub async fn handle_chat_cancel(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<NormalResponse<()>>, (StatusCode, Json<ErrorResponse>)> {
+    let auth_header = headers
+        .get(AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix(AUTHORIZATION_BEARER_PREFIX))
+        .ok_or((
+            StatusCode::UNAUTHORIZED,
+            Json(ChatError::Unauthorized.to_json()),
+        ))?;
+
+    // 无权取消时同样视为不存在，避免泄露其他用户的请求；n > 1 时一并取消全部回复
+    let state = state.lock().await;
+    let cancellations: Vec<&Cancellation> = state
+        .cancellations
+        .iter()
+        .filter(|(key, _)| cancellation_id(key) == id)
+        .map(|(_, c)| c)
+        .filter(|c| auth_header == AUTH_TOKEN.as_str() || auth_header == c.owner)
+        .collect();
+    if cancellations.is_empty() {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                status: ApiStatus::Failed,
+                code: Some(404),
+                error: Some("Request not found".to_string()),
+                message: Some("请求不存在或已完成".to_string()),
+            }),
+        ));
+    }
+    for cancellation in cancellations {
+        cancellation.token.cancel();
+    }
+    drop(state);
+
+    tracing::info!(id = %id, "收到取消请求");
+    Ok(Json(NormalResponse {
+        status: ApiStatus::Success,
+        data: None,
+        message: Some("已取消".to_string()),
+    }))
+}
+
+// n > 1 时每个回复以 `id:index` 登记，取消时按 id 匹配
+fn cancellation_id(key: &str) -> &str {
+    key.split_once(':').map_or(key, |(id, _)| id)
+}
+
+// 轮询选择token，跳过被拉黑或被其他实例租用的token
+// 指定标签时只选择带有该标签的 token，只选择属于 tenant 的 token（未指定时不属于任何租户）
+// 同步的快速请求额度已用完的 token 不参与选择，启用 QUOTA_SLOW_FALLBACK 时作为最后的选择
+// 未被拉黑、不在退避期且租户允许使用，轮询与按别名指定都先经过此检查
+fn is_available(token_info: &TokenInfo, tenant: Option<&str>) -> bool {
+    !TokenBlacklist::is_blocked(&token_info.token)
+        && !backoff::is_cooling_down(&token_info.token)
+        && Tenants::allows(tenant, &token_info.tags)
+}
+
+async fn select_pool_token(
+    state: &Mutex<AppState>,
+    tenant: Option<&str>,
+    tag: Option<&str>,
+) -> Option<(String, String)> {
+    static CURRENT_KEY_INDEX: AtomicUsize = AtomicUsize::new(0);
+
+    // 先在持有状态锁时按顺序列出候选，租约的文件读写在释放锁之后进行
+    let candidates: Vec<(String, String)> = {
+        let state = state.lock().await;
+        let token_infos = &state.token_infos;
+        if token_infos.is_empty() {
+            return None;
+        }
+
+        let len = token_infos.len();
+        let start = CURRENT_KEY_INDEX.fetch_add(1, Ordering::SeqCst) % len;
+        let mut candidates = Vec::new();
+        for allow_exhausted in [false, true] {
+            if allow_exhausted && !quota::slow_fallback() {
+                break;
+            }
+            for offset in 0..len {
+                let token_info = &token_infos[(start + offset) % len];
+                if !is_available(token_info, tenant)
+                    || tag.is_some_and(|tag| !token_info.tags.iter().any(|t| t == tag))
+                    || QuotaSnapshots::is_exhausted(&token_info.token) != allow_exhausted
+                {
+                    continue;
+                }
+                candidates.push((token_info.token.clone(), token_info.checksum.clone()));
+            }
+        }
+        candidates
+    };
+
+    for (token, checksum) in candidates {
+        if lease::try_acquire(&token).await {
+            return Some((token, checksum));
+        }
+    }
+    None
+}
+
+// 活动请求计数的守卫，释放时减少计数
+//
+// 客户端中途断开时响应体会被丢弃，上游的字节流随之关闭，守卫也一并释放。
+// 所有出错的分支都先通过 fail_request 将日志记录为失败，上游响应提前结束时同样如此，
+// 因此未调用 complete 且日志未记录为失败时只可能是客户端断开或通过接口取消，将日志标记为已取消
+struct ActiveRequest {
+    state: Arc<Mutex<AppState>>,
+    log_id: u64,
+    cancel_key: String,
+    completed: AtomicBool,
+    // 随守卫一起释放占用的并发数，n > 1 时各回复共用同一个许可
+    _permit: Option<Arc<Permit>>,
+}
+
+impl ActiveRequest {
+    fn complete(&self) {
+        self.completed.store(true, Ordering::Relaxed);
+    }
+
+    fn is_completed(&self) -> bool {
+        self.completed.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for ActiveRequest {
+    fn drop(&mut self) {
+        let state = self.state.clone();
+        let log_id = self.log_id;
+        let cancel_key = std::mem::take(&mut self.cancel_key);
+        let completed = self.completed.load(Ordering::Relaxed);
+        tokio::spawn(async move {
+            let mut state = state.lock().await;
+            state.active_requests = state.active_requests.saturating_sub(1);
+            state.cancellations.remove(&cancel_key);
+            if completed {
+                return;
+            }
+            if let Some(log) = state
+                .request_logs
+                .iter_mut()
+                .rev()
+                .find(|log| log.id == log_id)
+                .filter(|log| !matches!(log.status, LogStatus::Failed))
+            {
+                tracing::info!("请求在响应完成前中止");
+                log.status = LogStatus::Cancelled;
+                log_sink::submit(log);
+            }
+        });
+    }
+}
+
+// 请求被取消时的响应，日志由 ActiveRequest 标记为已取消
+fn request_cancelled() -> (StatusCode, Json<ErrorResponse>) {
+    tracing::info!("请求已被取消");
+    (
+        // 499 Client Closed Request
+        StatusCode::from_u16(499).unwrap(),
+        Json(ChatError::RequestCancelled.to_json()),
+    )
+}
+
+// 上游超时的响应，包括连接、读取超时与 SERVICE_TIMEOUT
+fn upstream_timeout() -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::GATEWAY_TIMEOUT,
+        Json(ChatError::UpstreamTimeout.to_json()),
+    )
+}
+
+// 读取上游字节流失败时的响应，读取超时单独返回 504
+fn chunk_error(e: reqwest::Error) -> (StatusCode, Json<ErrorResponse>) {
+    if e.is_timeout() {
+        return upstream_timeout();
+    }
+    let error_message = Text::ChunkReadFailed.format(e);
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(ChatError::RequestFailed(error_message).to_json()),
+    )
+}
+
+// 记录到日志中的错误信息
+fn error_text((_, Json(error)): &(StatusCode, Json<ErrorResponse>)) -> String {
+    error.message.clone().unwrap_or_default()
+}
+
+// 将请求日志标记为失败并计入错误数
+async fn fail_request(state: &Mutex<AppState>, log_id: u64, error: String) {
+    fail_request_after(state, log_id, error, None).await;
+}
+
+// 同 fail_request，并记录失败前的总用时(秒)
+async fn fail_request_after(
+    state: &Mutex<AppState>,
+    log_id: u64,
+    error: String,
+    total: Option<f64>,
+) {
+    let mut state = state.lock().await;
+    if let Some(log) = state
+        .request_logs
+        .iter_mut()
+        .rev()
+        .find(|log| log.id == log_id)
+    {
+        log.status = LogStatus::Failed;
+        log.error = Some(error);
+        if let Some(total) = total {
+            log.timing.total = total;
+        }
+        log_sink::submit(log);
+    }
+    state.error_requests += 1;
+}
+
+// 号池中的 token 被上游限流时进入冷却，直接传入的 token 不处理
+fn note_rate_limit(uses_pool: bool, auth_token: &str, kind: ErrorKind) {
+    if uses_pool && kind == ErrorKind::RateLimited {
+        backoff::rate_limited(auth_token);
+    }
+}
+
+// 重新生成 checksum，号池中的 token 同时更新并写入 token 文件，直接传入的 token 仅在本次请求中使用
+async fn refresh_checksum(state: &Mutex<AppState>, auth_token: &str) -> String {
+    let tokens = [auth_token.to_string()];
+    if rotation::rotate(state, &tokens, ROTATION_REJECTED)
+        .await
+        .is_empty()
+    {
+        return generate_checksum_with_default();
+    }
+
+    state
+        .lock()
+        .await
+        .token_infos
+        .iter()
+        .find(|info| info.token == auth_token)
+        .map(|info| info.checksum.clone())
+        .unwrap_or_else(generate_checksum_with_default)
+}
+
+// 请求占用的并发许可，未启用并发限制时为 None
+type DispatchSlot = OnceCell<Option<Arc<Permit>>>;
+
+// 单个回复在整个响应中的位置，n > 1 时各回复共用 response_id、dispatch 与 failure
+struct ResponseSlot {
+    response_id: String,
+    index: u32,
+    dispatch: Arc<DispatchSlot>,
+    failure: ResponseFailure,
+}
+
+async fn process_chat(
+    state: Arc<Mutex<AppState>>,
+    headers: HeaderMap,
+    client_ip: IpAddr,
+    mut request: ChatRequest,
+    slot: ResponseSlot,
+) -> Result<Response<Body>, (StatusCode, Json<ErrorResponse>)> {
+    let ResponseSlot {
+        response_id,
+        index,
+        dispatch,
+        failure,
+    } = slot;
     let allow_claude = AppConfig::get_allow_claude();
+    let multiple = request.n.is_some_and(|n| n > 1);
+    // 需要校验 JSON 输出时等待完整回复
+    let json_format = request
+        .response_format
+        .take()
+        .filter(|format| format.is_json() && json_mode::validation_enabled());
+    let include_usage = request.include_usage();
+    let metadata = request.take_metadata();
 
     let is_search = request.model.ends_with("-online");
     let model_name = if is_search {
@@ -75,6 +918,7 @@ pub async fn handle_chat(
     // 验证模型是否支持并获取模型信息
     let model = AVAILABLE_MODELS.iter().find(|m| m.id == model_name);
     let model_supported = model.is_some();
+    let is_o1 = model.is_some_and(Model::is_o1);
 
     if !(model_supported || allow_claude && request.model.starts_with("claude")) {
         return Err((
@@ -105,29 +949,208 @@ pub async fn handle_chat(
 
     let mut current_config = KeyConfig::new_with_global();
 
-    // 验证认证token并获取token信息
-    let (auth_token, checksum) = match auth_header {
-        // 管理员Token验证逻辑
-        token
-            if token == AUTH_TOKEN.as_str()
-                || (AppConfig::is_share() && token == AppConfig::get_share_token().as_str()) =>
-        {
-            static CURRENT_KEY_INDEX: AtomicUsize = AtomicUsize::new(0);
-            let state_guard = state.lock().await;
-            let token_infos = &state_guard.token_infos;
+    // 有效的 API key 与管理员 token 一样使用号池
+    let api_key = ApiKeys::authenticate(auth_header);
+    if api_key
+        .as_ref()
+        .is_some_and(|api_key| !api_key.has_scope(API_KEY_SCOPE_CHAT))
+    {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(ChatError::Unauthorized.to_json()),
+        ));
+    }
 
-            // 检查是否存在可用的token
-            if token_infos.is_empty() {
-                return Err((
-                    StatusCode::SERVICE_UNAVAILABLE,
-                    Json(ChatError::NoTokens.to_json()),
-                ));
-            }
+    // 绑定租户的 API key 只能通过该租户的路由使用，租户路由只接受该租户的 API key 与管理员 token
+    let tenant = request.tenant.take();
+    let tenant_allowed = match api_key {
+        Some(ref api_key) => api_key.tenant == tenant,
+        None => tenant.is_none() || auth_header == AUTH_TOKEN.as_str(),
+    };
+    if !tenant_allowed {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(ChatError::Unauthorized.to_json()),
+        ));
+    }
+    let tenant = tenant.as_deref();
 
-            // 轮询选择token
-            let index = CURRENT_KEY_INDEX.fetch_add(1, Ordering::SeqCst) % token_infos.len();
-            let token_info = &token_infos[index];
-            (token_info.token.clone(), token_info.checksum.clone())
+    // 来源地址不在密钥允许范围内时拒绝，并记录一条失败日志用于审计
+    if let Some(api_key) = api_key
+        .as_ref()
+        .filter(|api_key| !api_key.allows_ip(client_ip))
+    {
+        tracing::warn!("API key {} 拒绝来自 {} 的请求", api_key.id, client_ip);
+        let mut state = state.lock().await;
+        let next_id = state.next_log_id();
+        let log = RequestLog {
+            stream: request.stream,
+            error: Some(format!("IP not allowed: {}", client_ip)),
+            api_key: Some(api_key.id.clone()),
+            metadata: metadata.clone(),
+            ..RequestLog::rejected(
+                next_id,
+                request_time,
+                request.model.clone(),
+                request_id::get(&headers),
+            )
+        };
+        log_sink::submit(&log);
+        state.push_log(log);
+        state.total_requests += 1;
+        state.error_requests += 1;
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(ChatError::IpNotAllowed(client_ip.to_string()).to_json()),
+        ));
+    }
+
+    // 使用号池的请求不受用户模型策略限制
+    let uses_pool = auth_header == AUTH_TOKEN.as_str()
+        || (AppConfig::is_share() && auth_header == AppConfig::get_share_token().as_str())
+        || api_key.is_some();
+
+    // 使用号池时可通过请求头指定 token 别名，代替轮询选择
+    let pinned_alias = headers
+        .get(HEADER_NAME_TOKEN_ALIAS)
+        .and_then(|h| h.to_str().ok())
+        .map(str::trim)
+        .filter(|alias| uses_pool && !alias.is_empty());
+
+    // 使用号池时可通过请求参数、模型名的 @标签 后缀或请求头指定标签，只在带有该标签的 token 中轮询
+    let token_tag = request
+        .token_tag
+        .take()
+        .or_else(|| {
+            headers
+                .get(HEADER_NAME_TOKEN_TAG)
+                .and_then(|h| h.to_str().ok())
+                .map(str::to_string)
+        })
+        .map(|tag| tag.trim().to_string())
+        .filter(|tag| uses_pool && !tag.is_empty());
+
+    // 启用公共号池时可通过请求头只使用公共 token，配额按 API key 的所有者或客户端 IP 计算
+    // 租户请求不使用公共号池
+    let public_pool_user = (uses_pool
+        && tenant.is_none()
+        && public_pool::is_enabled()
+        && headers
+            .get(HEADER_NAME_PUBLIC_POOL)
+            .is_some_and(|v| v.as_bytes() == TRUE.as_bytes()))
+    .then(|| match api_key {
+        Some(ref api_key) => api_key.owner.clone().unwrap_or_else(|| api_key.id.clone()),
+        None => client_ip.to_string(),
+    });
+
+    // 达到并发上限时按优先级排队，管理员优先，公共号池最后
+    let tier = if auth_header == AUTH_TOKEN.as_str() {
+        Tier::High
+    } else if public_pool_user.is_some() {
+        Tier::Low
+    } else {
+        Tier::Normal
+    };
+    // n > 1 时整个请求只占用一个许可，由最先到达的回复获取，避免各回复互相等待
+    let permit = dispatch
+        .get_or_try_init(|| async {
+            priority::acquire(&state, tier)
+                .await
+                .map(|permit| permit.map(Arc::new))
+                .ok_or((
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    Json(ChatError::ServerBusy.to_json()),
+                ))
+        })
+        .await?
+        .clone();
+
+    // 验证认证token并获取token信息
+    let (auth_token, checksum) = match auth_header {
+        // 管理员Token验证逻辑
+        _ if uses_pool => {
+            // 检查是否存在可用的token
+            if state.lock().await.token_infos.is_empty() {
+                return Err((
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    Json(ChatError::NoTokens.to_json()),
+                ));
+            }
+
+            if let Some(ref user) = public_pool_user {
+                if !public_pool::try_consume(user) {
+                    return Err((
+                        StatusCode::TOO_MANY_REQUESTS,
+                        Json(ChatError::PublicPoolQuotaExceeded.to_json()),
+                    ));
+                }
+                let selected = match public_pool::select(&state).await {
+                    Some(selected) => Some(selected),
+                    None => queue::wait_for(|| public_pool::select(&state)).await,
+                };
+
+                selected.ok_or_else(|| {
+                    public_pool::refund(user);
+                    (
+                        StatusCode::SERVICE_UNAVAILABLE,
+                        Json(ChatError::NoTokens.to_json()),
+                    )
+                })?
+            } else if let Some(alias) = pinned_alias {
+                let (token, checksum, eligible) = state
+                    .lock()
+                    .await
+                    .token_infos
+                    .iter()
+                    .find(|info| {
+                        info.alias.as_deref() == Some(alias) && Tenants::allows(tenant, &info.tags)
+                    })
+                    .filter(|info| !TokenBlacklist::is_blocked(&info.token))
+                    .map(|info| {
+                        // 与轮询相同，退避中或配额耗尽（未开启降级时）的 token 暂不可用
+                        let eligible = is_available(info, tenant)
+                            && (!QuotaSnapshots::is_exhausted(&info.token)
+                                || quota::slow_fallback());
+                        (info.token.clone(), info.checksum.clone(), eligible)
+                    })
+                    .ok_or((
+                        StatusCode::BAD_REQUEST,
+                        Json(ChatError::TokenAliasNotFound(alias.to_string()).to_json()),
+                    ))?;
+                if !eligible || !lease::try_acquire(&token).await {
+                    return Err((
+                        StatusCode::SERVICE_UNAVAILABLE,
+                        Json(ChatError::NoTokens.to_json()),
+                    ));
+                }
+                (token, checksum)
+            } else {
+                let tag = token_tag.as_deref();
+                if let Some(tag) = tag {
+                    let tagged = state.lock().await.token_infos.iter().any(|info| {
+                        info.tags.iter().any(|t| t == tag)
+                            && Tenants::allows(tenant, &info.tags)
+                            && !TokenBlacklist::is_blocked(&info.token)
+                    });
+                    if !tagged {
+                        return Err((
+                            StatusCode::BAD_REQUEST,
+                            Json(ChatError::TokenTagNotFound(tag.to_string()).to_json()),
+                        ));
+                    }
+                }
+
+                // 全部不可用时按配置排队等待
+                let selected = match select_pool_token(&state, tenant, tag).await {
+                    Some(selected) => Some(selected),
+                    None => queue::wait_for(|| select_pool_token(&state, tenant, tag)).await,
+                };
+
+                selected.ok_or((
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    Json(ChatError::NoTokens.to_json()),
+                ))?
+            }
         }
 
         token if AppConfig::get_dynamic_key() && token.starts_with(&*KEY_PREFIX) => {
@@ -151,9 +1174,102 @@ pub async fn handle_chat(
         ))?,
     };
 
+    // 请求中指定的慢速池选项优先于全局与动态密钥配置
+    if request.slow_pool.is_some() {
+        current_config.enable_slow_pool = request.slow_pool;
+    }
+
+    // 号池中快速请求额度已用完的 token 只能使用慢速池
+    if uses_pool && quota::slow_fallback() && QuotaSnapshots::is_exhausted(&auth_token) {
+        current_config.enable_slow_pool = Some(true);
+    }
+
     let current_config = current_config;
 
+    // 黑名单中的 token 不允许使用
+    if TokenBlacklist::is_blocked(&auth_token) {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(ChatError::Unauthorized.to_json()),
+        ));
+    }
+
+    if let Some(user_id) = extract_user_id(&auth_token) {
+        tracing::Span::current().record("user", user_id.as_str());
+
+        // 检查用户模型策略
+        if !uses_pool
+            && ModelPolicies::get(&user_id).is_some_and(|policy| !policy.permits(&model_name))
+        {
+            return Err((
+                StatusCode::FORBIDDEN,
+                Json(ChatError::ModelNotAllowed(request.model).to_json()),
+            ));
+        }
+    }
+
+    // 命中拦截规则或被外部审核接口标记时拒绝请求，需在计算缓存键之前完成替换
+    if let Err(reason) = moderation::check_prompt(&mut request.messages).await {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ChatError::ContentBlocked(reason).to_json()),
+        ));
+    }
+
+    // 启用会话历史时插入保存的历史消息，响应完整结束后写入本轮的消息与回复
+    let turn = match request.conversation_id.as_deref().map(str::trim) {
+        Some(id) if conversation::history_enabled() && !id.is_empty() => Some(
+            conversation::begin_turn(auth_header, id, &mut request.messages),
+        ),
+        _ => None,
+    };
+
+    // 注入按模型与用户配置的系统提示词，用户为 API key 的所有者或自有 token 的用户 ID
+    let prompt_user = match api_key {
+        Some(ref api_key) => Some(api_key.owner.clone().unwrap_or_else(|| api_key.id.clone())),
+        None if !uses_pool => extract_user_id(&auth_token),
+        None => None,
+    };
+    SystemPrompts::apply(&model_name, prompt_user.as_deref(), &mut request.messages);
+    let prompt_tokens = estimate_prompt_tokens(&request.messages);
+
+    // 查询响应缓存，联网搜索的结果具有时效性，不做缓存；n > 1 时各回复应当不同，同样不使用缓存
+    let cache_key = if cache::is_enabled() && !is_search && !multiple {
+        cache::cache_key(&request.model, &request.messages)
+    } else {
+        None
+    };
+    if let Some(text) = cache_key.as_ref().and_then(cache::get) {
+        tracing::debug!("命中响应缓存");
+        if let Some(ref turn) = turn {
+            turn.finish(&text);
+        }
+        return Ok(complete_response(
+            response_id,
+            index,
+            request.model,
+            request.stream,
+            include_usage,
+            is_o1,
+            prompt_tokens,
+            text,
+            FINISH_REASON_STOP,
+            metadata,
+        ));
+    }
+
+    // 缓存命中的回复不来自上游，不参与质量抽样
+    let sample_quality = quality::should_sample();
+    let archive_payload = payload::is_enabled();
+
     let current_id: u64;
+    // 通过取消接口中止请求，上游的请求或字节流随之结束
+    let cancel = CancellationToken::new();
+    let cancel_key = if multiple {
+        format!("{}:{}", response_id, index)
+    } else {
+        response_id.clone()
+    };
 
     // 更新请求日志
     {
@@ -200,8 +1316,9 @@ pub async fn handle_chat(
             ));
         }
 
-        let next_id = state.request_logs.last().map_or(1, |log| log.id + 1);
+        let next_id = state.next_log_id();
         current_id = next_id;
+        tracing::Span::current().record("log_id", next_id);
 
         // 如果需要获取用户使用情况,创建后台任务获取profile
         if model
@@ -245,30 +1362,72 @@ pub async fn handle_chat(
             });
         }
 
-        state.request_logs.push(RequestLog {
+        let log = RequestLog {
             id: next_id,
             timestamp: request_time,
             model: request.model.clone(),
             token_info: TokenInfo {
                 token: auth_token.clone(),
                 checksum: checksum.clone(),
+                alias: pinned_alias.map(str::to_string),
                 profile: None,
+                warmup: None,
+                is_public: public_pool_user.is_some(),
+                note: None,
+                contact: None,
+                tags: token_tag.into_iter().collect(),
             },
             prompt: None,
             timing: TimingInfo {
                 total: 0.0,
                 first: None,
+                upstream: None,
             },
             stream: request.stream,
             status: LogStatus::Pending,
             error: None,
-        });
+            cost: None,
+            api_key: api_key.map(|api_key| api_key.id),
+            reconciliation: None,
+            slow_pool: current_config.enable_slow_pool(),
+            metadata: metadata.clone(),
+            request_id: request_id::get(&headers),
+        };
+        log_stream::publish(&log);
+        state.push_log(log);
+        state.cancellations.insert(
+            cancel_key.clone(),
+            Cancellation {
+                owner: auth_header.to_string(),
+                token: cancel.clone(),
+            },
+        );
+    }
 
-        if state.request_logs.len() > *REQUEST_LOGS_LIMIT {
-            state.request_logs.remove(0);
-        }
+    // 此后由守卫负责释放活动请求计数，流式响应中守卫随响应体一起释放
+    let active = ActiveRequest {
+        state: state.clone(),
+        log_id: current_id,
+        cancel_key,
+        completed: AtomicBool::new(false),
+        _permit: permit,
+    };
+
+    // 用于费用估算，需在消息被消耗之前计算
+    if archive_payload {
+        payload::record_request(current_id, &request.messages);
     }
 
+    let conversation_id = request
+        .conversation_id
+        .as_deref()
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(|id| conversation::upstream_id(auth_header, id));
+
+    // 模拟上游时回复内容由请求决定，需在消息被编码前生成
+    let mock_reply = mock::is_enabled().then(|| mock::reply(&model_name, &request.messages));
+
     // 将消息转换为hex格式
     let hex_data = match super::adapter::encode_chat_message(
         request.messages,
@@ -276,23 +1435,21 @@ pub async fn handle_chat(
         current_config.disable_vision(),
         current_config.enable_slow_pool(),
         is_search,
+        conversation_id,
     )
     .await
     {
         Ok(data) => data,
         Err(e) => {
-            let mut state = state.lock().await;
-            if let Some(log) = state
-                .request_logs
-                .iter_mut()
-                .rev()
-                .find(|log| log.id == current_id)
-            {
-                log.status = LogStatus::Failed;
-                log.error = Some(e.to_string());
+            tracing::error!("编码聊天消息失败: {}", e);
+            fail_request(&state, current_id, e.to_string()).await;
+            // 图片问题属于请求错误，其余为内部错误
+            if let Some(e) = e.downcast_ref::<ImageError>() {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    Json(ChatError::InvalidImage(e.to_string()).to_json()),
+                ));
             }
-            state.active_requests -= 1;
-            state.error_requests += 1;
             return Err((
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(
@@ -302,19 +1459,52 @@ pub async fn handle_chat(
         }
     };
 
-    // 构建请求客户端
-    let client = build_client(&auth_token, &checksum, is_search);
-    // 添加超时设置
-    let response = tokio::time::timeout(
-        std::time::Duration::from_secs(*SERVICE_TIMEOUT),
-        client.body(hex_data).send(),
-    )
-    .await;
+    // o1 系列模型与需要校验 JSON 的请求等待完整结果后再以 SSE 的形式一次性返回
+    let stream = request.stream && !is_o1 && json_format.is_none();
+
+    // 对账需要请求前的用量，必须在发出请求前取得
+    let usage_before = if stream && mock_reply.is_none() {
+        reconcile::snapshot(&auth_token).await.map(Arc::new)
+    } else {
+        None
+    };
+
+    let convert_web_ref = current_config.include_web_references();
+
+    // 开发用的故障注入，决定是否以模拟的故障代替上游响应
+    let fault = fault::pick(&headers);
+    if let Some(fault) = fault {
+        tracing::info!("注入故障: {}", fault.as_str_name());
+    }
+
+    // 上游因 checksum 失效拒绝请求时重新生成并重试一次
+    let mut checksum = checksum;
+    let mut checksum_refreshed = false;
+    let (upstream, decoder, start_time) = 'upstream: loop {
+        // 构建请求客户端
+        let client = build_client(&auth_token, &checksum, is_search);
+        // 添加超时设置，注入的故障与模拟上游不会发送请求，超时故障一直等待到超时
+        let timeout = std::time::Duration::from_secs(*SERVICE_TIMEOUT);
+        let send = async {
+            match (fault.map(fault::response), &mock_reply) {
+                (Some(Some(response)), _) => Ok(Ok(response)),
+                (Some(None), _) => tokio::time::timeout(timeout, std::future::pending()).await,
+                (None, Some(reply)) => Ok(Ok(mock::response(reply))),
+                (None, None) => {
+                    tokio::time::timeout(timeout, send_with_retry(client.body(hex_data.clone())))
+                        .await
+                }
+            }
+        };
+        let send_time = std::time::Instant::now();
+        let response = tokio::select! {
+            response = send => response,
+            () = cancel.cancelled() => return Err(request_cancelled()),
+        };
 
-    // 处理请求结果
-    let response = match response {
-        Ok(inner_response) => match inner_response {
-            Ok(resp) => {
+        // 处理请求结果
+        let response = match response {
+            Ok(Ok(resp)) => {
                 // 更新请求日志为成功
                 {
                     let mut state = state.lock().await;
@@ -325,442 +1515,1203 @@ pub async fn handle_chat(
                         .find(|log| log.id == current_id)
                     {
                         log.status = LogStatus::Success;
+                        log.timing.upstream =
+                            Some(format_time_ms(send_time.elapsed().as_secs_f64()));
                     }
                 }
                 resp
             }
-            Err(e) => {
-                // 更新请求日志为失败
-                {
-                    let mut state = state.lock().await;
-                    if let Some(log) = state
-                        .request_logs
-                        .iter_mut()
-                        .rev()
-                        .find(|log| log.id == current_id)
-                    {
-                        log.status = LogStatus::Failed;
-                        log.error = Some(e.to_string());
-                    }
-                    state.active_requests -= 1;
-                    state.error_requests += 1;
-                }
+            Ok(Err(e)) if !e.is_timeout() => {
+                tracing::warn!("上游请求失败: {}", e);
+                fail_request(&state, current_id, e.to_string()).await;
                 return Err((
                     StatusCode::INTERNAL_SERVER_ERROR,
                     Json(ChatError::RequestFailed(e.to_string()).to_json()),
                 ));
             }
-        },
-        Err(_) => {
-            // 处理超时错误
-            {
-                let mut state = state.lock().await;
-                if let Some(log) = state
-                    .request_logs
-                    .iter_mut()
-                    .rev()
-                    .find(|log| log.id == current_id)
-                {
-                    log.status = LogStatus::Failed;
-                    log.error = Some("Request timeout".to_string());
+            // 连接超时与整体超时同样处理
+            Ok(Err(_)) | Err(_) => {
+                tracing::warn!("上游请求超时");
+                let error = upstream_timeout();
+                fail_request(&state, current_id, error_text(&error)).await;
+                return Err(error);
+            }
+        };
+
+        // 首先处理stream直到获得第一个结果
+        let start_time = std::time::Instant::now();
+        let mut decoder = StreamDecoder::new();
+        let mut stream = response
+            .bytes_stream()
+            .take_until(cancel.clone().cancelled_owned())
+            .boxed();
+        while !decoder.is_first_result_ready() {
+            match stream.next().await {
+                Some(Ok(chunk)) => {
+                    if let Err(StreamError::ChatError(error)) =
+                        decoder.decode(&chunk, convert_web_ref)
+                    {
+                        if !checksum_refreshed && error.is_checksum_error() {
+                            tracing::info!("上游拒绝了 checksum，重新生成后重试");
+                            checksum_refreshed = true;
+                            checksum = refresh_checksum(&state, &auth_token).await;
+                            continue 'upstream;
+                        }
+                        let error_response = error.to_error_response();
+                        tracing::warn!("上游返回错误: {}", error_response.native_code());
+                        note_rate_limit(uses_pool, &auth_token, error_response.kind);
+                        fail_request_after(
+                            &state,
+                            current_id,
+                            error_response.to_log(),
+                            Some(format_time_ms(start_time.elapsed().as_secs_f64())),
+                        )
+                        .await;
+                        return Err((
+                            error_response.status_code(),
+                            Json(error_response.to_common()),
+                        ));
+                    }
+                }
+                Some(Err(e)) => {
+                    let error = chunk_error(e);
+                    fail_request(&state, current_id, error_text(&error)).await;
+                    return Err(error);
+                }
+                None if cancel.is_cancelled() => return Err(request_cancelled()),
+                None => {
+                    fail_request(&state, current_id, "Empty stream response".to_string()).await;
+                    return Err((
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(
+                            ChatError::RequestFailed(Text::StreamEmpty.get().to_string()).to_json(),
+                        ),
+                    ));
                 }
-                state.active_requests -= 1;
-                state.error_requests += 1;
             }
-            return Err((
-                StatusCode::GATEWAY_TIMEOUT,
-                Json(ChatError::RequestFailed("Request timeout".to_string()).to_json()),
-            ));
         }
+
+        if uses_pool {
+            backoff::succeeded(&auth_token);
+        }
+        break (stream, decoder, start_time);
     };
 
-    // 释放活动请求计数
-    {
-        let mut state = state.lock().await;
-        state.active_requests -= 1;
+    let reply = UpstreamReply {
+        state,
+        current_id,
+        response_id,
+        index,
+        model: request.model,
+        auth_token,
+        uses_pool,
+        upstream,
+        decoder,
+        start_time,
+        convert_web_ref,
+        cancel,
+        active,
+        cache_key,
+        turn,
+        metadata,
+        sample_quality,
+        archive_payload,
+        include_usage,
+        prompt_tokens,
+    };
+    if stream {
+        Ok(stream_upstream(
+            reply,
+            usage_before,
+            request_id::get(&headers).unwrap_or_default(),
+            failure,
+        ))
+    } else {
+        collect_upstream(reply, request.stream, is_o1, json_format).await
     }
+}
 
-    let convert_web_ref = current_config.include_web_references();
+// 收到上游的第一个结果后，流式与非流式响应共用的请求信息
+struct UpstreamReply {
+    state: Arc<Mutex<AppState>>,
+    current_id: u64,
+    response_id: String,
+    index: u32,
+    model: String,
+    auth_token: String,
+    uses_pool: bool,
+    upstream: BoxStream<'static, reqwest::Result<Bytes>>,
+    decoder: StreamDecoder,
+    start_time: std::time::Instant,
+    convert_web_ref: bool,
+    cancel: CancellationToken,
+    active: ActiveRequest,
+    cache_key: Option<cache::CacheKey>,
+    turn: Option<conversation::Turn>,
+    metadata: Option<HashMap<String, String>>,
+    sample_quality: bool,
+    archive_payload: bool,
+    include_usage: bool,
+    prompt_tokens: u32,
+}
 
-    if request.stream {
-        let response_id = format!("chatcmpl-{}", Uuid::new_v4().simple());
-        let is_start = Arc::new(AtomicBool::new(true));
-        let start_time = std::time::Instant::now();
-        let first_chunk_time = Arc::new(Mutex::new(None::<f64>));
-        let decoder = Arc::new(Mutex::new(StreamDecoder::new()));
-
-        // 定义消息处理器的上下文结构体
-        struct MessageProcessContext<'a> {
-            response_id: &'a str,
-            model: &'a str,
-            is_start: &'a AtomicBool,
-            first_chunk_time: &'a Mutex<Option<f64>>,
-            start_time: std::time::Instant,
-            state: &'a Mutex<AppState>,
-            current_id: u64,
-        }
+// 以 SSE 逐个转发上游的片段，状态码发送后出现的错误以错误片段结束响应并标记 failure
+fn stream_upstream(
+    reply: UpstreamReply,
+    usage_before: Option<Arc<UsageProfile>>,
+    request_id: String,
+    failure: ResponseFailure,
+) -> Response<Body> {
+    let UpstreamReply {
+        state,
+        current_id,
+        response_id,
+        index,
+        model,
+        auth_token,
+        uses_pool,
+        upstream,
+        decoder,
+        start_time,
+        convert_web_ref,
+        cancel,
+        active,
+        cache_key,
+        turn,
+        metadata,
+        sample_quality,
+        archive_payload,
+        include_usage,
+        prompt_tokens,
+    } = reply;
 
-        // 处理消息并生成响应数据的辅助函数
-        async fn process_messages(
-            messages: Vec<StreamMessage>,
-            ctx: &MessageProcessContext<'_>,
-        ) -> String {
-            let mut response_data = String::new();
-
-            for message in messages {
-                match message {
-                    StreamMessage::Content(text) => {
-                        let is_first = ctx.is_start.load(Ordering::SeqCst);
-                        if is_first {
-                            if let Ok(mut first_time) = ctx.first_chunk_time.try_lock() {
-                                *first_time = Some(ctx.start_time.elapsed().as_secs_f64());
-                            }
-                        }
+    let is_start = Arc::new(AtomicBool::new(true));
+    let first_chunk_time = Arc::new(Mutex::new(None::<f64>));
+    let decoder = Arc::new(Mutex::new(decoder));
+    let full_text = Arc::new(parking_lot::Mutex::new(String::new()));
+    let completion_tokens = Arc::new(AtomicU32::new(0));
+    let blocked = Arc::new(AtomicBool::new(false));
 
-                        let response = ChatResponse {
-                            id: ctx.response_id.to_string(),
-                            object: OBJECT_CHAT_COMPLETION_CHUNK.to_string(),
-                            created: chrono::Utc::now().timestamp(),
-                            model: if is_first {
-                                Some(ctx.model.to_string())
-                            } else {
-                                None
-                            },
-                            choices: vec![Choice {
-                                index: 0,
-                                message: None,
-                                delta: Some(Delta {
-                                    role: if is_first {
-                                        Some(Role::Assistant)
-                                    } else {
-                                        None
-                                    },
-                                    content: if is_first {
-                                        ctx.is_start.store(false, Ordering::SeqCst);
-                                        Some(text.trim_leading_newlines())
-                                    } else {
-                                        Some(text)
-                                    },
-                                }),
-                                finish_reason: None,
-                            }],
-                            usage: None,
-                        };
+    // 定义消息处理器的上下文结构体
+    struct MessageProcessContext<'a> {
+        response_id: &'a str,
+        index: i32,
+        model: &'a str,
+        is_start: &'a AtomicBool,
+        first_chunk_time: &'a Mutex<Option<f64>>,
+        start_time: std::time::Instant,
+        state: &'a Arc<Mutex<AppState>>,
+        current_id: u64,
+        auth_token: &'a str,
+        usage_before: Option<&'a Arc<UsageProfile>>,
+        cache_key: Option<&'a cache::CacheKey>,
+        turn: Option<&'a conversation::Turn>,
+        sample_quality: bool,
+        archive_payload: bool,
+        active: &'a ActiveRequest,
+        metadata: Option<&'a HashMap<String, String>>,
+        full_text: &'a parking_lot::Mutex<String>,
+        include_usage: bool,
+        prompt_tokens: u32,
+        completion_tokens: &'a AtomicU32,
+        // 输出命中拦截规则后不再发送内容
+        blocked: &'a AtomicBool,
+    }
 
-                        response_data.push_str(&format!(
-                            "data: {}\n\n",
-                            serde_json::to_string(&response).unwrap()
-                        ));
+    // 处理消息并生成响应数据的辅助函数
+    async fn process_messages(
+        messages: Vec<StreamMessage>,
+        ctx: &MessageProcessContext<'_>,
+    ) -> String {
+        let mut response_data = String::new();
+
+        for message in messages {
+            match message {
+                StreamMessage::Content(mut text) => {
+                    if ctx.blocked.load(Ordering::Relaxed) {
+                        continue;
                     }
-                    StreamMessage::StreamEnd => {
-                        // 计算总时间和首次片段时间
-                        let total_time = ctx.start_time.elapsed().as_secs_f64();
-                        let first_time = ctx.first_chunk_time.lock().await.unwrap_or(total_time);
+                    if moderation::check_output(&mut text).is_err() {
+                        ctx.blocked.store(true, Ordering::Relaxed);
+                        continue;
+                    }
+                    let is_first = ctx.is_start.load(Ordering::SeqCst);
+                    ctx.completion_tokens
+                        .fetch_add(estimate_tokens(&text), Ordering::Relaxed);
+                    if ctx.cache_key.is_some()
+                        || ctx.turn.is_some()
+                        || ctx.sample_quality
+                        || ctx.archive_payload
+                    {
+                        ctx.full_text.lock().push_str(&text);
+                    }
+                    if is_first {
+                        if let Ok(mut first_time) = ctx.first_chunk_time.try_lock() {
+                            *first_time = Some(ctx.start_time.elapsed().as_secs_f64());
+                        }
+                    }
+
+                    let response = ChatResponse {
+                        id: ctx.response_id.to_string(),
+                        object: OBJECT_CHAT_COMPLETION_CHUNK.to_string(),
+                        created: chrono::Utc::now().timestamp(),
+                        model: if is_first {
+                            Some(ctx.model.to_string())
+                        } else {
+                            None
+                        },
+                        choices: vec![Choice {
+                            index: ctx.index,
+                            message: None,
+                            delta: Some(Delta {
+                                role: if is_first {
+                                    Some(Role::Assistant)
+                                } else {
+                                    None
+                                },
+                                content: if is_first {
+                                    ctx.is_start.store(false, Ordering::SeqCst);
+                                    Some(text.trim_leading_newlines())
+                                } else {
+                                    Some(text)
+                                },
+                            }),
+                            logprobs: None,
+                            finish_reason: None,
+                        }],
+                        usage: None,
+                        metadata: None,
+                    };
 
+                    response_data.push_str(&format!(
+                        "data: {}\n\n",
+                        serde_json::to_string(&response).unwrap()
+                    ));
+                }
+                StreamMessage::StreamEnd => {
+                    // 计算总时间和首次片段时间
+                    let total_time = ctx.start_time.elapsed().as_secs_f64();
+                    let first_time = ctx.first_chunk_time.lock().await.unwrap_or(total_time);
+
+                    {
+                        let mut state = ctx.state.lock().await;
+                        if let Some(log) = state
+                            .request_logs
+                            .iter_mut()
+                            .rev()
+                            .find(|log| log.id == ctx.current_id)
                         {
-                            let mut state = ctx.state.lock().await;
-                            if let Some(log) = state
-                                .request_logs
-                                .iter_mut()
-                                .rev()
-                                .find(|log| log.id == ctx.current_id)
-                            {
-                                log.timing.total = format_time_ms(total_time);
-                                log.timing.first = Some(format_time_ms(first_time));
-                            }
+                            log.timing.total = format_time_ms(total_time);
+                            log.timing.first = Some(format_time_ms(first_time));
+                            record_cost(
+                                log,
+                                ctx.prompt_tokens,
+                                ctx.completion_tokens.load(Ordering::Relaxed),
+                            );
+                            log_sink::submit(log);
                         }
+                    }
+                    ctx.active.complete();
 
-                        let response = ChatResponse {
-                            id: ctx.response_id.to_string(),
-                            object: OBJECT_CHAT_COMPLETION_CHUNK.to_string(),
-                            created: chrono::Utc::now().timestamp(),
-                            model: None,
-                            choices: vec![Choice {
-                                index: 0,
-                                message: None,
-                                delta: Some(Delta {
-                                    role: None,
-                                    content: None,
-                                }),
-                                finish_reason: Some(FINISH_REASON_STOP.to_string()),
-                            }],
-                            usage: None,
-                        };
+                    if let Some(before) = ctx.usage_before {
+                        reconcile::spawn(
+                            ctx.state.clone(),
+                            ctx.current_id,
+                            ctx.auth_token.to_string(),
+                            (**before).clone(),
+                            ctx.prompt_tokens + ctx.completion_tokens.load(Ordering::Relaxed),
+                        );
+                    }
+
+                    let blocked = ctx.blocked.load(Ordering::Relaxed);
+
+                    // 完整结束的响应才写入缓存与会话历史
+                    let text = std::mem::take(&mut *ctx.full_text.lock());
+                    if ctx.archive_payload {
+                        payload::record_response(ctx.current_id, &text);
+                    }
+                    if !blocked && !text.is_empty() {
+                        if ctx.sample_quality {
+                            quality::record(
+                                ctx.model,
+                                &text,
+                                ctx.completion_tokens.load(Ordering::Relaxed),
+                            );
+                        }
+                        if let Some(turn) = ctx.turn {
+                            turn.finish(&text);
+                        }
+                        if let Some(key) = ctx.cache_key {
+                            cache::insert(*key, text);
+                        }
+                    }
+
+                    let response = ChatResponse {
+                        id: ctx.response_id.to_string(),
+                        object: OBJECT_CHAT_COMPLETION_CHUNK.to_string(),
+                        created: chrono::Utc::now().timestamp(),
+                        model: None,
+                        choices: vec![Choice {
+                            index: ctx.index,
+                            message: None,
+                            delta: Some(Delta {
+                                role: None,
+                                content: None,
+                            }),
+                            logprobs: None,
+                            finish_reason: Some(
+                                if blocked {
+                                    FINISH_REASON_CONTENT_FILTER
+                                } else {
+                                    FINISH_REASON_STOP
+                                }
+                                .to_string(),
+                            ),
+                        }],
+                        usage: None,
+                        metadata: ctx.metadata.cloned(),
+                    };
+                    response_data.push_str(&format!(
+                        "data: {}\n\n",
+                        serde_json::to_string(&response).unwrap()
+                    ));
+                    if ctx.include_usage {
                         response_data.push_str(&format!(
-                            "data: {}\n\ndata: [DONE]\n\n",
-                            serde_json::to_string(&response).unwrap()
+                            "data: {}\n\n",
+                            // o1 系列模型不会以流式返回
+                            serde_json::to_string(&usage_chunk(
+                                ctx.response_id.to_string(),
+                                usage(
+                                    ctx.prompt_tokens,
+                                    ctx.completion_tokens.load(Ordering::Relaxed),
+                                    false,
+                                ),
+                            ))
+                            .unwrap()
                         ));
                     }
-                    StreamMessage::Debug(debug_prompt) => {
-                        if let Ok(mut state) = ctx.state.try_lock() {
-                            if let Some(log) = state
-                                .request_logs
-                                .iter_mut()
-                                .rev()
-                                .find(|log| log.id == ctx.current_id)
-                            {
-                                log.prompt = Some(debug_prompt);
-                            }
+                    response_data.push_str("data: [DONE]\n\n");
+                }
+                StreamMessage::Debug(debug_prompt) => {
+                    if let Ok(mut state) = ctx.state.try_lock() {
+                        if let Some(log) = state
+                            .request_logs
+                            .iter_mut()
+                            .rev()
+                            .find(|log| log.id == ctx.current_id)
+                        {
+                            log.prompt = Some(debug_prompt);
                         }
                     }
-                    _ => {} // 忽略其他消息类型
                 }
+                _ => {} // 忽略其他消息类型
             }
-
-            response_data
         }
 
-        // 首先处理stream直到获得第一个结果
-        let mut stream = response.bytes_stream();
-        while !decoder.lock().await.is_first_result_ready() {
-            match stream.next().await {
-                Some(Ok(chunk)) => {
-                    if let Err(StreamError::ChatError(error)) =
-                        decoder.lock().await.decode(&chunk, convert_web_ref)
-                    {
-                        let error_response = error.to_error_response();
-                        // 更新请求日志为失败
-                        {
-                            let mut state = state.lock().await;
-                            if let Some(log) = state
-                                .request_logs
-                                .iter_mut()
-                                .rev()
-                                .find(|log| log.id == current_id)
-                            {
-                                log.status = LogStatus::Failed;
-                                log.error = Some(error_response.native_code());
-                                log.timing.total =
-                                    format_time_ms(start_time.elapsed().as_secs_f64());
-                                state.error_requests += 1;
+        response_data
+    }
+
+    // 处理后续的stream
+    let turn = turn.map(Arc::new);
+    let metadata = metadata.map(Arc::new);
+    let active = Arc::new(active);
+    let finished = active.clone();
+    let stream = upstream.then({
+        let decoder = decoder.clone();
+        let turn = turn.clone();
+        let metadata = metadata.clone();
+        let response_id = response_id.clone();
+        let model = model.clone();
+        let is_start = is_start.clone();
+        let first_chunk_time = first_chunk_time.clone();
+        let state = state.clone();
+        let full_text = full_text.clone();
+        let completion_tokens = completion_tokens.clone();
+        let blocked = blocked.clone();
+        let auth_token = auth_token.clone();
+        let usage_before = usage_before.clone();
+        let cancel = cancel.clone();
+        let failure = failure.clone();
+        let request_id = request_id.clone();
+        let span = tracing::Span::current();
+
+        move |chunk| {
+            let decoder = decoder.clone();
+            let turn = turn.clone();
+            let active = active.clone();
+            let metadata = metadata.clone();
+            let response_id = response_id.clone();
+            let model = model.clone();
+            let is_start = is_start.clone();
+            let first_chunk_time = first_chunk_time.clone();
+            let state = state.clone();
+            let full_text = full_text.clone();
+            let completion_tokens = completion_tokens.clone();
+            let blocked = blocked.clone();
+            let auth_token = auth_token.clone();
+            let usage_before = usage_before.clone();
+            let cancel = cancel.clone();
+            let failure = failure.clone();
+            let request_id = request_id.clone();
+
+            let fut = async move {
+                // 读取失败（如读取超时）后字节流随之结束，日志记录为失败
+                let chunk = match chunk {
+                    Ok(chunk) => chunk,
+                    Err(e) => {
+                        tracing::warn!("读取上游响应失败: {}", e);
+                        let error = chunk_error(e);
+                        fail_request(&state, current_id, error_text(&error)).await;
+                        failure.mark();
+                        return Ok(stream_error_chunk(
+                            &response_id,
+                            index,
+                            &error.1,
+                            &request_id,
+                        ));
+                    }
+                };
+
+                let ctx = MessageProcessContext {
+                    response_id: &response_id,
+                    index: index as i32,
+                    model: &model,
+                    is_start: &is_start,
+                    first_chunk_time: &first_chunk_time,
+                    start_time,
+                    state: &state,
+                    current_id,
+                    auth_token: &auth_token,
+                    usage_before: usage_before.as_ref(),
+                    cache_key: cache_key.as_ref(),
+                    turn: turn.as_deref(),
+                    sample_quality,
+                    archive_payload,
+                    active: &active,
+                    metadata: metadata.as_deref(),
+                    full_text: &full_text,
+                    include_usage,
+                    prompt_tokens,
+                    completion_tokens: &completion_tokens,
+                    blocked: &blocked,
+                };
+
+                // 使用decoder处理chunk
+                let messages = match decoder.lock().await.decode(&chunk, convert_web_ref) {
+                    Ok(msgs) => msgs,
+                    Err(e) => {
+                        tracing::warn!("流解析错误: {}", e);
+                        // 上游中途返回错误或数据无法解析时记录为失败，而不是客户端断开
+                        let (log_error, error) = match e {
+                            StreamError::ChatError(error) => {
+                                let error_response = error.to_error_response();
+                                note_rate_limit(uses_pool, &auth_token, error_response.kind);
+                                (error_response.to_log(), error_response.to_common())
                             }
-                        }
-                        return Err((
-                            error_response.status_code(),
-                            Json(error_response.to_common()),
+                            e => (
+                                e.to_string(),
+                                ErrorResponse {
+                                    status: ApiStatus::Error,
+                                    code: Some(500),
+                                    error: Some(e.to_string()),
+                                    message: e.message(),
+                                },
+                            ),
+                        };
+                        fail_request(&state, current_id, log_error).await;
+                        // 不再读取上游的剩余数据，响应在错误片段之后结束
+                        cancel.cancel();
+                        failure.mark();
+                        return Ok::<_, Infallible>(stream_error_chunk(
+                            &response_id,
+                            index,
+                            &error,
+                            &request_id,
                         ));
                     }
+                };
+
+                let mut response_data = String::new();
+
+                if let Some(first_msg) = decoder.lock().await.take_first_result() {
+                    let first_response = process_messages(first_msg, &ctx).await;
+                    response_data.push_str(&first_response);
                 }
-                Some(Err(e)) => {
-                    let error_message = format!("Failed to read response chunk: {}", e);
-                    return Err((
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(ChatError::RequestFailed(error_message).to_json()),
-                    ));
+
+                let current_response = process_messages(messages, &ctx).await;
+                if !current_response.is_empty() {
+                    response_data.push_str(&current_response);
                 }
-                None => {
-                    // 更新请求日志为失败
-                    {
-                        let mut state = state.lock().await;
+
+                Ok(Bytes::from(response_data))
+            };
+            fut.instrument(span.clone())
+        }
+    });
+
+    // 上游的字节流在 StreamEnd 之前结束时视为中断，记录为失败并以错误片段结束响应
+    // 通过接口取消时字节流同样提前结束，此时由 ActiveRequest 标记为已取消
+    let stream = stream.chain(futures::stream::once({
+        let state = state.clone();
+        let request_id = request_id.clone();
+        async move {
+            if finished.is_completed() || cancel.is_cancelled() {
+                return Ok(Bytes::new());
+            }
+            let failed = state
+                .lock()
+                .await
+                .request_logs
+                .iter()
+                .rev()
+                .find(|log| log.id == current_id)
+                .is_some_and(|log| matches!(log.status, LogStatus::Failed));
+            if failed {
+                return Ok(Bytes::new());
+            }
+            tracing::warn!("上游响应在完成前中断");
+            let error = (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ChatError::RequestFailed(Text::StreamTruncated.get().to_string()).to_json()),
+            );
+            fail_request(&state, current_id, error_text(&error)).await;
+            failure.mark();
+            Ok(stream_error_chunk(
+                &response_id,
+                index,
+                &error.1,
+                &request_id,
+            ))
+        }
+    }));
+
+    Response::builder()
+        .header("Cache-Control", "no-cache")
+        .header("Connection", "keep-alive")
+        .header(CONTENT_TYPE, "text/event-stream")
+        .body(Body::from_stream(stream))
+        .unwrap()
+}
+
+// 读取上游的完整回复后一次性返回，stream 为客户端是否请求了流式响应
+async fn collect_upstream(
+    reply: UpstreamReply,
+    stream: bool,
+    is_o1: bool,
+    json_format: Option<ResponseFormat>,
+) -> Result<Response<Body>, (StatusCode, Json<ErrorResponse>)> {
+    let UpstreamReply {
+        state,
+        current_id,
+        response_id,
+        index,
+        model,
+        auth_token,
+        uses_pool,
+        mut upstream,
+        decoder,
+        start_time,
+        convert_web_ref,
+        cancel,
+        active,
+        cache_key,
+        turn,
+        metadata,
+        sample_quality,
+        archive_payload,
+        include_usage,
+        prompt_tokens,
+    } = reply;
+
+    let mut decoder = decoder;
+    let mut first_chunk_time = None::<f64>;
+    let mut full_text = String::with_capacity(1024);
+    // 读取第一个结果时已解码的消息
+    let mut messages = decoder.take_first_result().unwrap_or_default();
+    // 是否收到了上游的结束消息，没有收到时响应不完整
+    let mut ended = false;
+
+    loop {
+        for message in messages {
+            match message {
+                StreamMessage::Content(text) => {
+                    if first_chunk_time.is_none() {
+                        first_chunk_time = Some(start_time.elapsed().as_secs_f64());
+                    }
+                    full_text.push_str(&text);
+                }
+                StreamMessage::StreamEnd => ended = true,
+                StreamMessage::Debug(debug_prompt) => {
+                    if let Ok(mut state) = state.try_lock() {
                         if let Some(log) = state
                             .request_logs
                             .iter_mut()
                             .rev()
                             .find(|log| log.id == current_id)
                         {
-                            log.status = LogStatus::Failed;
-                            log.error = Some("Empty stream response".to_string());
-                            state.error_requests += 1;
+                            log.prompt = Some(debug_prompt);
                         }
                     }
-                    return Err((
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(
-                            ChatError::RequestFailed("Empty stream response".to_string()).to_json(),
-                        ),
-                    ));
                 }
+                _ => {}
             }
         }
 
-        // 处理后续的stream
-        let stream = stream.then({
-            let decoder = decoder.clone();
-            let response_id = response_id.clone();
-            let model = request.model.clone();
-            let is_start = is_start.clone();
-            let first_chunk_time = first_chunk_time.clone();
-            let state = state.clone();
+        // 逐个处理chunks
+        let Some(chunk) = upstream.next().await else {
+            break;
+        };
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                let error = chunk_error(e);
+                fail_request(&state, current_id, error_text(&error)).await;
+                return Err(error);
+            }
+        };
 
-            move |chunk| {
-                let decoder = decoder.clone();
-                let response_id = response_id.clone();
-                let model = model.clone();
-                let is_start = is_start.clone();
-                let first_chunk_time = first_chunk_time.clone();
-                let state = state.clone();
-
-                async move {
-                    let chunk = chunk.unwrap_or_default();
-
-                    let ctx = MessageProcessContext {
-                        response_id: &response_id,
-                        model: &model,
-                        is_start: &is_start,
-                        first_chunk_time: &first_chunk_time,
-                        start_time,
-                        state: &state,
-                        current_id,
-                    };
+        // 立即处理当前chunk
+        messages = match decoder.decode(&chunk, convert_web_ref) {
+            Ok(messages) => messages,
+            Err(StreamError::ChatError(error)) => {
+                let error_response = error.to_error_response();
+                tracing::warn!("上游返回错误: {}", error_response.native_code());
+                note_rate_limit(uses_pool, &auth_token, error_response.kind);
+                fail_request(&state, current_id, error_response.to_log()).await;
+                return Err((
+                    error_response.status_code(),
+                    Json(error_response.to_common()),
+                ));
+            }
+            Err(e) => {
+                tracing::warn!("流解析错误: {}", e);
+                let error_response = ErrorResponse {
+                    status: ApiStatus::Error,
+                    code: Some(500),
+                    error: Some(e.to_string()),
+                    message: e.message(),
+                };
+                fail_request(&state, current_id, e.to_string()).await;
+                return Err((StatusCode::INTERNAL_SERVER_ERROR, Json(error_response)));
+            }
+        };
+    }
 
-                    // 使用decoder处理chunk
-                    let messages = match decoder.lock().await.decode(&chunk, convert_web_ref) {
-                        Ok(msgs) => msgs,
-                        Err(e) => {
-                            eprintln!("[警告] Stream error: {}", e);
-                            return Ok::<_, Infallible>(Bytes::new());
-                        }
-                    };
+    // 已取消的请求不返回部分内容
+    if cancel.is_cancelled() {
+        return Err(request_cancelled());
+    }
 
-                    let mut response_data = String::new();
+    // 上游在结束消息之前断开时不返回部分内容
+    if !ended && !full_text.is_empty() {
+        tracing::warn!("上游响应在完成前中断");
+        let error = (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ChatError::RequestFailed(Text::StreamTruncated.get().to_string()).to_json()),
+        );
+        fail_request(&state, current_id, error_text(&error)).await;
+        return Err(error);
+    }
 
-                    if let Some(first_msg) = decoder.lock().await.take_first_result() {
-                        let first_response = process_messages(first_msg, &ctx).await;
-                        response_data.push_str(&first_response);
-                    }
+    // 检查响应是否为空
+    if full_text.is_empty() {
+        fail_request(&state, current_id, "Empty response received".to_string()).await;
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ChatError::RequestFailed(Text::EmptyResponse.get().to_string()).to_json()),
+        ));
+    }
 
-                    let current_response = process_messages(messages, &ctx).await;
-                    if !current_response.is_empty() {
-                        response_data.push_str(&current_response);
-                    }
+    // 命中拦截规则时不返回内容
+    let blocked = moderation::check_output(&mut full_text).is_err();
+    if blocked {
+        full_text.clear();
+    }
 
-                    Ok(Bytes::from(response_data))
-                }
+    if let Some(ref format) = json_format.filter(|_| !blocked) {
+        full_text = match json_mode::finalize(format, full_text) {
+            Ok(text) => text,
+            Err(e) => {
+                let error = (
+                    StatusCode::BAD_GATEWAY,
+                    Json(ChatError::InvalidJsonOutput(e).to_json()),
+                );
+                fail_request(&state, current_id, error_text(&error)).await;
+                return Err(error);
             }
-        });
+        };
+    }
+
+    let completion_tokens = estimate_tokens(&full_text);
+
+    if archive_payload {
+        payload::record_response(current_id, &full_text);
+    }
+    if !blocked {
+        if sample_quality {
+            quality::record(&model, &full_text, completion_tokens);
+        }
+        if let Some(ref turn) = turn {
+            turn.finish(&full_text);
+        }
+        if let Some(key) = cache_key {
+            cache::insert(key, full_text.clone());
+        }
+    }
 
-        Ok(Response::builder()
+    {
+        // 更新请求日志时间信息和状态
+        let total_time = format_time_ms(start_time.elapsed().as_secs_f64());
+        let mut state = state.lock().await;
+        if let Some(log) = state
+            .request_logs
+            .iter_mut()
+            .rev()
+            .find(|log| log.id == current_id)
+        {
+            log.timing.total = total_time;
+            log.timing.first = first_chunk_time;
+            log.status = LogStatus::Success;
+            record_cost(log, prompt_tokens, completion_tokens);
+            log_sink::submit(log);
+        }
+    }
+    active.complete();
+
+    Ok(complete_response(
+        response_id,
+        index,
+        model,
+        stream,
+        include_usage,
+        is_o1,
+        prompt_tokens,
+        full_text,
+        if blocked {
+            FINISH_REASON_CONTENT_FILTER
+        } else {
+            FINISH_REASON_STOP
+        },
+        metadata,
+    ))
+}
+
+// n > 1 时并发发起 n 个请求，任一请求失败时取消其余请求并返回该错误
+//
+// 非流式响应合并为多个 choice；流式响应按到达顺序交错转发各回复的片段，
+// 各回复的用量片段被取出累加，与 [DONE] 一起在全部回复结束后统一发送
+async fn process_choices(
+    state: Arc<Mutex<AppState>>,
+    headers: HeaderMap,
+    client_ip: IpAddr,
+    request: ChatRequest,
+    response_id: String,
+    failure: ResponseFailure,
+) -> Result<Response<Body>, (StatusCode, Json<ErrorResponse>)> {
+    let choices = request.n.unwrap_or(1);
+    let include_usage = request.include_usage();
+    let is_o1 = AVAILABLE_MODELS
+        .iter()
+        .find(|m| m.id == request.model.trim_end_matches("-online"))
+        .is_some_and(Model::is_o1);
+    let stream = request.stream;
+
+    let dispatch = Arc::new(DispatchSlot::new());
+    let responses = futures::future::try_join_all((0..choices).map(|index| {
+        process_chat(
+            state.clone(),
+            headers.clone(),
+            client_ip,
+            request.clone(),
+            ResponseSlot {
+                response_id: response_id.clone(),
+                index,
+                dispatch: dispatch.clone(),
+                failure: failure.clone(),
+            },
+        )
+    }))
+    .await?;
+
+    if stream {
+        // 各回复的提示相同，prompt_tokens 只计一次，completion_tokens 累加
+        let totals = Arc::new(parking_lot::Mutex::new((0u32, 0u32)));
+        let tail = {
+            let totals = totals.clone();
+            futures::stream::once(futures::future::lazy(move |_| {
+                let mut tail = String::new();
+                if include_usage {
+                    let (prompt_tokens, completion_tokens) = *totals.lock();
+                    tail.push_str(&format!(
+                        "data: {}\n\n",
+                        serde_json::to_string(&usage_chunk(
+                            response_id,
+                            usage(prompt_tokens, completion_tokens, is_o1)
+                        ))
+                        .unwrap()
+                    ));
+                }
+                tail.push_str("data: [DONE]\n\n");
+                Ok(Bytes::from(tail))
+            }))
+        };
+
+        // 每个数据块都由完整的事件组成，[DONE] 只会出现在数据块末尾
+        let body = futures::stream::select_all(
+            responses
+                .into_iter()
+                .map(|response| response.into_body().into_data_stream()),
+        )
+        .map(move |chunk| {
+            chunk.map(|chunk| {
+                let Some(rest) = chunk.strip_suffix(b"data: [DONE]\n\n".as_slice()) else {
+                    return chunk;
+                };
+                let mut end = rest.len();
+                if let Some((start, usage)) = include_usage.then(|| take_usage(rest)).flatten() {
+                    let mut totals = totals.lock();
+                    totals.0 = totals.0.max(usage.prompt_tokens);
+                    totals.1 += usage.completion_tokens;
+                    end = start;
+                }
+                chunk.slice(..end)
+            })
+        })
+        .chain(tail);
+
+        return Ok(Response::builder()
             .header("Cache-Control", "no-cache")
             .header("Connection", "keep-alive")
             .header(CONTENT_TYPE, "text/event-stream")
-            .body(Body::from_stream(stream))
-            .unwrap())
-    } else {
-        // 非流式响应
-        let start_time = std::time::Instant::now();
-        let mut first_chunk_time = None::<f64>;
-        let mut decoder = StreamDecoder::new();
-        let mut full_text = String::with_capacity(1024);
-        let mut stream = response.bytes_stream();
+            .body(Body::from_stream(body))
+            .unwrap());
+    }
 
-        // 逐个处理chunks
-        while let Some(chunk) = stream.next().await {
-            let chunk = chunk.map_err(|e| {
-                let error_message = format!("Failed to read response chunk: {}", e);
+    let mut merged: Option<ChatResponse> = None;
+    for response in responses {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .map_err(|e| {
                 (
                     StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(ChatError::RequestFailed(error_message).to_json()),
+                    Json(ChatError::RequestFailed(e.to_string()).to_json()),
                 )
             })?;
-
-            // 立即处理当前chunk
-            match decoder.decode(&chunk, convert_web_ref) {
-                Ok(messages) => {
-                    for message in messages {
-                        match message {
-                            StreamMessage::Content(text) => {
-                                if first_chunk_time.is_none() {
-                                    first_chunk_time = Some(start_time.elapsed().as_secs_f64());
-                                }
-                                full_text.push_str(&text);
-                            }
-                            StreamMessage::Debug(debug_prompt) => {
-                                if let Ok(mut state) = state.try_lock() {
-                                    if let Some(log) = state
-                                        .request_logs
-                                        .iter_mut()
-                                        .rev()
-                                        .find(|log| log.id == current_id)
-                                    {
-                                        log.prompt = Some(debug_prompt);
-                                    }
-                                }
-                            }
-                            _ => {}
-                        }
-                    }
-                }
-                Err(StreamError::ChatError(error)) => {
-                    let error_response = error.to_error_response();
-                    return Err((
-                        error_response.status_code(),
-                        Json(error_response.to_common()),
-                    ));
-                }
-                Err(e) => {
-                    let error_response = ErrorResponse {
-                        status: ApiStatus::Error,
-                        code: Some(500),
-                        error: Some(e.to_string()),
-                        message: None,
-                    };
-                    return Err((StatusCode::INTERNAL_SERVER_ERROR, Json(error_response)));
+        let response: ChatResponse = serde_json::from_slice(&bytes).map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ChatError::RequestFailed(e.to_string()).to_json()),
+            )
+        })?;
+        match merged {
+            Some(ref mut merged) => {
+                if let (Some(total), Some(usage)) = (merged.usage.as_mut(), response.usage) {
+                    total.completion_tokens += usage.completion_tokens;
+                    total.total_tokens = total.prompt_tokens + total.completion_tokens;
                 }
+                merged.choices.extend(response.choices)
             }
+            None => merged = Some(response),
         }
+    }
 
-        // 检查响应是否为空
-        if full_text.is_empty() {
-            // 更新请求日志为失败
-            {
-                let mut state = state.lock().await;
-                if let Some(log) = state
-                    .request_logs
-                    .iter_mut()
-                    .rev()
-                    .find(|log| log.id == current_id)
-                {
-                    log.status = LogStatus::Failed;
-                    log.error = Some("Empty response received".to_string());
-                    state.error_requests += 1;
-                }
-            }
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ChatError::RequestFailed("Empty response received".to_string()).to_json()),
+    Ok(Response::builder()
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(serde_json::to_string(&merged).unwrap()))
+        .unwrap())
+}
+
+// 取出数据块末尾的用量片段，返回该片段的起始位置与用量，出错结束的回复没有用量片段
+fn take_usage(events: &[u8]) -> Option<(usize, Usage)> {
+    let body = events.strip_suffix(b"\n\n")?;
+    let start = body
+        .windows(8)
+        .rposition(|w| w == b"\n\ndata: ")
+        .map_or(0, |pos| pos + 2);
+    let data = body[start..].strip_prefix(b"data: ")?;
+    let response: ChatResponse = serde_json::from_slice(data).ok()?;
+    if !response.choices.is_empty() {
+        return None;
+    }
+    Some((start, response.usage?))
+}
+
+// 流式响应中途出错时的最后一个片段：该回复以 finish_reason error 结束，并附带与 OpenAI 相同结构的错误对象
+fn stream_error_chunk(
+    response_id: &str,
+    index: u32,
+    error: &ErrorResponse,
+    request_id: &str,
+) -> Bytes {
+    #[derive(Serialize)]
+    struct ErrorObject<'a> {
+        message: &'a str,
+        #[serde(rename = "type")]
+        kind: &'a str,
+        code: Option<u16>,
+        request_id: &'a str,
+    }
+
+    #[derive(Serialize)]
+    struct ErrorChunk<'a> {
+        #[serde(flatten)]
+        chunk: ChatResponse,
+        error: ErrorObject<'a>,
+    }
+
+    let chunk = ErrorChunk {
+        chunk: ChatResponse {
+            id: response_id.to_string(),
+            object: OBJECT_CHAT_COMPLETION_CHUNK.to_string(),
+            created: chrono::Utc::now().timestamp(),
+            model: None,
+            choices: vec![Choice {
+                index: index as i32,
+                message: None,
+                delta: Some(Delta {
+                    role: None,
+                    content: None,
+                }),
+                logprobs: None,
+                finish_reason: Some(FINISH_REASON_ERROR.to_string()),
+            }],
+            usage: None,
+            metadata: None,
+        },
+        error: ErrorObject {
+            message: error.message.as_deref().unwrap_or_default(),
+            kind: error.error.as_deref().unwrap_or_default(),
+            code: error.code,
+            request_id,
+        },
+    };
+    Bytes::from(format!(
+        "data: {}\n\ndata: [DONE]\n\n",
+        serde_json::to_string(&chunk).unwrap()
+    ))
+}
+
+// 以完整的回复构造响应，流式请求以单个片段回放
+#[allow(clippy::too_many_arguments)]
+fn complete_response(
+    response_id: String,
+    index: u32,
+    model: String,
+    stream: bool,
+    include_usage: bool,
+    is_o1: bool,
+    prompt_tokens: u32,
+    text: String,
+    finish_reason: &str,
+    metadata: Option<HashMap<String, String>>,
+) -> Response<Body> {
+    let text = text.trim_leading_newlines();
+    let usage = usage(prompt_tokens, estimate_tokens(&text), is_o1);
+
+    if stream {
+        let content = ChatResponse {
+            id: response_id.clone(),
+            object: OBJECT_CHAT_COMPLETION_CHUNK.to_string(),
+            created: chrono::Utc::now().timestamp(),
+            model: Some(model),
+            choices: vec![Choice {
+                index: index as i32,
+                message: None,
+                delta: Some(Delta {
+                    role: Some(Role::Assistant),
+                    content: Some(text),
+                }),
+                logprobs: None,
+                finish_reason: None,
+            }],
+            usage: None,
+            metadata: None,
+        };
+        let end = ChatResponse {
+            id: response_id.clone(),
+            object: OBJECT_CHAT_COMPLETION_CHUNK.to_string(),
+            created: chrono::Utc::now().timestamp(),
+            model: None,
+            choices: vec![Choice {
+                index: index as i32,
+                message: None,
+                delta: Some(Delta {
+                    role: None,
+                    content: None,
+                }),
+                logprobs: None,
+                finish_reason: Some(finish_reason.to_string()),
+            }],
+            usage: None,
+            metadata: metadata.clone(),
+        };
+
+        let mut body = format!(
+            "data: {}\n\ndata: {}\n\n",
+            serde_json::to_string(&content).unwrap(),
+            serde_json::to_string(&end).unwrap()
+        );
+        if include_usage {
+            body.push_str(&format!(
+                "data: {}\n\n",
+                serde_json::to_string(&usage_chunk(response_id, usage)).unwrap()
             ));
         }
+        body.push_str("data: [DONE]\n\n");
 
+        Response::builder()
+            .header("Cache-Control", "no-cache")
+            .header("Connection", "keep-alive")
+            .header(CONTENT_TYPE, "text/event-stream")
+            .body(Body::from(body))
+            .unwrap()
+    } else {
         let response_data = ChatResponse {
-            id: format!("chatcmpl-{}", Uuid::new_v4().simple()),
+            id: response_id,
             object: OBJECT_CHAT_COMPLETION.to_string(),
             created: chrono::Utc::now().timestamp(),
-            model: Some(request.model),
+            model: Some(model),
             choices: vec![Choice {
-                index: 0,
+                index: index as i32,
                 message: Some(Message {
                     role: Role::Assistant,
-                    content: MessageContent::Text(full_text.trim_leading_newlines()),
+                    content: MessageContent::Text(text),
                 }),
                 delta: None,
-                finish_reason: Some(FINISH_REASON_STOP.to_string()),
+                logprobs: None,
+                finish_reason: Some(finish_reason.to_string()),
             }],
-            usage: Some(Usage {
-                prompt_tokens: 0,
-                completion_tokens: 0,
-                total_tokens: 0,
-            }),
+            usage: Some(usage),
+            metadata,
         };
 
-        {
-            // 更新请求日志时间信息和状态
-            let total_time = format_time_ms(start_time.elapsed().as_secs_f64());
-            let mut state = state.lock().await;
-            if let Some(log) = state
-                .request_logs
-                .iter_mut()
-                .rev()
-                .find(|log| log.id == current_id)
-            {
-                log.timing.total = total_time;
-                log.timing.first = first_chunk_time;
-                log.status = LogStatus::Success;
-            }
-        }
-
-        Ok(Response::builder()
+        Response::builder()
             .header(CONTENT_TYPE, "application/json")
             .body(Body::from(serde_json::to_string(&response_data).unwrap()))
-            .unwrap())
+            .unwrap()
+    }
+}
+
+// stream_options.include_usage 要求的末尾用量片段
+fn usage_chunk(response_id: String, usage: Usage) -> ChatResponse {
+    ChatResponse {
+        id: response_id,
+        object: OBJECT_CHAT_COMPLETION_CHUNK.to_string(),
+        created: chrono::Utc::now().timestamp(),
+        model: None,
+        choices: vec![],
+        usage: Some(usage),
+        metadata: None,
+    }
+}
+
+// 上游不返回用量，按文本长度估算；o1 系列模型额外包含 reasoning_tokens 字段，上游不提供推理内容，固定为0
+fn usage(prompt_tokens: u32, completion_tokens: u32, is_o1: bool) -> Usage {
+    Usage {
+        prompt_tokens,
+        completion_tokens,
+        total_tokens: prompt_tokens + completion_tokens,
+        completion_tokens_details: is_o1.then_some(CompletionTokensDetails {
+            reasoning_tokens: 0,
+        }),
+    }
+}
+
+// 粗略估算请求的 token 数，图片不计入
+fn estimate_prompt_tokens(messages: &[Message]) -> u32 {
+    messages
+        .iter()
+        .map(|message| match message.content {
+            MessageContent::Text(ref text) => estimate_tokens(text),
+            MessageContent::Vision(ref parts) => parts
+                .iter()
+                .filter_map(|part| part.text.as_deref())
+                .map(estimate_tokens)
+                .sum(),
+        })
+        .sum()
+}
+
+// 记录请求的估算费用并计入消费统计
+fn record_cost(log: &mut RequestLog, prompt_tokens: u32, completion_tokens: u32) {
+    let cost = CostInfo::estimate(&log.model, prompt_tokens, completion_tokens);
+    SpendLedger::record(&log.token_info.token, &cost);
+    log.cost = Some(cost);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct ErrorFrame {
+        id: String,
+        object: String,
+        choices: Vec<Choice>,
+        error: ErrorObject,
+    }
+
+    #[derive(Deserialize)]
+    struct ErrorObject {
+        message: String,
+        #[serde(rename = "type")]
+        kind: String,
+        code: Option<u16>,
+        request_id: String,
+    }
+
+    #[test]
+    fn test_stream_error_chunk_framing() {
+        let error = ErrorResponse {
+            status: ApiStatus::Failed,
+            code: Some(502),
+            error: Some("upstream_error".to_string()),
+            message: Some("stream interrupted".to_string()),
+        };
+        let chunk = stream_error_chunk("chatcmpl-1", 2, &error, "req-1");
+        let chunk = std::str::from_utf8(&chunk).unwrap();
+
+        // 一个完整的错误事件，随后以 [DONE] 结束
+        let events: Vec<&str> = chunk.split_terminator("\n\n").collect();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1], "data: [DONE]");
+        assert!(chunk.ends_with("\n\n"));
+
+        let data = events[0].strip_prefix("data: ").unwrap();
+        assert!(!data.contains('\n'));
+        let frame: ErrorFrame = serde_json::from_str(data).unwrap();
+        assert_eq!(frame.id, "chatcmpl-1");
+        assert_eq!(frame.object, OBJECT_CHAT_COMPLETION_CHUNK);
+        assert_eq!(frame.choices.len(), 1);
+        assert_eq!(frame.choices[0].index, 2);
+        assert_eq!(
+            frame.choices[0].finish_reason.as_deref(),
+            Some(FINISH_REASON_ERROR)
+        );
+        assert_eq!(frame.error.message, "stream interrupted");
+        assert_eq!(frame.error.kind, "upstream_error");
+        assert_eq!(frame.error.code, Some(502));
+        assert_eq!(frame.error.request_id, "req-1");
+    }
+
+    #[test]
+    fn test_stream_error_chunk_without_details() {
+        let error = ErrorResponse {
+            status: ApiStatus::Failed,
+            code: None,
+            error: None,
+            message: None,
+        };
+        let chunk = stream_error_chunk("chatcmpl-1", 0, &error, "req-1");
+        let data = std::str::from_utf8(&chunk)
+            .unwrap()
+            .strip_prefix("data: ")
+            .and_then(|rest| rest.split_once("\n\n"))
+            .unwrap()
+            .0;
+        let frame: ErrorFrame = serde_json::from_str(data).unwrap();
+        assert_eq!(frame.error.message, "");
+        assert_eq!(frame.error.kind, "");
+        assert_eq!(frame.error.code, None);
     }
 }
@@ -4,7 +4,18 @@ use crate::chat::{
 };
 use flate2::read::GzDecoder;
 use prost::Message;
-use std::{collections::BTreeMap, io::Read};
+use std::{
+    collections::BTreeMap,
+    io::Read,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+// 被丢弃的心跳帧数量：开始标志之后的空帧，以及解析后没有内容的帧
+static HEARTBEAT_FRAMES: AtomicU64 = AtomicU64::new(0);
+
+pub fn heartbeat_frames() -> u64 {
+    HEARTBEAT_FRAMES.load(Ordering::Relaxed)
+}
 
 // 解压gzip数据
 fn decompress_gzip(data: &[u8]) -> Option<Vec<u8>> {
@@ -67,6 +78,7 @@ pub struct StreamDecoder {
     first_result: Option<Vec<StreamMessage>>,
     first_result_ready: bool,
     first_result_taken: bool,
+    content_started: bool,
 }
 
 impl StreamDecoder {
@@ -76,6 +88,7 @@ impl StreamDecoder {
             first_result: None,
             first_result_ready: false,
             first_result_taken: false,
+            content_started: false,
         }
     }
 
@@ -121,9 +134,15 @@ impl StreamDecoder {
                 self.buffer[offset + 4],
             ]) as usize;
 
+            // 只有第一个空帧是开始标志，之后的空帧为心跳
             if msg_len == 0 {
                 offset += 5;
-                messages.push(StreamMessage::ContentStart);
+                if self.content_started {
+                    HEARTBEAT_FRAMES.fetch_add(1, Ordering::Relaxed);
+                } else {
+                    self.content_started = true;
+                    messages.push(StreamMessage::ContentStart);
+                }
                 continue;
             }
 
@@ -133,15 +152,22 @@ impl StreamDecoder {
 
             let msg_data = &self.buffer[offset + 5..offset + 5 + msg_len];
 
-            match self.process_message(msg_type, msg_data)? {
-                Some(msg) => {
-                    if convert_web_ref {
-                        messages.push(msg.convert_web_ref_to_content());
-                    } else {
-                        messages.push(msg);
-                    }
+            let msg = self.process_message(msg_type, msg_data)?.map(|msg| {
+                if convert_web_ref {
+                    msg.convert_web_ref_to_content()
+                } else {
+                    msg
+                }
+            });
+            match msg {
+                // 空内容会产生空的 SSE 事件，与心跳一同丢弃
+                Some(StreamMessage::Content(text)) if text.is_empty() => {
+                    HEARTBEAT_FRAMES.fetch_add(1, Ordering::Relaxed);
+                }
+                Some(msg) => messages.push(msg),
+                None => {
+                    HEARTBEAT_FRAMES.fetch_add(1, Ordering::Relaxed);
                 }
-                _ => {}
             }
 
             offset += 5 + msg_len;
@@ -173,7 +199,7 @@ impl StreamDecoder {
             2 => self.handle_json_message(msg_data),
             3 => self.handle_gzip_json_message(msg_data),
             t => {
-                eprintln!("收到未知消息类型: {}，请尝试联系开发者以获取支持", t);
+                tracing::warn!("收到未知消息类型: {}，请尝试联系开发者以获取支持", t);
                 crate::debug_println!("消息类型: {}，消息内容: {}", t, hex::encode(msg_data));
                 Ok(None)
             }
@@ -181,48 +207,29 @@ impl StreamDecoder {
     }
 
     fn handle_text_message(&self, msg_data: &[u8]) -> Result<Option<StreamMessage>, StreamError> {
-        if let Ok(response) = StreamChatResponse::decode(msg_data) {
-            // crate::debug_println!("[text] StreamChatResponse [hex: {}]: {:?}", hex::encode(msg_data), response);
-            if !response.text.is_empty() {
-                Ok(Some(StreamMessage::Content(response.text)))
-            } else if let Some(filled_prompt) = response.filled_prompt {
-                Ok(Some(StreamMessage::Debug(filled_prompt)))
-            } else if let Some(web_citation) = response.web_citation {
-                let mut refs = BTreeMap::new();
-                for reference in web_citation.references {
-                    refs.insert(reference.url, reference.title);
-                }
-                Ok(Some(StreamMessage::WebReference(refs)))
-            } else {
-                Ok(None)
-            }
-        } else {
-            Ok(None)
-        }
+        Ok(self.decode_response(msg_data))
     }
 
     fn handle_gzip_message(&self, msg_data: &[u8]) -> Result<Option<StreamMessage>, StreamError> {
-        if let Some(text) = decompress_gzip(msg_data) {
-            if let Ok(response) = StreamChatResponse::decode(&text[..]) {
-                // crate::debug_println!("[gzip] StreamChatResponse [hex: {}]: {:?}", hex::encode(msg_data), response);
-                if !response.text.is_empty() {
-                    Ok(Some(StreamMessage::Content(response.text)))
-                } else if let Some(filled_prompt) = response.filled_prompt {
-                    Ok(Some(StreamMessage::Debug(filled_prompt)))
-                } else if let Some(web_citation) = response.web_citation {
-                    let mut refs = BTreeMap::new();
-                    for reference in web_citation.references {
-                        refs.insert(reference.url, reference.title);
-                    }
-                    Ok(Some(StreamMessage::WebReference(refs)))
-                } else {
-                    Ok(None)
-                }
-            } else {
-                Ok(None)
+        Ok(decompress_gzip(msg_data).and_then(|text| self.decode_response(&text)))
+    }
+
+    // 解码响应，无法解析时忽略
+    fn decode_response(&self, msg_data: &[u8]) -> Option<StreamMessage> {
+        let response = StreamChatResponse::decode(msg_data).ok()?;
+        // crate::debug_println!("StreamChatResponse [hex: {}]: {:?}", hex::encode(msg_data), response);
+        if !response.text.is_empty() {
+            Some(StreamMessage::Content(response.text))
+        } else if let Some(filled_prompt) = response.filled_prompt {
+            Some(StreamMessage::Debug(filled_prompt))
+        } else if let Some(web_citation) = response.web_citation {
+            let mut refs = BTreeMap::new();
+            for reference in web_citation.references {
+                refs.insert(reference.url, reference.title);
             }
+            Some(StreamMessage::WebReference(refs))
         } else {
-            Ok(None)
+            None
         }
     }
 
@@ -403,4 +410,35 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_heartbeat_frames_dropped() {
+        let text = StreamChatResponse {
+            text: "hello".to_string(),
+            ..Default::default()
+        }
+        .encode_to_vec();
+
+        // 开始标志、两个心跳帧，然后是一条内容
+        let mut bytes = vec![0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        bytes.push(0);
+        bytes.extend_from_slice(&(text.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(&text);
+
+        let before = heartbeat_frames();
+        let mut decoder = StreamDecoder::new();
+        let messages = match decoder.decode(&bytes, false) {
+            Ok(messages) => messages,
+            Err(e) => panic!("解析错误: {}", e),
+        };
+
+        assert_eq!(
+            messages,
+            vec![
+                StreamMessage::ContentStart,
+                StreamMessage::Content("hello".to_string())
+            ]
+        );
+        assert!(heartbeat_frames() >= before + 2);
+    }
 }
@@ -0,0 +1,259 @@
+//! 本服务 API 的异步客户端，需启用 `client` feature
+//!
+//! 请求与响应直接复用服务端的类型，服务端接口变化时编译期即可发现不一致
+
+pub use crate::{
+    app::model::{
+        ChatRequest, CostInfo, LogStatus, RequestLog, StreamOptions, TimingInfo,
+        TokenAddRequestTokenInfo, TokenInfo, TokenWarmup, TokensDeleteRequest,
+        TokensDeleteResponse, TokensDeleteResponseExpectation, UsageReconciliation,
+    },
+    chat::{
+        model::{
+            ChatResponse, Choice, CompletionTokensDetails, Delta, ImageUrl, Message,
+            MessageContent, Role, Usage, VisionMessageContent,
+        },
+        route::{LogsQuery, LogsResponse, TokenInfoResponse},
+    },
+    common::model::{userinfo::TokenProfile, ApiStatus, ErrorResponse},
+};
+
+use crate::app::constant::{
+    AUTHORIZATION_BEARER_PREFIX, CHAT_CANCEL_PATH, CHAT_COMPLETIONS_PATH, ROUTE_LOGS_PATH,
+    ROUTE_TOKENS_ADD_PATH, ROUTE_TOKENS_DELETE_PATH, ROUTE_TOKENS_GET_PATH,
+};
+use futures::{stream::BoxStream, StreamExt as _};
+use reqwest::header::AUTHORIZATION;
+use serde::{de::DeserializeOwned, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug)]
+pub enum Error {
+    Request(reqwest::Error),
+    // 服务端返回的非成功状态码，能解析时附带错误内容
+    Api {
+        status: u16,
+        error: Option<ErrorResponse>,
+    },
+    Decode(serde_json::Error),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Request(e) => write!(f, "request failed: {}", e),
+            Error::Api { status, error } => {
+                match error
+                    .as_ref()
+                    .and_then(|e| e.message.as_ref().or(e.error.as_ref()))
+                {
+                    Some(message) => write!(f, "status {}: {}", status, message),
+                    None => write!(f, "status {}", status),
+                }
+            }
+            Error::Decode(e) => write!(f, "invalid response: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        Error::Request(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Decode(e)
+    }
+}
+
+impl ChatRequest {
+    pub fn new(model: impl Into<String>, messages: Vec<Message>) -> Self {
+        Self {
+            model: model.into(),
+            messages,
+            stream: false,
+            stream_options: None,
+            n: None,
+            logprobs: None,
+            top_logprobs: None,
+            response_format: None,
+            conversation_id: None,
+            slow_pool: None,
+            token_tag: None,
+            template: None,
+            template_vars: HashMap::new(),
+            metadata: HashMap::new(),
+            tenant: None,
+            extra: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    base_url: String,
+    route_prefix: String,
+    token: String,
+}
+
+impl Client {
+    /// `base_url` 为服务根地址（包含 `BASE_PATH`），`token` 为 `AUTH_TOKEN` 或请求使用的 token
+    ///
+    /// 管理接口（tokens、logs）需要使用 `AUTH_TOKEN`
+    pub fn new(base_url: impl Into<String>, token: impl Into<String>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            route_prefix: String::new(),
+            token: token.into(),
+        }
+    }
+
+    pub fn with_http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// 与服务端的 `ROUTE_PREFIX` 保持一致
+    pub fn with_route_prefix(mut self, route_prefix: impl Into<String>) -> Self {
+        self.route_prefix = route_prefix.into();
+        self
+    }
+
+    pub async fn chat(&self, mut request: ChatRequest) -> Result<ChatResponse, Error> {
+        request.stream = false;
+        let path = format!("{}{}", self.route_prefix, CHAT_COMPLETIONS_PATH);
+        let response = self.post(&path, &request).send().await?;
+        decode(check(response).await?).await
+    }
+
+    /// 返回逐个解析的 `chat.completion.chunk`，读到 `[DONE]` 时结束
+    pub async fn chat_stream(
+        &self,
+        mut request: ChatRequest,
+    ) -> Result<BoxStream<'static, Result<ChatResponse, Error>>, Error> {
+        request.stream = true;
+        let path = format!("{}{}", self.route_prefix, CHAT_COMPLETIONS_PATH);
+        let response = check(self.post(&path, &request).send().await?).await?;
+
+        let state = (response.bytes_stream().boxed(), Vec::new(), false);
+        let chunks = futures::stream::unfold(state, |(mut body, mut buffer, done)| async move {
+            if done {
+                return None;
+            }
+            loop {
+                // 每个事件以空行结尾
+                if let Some(pos) = buffer.windows(2).position(|w| w == b"\n\n") {
+                    let event: Vec<u8> = buffer.drain(..pos + 2).collect();
+                    let Some(data) = event
+                        .split(|&b| b == b'\n')
+                        .find_map(|line| line.strip_prefix(b"data: "))
+                    else {
+                        continue;
+                    };
+                    if data == b"[DONE]" {
+                        return None;
+                    }
+                    let chunk = serde_json::from_slice(data).map_err(Error::from);
+                    return Some((chunk, (body, buffer, false)));
+                }
+                match body.next().await {
+                    Some(Ok(bytes)) => buffer.extend_from_slice(&bytes),
+                    Some(Err(e)) => return Some((Err(e.into()), (body, buffer, true))),
+                    None => return None,
+                }
+            }
+        });
+        Ok(chunks.boxed())
+    }
+
+    /// 取消进行中的请求，`id` 为响应中的 `chatcmpl-` id
+    pub async fn cancel(&self, id: &str) -> Result<(), Error> {
+        let path = format!("{}{}/{}", self.route_prefix, CHAT_CANCEL_PATH, id);
+        let response = self
+            .http
+            .post(self.url(&path))
+            .header(AUTHORIZATION, self.authorization())
+            .send()
+            .await?;
+        check(response).await?;
+        Ok(())
+    }
+
+    pub async fn get_tokens(&self) -> Result<TokenInfoResponse, Error> {
+        let response = self
+            .http
+            .post(self.url(ROUTE_TOKENS_GET_PATH))
+            .header(AUTHORIZATION, self.authorization())
+            .send()
+            .await?;
+        decode(check(response).await?).await
+    }
+
+    pub async fn add_tokens(
+        &self,
+        tokens: &[TokenAddRequestTokenInfo],
+    ) -> Result<TokenInfoResponse, Error> {
+        let response = self.post(ROUTE_TOKENS_ADD_PATH, &tokens).send().await?;
+        decode(check(response).await?).await
+    }
+
+    pub async fn delete_tokens(
+        &self,
+        request: &TokensDeleteRequest,
+    ) -> Result<TokensDeleteResponse, Error> {
+        let response = self.post(ROUTE_TOKENS_DELETE_PATH, request).send().await?;
+        decode(check(response).await?).await
+    }
+
+    pub async fn logs(&self, query: &LogsQuery) -> Result<LogsResponse, Error> {
+        let response = self
+            .http
+            .post(self.url(ROUTE_LOGS_PATH))
+            .header(AUTHORIZATION, self.authorization())
+            .query(query)
+            .send()
+            .await?;
+        decode(check(response).await?).await
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    fn authorization(&self) -> String {
+        format!("{}{}", AUTHORIZATION_BEARER_PREFIX, self.token)
+    }
+
+    fn post<T: Serialize + ?Sized>(&self, path: &str, body: &T) -> reqwest::RequestBuilder {
+        let body = serde_json::to_vec(body).unwrap_or_default();
+        self.http
+            .post(self.url(path))
+            .header(AUTHORIZATION, self.authorization())
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body)
+    }
+}
+
+// 非成功状态码转为 Error::Api
+async fn check(response: reqwest::Response) -> Result<reqwest::Response, Error> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response.bytes().await.unwrap_or_default();
+    Err(Error::Api {
+        status: status.as_u16(),
+        error: serde_json::from_slice(&body).ok(),
+    })
+}
+
+async fn decode<T: DeserializeOwned>(response: reqwest::Response) -> Result<T, Error> {
+    let body = response.bytes().await?;
+    Ok(serde_json::from_slice(&body)?)
+}
@@ -5,15 +5,16 @@ use crate::{app::{
         HEADER_NAME_GHOST_MODE, TRUE,
     },
     lazy::{
-        CURSOR_API2_CHAT_URL, CURSOR_API2_CHAT_WEB_URL, CURSOR_API2_STRIPE_URL, CURSOR_USAGE_API_URL, CURSOR_USER_API_URL, REVERSE_PROXY_HOST, USE_REVERSE_PROXY
+        CURSOR_API2_CHAT_URL, CURSOR_API2_CHAT_WEB_URL, CURSOR_API2_STRIPE_URL, CURSOR_CLIENT_VERSION, CURSOR_USAGE_API_URL, CURSOR_USER_API_URL, REVERSE_PROXY_HOST, UPSTREAM_CONNECT_RETRIES, UPSTREAM_RETRY_BACKOFF, USE_REVERSE_PROXY
     },
-}, AppConfig};
+    model::AppConfig,
+}};
 use reqwest::header::{
         ACCEPT, ACCEPT_ENCODING, ACCEPT_LANGUAGE, CACHE_CONTROL, CONNECTION, CONTENT_TYPE, COOKIE,
         DNT, HOST, ORIGIN, PRAGMA, REFERER, TE, TRANSFER_ENCODING, USER_AGENT,
     };
-use reqwest::{Client, RequestBuilder};
-use std::sync::LazyLock;
+use reqwest::{Client, RequestBuilder, Response};
+use std::{sync::LazyLock, time::Duration};
 use uuid::Uuid;
 
 macro_rules! def_const {
@@ -96,7 +97,7 @@ pub fn build_client(auth_token: &str, checksum: &str, is_search: bool) -> Reques
         .header("x-amzn-trace-id", format!("Root={}", trace_id))
         .header("x-client-key", generate_hash())
         .header("x-cursor-checksum", checksum)
-        .header("x-cursor-client-version", "0.42.5")
+        .header("x-cursor-client-version", CURSOR_CLIENT_VERSION.as_str())
         .header("x-cursor-timezone", "Asia/Shanghai")
         .header(HEADER_NAME_GHOST_MODE, TRUE)
         .header("x-request-id", trace_id)
@@ -104,6 +105,35 @@ pub fn build_client(auth_token: &str, checksum: &str, is_search: bool) -> Reques
         .header(TRANSFER_ENCODING, "chunked")
 }
 
+/// 发送请求，连接上游失败时按指数退避重试
+///
+/// 只重试尚未建立连接的失败，此时上游还未收到请求，重试不会重复计费
+///
+/// # 参数
+///
+/// * `request` - 请求构建器，请求体需可复制
+///
+/// # 返回
+///
+/// * `reqwest::Result<Response>` - 最后一次尝试的结果
+pub async fn send_with_retry(request: RequestBuilder) -> reqwest::Result<Response> {
+    let mut attempt = 0;
+    loop {
+        let Some(current) = request.try_clone() else {
+            return request.send().await;
+        };
+        match current.send().await {
+            Err(e) if e.is_connect() && attempt < *UPSTREAM_CONNECT_RETRIES => {
+                let delay = Duration::from_millis(*UPSTREAM_RETRY_BACKOFF << attempt);
+                tracing::warn!("连接上游失败，{}ms 后重试: {}", delay.as_millis(), e);
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
 /// 返回预构建的获取 Stripe 账户信息的 Cursor API 客户端
 ///
 /// # 参数
@@ -134,7 +164,7 @@ pub fn build_profile_client(auth_token: &str) -> RequestBuilder {
         .bearer_auth(auth_token)
         .header(
             USER_AGENT,
-            "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Cursor/0.42.5 Chrome/124.0.6367.243 Electron/30.4.0 Safari/537.36",
+            format!("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Cursor/{} Chrome/124.0.6367.243 Electron/30.4.0 Safari/537.36", *CURSOR_CLIENT_VERSION),
         )
         .header("sec-ch-ua-platform", "\"Windows\"")
         .header(ACCEPT, VALUE_ACCEPT)
@@ -9,6 +9,7 @@ use config::ConfigData;
 use serde::Serialize;
 
 #[derive(Serialize)]
+#[cfg_attr(feature = "client", derive(serde::Deserialize, Debug))]
 pub enum ApiStatus {
     #[serde(rename = "healthy")]
     Healthy,
@@ -57,6 +58,7 @@ impl std::fmt::Display for NormalResponse<ConfigData> {
 // }
 
 #[derive(Serialize)]
+#[cfg_attr(feature = "client", derive(serde::Deserialize, Debug))]
 pub struct ErrorResponse {
     // status -> 成功 / 失败
     pub status: ApiStatus,
@@ -1,6 +1,8 @@
+use std::collections::{BTreeMap, HashMap};
+
 use serde::{Deserialize, Serialize};
 
-use crate::app::model::{PageContent, UsageCheck, VisionAbility, Proxies};
+use crate::app::model::{ModelAlias, PageContent, UsageCheck, UserModelPolicy, VisionAbility, Proxies};
 
 #[derive(Serialize)]
 pub struct ConfigData {
@@ -14,6 +16,46 @@ pub struct ConfigData {
     pub share_token: String,
     pub proxies: Proxies,
     pub include_web_references: bool,
+    pub token_warmup: bool,
+    pub token_warmup_required: bool,
+    pub ip_allowlist: Vec<String>,
+    pub ip_denylist: Vec<String>,
+    pub cors_allowed_origins: Vec<String>,
+    pub cors_admin_origins: Vec<String>,
+    pub cors_allowed_headers: Vec<String>,
+    pub cors_allow_credentials: bool,
+}
+
+// 以 TOML 导出的完整配置，pages 只包含已自定义的页面
+#[derive(Serialize)]
+pub struct ConfigDocument {
+    pub config: ConfigData,
+    pub pages: BTreeMap<&'static str, PageContent>,
+    pub model_aliases: Vec<ModelAlias>,
+    pub model_policies: Vec<UserModelPolicy>,
+    pub quotas: Vec<QuotaEntry>,
+}
+
+// 导入时 config 中的字段与 update 相同，省略的字段保持不变；
+// 未列出的页面恢复默认，模型别名、模型策略与额度快照整体替换
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct ConfigImportDocument {
+    pub config: ConfigUpdateRequest,
+    pub pages: HashMap<String, PageContent>,
+    pub model_aliases: Vec<ModelAlias>,
+    pub model_policies: Vec<UserModelPolicy>,
+    pub quotas: Vec<QuotaEntry>,
+}
+
+// 各 token 的额度快照，按 token 对应到号池中的账户
+#[derive(Serialize, Deserialize)]
+pub struct QuotaEntry {
+    pub token: String,
+    pub synced_at: i64,
+    pub fast_requests: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_fast_requests: Option<u32>,
 }
 
 #[derive(Deserialize, Default)]
@@ -30,4 +72,12 @@ pub struct ConfigUpdateRequest {
     pub share_token: Option<String>,
     pub proxies: Option<Proxies>,
     pub include_web_references: Option<bool>,
+    pub token_warmup: Option<bool>,
+    pub token_warmup_required: Option<bool>,
+    pub ip_allowlist: Option<Vec<String>>,
+    pub ip_denylist: Option<Vec<String>>,
+    pub cors_allowed_origins: Option<Vec<String>>,
+    pub cors_admin_origins: Option<Vec<String>>,
+    pub cors_allowed_headers: Option<Vec<String>>,
+    pub cors_allow_credentials: Option<bool>,
 }
@@ -1,27 +1,88 @@
 use super::ErrorResponse;
+use crate::app::i18n::Text;
 
 pub enum ChatError {
     ModelNotSupported(String),
+    ModelNotAllowed(String),
     EmptyMessages,
     NoTokens,
+    ServerBusy,
     RequestFailed(String),
+    InvalidImage(String),
     Unauthorized,
+    IpNotAllowed(String),
+    TokenAliasNotFound(String),
+    TokenTagNotFound(String),
+    TenantNotFound(String),
+    InvalidTemplate(String),
+    ContentBlocked(String),
+    PublicPoolQuotaExceeded,
+    RequestCancelled,
+    UpstreamTimeout,
+    InvalidChoices(String),
+    LogprobsUnsupported,
+    InvalidJsonOutput(String),
+    IpBlocked(String),
+    ImagesUnsupported,
 }
 
 impl ChatError {
+    // 错误信息使用当前请求的语言，error 字段不随语言变化
     pub fn to_json(&self) -> ErrorResponse {
         let (error, message) = match self {
-            ChatError::ModelNotSupported(model) => (
-                "model_not_supported",
-                format!("Model '{}' is not supported", model),
+            ChatError::ModelNotSupported(model) => {
+                ("model_not_supported", Text::ModelNotSupported.format(model))
+            }
+            ChatError::ModelNotAllowed(model) => {
+                ("model_not_allowed", Text::ModelNotAllowed.format(model))
+            }
+            ChatError::EmptyMessages => ("empty_messages", Text::EmptyMessages.get().to_string()),
+            ChatError::NoTokens => ("no_tokens", Text::NoTokens.get().to_string()),
+            ChatError::ServerBusy => ("server_busy", Text::ServerBusy.get().to_string()),
+            ChatError::RequestFailed(err) => ("request_failed", Text::RequestFailed.format(err)),
+            ChatError::InvalidImage(err) => ("invalid_image", Text::InvalidImage.format(err)),
+            ChatError::Unauthorized => ("unauthorized", Text::Unauthorized.get().to_string()),
+            ChatError::IpNotAllowed(ip) => ("ip_not_allowed", Text::IpNotAllowed.format(ip)),
+            ChatError::TokenAliasNotFound(alias) => (
+                "token_alias_not_found",
+                Text::TokenAliasNotFound.format(alias),
             ),
-            ChatError::EmptyMessages => (
-                "empty_messages",
-                "Message array cannot be empty".to_string(),
+            ChatError::TokenTagNotFound(tag) => {
+                ("token_tag_not_found", Text::TokenTagNotFound.format(tag))
+            }
+            ChatError::TenantNotFound(tenant) => {
+                ("tenant_not_found", Text::TenantNotFound.format(tenant))
+            }
+            ChatError::InvalidTemplate(err) => {
+                ("invalid_template", Text::InvalidTemplate.format(err))
+            }
+            ChatError::ContentBlocked(reason) => {
+                ("content_blocked", Text::ContentBlocked.format(reason))
+            }
+            ChatError::PublicPoolQuotaExceeded => (
+                "public_pool_quota_exceeded",
+                Text::PublicPoolQuotaExceeded.get().to_string(),
+            ),
+            ChatError::RequestCancelled => (
+                "request_cancelled",
+                Text::RequestCancelled.get().to_string(),
+            ),
+            ChatError::UpstreamTimeout => {
+                ("upstream_timeout", Text::UpstreamTimeout.get().to_string())
+            }
+            ChatError::InvalidChoices(err) => ("invalid_n", Text::InvalidChoices.format(err)),
+            ChatError::LogprobsUnsupported => (
+                "logprobs_unsupported",
+                Text::LogprobsUnsupported.get().to_string(),
+            ),
+            ChatError::InvalidJsonOutput(err) => {
+                ("invalid_json_output", Text::InvalidJsonOutput.format(err))
+            }
+            ChatError::IpBlocked(ip) => ("ip_blocked", Text::IpBlocked.format(ip)),
+            ChatError::ImagesUnsupported => (
+                "images_not_supported",
+                Text::ImagesUnsupported.get().to_string(),
             ),
-            ChatError::NoTokens => ("no_tokens", "No available tokens".to_string()),
-            ChatError::RequestFailed(err) => ("request_failed", format!("Request failed: {}", err)),
-            ChatError::Unauthorized => ("unauthorized", "Invalid authorization token".to_string()),
         };
 
         ErrorResponse {
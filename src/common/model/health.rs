@@ -9,18 +9,92 @@ pub struct HealthCheckResponse {
     pub uptime: i64,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub stats: Option<SystemStats>,
-    pub models: Vec<&'static str>,
+    pub models: HealthModels,
     pub endpoints: Vec<&'static str>,
 }
 
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum HealthModels {
+    // 旧格式，仅包含模型名称
+    Flat(Vec<&'static str>),
+    Stats(Vec<ModelStats>),
+}
+
+// 根据最近24小时的日志统计
+#[derive(Serialize)]
+pub struct ModelStats {
+    pub id: &'static str,
+    pub requests_24h: u64,
+    // 已结束请求中失败的比例
+    pub error_rate: f64,
+    // 成功请求的平均总用时(秒)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avg_latency: Option<f64>,
+}
+
 #[derive(Serialize)]
 pub struct SystemStats {
     pub started: String,
     pub total_requests: u64,
     pub active_requests: u64,
+    // 从上游响应中丢弃的心跳帧数量
+    pub heartbeat_frames: u64,
+    // 请求数最多的客户端 IP
+    pub ips: Vec<IpStats>,
+    pub last_hour: RecentStats,
+    pub token_pool: TokenPoolStats,
+    // 启用并发上限时各优先级的请求数
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub dispatch: Vec<DispatchStats>,
     pub system: SystemInfo,
 }
 
+// 根据最近一小时的日志统计
+#[derive(Serialize, Default)]
+pub struct RecentStats {
+    pub requests: u64,
+    pub pending: u64,
+    pub success: u64,
+    pub failed: u64,
+    pub cancelled: u64,
+    // 已结束请求中失败的比例
+    pub error_rate: f64,
+    // 成功请求的平均总用时与首字时间(秒)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avg_latency: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avg_first_token: Option<f64>,
+}
+
+// 号池中 token 的状态，被拉黑或已过期的 token 不计入 active
+#[derive(Serialize, Default)]
+pub struct TokenPoolStats {
+    pub total: u64,
+    pub active: u64,
+    // 有进行中请求的 token 数
+    pub pending: u64,
+    pub expired: u64,
+    pub blocked: u64,
+}
+
+#[derive(Serialize)]
+pub struct IpStats {
+    pub ip: String,
+    pub requests: u64,
+    // 被 IP 访问控制拒绝的请求数
+    pub denied: u64,
+}
+
+#[derive(Serialize)]
+pub struct DispatchStats {
+    pub tier: &'static str,
+    // 正在发往上游的请求数
+    pub dispatching: u64,
+    // 等待发出的请求数
+    pub waiting: u64,
+}
+
 #[derive(Serialize)]
 pub struct SystemInfo {
     pub memory: MemoryInfo,
@@ -9,7 +9,10 @@ pub enum GetUserInfo {
     Error { error: String },
 }
 
+// 启用 client 特性时，上游字段名之外同时接受本服务输出的字段名，供客户端解析
 #[derive(Serialize, Clone, Archive, RkyvDeserialize, RkyvSerialize)]
+#[archive(check_bytes)]
+#[cfg_attr(feature = "client", derive(serde::Deserialize))]
 pub struct TokenProfile {
     pub usage: UsageProfile,
     pub user: UserProfile,
@@ -17,6 +20,7 @@ pub struct TokenProfile {
 }
 
 #[derive(Deserialize, Serialize, PartialEq, Clone, Archive, RkyvDeserialize, RkyvSerialize)]
+#[archive(check_bytes)]
 pub enum MembershipType {
     #[serde(rename = "free")]
     Free,
@@ -29,59 +33,75 @@ pub enum MembershipType {
 }
 
 #[derive(Deserialize, Serialize, Clone, Archive, RkyvDeserialize, RkyvSerialize)]
+#[archive(check_bytes)]
 pub struct StripeProfile {
     #[serde(rename(deserialize = "membershipType"))]
+    #[cfg_attr(feature = "client", serde(alias = "membership_type"))]
     pub membership_type: MembershipType,
     #[serde(
         rename(deserialize = "paymentId"),
         default,
         skip_serializing_if = "Option::is_none"
     )]
+    #[cfg_attr(feature = "client", serde(alias = "payment_id"))]
     pub payment_id: Option<String>,
     #[serde(rename(deserialize = "daysRemainingOnTrial"))]
+    #[cfg_attr(feature = "client", serde(alias = "days_remaining_on_trial"))]
     pub days_remaining_on_trial: u32,
 }
 
 #[derive(Deserialize, Serialize, Clone, Archive, RkyvDeserialize, RkyvSerialize)]
+#[archive(check_bytes)]
 pub struct ModelUsage {
     #[serde(rename(deserialize = "numRequests", serialize = "requests"))]
+    #[cfg_attr(feature = "client", serde(alias = "requests"))]
     pub num_requests: u32,
     #[serde(
         rename(deserialize = "numRequestsTotal"),
         default,
         skip_serializing_if = "Option::is_none"
     )]
+    #[cfg_attr(feature = "client", serde(alias = "requests_total"))]
     pub requests_total: Option<u32>,
     #[serde(rename(deserialize = "numTokens", serialize = "tokens"))]
+    #[cfg_attr(feature = "client", serde(alias = "tokens"))]
     pub num_tokens: u32,
     #[serde(
         rename(deserialize = "maxRequestUsage"),
         skip_serializing_if = "Option::is_none"
     )]
+    #[cfg_attr(feature = "client", serde(alias = "max_requests"))]
     pub max_requests: Option<u32>,
     #[serde(
         rename(deserialize = "maxTokenUsage"),
         skip_serializing_if = "Option::is_none"
     )]
+    #[cfg_attr(feature = "client", serde(alias = "max_tokens"))]
     pub max_tokens: Option<u32>,
 }
 
 #[derive(Deserialize, Serialize, Clone, Archive, RkyvDeserialize, RkyvSerialize)]
+#[archive(check_bytes)]
 pub struct UsageProfile {
     #[serde(rename(deserialize = "gpt-4"))]
+    #[cfg_attr(feature = "client", serde(alias = "premium"))]
     pub premium: ModelUsage,
     #[serde(rename(deserialize = "gpt-3.5-turbo"))]
+    #[cfg_attr(feature = "client", serde(alias = "standard"))]
     pub standard: ModelUsage,
     #[serde(rename(deserialize = "gpt-4-32k"))]
+    #[cfg_attr(feature = "client", serde(alias = "unknown"))]
     pub unknown: ModelUsage,
 }
 
 #[derive(Deserialize, Serialize, Clone, Archive, RkyvDeserialize, RkyvSerialize)]
+#[archive(check_bytes)]
 pub struct UserProfile {
     pub email: String,
     // pub email_verified: bool,
     pub name: String,
     #[serde(rename(serialize = "id"))]
+    #[cfg_attr(feature = "client", serde(alias = "id"))]
     pub sub: String,
     pub updated_at: DateTime<Local>,
     // Image link, rendered in /logs?
@@ -5,12 +5,17 @@ mod token;
 pub use token::*;
 mod base64;
 pub use base64::*;
+mod crypto;
+pub use crypto::*;
 
 use super::model::{token::TokenPayload, userinfo::{StripeProfile, TokenProfile, UsageProfile, UserProfile}};
 use crate::app::{
     constant::{COMMA, FALSE, TRUE},
-    lazy::{TOKEN_DELIMITER, USE_COMMA_DELIMITER},
+    lazy::{BASE_PATH, REAL_IP_HEADER, TOKEN_DELIMITER, TRUSTED_PROXIES, USE_COMMA_DELIMITER},
+    model::ip_matches,
 };
+use axum::http::HeaderMap;
+use std::net::{IpAddr, SocketAddr};
 
 pub fn parse_bool_from_env(key: &str, default: bool) -> bool {
     std::env::var(key)
@@ -48,6 +53,47 @@ pub fn parse_usize_from_env(key: &str, default: usize) -> usize {
         .unwrap_or(default)
 }
 
+// 生成站内地址，重定向等需要完整路径的地方都应通过它加上 BASE_PATH
+pub fn url_for(path: &str) -> String {
+    format!("{}{}", *BASE_PATH, path)
+}
+
+fn is_trusted_proxy(ip: IpAddr) -> bool {
+    TRUSTED_PROXIES.iter().any(|rule| ip_matches(rule, ip))
+}
+
+// 配置了 REAL_IP_HEADER 且连接来自可信代理时，从右向左跳过可信代理的地址，取第一个不可信的地址
+// 代理会把上一跳的地址追加到末尾，左侧的地址由客户端提供，不能直接使用
+pub fn client_ip(headers: &HeaderMap, addr: SocketAddr) -> IpAddr {
+    let peer = addr.ip().to_canonical();
+    if REAL_IP_HEADER.is_empty() || !is_trusted_proxy(peer) {
+        return peer;
+    }
+    let Some(value) = headers
+        .get_all(REAL_IP_HEADER.as_str())
+        .iter()
+        .map(|v| v.to_str().ok())
+        .collect::<Option<Vec<_>>>()
+    else {
+        return peer;
+    };
+
+    let mut client = peer;
+    for entry in value.iter().flat_map(|v| v.split(',')).rev() {
+        match entry.trim().parse::<IpAddr>() {
+            Ok(ip) => {
+                client = ip.to_canonical();
+                if !is_trusted_proxy(client) {
+                    break;
+                }
+            }
+            // 无法解析的地址之前的内容都不可信
+            Err(_) => break,
+        }
+    }
+    client
+}
+
 pub trait TrimNewlines {
     fn trim_leading_newlines(self) -> Self;
 }
@@ -69,23 +115,7 @@ impl TrimNewlines for String {
 }
 
 pub async fn get_token_profile(auth_token: &str) -> Option<TokenProfile> {
-    let user_id = extract_user_id(auth_token)?;
-
-    // 构建请求客户端
-    let client = super::client::build_usage_client(&user_id, auth_token);
-
-    // 发送请求并获取响应
-    // let response = client.send().await.ok()?;
-    // let bytes = response.bytes().await?;
-    // println!("Raw response bytes: {:?}", bytes);
-    // let usage = serde_json::from_str::<UsageProfile>(&text).ok()?;
-    let usage = client
-        .send()
-        .await
-        .ok()?
-        .json::<UsageProfile>()
-        .await
-        .ok()?;
+    let usage = get_usage_profile(auth_token).await?;
 
     let user = get_user_profile(auth_token).await?;
 
@@ -100,6 +130,20 @@ pub async fn get_token_profile(auth_token: &str) -> Option<TokenProfile> {
     })
 }
 
+pub async fn get_usage_profile(auth_token: &str) -> Option<UsageProfile> {
+    let user_id = extract_user_id(auth_token)?;
+
+    // 构建请求客户端
+    let client = super::client::build_usage_client(&user_id, auth_token);
+
+    // 发送请求并获取响应
+    // let response = client.send().await.ok()?;
+    // let bytes = response.bytes().await?;
+    // println!("Raw response bytes: {:?}", bytes);
+    // let usage = serde_json::from_str::<UsageProfile>(&text).ok()?;
+    client.send().await.ok()?.json::<UsageProfile>().await.ok()
+}
+
 pub async fn get_stripe_profile(auth_token: &str) -> Option<StripeProfile> {
     let client = super::client::build_profile_client(auth_token);
     let response = client
@@ -205,6 +249,18 @@ pub fn format_time_ms(seconds: f64) -> f64 {
     (seconds * 1000.0).round() / 1000.0
 }
 
+// 粗略估算 token 数: ASCII 约4个字符一个 token，其余字符(如中文)按每字一个计算
+pub fn estimate_tokens(text: &str) -> u32 {
+    let (ascii, other) = text.chars().fold((0u32, 0u32), |(ascii, other), c| {
+        if c.is_ascii() {
+            (ascii + 1, other)
+        } else {
+            (ascii, other + 1)
+        }
+    });
+    ascii.div_ceil(4) + other
+}
+
 use crate::chat::config::key_config;
 
 /// 将 JWT token 转换为 TokenInfo
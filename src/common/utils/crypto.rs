@@ -0,0 +1,193 @@
+use crate::app::lazy::TOKEN_ENCRYPTION_KEY;
+use ::base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use rand::RngCore as _;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use sha2::{Digest, Sha256};
+use std::sync::LazyLock;
+
+// 加密后的字段前缀
+const ENCRYPTED_PREFIX: &str = "enc:";
+// 以上述前缀开头的明文在保存时加上该前缀转义，读取时去除，避免被当作密文
+const ESCAPED_PREFIX: &str = "raw:";
+
+#[derive(Debug)]
+pub struct EncryptError;
+
+impl std::fmt::Display for EncryptError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("加密凭据失败")
+    }
+}
+
+impl std::error::Error for EncryptError {}
+
+// 由 TOKEN_ENCRYPTION_KEY 的 SHA-256 派生 AES-256-GCM 密钥，未设置时不加密
+static KEY: LazyLock<Option<LessSafeKey>> = LazyLock::new(|| {
+    if TOKEN_ENCRYPTION_KEY.is_empty() {
+        return None;
+    }
+    derive_key(&TOKEN_ENCRYPTION_KEY)
+});
+
+fn derive_key(secret: &str) -> Option<LessSafeKey> {
+    let key = Sha256::digest(secret.as_bytes());
+    UnboundKey::new(&AES_256_GCM, &key)
+        .ok()
+        .map(LessSafeKey::new)
+}
+
+#[inline]
+pub fn is_encryption_enabled() -> bool {
+    KEY.is_some()
+}
+
+/// 加密写入文件的凭据字段，字段为空时原样返回
+///
+/// 格式为 `enc:` 加上 base64url 编码的 nonce 与密文，不含逗号和换行；
+/// 未设置密钥时以明文保存，以 `enc:` 或 `raw:` 开头的明文加上 `raw:` 转义。
+/// 加载时无法解密而保留在内存中的密文原样写回
+pub fn encrypt_field(value: &str) -> Result<String, EncryptError> {
+    match KEY.as_ref() {
+        Some(key) => encrypt_with(key, value),
+        None if is_ciphertext(value) => Ok(value.to_string()),
+        None => Ok(escape(value)),
+    }
+}
+
+fn escape(value: &str) -> String {
+    if value.starts_with(ENCRYPTED_PREFIX) || value.starts_with(ESCAPED_PREFIX) {
+        format!("{}{}", ESCAPED_PREFIX, value)
+    } else {
+        value.to_string()
+    }
+}
+
+// 格式完整的密文：前缀之后是 nonce、密文与认证标签的 base64url 编码
+fn is_ciphertext(value: &str) -> bool {
+    value
+        .strip_prefix(ENCRYPTED_PREFIX)
+        .and_then(|encoded| URL_SAFE_NO_PAD.decode(encoded).ok())
+        .is_some_and(|data| data.len() >= NONCE_LEN + AES_256_GCM.tag_len())
+}
+
+fn encrypt_with(key: &LessSafeKey, value: &str) -> Result<String, EncryptError> {
+    // 当前密钥无法解密的密文是加载时保留下来的，不再重复加密
+    if value.is_empty() || (is_ciphertext(value) && decrypt_with(Some(key), value).is_none()) {
+        return Ok(value.to_string());
+    }
+
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut nonce);
+    let mut in_out = value.as_bytes().to_vec();
+    key.seal_in_place_append_tag(
+        Nonce::assume_unique_for_key(nonce),
+        Aad::empty(),
+        &mut in_out,
+    )
+    .map_err(|_| EncryptError)?;
+
+    let mut data = nonce.to_vec();
+    data.extend_from_slice(&in_out);
+    Ok(format!(
+        "{}{}",
+        ENCRYPTED_PREFIX,
+        URL_SAFE_NO_PAD.encode(data)
+    ))
+}
+
+/// 解密从文件读取的凭据字段，明文字段原样返回
+///
+/// 未设置密钥或密钥不匹配时返回 `None`
+pub fn decrypt_field(value: &str) -> Option<String> {
+    decrypt_with(KEY.as_ref(), value)
+}
+
+fn decrypt_with(key: Option<&LessSafeKey>, value: &str) -> Option<String> {
+    if let Some(plain) = value.strip_prefix(ESCAPED_PREFIX) {
+        return Some(plain.to_string());
+    }
+    let Some(encoded) = value.strip_prefix(ENCRYPTED_PREFIX) else {
+        return Some(value.to_string());
+    };
+    let key = key?;
+
+    let mut data = URL_SAFE_NO_PAD.decode(encoded).ok()?;
+    if data.len() < NONCE_LEN {
+        return None;
+    }
+    let mut in_out = data.split_off(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(&data).ok()?;
+    let plain = key.open_in_place(nonce, Aad::empty(), &mut in_out).ok()?;
+    String::from_utf8(plain.to_vec()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_decrypt_roundtrip() {
+        let key = derive_key("secret").unwrap();
+        let test_cases = [
+            "token",
+            "user_01::eyJhbGciOiJIUzI1NiJ9.payload.sig",
+            "中文",
+            "a,b\nc",
+            "enc:token",
+            "raw:token",
+        ];
+
+        for case in test_cases {
+            let encrypted = encrypt_with(&key, case).unwrap();
+            assert!(encrypted.starts_with(ENCRYPTED_PREFIX));
+            assert!(!encrypted.contains([',', '\n']));
+            assert_eq!(decrypt_with(Some(&key), &encrypted).as_deref(), Some(case));
+        }
+    }
+
+    #[test]
+    fn test_encrypt_uses_random_nonce() {
+        let key = derive_key("secret").unwrap();
+        assert_ne!(
+            encrypt_with(&key, "token").unwrap(),
+            encrypt_with(&key, "token").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_encrypt_skips_empty_and_undecryptable() {
+        let key = derive_key("secret").unwrap();
+        assert_eq!(encrypt_with(&key, "").unwrap(), "");
+        // 其他密钥加密、加载时无法解密的密文原样写回
+        let foreign = encrypt_with(&derive_key("other").unwrap(), "token").unwrap();
+        assert_eq!(encrypt_with(&key, &foreign).unwrap(), foreign);
+    }
+
+    #[test]
+    fn test_plaintext_with_reserved_prefix() {
+        let key = derive_key("secret").unwrap();
+        for case in ["enc:token", "enc:", "raw:token"] {
+            // 未设置密钥时转义保存
+            let escaped = escape(case);
+            assert!(escaped.starts_with(ESCAPED_PREFIX));
+            assert_eq!(decrypt_with(None, &escaped).as_deref(), Some(case));
+            assert_eq!(decrypt_with(Some(&key), &escaped).as_deref(), Some(case));
+        }
+        assert_eq!(escape("token"), "token");
+    }
+
+    #[test]
+    fn test_decrypt_plain_and_invalid() {
+        let key = derive_key("secret").unwrap();
+        let encrypted = encrypt_with(&key, "token").unwrap();
+
+        assert_eq!(decrypt_with(None, "token").as_deref(), Some("token")); // 明文原样返回
+        assert_eq!(decrypt_with(None, &encrypted), None); // 未设置密钥
+        assert_eq!(
+            decrypt_with(Some(&derive_key("other").unwrap()), &encrypted),
+            None
+        ); // 密钥不匹配
+        assert_eq!(decrypt_with(Some(&key), "enc:!!"), None); // 无效的 base64
+        assert_eq!(decrypt_with(Some(&key), "enc:AAAA"), None); // 长度不足
+    }
+}
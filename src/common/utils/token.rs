@@ -1,19 +1,26 @@
-use super::generate_checksum_with_repair;
+use super::{decrypt_field, encrypt_field, generate_checksum_with_repair, EncryptError};
 use crate::app::{
-    constant::{COMMA, EMPTY_STRING},
+    constant::{COMMA, COMMA_STRING, EMPTY_STRING},
     lazy::TOKEN_LIST_FILE,
     model::TokenInfo,
 };
 use crate::common::model::token::TokenPayload;
 use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
 use chrono::{DateTime, Local, TimeZone};
+use parking_lot::Mutex;
+use std::{collections::HashMap, sync::LazyLock};
+
+// 各 token list 文件中无法解密的原始行，写入文件时原样保留
+// 密钥错误或轮换后，这些 token 不会因为增删改而被永久删除
+static UNDECRYPTABLE_LINES: LazyLock<Mutex<HashMap<String, Vec<String>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
 
 // 规范化文件内容并写入
 fn normalize_and_write(content: &str, file_path: &str) -> String {
     let normalized = content.replace("\r\n", "\n");
     if normalized != content {
         if let Err(e) = std::fs::write(file_path, &normalized) {
-            eprintln!("警告: 无法更新规范化的文件: {}", e);
+            tracing::warn!("无法更新规范化的文件: {}", e);
         }
     }
     normalized
@@ -40,20 +47,26 @@ pub fn parse_token(token_part: &str) -> String {
 
 // Token 加载函数
 pub fn load_tokens() -> Vec<TokenInfo> {
-    let token_list_file = TOKEN_LIST_FILE.as_str();
+    load_token_file(TOKEN_LIST_FILE.as_str())
+}
 
+// 读取 token list 格式的文件，不存在时创建空文件，读取后规范化回写
+pub fn load_token_file(token_list_file: &str) -> Vec<TokenInfo> {
     // 确保文件存在
-    if !std::path::Path::new(&token_list_file).exists() {
-        if let Err(e) = std::fs::write(&token_list_file, EMPTY_STRING) {
-            eprintln!("警告: 无法创建文件 '{}': {}", &token_list_file, e);
+    if !std::path::Path::new(token_list_file).exists() {
+        if let Err(e) = std::fs::write(token_list_file, EMPTY_STRING) {
+            tracing::warn!("无法创建文件 '{}': {}", token_list_file, e);
         }
     }
 
+    // 无法解密的行不加载，但会在回写时原样保留
+    let mut undecryptable = Vec::new();
+
     // 读取和规范化 token-list 文件
-    let token_map: std::collections::HashMap<String, String> =
-        match std::fs::read_to_string(&token_list_file) {
+    let token_map: std::collections::HashMap<String, TokenInfo> =
+        match std::fs::read_to_string(token_list_file) {
             Ok(content) => {
-                let normalized = normalize_and_write(&content, &token_list_file);
+                let normalized = normalize_and_write(&content, token_list_file);
                 normalized
                     .lines()
                     .filter_map(|line| {
@@ -62,54 +75,147 @@ pub fn load_tokens() -> Vec<TokenInfo> {
                             return None;
                         }
 
+                        // 第三列起依次为可选的别名、公共号池标记、备注、联系方式与标签
                         let parts: Vec<&str> = line.split(COMMA).collect();
-                        match parts[..] {
-                            [token_part, checksum] => {
-                                let token = parse_token(token_part);
-                                Some((token, generate_checksum_with_repair(checksum)))
-                            }
-                            _ => {
-                                eprintln!("警告: 忽略无效的token-list行: {}", line);
-                                None
-                            }
+                        if !(2..=7).contains(&parts.len()) {
+                            tracing::warn!("忽略无效的token-list行: {}", line);
+                            return None;
                         }
+                        let field =
+                            |index: usize| parts.get(index).and_then(|s| normalize_field(s));
+
+                        // 加密的 token 与 checksum 列在内存中解密，明文列原样读取
+                        let (Some(token), Some(checksum)) =
+                            (decrypt_field(parts[0]), decrypt_field(parts[1]))
+                        else {
+                            tracing::error!("无法解密token-list行，请检查 TOKEN_ENCRYPTION_KEY");
+                            undecryptable.push(line.to_string());
+                            return None;
+                        };
+
+                        let token = parse_token(&token);
+                        Some((
+                            token.clone(),
+                            TokenInfo {
+                                token,
+                                checksum: generate_checksum_with_repair(&checksum),
+                                alias: field(2),
+                                profile: None,
+                                warmup: None,
+                                is_public: parts.get(3).is_some_and(|flag| is_public_flag(flag)),
+                                note: field(4),
+                                contact: field(5),
+                                tags: parts
+                                    .get(6)
+                                    .map(|tags| parse_tags(tags))
+                                    .unwrap_or_default(),
+                            },
+                        ))
                     })
                     .collect()
             }
             Err(e) => {
-                eprintln!("警告: 无法读取token-list文件: {}", e);
+                tracing::warn!("无法读取token-list文件: {}", e);
                 std::collections::HashMap::new()
             }
         };
 
+    let token_infos: Vec<TokenInfo> = token_map.into_values().collect();
+
+    if !undecryptable.is_empty() {
+        tracing::warn!(
+            "token-list文件中有 {} 行无法解密，这些行会被原样保留",
+            undecryptable.len()
+        );
+    }
+    UNDECRYPTABLE_LINES
+        .lock()
+        .insert(token_list_file.to_string(), undecryptable);
+
     // 更新 token-list 文件
-    let token_list_content = token_map
-        .iter()
-        .map(|(token, checksum)| format!("{},{}", token, checksum))
-        .collect::<Vec<_>>()
-        .join("\n");
+    if let Err(e) = write_tokens(&token_infos, token_list_file) {
+        tracing::warn!("无法更新token-list文件: {}", e);
+    }
+
+    token_infos
+}
+
+// 规范化别名、备注等文本列，其中不能包含分隔符与换行
+pub fn normalize_field(value: &str) -> Option<String> {
+    let value = value.replace([',', '\r', '\n'], " ");
+    let value = value.trim();
+    if value.is_empty() {
+        None
+    } else {
+        Some(value.to_string())
+    }
+}
+
+// 解析以空白分隔的标签列
+pub fn parse_tags(value: &str) -> Vec<String> {
+    normalize_tags([value])
+}
 
-    if let Err(e) = std::fs::write(&token_list_file, token_list_content) {
-        eprintln!("警告: 无法更新token-list文件: {}", e);
+// 规范化标签，按空白与逗号拆分并去除重复的标签
+pub fn normalize_tags<S: AsRef<str>>(values: impl IntoIterator<Item = S>) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for value in values {
+        for tag in value
+            .as_ref()
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|tag| !tag.is_empty())
+        {
+            if !tags.iter().any(|t| t == tag) {
+                tags.push(tag.to_string());
+            }
+        }
     }
+    tags
+}
 
-    // 转换为 TokenInfo vector
-    token_map
-        .into_iter()
-        .map(|(token, checksum)| TokenInfo {
-            token: token.clone(),
-            checksum,
-            profile: None,
-        })
-        .collect()
+// 公共号池标记，位于 token list 文件的第四列
+pub const PUBLIC_TOKEN_FLAG: &str = "public";
+
+#[inline]
+pub fn is_public_flag(flag: &str) -> bool {
+    flag.trim().eq_ignore_ascii_case(PUBLIC_TOKEN_FLAG)
 }
 
+// 格式化为 token list 文件中的一行，省略末尾的空列
+fn format_token_line(info: &TokenInfo) -> Result<String, EncryptError> {
+    let token = encrypt_field(&info.token)?;
+    let checksum = encrypt_field(&info.checksum)?;
+    let tags = info.tags.join(" ");
+    let mut columns = vec![
+        token.as_str(),
+        checksum.as_str(),
+        info.alias.as_deref().unwrap_or_default(),
+        if info.is_public {
+            PUBLIC_TOKEN_FLAG
+        } else {
+            EMPTY_STRING
+        },
+        info.note.as_deref().unwrap_or_default(),
+        info.contact.as_deref().unwrap_or_default(),
+        tags.as_str(),
+    ];
+    while columns.len() > 2 && columns.last().is_some_and(|column| column.is_empty()) {
+        columns.pop();
+    }
+    Ok(columns.join(COMMA_STRING))
+}
+
+// 任一 token 加密失败时不写入文件，不会以明文保存
 pub fn write_tokens(token_infos: &[TokenInfo], file_path: &str) -> std::io::Result<()> {
-    let content = token_infos
+    let mut lines: Vec<String> = token_infos
         .iter()
-        .map(|info| format!("{},{}", info.token, info.checksum))
-        .collect::<Vec<String>>()
-        .join("\n");
+        .map(format_token_line)
+        .collect::<Result<_, _>>()
+        .map_err(std::io::Error::other)?;
+    if let Some(undecryptable) = UNDECRYPTABLE_LINES.lock().get(file_path) {
+        lines.extend(undecryptable.iter().cloned());
+    }
+    let content = lines.join("\n");
 
     std::fs::write(file_path, content)
 }
@@ -274,3 +380,15 @@ pub fn extract_time(token: &str) -> Option<DateTime<Local>> {
         .ok()
         .and_then(|timestamp| Local.timestamp_opt(timestamp, 0).single())
 }
+
+// 从 JWT token 中提取过期时间戳，只要求 payload 包含 exp 字段
+pub fn extract_exp(token: &str) -> Option<i64> {
+    #[derive(serde::Deserialize)]
+    struct ExpPayload {
+        exp: i64,
+    }
+
+    let payload = URL_SAFE_NO_PAD.decode(token.split('.').nth(1)?).ok()?;
+    let payload: ExpPayload = serde_json::from_slice(&payload).ok()?;
+    Some(payload.exp)
+}
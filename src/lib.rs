@@ -0,0 +1,6 @@
+pub mod app;
+pub mod chat;
+pub mod common;
+
+#[cfg(feature = "client")]
+pub mod client;
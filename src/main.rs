@@ -1,40 +1,72 @@
-mod app;
-mod chat;
-mod common;
+use cursor_api::{app, chat, common};
 
 use app::{
-    config::handle_config_update,
+    config::{handle_config_export, handle_config_import, handle_config_update},
     constant::{
-        PKG_VERSION, ROUTE_ABOUT_PATH, ROUTE_API_PATH, ROUTE_BASIC_CALIBRATION_PATH,
-        ROUTE_BUILD_KEY_PATH, ROUTE_CONFIG_PATH, ROUTE_ENV_EXAMPLE_PATH, ROUTE_GET_CHECKSUM,
-        ROUTE_GET_HASH, ROUTE_GET_TIMESTAMP_HEADER, ROUTE_HEALTH_PATH, ROUTE_LOGS_PATH,
-        ROUTE_README_PATH, ROUTE_ROOT_PATH, ROUTE_STATIC_PATH, ROUTE_TOKENS_ADD_PATH,
-        ROUTE_TOKENS_DELETE_PATH, ROUTE_TOKENS_GET_PATH, ROUTE_TOKENS_PATH,
-        ROUTE_TOKENS_RELOAD_PATH, ROUTE_TOKENS_UPDATE_PATH, ROUTE_USER_INFO_PATH,
+        PKG_VERSION, ROUTE_ABOUT_PATH, ROUTE_API_KEYS_PATH, ROUTE_API_PATH, ROUTE_AUDIT_LOGS_PATH,
+        ROUTE_AZURE_DEPLOYMENTS_PATH, ROUTE_BASIC_CALIBRATION_PATH, ROUTE_BUILD_KEY_PATH,
+        ROUTE_CHECKSUMS_PATH, ROUTE_CONFIG_EXPORT_PATH, ROUTE_CONFIG_IMPORT_PATH,
+        ROUTE_CONFIG_PATH, ROUTE_CONVERSATIONS_PATH, ROUTE_DAILY_STATS_PATH,
+        ROUTE_ENV_EXAMPLE_PATH, ROUTE_FAULTS_PATH, ROUTE_GET_CHECKSUM, ROUTE_GET_HASH,
+        ROUTE_GET_TIMESTAMP_HEADER, ROUTE_HEALTH_PATH, ROUTE_INVITE_CODES_PATH,
+        ROUTE_LATENCY_STATS_PATH, ROUTE_LOGS_CLEANUP_PATH, ROUTE_LOGS_PATH, ROUTE_LOGS_STREAM_PATH,
+        ROUTE_ME_PATH, ROUTE_MODEL_ALIASES_PATH, ROUTE_MODEL_CAPABILITIES_PATH,
+        ROUTE_MODEL_POLICIES_PATH, ROUTE_MODEL_PRICES_PATH, ROUTE_MODERATION_PATH,
+        ROUTE_PAYLOADS_PATH, ROUTE_PROMPT_TEMPLATES_PATH, ROUTE_QUALITY_PATH, ROUTE_README_PATH,
+        ROUTE_REGISTER_PATH, ROUTE_REPORTS_PATH, ROUTE_ROOT_PATH, ROUTE_RUNTIME_PATH,
+        ROUTE_SESSION_PATH, ROUTE_SPEND_PATH, ROUTE_STATIC_PATH, ROUTE_SYSTEM_PROMPTS_PATH,
+        ROUTE_TENANTS_PATH, ROUTE_TOKENS_ADD_PATH, ROUTE_TOKENS_BLACKLIST_PATH,
+        ROUTE_TOKENS_DELETED_PATH, ROUTE_TOKENS_DELETE_PATH, ROUTE_TOKENS_EXPORT_PATH,
+        ROUTE_TOKENS_GET_PATH, ROUTE_TOKENS_IMPORT_PATH, ROUTE_TOKENS_META_PATH, ROUTE_TOKENS_PATH,
+        ROUTE_TOKENS_RELOAD_PATH, ROUTE_TOKENS_UPDATE_PATH, ROUTE_TOKENS_VALIDATE_PATH,
+        ROUTE_TOKEN_PATH, ROUTE_TOKEN_RESTORE_PATH, ROUTE_TOKEN_STATS_PATH, ROUTE_USER_INFO_PATH,
+    },
+    lazy::{
+        AUTH_TOKEN, BASE_PATH, ENABLE_DEBUG_ECHO, ENABLE_FAULT_INJECTION, LOGS_CLEANUP_INTERVAL,
+        MOCK_UPSTREAM, ROUTE_AZURE_CHAT_PATH, ROUTE_CHAT_CANCEL_PATH, ROUTE_CHAT_PATH,
+        ROUTE_CHAT_TEMPLATE_PATH, ROUTE_CHAT_WS_PATH, ROUTE_COMPLETIONS_PATH,
+        ROUTE_DEBUG_ECHO_PATH, ROUTE_IMAGES_GENERATIONS_PATH, ROUTE_MODELS_PATH,
+        ROUTE_TENANT_CHAT_PATH, ROUTE_TENANT_MODELS_PATH, STATIC_DIR, STATS_SAVE_INTERVAL,
     },
-    lazy::{AUTH_TOKEN, ROUTE_CHAT_PATH, ROUTE_MODELS_PATH},
     model::*,
 };
 use axum::{
-    routing::{get, post},
+    middleware,
+    routing::{delete, get, post},
     Router,
 };
 use chat::{
+    completions::handle_completions,
+    conversation, idempotency,
+    images::handle_images_generations,
+    payload, quality, queue,
     route::{
-        handle_about, handle_add_tokens, handle_api_page, handle_basic_calibration,
-        handle_build_key, handle_build_key_page, handle_config_page, handle_delete_tokens,
-        handle_env_example, handle_get_checksum, handle_get_hash, handle_get_timestamp_header,
-        handle_get_tokens, handle_health, handle_logs, handle_logs_post, handle_readme,
-        handle_reload_tokens, handle_root, handle_static, handle_tokens_page, handle_update_tokens,
-        handle_user_info,
+        handle_about, handle_add_tokens, handle_api_keys, handle_api_page, handle_audit_logs,
+        handle_azure_deployments, handle_basic_calibration, handle_build_key,
+        handle_build_key_page, handle_checksums, handle_config_page, handle_conversations,
+        handle_daily_stats, handle_debug_echo, handle_delete_tokens, handle_env_example,
+        handle_export_tokens, handle_faults, handle_get_checksum, handle_get_deleted_tokens,
+        handle_get_hash, handle_get_timestamp_header, handle_get_tokens, handle_health,
+        handle_import_tokens, handle_invite_codes, handle_latency_stats, handle_logs,
+        handle_logs_cleanup, handle_logs_post, handle_logs_stream, handle_me, handle_model_aliases,
+        handle_model_capabilities, handle_model_policies, handle_model_prices,
+        handle_moderation_rules, handle_payload, handle_prompt_templates, handle_quality_trend,
+        handle_readme, handle_register, handle_reload_tokens, handle_reports, handle_restore_token,
+        handle_root, handle_runtime, handle_session, handle_soft_delete_token, handle_spend,
+        handle_static, handle_system_prompts, handle_tenants, handle_token_blacklist,
+        handle_token_meta, handle_token_stats, handle_tokens_page, handle_update_tokens,
+        handle_user_info, handle_validate_tokens,
+    },
+    service::{
+        handle_azure_chat, handle_chat, handle_chat_cancel, handle_chat_template, handle_chat_ws,
+        handle_models, handle_tenant_chat, handle_tenant_models,
     },
-    service::{handle_chat, handle_models},
 };
-use common::utils::{load_tokens, parse_string_from_env, parse_usize_from_env};
+use common::utils::{load_tokens, parse_usize_from_env, url_for};
 use std::sync::Arc;
 use tokio::signal;
 use tokio::sync::Mutex;
-use tower_http::{cors::CorsLayer, limit::RequestBodyLimitLayer};
+use tower_http::limit::RequestBodyLimitLayer;
 
 #[tokio::main]
 async fn main() {
@@ -42,15 +74,25 @@ async fn main() {
     std::panic::set_hook(Box::new(|info| {
         // std::env::set_var("RUST_BACKTRACE", "1");
         if let Some(msg) = info.payload().downcast_ref::<String>() {
-            eprintln!("{}", msg);
+            tracing::error!("{}", msg);
         } else if let Some(msg) = info.payload().downcast_ref::<&str>() {
-            eprintln!("{}", msg);
+            tracing::error!("{}", msg);
         }
     }));
 
     // 加载环境变量
     dotenvy::dotenv().ok();
 
+    // 初始化日志
+    app::logging::init();
+
+    // 自检模式：检查配置与依赖后退出，不启动服务
+    if std::env::args().skip(1).any(|arg| arg == "--check") {
+        AppConfig::init();
+        let passed = app::check::run().await;
+        std::process::exit(if passed { 0 } else { 1 });
+    }
+
     if AUTH_TOKEN.is_empty() {
         panic!("AUTH_TOKEN must be set")
     };
@@ -58,6 +100,12 @@ async fn main() {
     // 初始化全局配置
     AppConfig::init();
 
+    // 初始化 token 租约目录或检查 Redis 连接
+    app::lease::init().await;
+
+    // 启动外部日志转发
+    app::log_sink::init();
+
     // 加载 tokens
     let token_infos = load_tokens();
 
@@ -66,33 +114,89 @@ async fn main() {
 
     // 尝试加载保存的配置
     if let Err(e) = AppConfig::load_saved_config() {
-        eprintln!("加载保存的配置失败: {}", e);
+        tracing::error!("加载保存的配置失败: {}", e);
     }
 
-    // 创建一个克隆用于后台任务
-    let state_for_reload = state.clone();
+    // 加载 token 黑名单
+    if let Err(e) = TokenBlacklist::load() {
+        tracing::error!("加载 token 黑名单失败: {}", e);
+    }
 
-    // 启动后台任务在每个整1000秒时更新 checksum
-    tokio::spawn(async move {
-        loop {
-            // 获取当前时间戳
-            let now = std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs();
+    // 尝试加载保存的各项数据
+    for (name, load) in SAVED_DATA {
+        if let Err(e) = load() {
+            tracing::error!("加载保存的{}失败: {}", name, e);
+        }
+    }
 
-            // 计算距离下一个整1000秒的等待时间
-            let next_reload = (now / 1000 + 1) * 1000;
-            let wait_duration = next_reload - now;
+    // 启动 checksum 的刷新与定期轮换任务
+    app::rotation::init(state.clone());
 
-            // 等待到下一个整1000秒
-            tokio::time::sleep(std::time::Duration::from_secs(wait_duration)).await;
+    // 启动额度同步任务
+    app::quota::init(state.clone());
 
-            let mut app_state = state_for_reload.lock().await;
-            app_state.update_checksum();
-            // debug_println!("checksum 自动刷新: {}", next_reload);
-        }
-    });
+    // 启动后台任务定期保存请求统计
+    if *STATS_SAVE_INTERVAL > 0 {
+        let state_for_stats = state.clone();
+        tokio::spawn(async move {
+            let mut interval =
+                tokio::time::interval(std::time::Duration::from_secs(*STATS_SAVE_INTERVAL));
+            // 跳过立即触发的第一次
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                let stats = state_for_stats.lock().await.stats();
+                if let Err(e) = stats.save().await {
+                    tracing::warn!("保存统计失败: {}", e);
+                }
+                if let Err(e) = SpendLedger::save().await {
+                    tracing::warn!("保存消费统计失败: {}", e);
+                }
+                if conversation::history_enabled() {
+                    if let Err(e) = Conversations::save().await {
+                        tracing::warn!("保存会话历史失败: {}", e);
+                    }
+                }
+                if quality::is_enabled() {
+                    if let Err(e) = QualitySamples::save().await {
+                        tracing::warn!("保存质量抽样失败: {}", e);
+                    }
+                }
+                if payload::is_enabled() {
+                    if let Err(e) = Payloads::save().await {
+                        tracing::warn!("保存请求内容失败: {}", e);
+                    }
+                }
+            }
+        });
+    }
+
+    // 启动后台任务定期按保留策略清理日志，并写入文件以回收空间
+    if *LOGS_CLEANUP_INTERVAL > 0 {
+        let state_for_cleanup = state.clone();
+        tokio::spawn(async move {
+            let mut interval =
+                tokio::time::interval(std::time::Duration::from_secs(*LOGS_CLEANUP_INTERVAL));
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                let mut state = state_for_cleanup.lock().await;
+                let removed = state.prune_logs();
+                if removed > 0 {
+                    tracing::info!("已清理 {} 条过期日志", removed);
+                    if let Err(e) = state.save_logs().await {
+                        tracing::warn!("保存日志失败: {}", e);
+                    }
+                }
+            }
+        });
+    }
+
+    // 启动每日使用报告任务
+    app::report::init(state.clone());
+
+    // 启动每日用量汇总任务
+    app::daily_summary::init(state.clone());
 
     // 创建一个克隆用于信号处理
     let state_for_shutdown = state.clone();
@@ -121,26 +225,71 @@ async fn main() {
             _ = terminate => {},
         }
 
-        println!("正在关闭服务器...");
+        tracing::info!("正在关闭服务器...");
 
         // 保存配置
         if let Err(e) = AppConfig::save_config() {
-            eprintln!("保存配置失败: {}", e);
+            tracing::error!("保存配置失败: {}", e);
         } else {
-            println!("配置已保存");
+            tracing::info!("配置已保存");
         }
 
-        // 保存日志
+        // 释放 token 租约
+        app::lease::release_all().await;
+
         let state = state_for_shutdown.lock().await;
+
+        // 保存统计
+        if let Err(e) = state.stats().save().await {
+            tracing::error!("保存统计失败: {}", e);
+        } else {
+            tracing::info!("统计已保存");
+        }
+
+        // 保存消费统计
+        if let Err(e) = SpendLedger::save().await {
+            tracing::error!("保存消费统计失败: {}", e);
+        } else {
+            tracing::info!("消费统计已保存");
+        }
+
+        // 保存会话历史
+        if conversation::history_enabled() {
+            if let Err(e) = Conversations::save().await {
+                tracing::error!("保存会话历史失败: {}", e);
+            } else {
+                tracing::info!("会话历史已保存");
+            }
+        }
+
+        // 保存质量抽样
+        if quality::is_enabled() {
+            if let Err(e) = QualitySamples::save().await {
+                tracing::error!("保存质量抽样失败: {}", e);
+            } else {
+                tracing::info!("质量抽样已保存");
+            }
+        }
+
+        // 保存请求内容
+        if payload::is_enabled() {
+            if let Err(e) = Payloads::save().await {
+                tracing::error!("保存请求内容失败: {}", e);
+            } else {
+                tracing::info!("请求内容已保存");
+            }
+        }
+
+        // 保存日志
         if let Err(e) = state.save_logs().await {
-            eprintln!("保存日志失败: {}", e);
+            tracing::error!("保存日志失败: {}", e);
         } else {
-            println!("日志已保存");
+            tracing::info!("日志已保存");
         }
     };
 
     // 设置路由
-    let app = Router::new()
+    let mut app = Router::new()
         .route(ROUTE_ROOT_PATH, get(handle_root))
         .route(ROUTE_HEALTH_PATH, get(handle_health))
         .route(ROUTE_TOKENS_PATH, get(handle_tokens_page))
@@ -150,12 +299,60 @@ async fn main() {
         .route(ROUTE_TOKENS_UPDATE_PATH, post(handle_update_tokens))
         .route(ROUTE_TOKENS_ADD_PATH, post(handle_add_tokens))
         .route(ROUTE_TOKENS_DELETE_PATH, post(handle_delete_tokens))
-        .route(ROUTE_CHAT_PATH.as_str(), post(handle_chat))
+        .route(ROUTE_TOKENS_IMPORT_PATH, post(handle_import_tokens))
+        .route(ROUTE_TOKENS_EXPORT_PATH, post(handle_export_tokens))
+        .route(ROUTE_TOKENS_META_PATH, post(handle_token_meta))
+        .route(ROUTE_TOKENS_BLACKLIST_PATH, post(handle_token_blacklist))
+        .route(ROUTE_TOKENS_DELETED_PATH, post(handle_get_deleted_tokens))
+        .route(ROUTE_TOKEN_PATH, delete(handle_soft_delete_token))
+        .route(ROUTE_TOKEN_RESTORE_PATH, post(handle_restore_token))
+        .route(ROUTE_TOKENS_VALIDATE_PATH, post(handle_validate_tokens))
+        .route(
+            ROUTE_CHAT_PATH.as_str(),
+            post(handle_chat)
+                .layer(middleware::map_response(queue::add_retry_after))
+                .layer(middleware::from_fn(idempotency::apply)),
+        )
+        .route(
+            ROUTE_CHAT_TEMPLATE_PATH.as_str(),
+            post(handle_chat_template)
+                .layer(middleware::map_response(queue::add_retry_after))
+                .layer(middleware::from_fn(idempotency::apply)),
+        )
+        .route(ROUTE_CHAT_WS_PATH.as_str(), get(handle_chat_ws))
+        .route(ROUTE_TENANT_MODELS_PATH.as_str(), get(handle_tenant_models))
+        .route(
+            ROUTE_TENANT_CHAT_PATH.as_str(),
+            post(handle_tenant_chat)
+                .layer(middleware::map_response(queue::add_retry_after))
+                .layer(middleware::from_fn(idempotency::apply)),
+        )
+        .route(
+            ROUTE_AZURE_CHAT_PATH.as_str(),
+            post(handle_azure_chat)
+                .layer(middleware::map_response(queue::add_retry_after))
+                .layer(middleware::from_fn(idempotency::apply)),
+        )
+        .route(
+            ROUTE_COMPLETIONS_PATH.as_str(),
+            post(handle_completions)
+                .layer(middleware::map_response(queue::add_retry_after))
+                .layer(middleware::from_fn(idempotency::apply)),
+        )
+        .route(
+            ROUTE_IMAGES_GENERATIONS_PATH.as_str(),
+            post(handle_images_generations),
+        )
+        .route(ROUTE_CHAT_CANCEL_PATH.as_str(), post(handle_chat_cancel))
         .route(ROUTE_LOGS_PATH, get(handle_logs))
         .route(ROUTE_LOGS_PATH, post(handle_logs_post))
+        .route(ROUTE_LOGS_CLEANUP_PATH, post(handle_logs_cleanup))
+        .route(ROUTE_LOGS_STREAM_PATH, get(handle_logs_stream))
         .route(ROUTE_ENV_EXAMPLE_PATH, get(handle_env_example))
         .route(ROUTE_CONFIG_PATH, get(handle_config_page))
         .route(ROUTE_CONFIG_PATH, post(handle_config_update))
+        .route(ROUTE_CONFIG_EXPORT_PATH, post(handle_config_export))
+        .route(ROUTE_CONFIG_IMPORT_PATH, post(handle_config_import))
         .route(ROUTE_STATIC_PATH, get(handle_static))
         .route(ROUTE_ABOUT_PATH, get(handle_about))
         .route(ROUTE_README_PATH, get(handle_readme))
@@ -167,31 +364,86 @@ async fn main() {
         .route(ROUTE_USER_INFO_PATH, post(handle_user_info))
         .route(ROUTE_BUILD_KEY_PATH, get(handle_build_key_page))
         .route(ROUTE_BUILD_KEY_PATH, post(handle_build_key))
+        .route(ROUTE_MODEL_POLICIES_PATH, post(handle_model_policies))
+        .route(ROUTE_MODEL_ALIASES_PATH, post(handle_model_aliases))
+        .route(ROUTE_MODEL_PRICES_PATH, post(handle_model_prices))
+        .route(ROUTE_SPEND_PATH, post(handle_spend))
+        .route(ROUTE_API_KEYS_PATH, post(handle_api_keys))
+        .route(ROUTE_RUNTIME_PATH, post(handle_runtime))
+        .route(ROUTE_AUDIT_LOGS_PATH, post(handle_audit_logs))
+        .route(ROUTE_PROMPT_TEMPLATES_PATH, post(handle_prompt_templates))
+        .route(ROUTE_MODERATION_PATH, post(handle_moderation_rules))
+        .route(ROUTE_REPORTS_PATH, post(handle_reports))
+        .route(ROUTE_CHECKSUMS_PATH, post(handle_checksums))
+        .route(ROUTE_SESSION_PATH, post(handle_session))
+        .route(ROUTE_PAYLOADS_PATH, get(handle_payload))
+        .route(ROUTE_TENANTS_PATH, post(handle_tenants))
+        .route(ROUTE_SYSTEM_PROMPTS_PATH, post(handle_system_prompts))
+        .route(
+            ROUTE_MODEL_CAPABILITIES_PATH,
+            post(handle_model_capabilities),
+        )
+        .route(ROUTE_AZURE_DEPLOYMENTS_PATH, post(handle_azure_deployments))
+        .route(ROUTE_INVITE_CODES_PATH, post(handle_invite_codes))
+        .route(ROUTE_REGISTER_PATH, post(handle_register))
+        .route(ROUTE_TOKEN_STATS_PATH, get(handle_token_stats))
+        .route(ROUTE_ME_PATH, get(handle_me))
+        .route(ROUTE_DAILY_STATS_PATH, get(handle_daily_stats))
+        .route(ROUTE_LATENCY_STATS_PATH, get(handle_latency_stats))
+        .route(ROUTE_CONVERSATIONS_PATH, post(handle_conversations))
+        .route(ROUTE_QUALITY_PATH, get(handle_quality_trend));
+
+    // 开发者模式下才开放调试接口
+    if *ENABLE_DEBUG_ECHO {
+        app = app.route(ROUTE_DEBUG_ECHO_PATH.as_str(), post(handle_debug_echo));
+    }
+    if *ENABLE_FAULT_INJECTION {
+        tracing::warn!("已开启故障注入，请勿在生产环境中使用");
+        app = app.route(ROUTE_FAULTS_PATH, post(handle_faults));
+    }
+    if *MOCK_UPSTREAM {
+        tracing::warn!("已开启模拟上游，对话请求不会发送到上游");
+    }
+    if app::static_dir::is_enabled() {
+        if std::path::Path::new(STATIC_DIR.as_str()).is_dir() {
+            tracing::info!("优先从 {} 提供页面与静态文件", *STATIC_DIR);
+        } else {
+            tracing::warn!("STATIC_DIR {} 不是目录，将使用内置页面", *STATIC_DIR);
+        }
+    }
+
+    // 部署在子路径下时整体挂载到 BASE_PATH
+    if !BASE_PATH.is_empty() {
+        app = Router::new()
+            .nest(&BASE_PATH, app)
+            .route(&url_for(ROUTE_ROOT_PATH), get(handle_root));
+    }
+
+    let app = app
+        .layer(middleware::from_fn(app::ip_filter::enforce))
+        .layer(middleware::from_fn(app::i18n::apply))
         .layer(RequestBodyLimitLayer::new(
             1024 * 1024 * parse_usize_from_env("REQUEST_BODY_LIMIT_MB", 2),
         ))
-        .layer(CorsLayer::permissive())
+        .layer(middleware::from_fn(app::cors::apply))
+        .layer(middleware::from_fn(app::request_id::assign))
         .with_state(state);
 
     // 启动服务器
-    let port = parse_string_from_env("PORT", "3000");
-    let addr = format!("0.0.0.0:{}", port);
-    println!("服务器运行在端口 {}", port);
-    println!("当前版本: v{}", PKG_VERSION);
+    tracing::info!("当前版本: v{}", PKG_VERSION);
     // if PKG_VERSION.contains("pre") {
     // println!("当前是测试版，有问题及时反馈哦~");
     // }
 
-    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    let server = axum::serve(listener, app);
+    let server = app::listen::serve(app);
     tokio::select! {
         result = server => {
             if let Err(e) = result {
-                eprintln!("服务器错误: {}", e);
+                tracing::error!("服务器错误: {}", e);
             }
         }
         _ = shutdown_signal => {
-            println!("服务器已关闭");
+            tracing::info!("服务器已关闭");
         }
     }
 }
@@ -109,27 +109,47 @@ function initializeTokenHandling(inputId) {
   document.getElementById(inputId).addEventListener('change', (e) => {
     if (e.target.value) {
       saveAuthToken(e.target.value);
+      updateSession('login', e.target.value);
     } else {
       localStorage.removeItem('authToken');
       localStorage.removeItem('authTokenExpiry');
+      updateSession('logout');
     }
   });
 }
 
+// 网页会话管理，登录后页面请求可不携带令牌
+/**
+ * 登录或退出网页会话，失败时静默忽略
+ * @param {'login'|'logout'} action - 操作类型
+ * @param {string} [token] - 登录时使用的 AUTH_TOKEN
+ * @returns {Promise<void>}
+ */
+async function updateSession(action, token) {
+  try {
+    await fetch('api/session', {
+      method: 'POST',
+      headers: {
+        ...(token && { 'Authorization': `Bearer ${token}` }),
+        'Content-Type': 'application/json'
+      },
+      body: JSON.stringify({ action })
+    });
+  } catch (error) {
+    // 会话不可用时仍可使用令牌
+  }
+}
+
 // API 请求通用处理
 async function makeAuthenticatedRequest(url, options = {}) {
   const tokenId = options.tokenId || 'authToken';
   const token = document.getElementById(tokenId).value;
 
-  if (!token) {
-    showGlobalMessage('请输入 AUTH_TOKEN', true);
-    return null;
-  }
-
+  // 未输入令牌时依赖网页会话
   const defaultOptions = {
     method: 'POST',
     headers: {
-      'Authorization': `Bearer ${token}`,
+      ...(token && { 'Authorization': `Bearer ${token}` }),
       'Content-Type': 'application/json'
     }
   };
@@ -137,6 +157,9 @@ async function makeAuthenticatedRequest(url, options = {}) {
   try {
     const response = await fetch(url, { ...defaultOptions, ...options });
 
+    if (response.status === 401 && !token) {
+      throw new Error('请输入 AUTH_TOKEN');
+    }
     if (!response.ok) {
       throw new Error(`HTTP error! status: ${response.status}`);
     }